@@ -0,0 +1,152 @@
+#!/usr/bin/env python3
+# RISC-V Tiny VM - Ivi Ballou / Amechania
+#
+# Builds the rv32u{i,m,a,c,f,d,zba,zbb,zbc,zbs}-p-* and rv64u{i,m}-p-* test
+# binaries from the sources in isa/.
+#
+# Only llvm-mc is needed, no RISC-V linker: every test lives in a single
+# section, so the assembler resolves all the pc-relative references itself.
+# The section is then wrapped into an executable linked at 0x80000000, ELF32 or
+# ELF64 after the suite, along with its symbol table so the runner can find `tohost`.
+#
+# Usage: build.py [LLVM_MC]
+
+import os
+import struct
+import subprocess
+import sys
+import tempfile
+
+BASE = 0x8000_0000
+ROOT = os.path.dirname(os.path.abspath(__file__))
+SUITES = {"rv32ui": "+m", "rv32um": "+m", "rv32ua": "+m,+a", "rv32uc": "+m,+c",
+          "rv32uf": "+m,+f", "rv32ud": "+m,+f,+d", "rv32uzba": "+zba", "rv32uzbb": "+zbb",
+          "rv32uzbc": "+zbc", "rv32uzbs": "+zbs", "rv64ui": "+m", "rv64um": "+m"}
+GLOBALS = {"_start", "tohost", "fromhost"}
+
+SHT_PROGBITS, SHT_SYMTAB, SHT_STRTAB, SHT_RELA = 1, 2, 3, 4
+STB_LOCAL, STB_GLOBAL = 0, 1
+STT_NOTYPE, STT_FUNC, STT_OBJECT = 0, 2, 1
+
+
+# Field layouts of the ELF32 and ELF64 headers, section headers and symbols
+class Layout:
+    def __init__(self, elf64):
+        self.elf64 = elf64
+        self.ehdr_size, self.phdr_size, self.shdr_size, self.sym_size = (64, 56, 64, 24) if elf64 else (52, 32, 40, 16)
+        self.shoff_at, self.shnum_at = (40, 58) if elf64 else (32, 46)
+        self.shdr = "<IIQQQQIIQQ" if elf64 else "<IIIIIIIIII"
+
+
+def read_object(path):
+    """Returns the .text bytes and (name, offset) of every symbol in it."""
+    data = open(path, "rb").read()
+    layout = Layout(data[4] == 2)
+    e_shoff, = struct.unpack_from("<Q" if layout.elf64 else "<I", data, layout.shoff_at)
+    e_shentsize, e_shnum, e_shstrndx = struct.unpack_from("<HHH", data, layout.shnum_at)
+    sections = [struct.unpack_from(layout.shdr, data, e_shoff + i * e_shentsize) for i in range(e_shnum)]
+    shstr = sections[e_shstrndx]
+
+    def name_at(strtab, offset):
+        start = strtab[4] + offset
+        return data[start:data.index(b"\0", start)].decode()
+
+    names = [name_at(shstr, s[0]) for s in sections]
+    if any(s[1] in (SHT_RELA, 9) for s in sections):
+        sys.exit(f"{path}: has relocations, everything must be in one section with local symbols")
+    text_index = names.index(".text")
+    text = sections[text_index]
+    code = data[text[4]:text[4] + text[5]]
+
+    symbols = []
+    for s in sections:
+        if s[1] != SHT_SYMTAB:
+            continue
+        strtab = sections[s[6]]
+        for i in range(s[5] // layout.sym_size):
+            offset = s[4] + i * layout.sym_size
+            if layout.elf64:
+                st_name, _, _, st_shndx, st_value, _ = struct.unpack_from("<IBBHQQ", data, offset)
+            else:
+                st_name, st_value, _, _, _, st_shndx = struct.unpack_from("<IIIBBH", data, offset)
+            name = name_at(strtab, st_name)
+            if st_shndx == text_index and name and not name.startswith(".L"):
+                symbols.append((name, st_value))
+    return code, symbols
+
+
+def write_executable(path, code, symbols, elf64):
+    layout = Layout(elf64)
+    # Locals have to come before globals in the symbol table
+    symbols = sorted(symbols, key=lambda sym: (sym[0] in GLOBALS, sym[1]))
+    strtab = b"\0"
+    symtab = bytes(layout.sym_size)
+    first_global = None
+    for index, (name, value) in enumerate(symbols, start=1):
+        is_global = name in GLOBALS
+        if is_global and first_global is None:
+            first_global = index
+        kind = STT_OBJECT if name in ("tohost", "fromhost") else STT_NOTYPE
+        info = ((STB_GLOBAL if is_global else STB_LOCAL) << 4) | kind
+        if elf64:
+            symtab += struct.pack("<IBBHQQ", len(strtab), info, 0, 1, BASE + value, 0)
+        else:
+            symtab += struct.pack("<IIIBBH", len(strtab), BASE + value, 0, info, 0, 1)
+        strtab += name.encode() + b"\0"
+    shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0"
+
+    ehdr_size, phdr_size, shdr_size = layout.ehdr_size, layout.phdr_size, layout.shdr_size
+    text_off = 0x1000
+    symtab_off = text_off + len(code)
+    strtab_off = symtab_off + len(symtab)
+    shstrtab_off = strtab_off + len(strtab)
+    align = 8 if elf64 else 4
+    shoff = (shstrtab_off + len(shstrtab) + align - 1) & ~(align - 1)
+
+    ehdr = b"\x7fELF" + bytes([2 if elf64 else 1, 1, 1]) + bytes(9)
+    address = "Q" if elf64 else "I"
+    ehdr += struct.pack(f"<HHI{address}{address}{address}IHHHHHH", 2, 0xF3, 1, BASE, ehdr_size, shoff, 0,
+                        ehdr_size, phdr_size, 1, shdr_size, 5, 4)
+    # PT_LOAD, RWX
+    if elf64:
+        phdr = struct.pack("<IIQQQQQQ", 1, 7, text_off, BASE, BASE, len(code), len(code), 0x1000)
+    else:
+        phdr = struct.pack("<IIIIIIII", 1, text_off, BASE, BASE, len(code), len(code), 7, 0x1000)
+    shdrs = bytes(shdr_size)
+    shdrs += struct.pack(layout.shdr, 1, SHT_PROGBITS, 0x7, BASE, text_off, len(code), 0, 0, 64, 0)
+    shdrs += struct.pack(layout.shdr, 7, SHT_SYMTAB, 0, 0, symtab_off, len(symtab), 3,
+                         first_global or len(symbols) + 1, 8 if elf64 else 4, layout.sym_size)
+    shdrs += struct.pack(layout.shdr, 15, SHT_STRTAB, 0, 0, strtab_off, len(strtab), 0, 0, 1, 0)
+    shdrs += struct.pack(layout.shdr, 23, SHT_STRTAB, 0, 0, shstrtab_off, len(shstrtab), 0, 0, 1, 0)
+
+    image = bytearray(ehdr + phdr)
+    image += bytes(text_off - len(image))
+    image += code + symtab + strtab + shstrtab
+    image += bytes(shoff - len(image))
+    image += shdrs
+    with open(path, "wb") as f:
+        f.write(image)
+
+
+def main():
+    llvm_mc = sys.argv[1] if len(sys.argv) > 1 else "llvm-mc"
+    env = os.path.join(ROOT, "env")
+    with tempfile.TemporaryDirectory() as tmp:
+        for suite, attrs in SUITES.items():
+            elf64 = suite.startswith("rv64")
+            src_dir = os.path.join(ROOT, "isa", suite)
+            for source in sorted(os.listdir(src_dir)):
+                test, ext = os.path.splitext(source)
+                if ext != ".s":
+                    continue
+                obj = os.path.join(tmp, test + ".o")
+                subprocess.run([llvm_mc, f"-triple=riscv{64 if elf64 else 32}", f"-mattr={attrs}", "-filetype=obj",
+                                "-I", env, "-o", obj, os.path.join(src_dir, source)], check=True)
+                code, symbols = read_object(obj)
+                out = os.path.join(ROOT, "isa", f"{suite}-p-{test}")
+                write_executable(out, code, symbols, elf64)
+                print(out)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,180 @@
+# RISC-V Tiny VM - Ivi Ballou / Amechania
+#
+# Physical memory, machine mode ("p") test environment, following the layout
+# of riscv-test-env/p/riscv_test.h. Written as assembler macros instead of C
+# preprocessor ones, so llvm-mc can build the tests without a full toolchain.
+#
+# The result is reported through tohost: 1 means every case passed,
+# (TESTNUM << 1) | 1 means test TESTNUM failed.
+
+.option norelax
+.option nopic
+
+# mcause values the trap vector cares about
+.equ CAUSE_USER_ECALL, 8
+.equ CAUSE_SUPERVISOR_ECALL, 9
+.equ CAUSE_MACHINE_ECALL, 11
+
+.equ MSTATUS_FS, 0x6000
+
+.macro RVTEST_RV32U
+.endm
+
+.macro RVTEST_RV32M
+.endm
+
+.macro RVTEST_RV64U
+.endm
+
+.macro RVTEST_RV64M
+.endm
+
+# The F and D tests need the FPU turned on before dropping to user mode
+.macro RVTEST_RV32UF
+    .equ RVTEST_FP, 1
+.endm
+
+.macro RVTEST_RV32UD
+    .equ RVTEST_FP, 1
+.endm
+
+# mstatus.FS = Initial, and clear fcsr
+.macro RVTEST_FP_ENABLE
+    li t0, MSTATUS_FS & (MSTATUS_FS >> 1)
+    csrs mstatus, t0
+    csrwi fcsr, 0
+.endm
+
+.macro INIT_XREG
+    li x1, 0
+    li x2, 0
+    li x3, 0
+    li x4, 0
+    li x5, 0
+    li x6, 0
+    li x7, 0
+    li x8, 0
+    li x9, 0
+    li x10, 0
+    li x11, 0
+    li x12, 0
+    li x13, 0
+    li x14, 0
+    li x15, 0
+    li x16, 0
+    li x17, 0
+    li x18, 0
+    li x19, 0
+    li x20, 0
+    li x21, 0
+    li x22, 0
+    li x23, 0
+    li x24, 0
+    li x25, 0
+    li x26, 0
+    li x27, 0
+    li x28, 0
+    li x29, 0
+    li x30, 0
+    li x31, 0
+.endm
+
+.macro RVTEST_CODE_BEGIN
+    .text
+    .balign 64
+_start:
+    j reset_vector
+    .balign 4
+trap_vector:
+    # Test whether the test came from pass/fail
+    csrr t5, mcause
+    li t6, CAUSE_USER_ECALL
+    beq t5, t6, write_tohost
+    li t6, CAUSE_SUPERVISOR_ECALL
+    beq t5, t6, write_tohost
+    li t6, CAUSE_MACHINE_ECALL
+    beq t5, t6, write_tohost
+    # Anything else is an unexpected exception
+other_exception:
+    ori gp, gp, 1337
+write_tohost:
+    sw gp, tohost, t5
+    sw zero, tohost + 4, t5
+    j write_tohost
+reset_vector:
+    INIT_XREG
+    # Each block points mtvec past itself, so CSRs we don't implement are skipped
+    la t0, 1f
+    csrw mtvec, t0
+    csrwi satp, 0
+    .balign 4
+1:
+    la t0, 1f
+    csrw mtvec, t0
+    li t0, -1
+    csrw pmpaddr0, t0
+    li t0, 0x1F
+    csrw pmpcfg0, t0
+    .balign 4
+1:
+    la t0, 1f
+    csrw mtvec, t0
+    csrwi medeleg, 0
+    csrwi mideleg, 0
+    .balign 4
+1:
+    csrwi mie, 0
+    la t0, trap_vector
+    csrw mtvec, t0
+    csrwi mstatus, 0
+    .ifdef RVTEST_FP
+    RVTEST_FP_ENABLE
+    .endif
+    la t0, 1f
+    csrw mepc, t0
+    csrr a0, mhartid
+    mret
+1:
+.endm
+
+.macro RVTEST_CODE_END
+    unimp
+.endm
+
+.macro RVTEST_PASS
+    fence
+    li gp, 1
+    li a7, 93
+    li a0, 0
+    ecall
+.endm
+
+.macro RVTEST_FAIL
+    fence
+1:
+    beqz gp, 1b
+    sll gp, gp, 1
+    or gp, gp, 1
+    li a7, 93
+    addi a0, gp, 0
+    ecall
+.endm
+
+# tohost and fromhost get their own page, like the .tohost section upstream
+.macro RVTEST_DATA_BEGIN
+    .balign 4096
+tohost:
+    .word 0
+    .word 0
+    .balign 64
+fromhost:
+    .word 0
+    .word 0
+    .balign 4096
+begin_signature:
+.endm
+
+.macro RVTEST_DATA_END
+    .balign 16
+end_signature:
+.endm
@@ -0,0 +1,436 @@
+# RISC-V Tiny VM - Ivi Ballou / Amechania
+#
+# Test case macros, following riscv-tests/isa/macros/scalar/test_macros.h.
+# Every case loads its test number into TESTNUM (gp) first, so a failing
+# case can be identified from the value written to tohost.
+
+#-----------------------------------------------------------------------
+# Helper
+#-----------------------------------------------------------------------
+
+.macro TEST_CHECK testreg, correctval
+    li x7, \correctval
+    bne \testreg, x7, fail
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for instructions with immediate operand
+#-----------------------------------------------------------------------
+
+.macro TEST_IMM_OP testnum, inst, result, val1, imm
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x14, x1, \imm
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_IMM_SRC1_EQ_DEST testnum, inst, result, val1, imm
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x1, x1, \imm
+    TEST_CHECK x1, \result
+.endm
+
+.macro TEST_IMM_ZEROSRC1 testnum, inst, result, imm
+test_\testnum:
+    li gp, \testnum
+    \inst x1, x0, \imm
+    TEST_CHECK x1, \result
+.endm
+
+.macro TEST_IMM_ZERODEST testnum, inst, val1, imm
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x0, x1, \imm
+    TEST_CHECK x0, 0
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for an instruction with register operands
+#-----------------------------------------------------------------------
+
+.macro TEST_R_OP testnum, inst, result, val1
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x14, x1
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_R_SRC1_EQ_DEST testnum, inst, result, val1
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x1, x1
+    TEST_CHECK x1, \result
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for register-register instructions
+#-----------------------------------------------------------------------
+
+.macro TEST_RR_OP testnum, inst, result, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x14, x1, x2
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_SRC1_EQ_DEST testnum, inst, result, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x1, x1, x2
+    TEST_CHECK x1, \result
+.endm
+
+.macro TEST_RR_SRC2_EQ_DEST testnum, inst, result, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x2, x1, x2
+    TEST_CHECK x2, \result
+.endm
+
+.macro TEST_RR_SRC12_EQ_DEST testnum, inst, result, val1
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    \inst x1, x1, x1
+    TEST_CHECK x1, \result
+.endm
+
+.macro TEST_RR_ZEROSRC1 testnum, inst, result, val
+test_\testnum:
+    li gp, \testnum
+    li x2, \val
+    \inst x14, x0, x2
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_ZEROSRC2 testnum, inst, result, val
+test_\testnum:
+    li gp, \testnum
+    li x1, \val
+    \inst x14, x1, x0
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_ZERODEST testnum, inst, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x0, x1, x2
+    TEST_CHECK x0, 0
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for load and store instructions
+#-----------------------------------------------------------------------
+
+.macro TEST_LD_OP testnum, inst, result, offset, base
+test_\testnum:
+    li gp, \testnum
+    la x2, \base
+    \inst x14, \offset(x2)
+    TEST_CHECK x14, \result
+.endm
+
+.macro TEST_ST_OP testnum, load_inst, store_inst, result, offset, base
+test_\testnum:
+    li gp, \testnum
+    la x2, \base
+    li x1, \result
+    \store_inst x1, \offset(x2)
+    \load_inst x14, \offset(x2)
+    TEST_CHECK x14, \result
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for branch instructions
+#-----------------------------------------------------------------------
+
+.macro TEST_BR2_OP_TAKEN testnum, inst, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x1, x2, 2f
+    bne x0, gp, fail
+1:
+    bne x0, gp, 3f
+2:
+    \inst x1, x2, 1b
+    bne x0, gp, fail
+3:
+.endm
+
+.macro TEST_BR2_OP_NOTTAKEN testnum, inst, val1, val2
+test_\testnum:
+    li gp, \testnum
+    li x1, \val1
+    li x2, \val2
+    \inst x1, x2, 1f
+    bne x0, gp, 2f
+1:
+    bne x0, gp, fail
+2:
+    \inst x1, x2, 1b
+3:
+.endm
+
+#-----------------------------------------------------------------------
+# Tests for floating-point instructions
+#-----------------------------------------------------------------------
+
+# The operands and expected results live in subsection 1, after the rest of
+# the test, so they stay in the one section. The NaNs are spelled out, as
+# the assembler's own `nan` has every fraction bit set: qNaNf and qNaN are
+# the canonical NaNs, sNaNf and sNaN signaling ones.
+
+.macro FP_VALUE_S value
+.ifc \value, qNaNf
+    .word 0x7fc00000
+.else
+.ifc \value, sNaNf
+    .word 0x7f800001
+.else
+    .float \value
+.endif
+.endif
+.endm
+
+.macro FP_VALUE_D value
+.ifc \value, qNaN
+    .quad 0x7ff8000000000000
+.else
+.ifc \value, sNaN
+    .quad 0x7ff0000000000001
+.else
+    .double \value
+.endif
+.endif
+.endm
+
+.macro FP_DATA_S testnum, val1, val2, val3, result
+    .subsection 1
+    .balign 4
+test_\testnum\()_data:
+    FP_VALUE_S \val1
+    FP_VALUE_S \val2
+    FP_VALUE_S \val3
+    FP_VALUE_S \result
+    .subsection 0
+.endm
+
+.macro FP_DATA_D testnum, val1, val2, val3, result
+    .subsection 1
+    .balign 8
+test_\testnum\()_data:
+    FP_VALUE_D \val1
+    FP_VALUE_D \val2
+    FP_VALUE_D \val3
+    FP_VALUE_D \result
+    .subsection 0
+.endm
+
+# Loads the operands into f0-f2 and the expected result into a3 (and t1)
+.macro FP_LOAD_S testnum
+    li gp, \testnum
+    la a0, test_\testnum\()_data
+    flw f0, 0(a0)
+    flw f1, 4(a0)
+    flw f2, 8(a0)
+    lw a3, 12(a0)
+.endm
+
+.macro FP_LOAD_D testnum
+    li gp, \testnum
+    la a0, test_\testnum\()_data
+    fld f0, 0(a0)
+    fld f1, 8(a0)
+    fld f2, 16(a0)
+    lw a3, 24(a0)
+    lw t1, 28(a0)
+.endm
+
+# Compares a0 (and t2) to the expected result, and the exception flags
+# raised to `flags`. Reading the flags also clears them for the next case.
+.macro FP_CHECK_S flags
+    fsflags a1, x0
+    li a2, \flags
+    bne a0, a3, fail
+    bne a1, a2, fail
+.endm
+
+.macro FP_CHECK_D flags
+    fsflags a1, x0
+    li a2, \flags
+    bne a0, a3, fail
+    bne t1, t2, fail
+    bne a1, a2, fail
+.endm
+
+# A double result goes through memory, RV32 has no FMV.X.D
+.macro FP_RESULT_D reg
+    fsd \reg, 0(a0)
+    lw t2, 4(a0)
+    lw a0, 0(a0)
+.endm
+
+.macro TEST_FP_OP1_S testnum, inst, flags, result, val1
+test_\testnum:
+    FP_LOAD_S \testnum
+    \inst f3, f0
+    fmv.x.s a0, f3
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, \val1, 0.0, 0.0, \result
+.endm
+
+.macro TEST_FP_OP2_S testnum, inst, flags, result, val1, val2
+test_\testnum:
+    FP_LOAD_S \testnum
+    \inst f3, f0, f1
+    fmv.x.s a0, f3
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, \val1, \val2, 0.0, \result
+.endm
+
+.macro TEST_FP_OP3_S testnum, inst, flags, result, val1, val2, val3
+test_\testnum:
+    FP_LOAD_S \testnum
+    \inst f3, f0, f1, f2
+    fmv.x.s a0, f3
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, \val1, \val2, \val3, \result
+.endm
+
+.macro TEST_FP_INT_OP_S testnum, inst, flags, result, val1, rm
+test_\testnum:
+    FP_LOAD_S \testnum
+    \inst a0, f0, \rm
+    li a3, \result
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, \val1, 0.0, 0.0, 0.0
+.endm
+
+.macro TEST_FP_CMP_OP_S testnum, inst, flags, result, val1, val2
+test_\testnum:
+    FP_LOAD_S \testnum
+    \inst a0, f0, f1
+    li a3, \result
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, \val1, \val2, 0.0, 0.0
+.endm
+
+.macro TEST_INT_FP_OP_S testnum, inst, flags, result, val1
+test_\testnum:
+    FP_LOAD_S \testnum
+    li a0, \val1
+    \inst f3, a0
+    fmv.x.s a0, f3
+    FP_CHECK_S \flags
+    FP_DATA_S \testnum, 0.0, 0.0, 0.0, \result
+.endm
+
+.macro TEST_FCLASS_S testnum, correct, input
+test_\testnum:
+    li gp, \testnum
+    li a0, \input
+    fmv.w.x fa0, a0
+    fclass.s a0, fa0
+    TEST_CHECK a0, \correct
+.endm
+
+.macro TEST_FCLASS_D testnum, correct, input
+test_\testnum:
+    li gp, \testnum
+    la a0, test_\testnum\()_data
+    fld fa0, 0(a0)
+    fclass.d a0, fa0
+    TEST_CHECK a0, \correct
+    .subsection 1
+    .balign 8
+test_\testnum\()_data:
+    .quad \input
+    .subsection 0
+.endm
+
+.macro TEST_FP_OP1_D testnum, inst, flags, result, val1
+test_\testnum:
+    FP_LOAD_D \testnum
+    \inst f3, f0
+    FP_RESULT_D f3
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, \val1, 0.0, 0.0, \result
+.endm
+
+.macro TEST_FP_OP2_D testnum, inst, flags, result, val1, val2
+test_\testnum:
+    FP_LOAD_D \testnum
+    \inst f3, f0, f1
+    FP_RESULT_D f3
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, \val1, \val2, 0.0, \result
+.endm
+
+.macro TEST_FP_OP3_D testnum, inst, flags, result, val1, val2, val3
+test_\testnum:
+    FP_LOAD_D \testnum
+    \inst f3, f0, f1, f2
+    FP_RESULT_D f3
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, \val1, \val2, \val3, \result
+.endm
+
+.macro TEST_FP_INT_OP_D testnum, inst, flags, result, val1, rm
+test_\testnum:
+    FP_LOAD_D \testnum
+    \inst a0, f0, \rm
+    li a3, \result
+    li t1, 0
+    li t2, 0
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, \val1, 0.0, 0.0, 0.0
+.endm
+
+.macro TEST_FP_CMP_OP_D testnum, inst, flags, result, val1, val2
+test_\testnum:
+    FP_LOAD_D \testnum
+    \inst a0, f0, f1
+    li a3, \result
+    li t1, 0
+    li t2, 0
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, \val1, \val2, 0.0, 0.0
+.endm
+
+.macro TEST_INT_FP_OP_D testnum, inst, flags, result, val1
+test_\testnum:
+    FP_LOAD_D \testnum
+    li a1, \val1
+    \inst f3, a1
+    FP_RESULT_D f3
+    FP_CHECK_D \flags
+    FP_DATA_D \testnum, 0.0, 0.0, 0.0, \result
+.endm
+
+#-----------------------------------------------------------------------
+# Pass and fail code (assumes test num is in gp)
+#-----------------------------------------------------------------------
+
+.macro TEST_PASSFAIL
+    bne x0, gp, pass
+fail:
+    RVTEST_FAIL
+pass:
+    RVTEST_PASS
+.endm
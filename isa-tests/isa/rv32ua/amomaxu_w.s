@@ -0,0 +1,51 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# amomaxu_w.s
+#-----------------------------------------------------------------------------
+#
+# Test amomaxu.w instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+test_2:
+  li gp, 2
+  li a0, 0x80000000
+  li a1, 0xfffff800
+  la a3, amo_operand
+  sw a0, 0(a3)
+  amomaxu.w a4, a1, 0(a3)
+  TEST_CHECK a4, 0x80000000
+
+test_3:
+  li gp, 3
+  lw a5, 0(a3)
+  TEST_CHECK a5, 0xfffff800
+
+  # try again with a different value in memory
+test_4:
+  li gp, 4
+  li a1, 0xffffffff
+  sw x0, 0(a3)
+  amomaxu.w a4, a1, 0(a3)
+  TEST_CHECK a4, 0
+
+test_5:
+  li gp, 5
+  lw a5, 0(a3)
+  TEST_CHECK a5, 0xffffffff
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+amo_operand:
+  .word 0
+
+RVTEST_DATA_END
@@ -0,0 +1,90 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lrsc.s
+#-----------------------------------------------------------------------------
+#
+# Test LR/SC instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  # make sure that sc without a reservation fails
+test_2:
+  li gp, 2
+  la a0, foo
+  li a5, 0xdeadbeef
+  sc.w a4, a5, (a0)
+  TEST_CHECK a4, 1
+
+  # make sure the failing sc did not commit into memory
+test_3:
+  li gp, 3
+  lw a4, foo
+  TEST_CHECK a4, 0
+
+  # make sure that sc with a reservation succeeds
+test_4:
+  li gp, 4
+  la a0, foo
+  lr.w a4, (a0)
+  addi a4, a4, 1
+  sc.w a5, a4, (a0)
+  TEST_CHECK a5, 0
+
+test_5:
+  li gp, 5
+  lw a4, foo
+  TEST_CHECK a4, 1
+
+  # the reservation is used up by the sc
+test_6:
+  li gp, 6
+  sc.w a5, a4, (a0)
+  TEST_CHECK a5, 1
+
+  # a store to the reserved word breaks the reservation
+test_7:
+  li gp, 7
+  lr.w a4, (a0)
+  sw zero, 0(a0)
+  sc.w a5, a4, (a0)
+  TEST_CHECK a5, 1
+
+  # sc to another word than the one reserved fails
+test_8:
+  li gp, 8
+  la a1, bar
+  lr.w a4, (a0)
+  sc.w a5, a4, (a1)
+  TEST_CHECK a5, 1
+
+  # increment a counter in a lr/sc loop
+test_9:
+  li gp, 9
+  li a1, 1024
+1:
+  lr.w a4, (a0)
+  addi a4, a4, 1
+  sc.w a5, a4, (a0)
+  bnez a5, 1b
+  addi a1, a1, -1
+  bnez a1, 1b
+  lw a4, foo
+  TEST_CHECK a4, 1024
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+foo:
+  .word 0
+bar:
+  .word 0
+
+RVTEST_DATA_END
@@ -0,0 +1,209 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rvc.s
+#-----------------------------------------------------------------------------
+#
+# Test RVC corner cases.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Fetching a 4-byte instruction across a page boundary
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  li a1, 666
+  j 1f
+  .balign 4096
+  .skip 4094
+1:
+  .option push
+  .option norvc
+  addi a1, a1, 1
+  .option pop
+  TEST_CHECK a1, 667
+
+  #-------------------------------------------------------------
+  # Stack pointer arithmetic
+  #-------------------------------------------------------------
+
+test_3:
+  li gp, 3
+  li sp, 0x1234
+  c.addi4spn a0, sp, 1020
+  TEST_CHECK a0, 0x1234 + 1020
+
+test_4:
+  li gp, 4
+  c.addi16sp sp, 496
+  TEST_CHECK sp, 0x1234 + 496
+
+test_5:
+  li gp, 5
+  c.addi16sp sp, -512
+  TEST_CHECK sp, 0x1234 + 496 - 512
+
+  #-------------------------------------------------------------
+  # Loads and stores
+  #-------------------------------------------------------------
+
+test_6:
+  li gp, 6
+  la a1, tdat
+  c.lw a0, 4(a1)
+  addi a0, a0, 1
+  c.sw a0, 4(a1)
+  c.lw a2, 4(a1)
+  TEST_CHECK a2, 0xfedcba99
+
+test_7:
+  li gp, 7
+  mv sp, a1
+  c.lwsp a3, 4(sp)
+  c.swsp a3, 8(sp)
+  lw a4, 8(a1)
+  TEST_CHECK a4, 0xfedcba99
+
+  #-------------------------------------------------------------
+  # Arithmetic
+  #-------------------------------------------------------------
+
+test_8:
+  li gp, 8
+  c.li a0, 0
+  c.addi a0, 1
+  TEST_CHECK a0, 1
+
+test_9:
+  li gp, 9
+  c.lui s0, 0xfffe1
+  c.srai s0, 12
+  TEST_CHECK s0, 0xffffffe1
+
+test_10:
+  li gp, 10
+  c.lui s0, 0xfffe1
+  c.srli s0, 12
+  TEST_CHECK s0, 0x000fffe1
+
+test_11:
+  li gp, 11
+  c.li s0, -2
+  c.andi s0, ~0x10
+  TEST_CHECK s0, 0xffffffee
+
+test_12:
+  li gp, 12
+  li s1, 20
+  li a0, 6
+  c.sub s1, a0
+  TEST_CHECK s1, 14
+
+test_13:
+  li gp, 13
+  li s1, 20
+  c.xor s1, a0
+  TEST_CHECK s1, 18
+
+test_14:
+  li gp, 14
+  li s1, 20
+  c.or s1, a0
+  TEST_CHECK s1, 22
+
+test_15:
+  li gp, 15
+  li s1, 20
+  c.and s1, a0
+  TEST_CHECK s1, 4
+
+test_16:
+  li gp, 16
+  li s0, 0x1234
+  c.slli s0, 4
+  TEST_CHECK s0, 0x12340
+
+test_17:
+  li gp, 17
+  li a0, 0x123
+  c.mv t0, a0
+  c.add t0, a0
+  TEST_CHECK t0, 0x246
+
+  #-------------------------------------------------------------
+  # Control flow
+  #-------------------------------------------------------------
+
+test_18:
+  li gp, 18
+  li ra, 0
+  c.j 1f
+  c.j 2f
+1:
+  c.j 1f
+2:
+  j fail
+1:
+  TEST_CHECK ra, 0
+
+test_19:
+  li gp, 19
+  li a0, 1
+  c.beqz a0, 2f
+  c.bnez a0, 1f
+2:
+  j fail
+1:
+  li a0, 0
+  c.bnez a0, 2b
+  c.beqz a0, 1f
+  j fail
+1:
+
+test_20:
+  li gp, 20
+  la t0, 1f
+  c.jal 1f
+1:
+  sub ra, ra, t0
+  TEST_CHECK ra, 0
+
+test_21:
+  li gp, 21
+  la t0, 1f
+  li ra, 0
+  c.jalr t0
+  c.j 2f
+1:
+  sub ra, ra, t0
+  TEST_CHECK ra, -2
+  c.j 3f
+2:
+  j fail
+3:
+
+test_22:
+  li gp, 22
+  la t0, 1f
+  c.jr t0
+  j fail
+1:
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+  .word 0x01234567
+  .word 0xfedcba98
+  .word 0
+
+RVTEST_DATA_END
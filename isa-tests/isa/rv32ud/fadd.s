@@ -0,0 +1,39 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fadd.s
+#-----------------------------------------------------------------------------
+#
+# Test fadd.d, fsub.d and fmul.d instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP2_D 2, fadd.d, 0, 3.5, 2.5, 1.0
+  TEST_FP_OP2_D 3, fadd.d, 1, -1234, -1235.1, 1.1
+  TEST_FP_OP2_D 4, fadd.d, 1, 3.14159266, 3.14159265, 0.00000001
+  TEST_FP_OP2_D 5, fsub.d, 0, 1.5, 2.5, 1.0
+  TEST_FP_OP2_D 6, fsub.d, 1, -1234, -1235.1, -1.1
+  TEST_FP_OP2_D 7, fsub.d, 1, 3.1415926400000003, 3.14159265, 0.00000001
+  TEST_FP_OP2_D 8, fmul.d, 0, 2.5, 2.5, 1.0
+  TEST_FP_OP2_D 9, fmul.d, 1, 1358.61, -1235.1, -1.1
+  TEST_FP_OP2_D 10, fmul.d, 1, 3.14159265e-8, 3.14159265, 0.00000001
+
+  # Inf - Inf is invalid and gives the canonical NaN
+  TEST_FP_OP2_D 11, fsub.d, 0x10, qNaN, inf, inf
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,37 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fclass.s
+#-----------------------------------------------------------------------------
+#
+# Test fclass.d instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FCLASS_D 2, 0x001, 0xfff0000000000000
+  TEST_FCLASS_D 3, 0x002, 0xbff0000000000000
+  TEST_FCLASS_D 4, 0x004, 0x800fffffffffffff
+  TEST_FCLASS_D 5, 0x008, 0x8000000000000000
+  TEST_FCLASS_D 6, 0x010, 0x0000000000000000
+  TEST_FCLASS_D 7, 0x020, 0x000fffffffffffff
+  TEST_FCLASS_D 8, 0x040, 0x3ff0000000000000
+  TEST_FCLASS_D 9, 0x080, 0x7ff0000000000000
+  TEST_FCLASS_D 10, 0x100, 0x7ff0000000000001
+  TEST_FCLASS_D 11, 0x200, 0x7ff8000000000000
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,77 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fcvt.s
+#-----------------------------------------------------------------------------
+#
+# Test fcvt.d.{w|wu}, fcvt.s.d and fcvt.d.s instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_INT_FP_OP_D 2, fcvt.d.w, 0x00, 2.0, 2
+  TEST_INT_FP_OP_D 3, fcvt.d.w, 0x00, -2.0, -2
+  TEST_INT_FP_OP_D 4, fcvt.d.wu, 0x00, 2.0, 2
+  TEST_INT_FP_OP_D 5, fcvt.d.wu, 0x00, 4294967294, -2
+
+  #-------------------------------------------------------------
+  # Conversions between single and double
+  #-------------------------------------------------------------
+
+test_10:
+  li gp, 10
+  la a1, tdat
+  fld f0, 0(a1)
+  fcvt.s.d f1, f0
+  fmv.x.s a0, f1
+  TEST_CHECK a0, 0xbfc00000
+
+test_11:
+  li gp, 11
+  fcvt.d.s f2, f1
+  fsd f2, 16(a1)
+  lw a0, 16(a1)
+  lw a2, 20(a1)
+  TEST_CHECK a0, 0
+  TEST_CHECK a2, 0xbff80000
+
+  # A double NaN becomes the canonical single NaN
+test_12:
+  li gp, 12
+  fld f0, 8(a1)
+  fcvt.s.d f1, f0
+  fmv.x.s a0, f1
+  TEST_CHECK a0, 0x7fc00000
+
+  # Narrowing rounds, and can overflow
+test_13:
+  li gp, 13
+  la a1, tdat_big
+  fld f0, 0(a1)
+  fcvt.s.d f1, f0
+  fmv.x.s a0, f1
+  TEST_CHECK a0, 0x7f800000
+  fsflags a0, x0
+  TEST_CHECK a0, 0x05
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+  .double -1.5
+  .quad 0x7ff8000012345678
+  .quad 0
+tdat_big:
+  .double 1e300
+
+RVTEST_DATA_END
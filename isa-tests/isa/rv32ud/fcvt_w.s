@@ -0,0 +1,58 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fcvt_w.s
+#-----------------------------------------------------------------------------
+#
+# Test fcvt.w.d and fcvt.wu.d instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_INT_OP_D 2, fcvt.w.d, 0x01, -1, -1.1, rtz
+  TEST_FP_INT_OP_D 3, fcvt.w.d, 0x00, -1, -1.0, rtz
+  TEST_FP_INT_OP_D 4, fcvt.w.d, 0x01, 0, -0.9, rtz
+  TEST_FP_INT_OP_D 5, fcvt.w.d, 0x01, 0, 0.9, rtz
+  TEST_FP_INT_OP_D 6, fcvt.w.d, 0x00, 1, 1.0, rtz
+  TEST_FP_INT_OP_D 7, fcvt.w.d, 0x01, 1, 1.1, rtz
+  TEST_FP_INT_OP_D 8, fcvt.w.d, 0x10, 0x80000000, -3e9, rtz
+  TEST_FP_INT_OP_D 9, fcvt.w.d, 0x10, 0x7fffffff, 3e9, rtz
+
+  TEST_FP_INT_OP_D 12, fcvt.wu.d, 0x10, 0, -3.0, rtz
+  TEST_FP_INT_OP_D 13, fcvt.wu.d, 0x10, 0, -1.0, rtz
+  TEST_FP_INT_OP_D 14, fcvt.wu.d, 0x01, 0, -0.9, rtz
+  TEST_FP_INT_OP_D 15, fcvt.wu.d, 0x01, 0, 0.9, rtz
+  TEST_FP_INT_OP_D 16, fcvt.wu.d, 0x00, 1, 1.0, rtz
+  TEST_FP_INT_OP_D 17, fcvt.wu.d, 0x01, 1, 1.1, rtz
+  TEST_FP_INT_OP_D 18, fcvt.wu.d, 0x10, 0, -3e9, rtz
+  TEST_FP_INT_OP_D 19, fcvt.wu.d, 0x00, 3000000000, 3e9, rtz
+
+  # The other rounding modes
+  TEST_FP_INT_OP_D 20, fcvt.w.d, 0x01, 2, 2.5, rne
+  TEST_FP_INT_OP_D 21, fcvt.w.d, 0x01, -3, -2.5, rdn
+  TEST_FP_INT_OP_D 22, fcvt.w.d, 0x01, -2, -2.5, rup
+  TEST_FP_INT_OP_D 23, fcvt.w.d, 0x01, 3, 2.5, rmm
+
+  # NaN and infinities
+  TEST_FP_INT_OP_D 42, fcvt.w.d, 0x10, 0x7fffffff, qNaN, rtz
+  TEST_FP_INT_OP_D 43, fcvt.w.d, 0x10, 0x80000000, -inf, rtz
+  TEST_FP_INT_OP_D 44, fcvt.w.d, 0x10, 0x7fffffff, inf, rtz
+  TEST_FP_INT_OP_D 52, fcvt.wu.d, 0x10, 0xffffffff, qNaN, rtz
+  TEST_FP_INT_OP_D 53, fcvt.wu.d, 0x10, 0, -inf, rtz
+  TEST_FP_INT_OP_D 54, fcvt.wu.d, 0x10, 0xffffffff, inf, rtz
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
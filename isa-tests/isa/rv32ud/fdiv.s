@@ -0,0 +1,39 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fdiv.s
+#-----------------------------------------------------------------------------
+#
+# Test fdiv.d and fsqrt.d instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP2_D 2, fdiv.d, 1, 1.1557273520668288, 3.14159265, 2.71828182
+  TEST_FP_OP2_D 3, fdiv.d, 1, -0.9991093838555584, -1234, 1235.1
+  TEST_FP_OP2_D 4, fdiv.d, 0, 3.14159265, 3.14159265, 1.0
+
+  TEST_FP_OP1_D 5, fsqrt.d, 1, 1.7724538498928541, 3.14159265
+  TEST_FP_OP1_D 6, fsqrt.d, 0, 100, 10000
+  TEST_FP_OP1_D 7, fsqrt.d, 0x10, qNaN, -1.0
+  TEST_FP_OP1_D 8, fsqrt.d, 1, 13.076696830622021, 171.0
+
+  # Division by zero
+  TEST_FP_OP2_D 9, fdiv.d, 0x08, -inf, -1.0, 0.0
+  TEST_FP_OP2_D 10, fdiv.d, 0x10, qNaN, 0.0, 0.0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,45 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fmadd.s
+#-----------------------------------------------------------------------------
+#
+# Test f[n]m{add|sub}.d instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP3_D 2, fmadd.d, 0, 3.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_D 3, fmadd.d, 1, 1236.1999999999998, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_D 4, fmadd.d, 0, -12.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_D 5, fnmadd.d, 0, -3.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_D 6, fnmadd.d, 1, -1236.1999999999998, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_D 7, fnmadd.d, 0, 12.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_D 8, fmsub.d, 0, 1.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_D 9, fmsub.d, 1, 1234, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_D 10, fmsub.d, 0, -8.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_D 11, fnmsub.d, 0, -1.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_D 12, fnmsub.d, 1, -1234, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_D 13, fnmsub.d, 0, 8.0, 2.0, -5.0, -2.0
+
+  # inf * 0 is invalid, even when added to a quiet NaN
+  TEST_FP_OP3_D 14, fmadd.d, 0x10, qNaN, inf, 0.0, 1.0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
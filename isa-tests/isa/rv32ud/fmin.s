@@ -0,0 +1,51 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fmin.s
+#-----------------------------------------------------------------------------
+#
+# Test fmin.d and fmax.d instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UD
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP2_D 2, fmin.d, 0, 1.0, 2.5, 1.0
+  TEST_FP_OP2_D 3, fmin.d, 0, -1235.1, -1235.1, 1.1
+  TEST_FP_OP2_D 4, fmin.d, 0, -1235.1, 1.1, -1235.1
+  TEST_FP_OP2_D 5, fmin.d, 0, -1235.1, qNaN, -1235.1
+  TEST_FP_OP2_D 6, fmin.d, 0, 0.00000001, 3.14159265, 0.00000001
+  TEST_FP_OP2_D 7, fmin.d, 0, -2.0, -1.0, -2.0
+
+  TEST_FP_OP2_D 12, fmax.d, 0, 2.5, 2.5, 1.0
+  TEST_FP_OP2_D 13, fmax.d, 0, 1.1, -1235.1, 1.1
+  TEST_FP_OP2_D 14, fmax.d, 0, 1.1, 1.1, -1235.1
+  TEST_FP_OP2_D 15, fmax.d, 0, -1235.1, qNaN, -1235.1
+  TEST_FP_OP2_D 16, fmax.d, 0, 3.14159265, 3.14159265, 0.00000001
+  TEST_FP_OP2_D 17, fmax.d, 0, -1.0, -1.0, -2.0
+
+  # FMAX(sNaN, x) = x
+  TEST_FP_OP2_D 20, fmax.d, 0x10, 1.0, sNaN, 1.0
+  # FMAX(qNaN, qNaN) = canonical NaN
+  TEST_FP_OP2_D 21, fmax.d, 0x00, qNaN, qNaN, qNaN
+
+  # -0.0 < +0.0
+  TEST_FP_OP2_D 30, fmin.d, 0, -0.0, -0.0, 0.0
+  TEST_FP_OP2_D 31, fmin.d, 0, -0.0, 0.0, -0.0
+  TEST_FP_OP2_D 32, fmax.d, 0, 0.0, -0.0, 0.0
+  TEST_FP_OP2_D 33, fmax.d, 0, 0.0, 0.0, -0.0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,39 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fadd.s
+#-----------------------------------------------------------------------------
+#
+# Test fadd.s, fsub.s and fmul.s instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP2_S 2, fadd.s, 0, 3.5, 2.5, 1.0
+  TEST_FP_OP2_S 3, fadd.s, 1, -1234, -1235.1, 1.1
+  TEST_FP_OP2_S 4, fadd.s, 1, 3.14159265, 3.14159265, 0.00000001
+  TEST_FP_OP2_S 5, fsub.s, 0, 1.5, 2.5, 1.0
+  TEST_FP_OP2_S 6, fsub.s, 1, -1234, -1235.1, -1.1
+  TEST_FP_OP2_S 7, fsub.s, 1, 3.14159265, 3.14159265, 0.00000001
+  TEST_FP_OP2_S 8, fmul.s, 0, 2.5, 2.5, 1.0
+  TEST_FP_OP2_S 9, fmul.s, 1, 1358.61, -1235.1, -1.1
+  TEST_FP_OP2_S 10, fmul.s, 1, 3.14159265e-8, 3.14159265, 0.00000001
+
+  # Inf - Inf is invalid and gives the canonical NaN
+  TEST_FP_OP2_S 11, fsub.s, 0x10, qNaNf, inf, inf
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
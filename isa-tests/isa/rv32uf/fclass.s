@@ -0,0 +1,37 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fclass.s
+#-----------------------------------------------------------------------------
+#
+# Test fclass.s instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FCLASS_S 2, 0x001, 0xff800000
+  TEST_FCLASS_S 3, 0x002, 0xbf800000
+  TEST_FCLASS_S 4, 0x004, 0x807fffff
+  TEST_FCLASS_S 5, 0x008, 0x80000000
+  TEST_FCLASS_S 6, 0x010, 0x00000000
+  TEST_FCLASS_S 7, 0x020, 0x007fffff
+  TEST_FCLASS_S 8, 0x040, 0x3f800000
+  TEST_FCLASS_S 9, 0x080, 0x7f800000
+  TEST_FCLASS_S 10, 0x100, 0x7f800001
+  TEST_FCLASS_S 11, 0x200, 0x7fc00000
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,51 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fcmp.s
+#-----------------------------------------------------------------------------
+#
+# Test f{eq|lt|le}.s instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_CMP_OP_S 2, feq.s, 0x00, 1, -1.36, -1.36
+  TEST_FP_CMP_OP_S 3, fle.s, 0x00, 1, -1.36, -1.36
+  TEST_FP_CMP_OP_S 4, flt.s, 0x00, 0, -1.36, -1.36
+
+  TEST_FP_CMP_OP_S 5, feq.s, 0x00, 0, -1.37, -1.36
+  TEST_FP_CMP_OP_S 6, fle.s, 0x00, 1, -1.37, -1.36
+  TEST_FP_CMP_OP_S 7, flt.s, 0x00, 1, -1.37, -1.36
+
+  # Only sNaN should signal invalid for feq.
+  TEST_FP_CMP_OP_S 8, feq.s, 0x00, 0, qNaNf, 0
+  TEST_FP_CMP_OP_S 9, feq.s, 0x00, 0, qNaNf, qNaNf
+  TEST_FP_CMP_OP_S 10, feq.s, 0x10, 0, sNaNf, 0
+
+  # qNaN should signal invalid for fle/flt.
+  TEST_FP_CMP_OP_S 11, flt.s, 0x10, 0, qNaNf, 0
+  TEST_FP_CMP_OP_S 12, flt.s, 0x10, 0, qNaNf, qNaNf
+  TEST_FP_CMP_OP_S 13, flt.s, 0x10, 0, sNaNf, 0
+  TEST_FP_CMP_OP_S 14, fle.s, 0x10, 0, qNaNf, 0
+  TEST_FP_CMP_OP_S 15, fle.s, 0x10, 0, qNaNf, qNaNf
+  TEST_FP_CMP_OP_S 16, fle.s, 0x10, 0, sNaNf, 0
+
+  # -0.0 == +0.0
+  TEST_FP_CMP_OP_S 17, feq.s, 0x00, 1, -0.0, 0.0
+  TEST_FP_CMP_OP_S 18, flt.s, 0x00, 0, -0.0, 0.0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
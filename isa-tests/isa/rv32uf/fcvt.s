@@ -0,0 +1,31 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fcvt.s
+#-----------------------------------------------------------------------------
+#
+# Test fcvt.s.{w|wu} instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_INT_FP_OP_S 2, fcvt.s.w, 0x00, 2.0, 2
+  TEST_INT_FP_OP_S 3, fcvt.s.w, 0x00, -2.0, -2
+  TEST_INT_FP_OP_S 4, fcvt.s.wu, 0x00, 2.0, 2
+  TEST_INT_FP_OP_S 5, fcvt.s.wu, 0x01, 4.2949673e9, -2
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
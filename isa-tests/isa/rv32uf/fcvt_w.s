@@ -0,0 +1,58 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fcvt_w.s
+#-----------------------------------------------------------------------------
+#
+# Test fcvt.w.s and fcvt.wu.s instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_INT_OP_S 2, fcvt.w.s, 0x01, -1, -1.1, rtz
+  TEST_FP_INT_OP_S 3, fcvt.w.s, 0x00, -1, -1.0, rtz
+  TEST_FP_INT_OP_S 4, fcvt.w.s, 0x01, 0, -0.9, rtz
+  TEST_FP_INT_OP_S 5, fcvt.w.s, 0x01, 0, 0.9, rtz
+  TEST_FP_INT_OP_S 6, fcvt.w.s, 0x00, 1, 1.0, rtz
+  TEST_FP_INT_OP_S 7, fcvt.w.s, 0x01, 1, 1.1, rtz
+  TEST_FP_INT_OP_S 8, fcvt.w.s, 0x10, 0x80000000, -3e9, rtz
+  TEST_FP_INT_OP_S 9, fcvt.w.s, 0x10, 0x7fffffff, 3e9, rtz
+
+  TEST_FP_INT_OP_S 12, fcvt.wu.s, 0x10, 0, -3.0, rtz
+  TEST_FP_INT_OP_S 13, fcvt.wu.s, 0x10, 0, -1.0, rtz
+  TEST_FP_INT_OP_S 14, fcvt.wu.s, 0x01, 0, -0.9, rtz
+  TEST_FP_INT_OP_S 15, fcvt.wu.s, 0x01, 0, 0.9, rtz
+  TEST_FP_INT_OP_S 16, fcvt.wu.s, 0x00, 1, 1.0, rtz
+  TEST_FP_INT_OP_S 17, fcvt.wu.s, 0x01, 1, 1.1, rtz
+  TEST_FP_INT_OP_S 18, fcvt.wu.s, 0x10, 0, -3e9, rtz
+  TEST_FP_INT_OP_S 19, fcvt.wu.s, 0x00, 3000000000, 3e9, rtz
+
+  # The other rounding modes
+  TEST_FP_INT_OP_S 20, fcvt.w.s, 0x01, 2, 2.5, rne
+  TEST_FP_INT_OP_S 21, fcvt.w.s, 0x01, -3, -2.5, rdn
+  TEST_FP_INT_OP_S 22, fcvt.w.s, 0x01, -2, -2.5, rup
+  TEST_FP_INT_OP_S 23, fcvt.w.s, 0x01, 3, 2.5, rmm
+
+  # NaN and infinities
+  TEST_FP_INT_OP_S 42, fcvt.w.s, 0x10, 0x7fffffff, qNaNf, rtz
+  TEST_FP_INT_OP_S 43, fcvt.w.s, 0x10, 0x80000000, -inf, rtz
+  TEST_FP_INT_OP_S 44, fcvt.w.s, 0x10, 0x7fffffff, inf, rtz
+  TEST_FP_INT_OP_S 52, fcvt.wu.s, 0x10, 0xffffffff, qNaNf, rtz
+  TEST_FP_INT_OP_S 53, fcvt.wu.s, 0x10, 0, -inf, rtz
+  TEST_FP_INT_OP_S 54, fcvt.wu.s, 0x10, 0xffffffff, inf, rtz
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
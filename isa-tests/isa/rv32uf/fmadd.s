@@ -0,0 +1,45 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fmadd.s
+#-----------------------------------------------------------------------------
+#
+# Test f[n]m{add|sub}.s instructions.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_FP_OP3_S 2, fmadd.s, 0, 3.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_S 3, fmadd.s, 1, 1236.2, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_S 4, fmadd.s, 0, -12.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_S 5, fnmadd.s, 0, -3.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_S 6, fnmadd.s, 1, -1236.2, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_S 7, fnmadd.s, 0, 12.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_S 8, fmsub.s, 0, 1.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_S 9, fmsub.s, 1, 1234, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_S 10, fmsub.s, 0, -8.0, 2.0, -5.0, -2.0
+
+  TEST_FP_OP3_S 11, fnmsub.s, 0, -1.5, 1.0, 2.5, 1.0
+  TEST_FP_OP3_S 12, fnmsub.s, 1, -1234, -1.0, -1235.1, 1.1
+  TEST_FP_OP3_S 13, fnmsub.s, 0, 8.0, 2.0, -5.0, -2.0
+
+  # inf * 0 is invalid, even when added to a quiet NaN
+  TEST_FP_OP3_S 14, fmadd.s, 0x10, qNaNf, inf, 0.0, 1.0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
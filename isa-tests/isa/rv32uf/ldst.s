@@ -0,0 +1,51 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# ldst.s
+#-----------------------------------------------------------------------------
+#
+# This test verifies that flw and fsw work properly.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Load and store tests
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  la a1, tdat
+  flw f1, 4(a1)
+  fsw f1, 20(a1)
+  lw a0, 20(a1)
+  TEST_CHECK a0, 0x40000000
+
+test_3:
+  li gp, 3
+  la a1, tdat
+  flw f1, 0(a1)
+  fsw f1, 24(a1)
+  lw a0, 24(a1)
+  TEST_CHECK a0, 0xbf800000
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+  .word 0xbf800000
+  .word 0x40000000
+  .word 0x40400000
+  .word 0xc0800000
+  .word 0xdeadbeef
+  .word 0xcafebabe
+  .word 0xabad1dea
+  .word 0x1337d00d
+
+RVTEST_DATA_END
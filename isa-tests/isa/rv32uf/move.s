@@ -0,0 +1,94 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# move.s
+#-----------------------------------------------------------------------------
+#
+# This test verifies that fmv.s.x, fmv.x.s, and fsgnj[x|n].s work properly.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32UF
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # fcsr tests
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  csrwi fcsr, 1
+  li a0, 0x1234
+  fscsr a1, a0
+  TEST_CHECK a1, 1
+
+test_3:
+  li gp, 3
+  frcsr a0
+  TEST_CHECK a0, 0x34
+
+test_4:
+  li gp, 4
+  frflags a0
+  TEST_CHECK a0, 0x14
+
+test_5:
+  li gp, 5
+  csrrwi a0, frm, 2
+  TEST_CHECK a0, 0x01
+
+test_6:
+  li gp, 6
+  frcsr a0
+  TEST_CHECK a0, 0x54
+
+test_7:
+  li gp, 7
+  csrrci a0, fflags, 4
+  TEST_CHECK a0, 0x14
+
+test_8:
+  li gp, 8
+  frcsr a0
+  TEST_CHECK a0, 0x50
+
+  #-------------------------------------------------------------
+  # Sign injection tests
+  #-------------------------------------------------------------
+
+.macro TEST_FSGNJS testnum, inst, new_sign, rs1_sign, rs2_sign
+test_\testnum:
+  li gp, \testnum
+  li a1, (\rs1_sign << 31) | 0x12345678
+  li a2, -\rs2_sign
+  fmv.w.x f1, a1
+  fmv.w.x f2, a2
+  \inst f0, f1, f2
+  fmv.x.w a0, f0
+  TEST_CHECK a0, 0x12345678 | (-\new_sign << 31)
+.endm
+
+  TEST_FSGNJS 10, fsgnj.s, 0, 0, 0
+  TEST_FSGNJS 11, fsgnj.s, 1, 0, 1
+  TEST_FSGNJS 12, fsgnj.s, 0, 1, 0
+  TEST_FSGNJS 13, fsgnj.s, 1, 1, 1
+
+  TEST_FSGNJS 20, fsgnjn.s, 1, 0, 0
+  TEST_FSGNJS 21, fsgnjn.s, 0, 0, 1
+  TEST_FSGNJS 22, fsgnjn.s, 1, 1, 0
+  TEST_FSGNJS 23, fsgnjn.s, 0, 1, 1
+
+  TEST_FSGNJS 30, fsgnjx.s, 0, 0, 0
+  TEST_FSGNJS 31, fsgnjx.s, 1, 0, 1
+  TEST_FSGNJS 32, fsgnjx.s, 1, 1, 0
+  TEST_FSGNJS 33, fsgnjx.s, 0, 1, 1
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
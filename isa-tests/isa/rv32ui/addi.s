@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# addi.s
+#-----------------------------------------------------------------------------
+#
+# Test addi instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, addi, 0x00000000, 0x00000000, 0
+  TEST_IMM_OP 3, addi, 0x00000002, 0x00000001, 1
+  TEST_IMM_OP 4, addi, 0x0000000a, 0x00000003, 7
+  TEST_IMM_OP 5, addi, 0xfffff800, 0x00000000, -2048
+  TEST_IMM_OP 6, addi, 0x80000000, 0x80000000, 0
+  TEST_IMM_OP 7, addi, 0x7ffff800, 0x80000000, -2048
+  TEST_IMM_OP 8, addi, 0x000007ff, 0x00000000, 2047
+  TEST_IMM_OP 9, addi, 0x7fffffff, 0x7fffffff, 0
+  TEST_IMM_OP 10, addi, 0x800007fe, 0x7fffffff, 2047
+  TEST_IMM_OP 11, addi, 0x800007ff, 0x80000000, 2047
+  TEST_IMM_OP 12, addi, 0x7ffff7ff, 0x7fffffff, -2048
+  TEST_IMM_OP 13, addi, 0xffffffff, 0x00000000, -1
+  TEST_IMM_OP 14, addi, 0x00000000, 0xffffffff, 1
+  TEST_IMM_OP 15, addi, 0xfffffffe, 0xffffffff, -1
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 16, addi, 0x00000018, 0x0000000d, 11
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 17, addi, 0x0000000b, 11
+  TEST_IMM_ZERODEST 18, addi, 0x0000000d, 11
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
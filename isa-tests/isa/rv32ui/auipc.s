@@ -0,0 +1,51 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# auipc.s
+#-----------------------------------------------------------------------------
+#
+# Test auipc instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  .balign 8
+  la a0, 1f + 10000
+  jal a1, 1f
+1:
+  sub a0, a0, a1
+  TEST_CHECK a0, 10000
+
+test_3:
+  li gp, 3
+  .balign 8
+  la a0, 1f - 10000
+  jal a1, 1f
+1:
+  sub a0, a0, a1
+  TEST_CHECK a0, -10000
+
+test_4:
+  li gp, 4
+  auipc a0, 0x80000
+  auipc a1, 0
+  sub a0, a0, a1
+  TEST_CHECK a0, 0x7ffffffc
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
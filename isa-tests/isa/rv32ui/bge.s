@@ -0,0 +1,59 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# bge.s
+#-----------------------------------------------------------------------------
+#
+# Test bge instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Branch tests
+  #-------------------------------------------------------------
+
+  TEST_BR2_OP_TAKEN 2, bge, 0x00000000, 0x00000000
+  TEST_BR2_OP_TAKEN 3, bge, 0x00000001, 0x00000001
+  TEST_BR2_OP_TAKEN 4, bge, 0xffffffff, 0xffffffff
+  TEST_BR2_OP_NOTTAKEN 5, bge, 0x00000000, 0x00000001
+  TEST_BR2_OP_TAKEN 6, bge, 0x00000001, 0x00000000
+  TEST_BR2_OP_NOTTAKEN 7, bge, 0xffffffff, 0x00000001
+  TEST_BR2_OP_TAKEN 8, bge, 0x00000001, 0xffffffff
+  TEST_BR2_OP_TAKEN 9, bge, 0xffffffff, 0xfffffffe
+  TEST_BR2_OP_NOTTAKEN 10, bge, 0xfffffffe, 0xffffffff
+  TEST_BR2_OP_TAKEN 11, bge, 0x7fffffff, 0x80000000
+  TEST_BR2_OP_NOTTAKEN 12, bge, 0x80000000, 0x7fffffff
+  TEST_BR2_OP_NOTTAKEN 13, bge, 0xffffffff, 0x00000000
+  TEST_BR2_OP_TAKEN 14, bge, 0x00000000, 0xffffffff
+
+  #-------------------------------------------------------------
+  # Test delay slot instructions not executed nor bypassed
+  #-------------------------------------------------------------
+
+test_15:
+  li gp, 15
+  li x1, 1
+  li x5, 0x00000000
+  li x6, 0x00000000
+  bge x5, x6, 1f
+  addi x1, x1, 1
+  addi x1, x1, 1
+  addi x1, x1, 1
+  addi x1, x1, 1
+1:
+  addi x1, x1, 1
+  addi x1, x1, 1
+  TEST_CHECK x1, 3
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,69 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# fence_i.s
+#-----------------------------------------------------------------------------
+#
+# Test fence_i instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  # Overwrite an instruction and make sure the new one runs
+test_2:
+  li gp, 2
+  li a3, 111
+  la a0, patch
+  lw a1, insn
+  sw a1, 0(a0)
+  fence.i
+patch:
+  addi a3, a3, 1
+  TEST_CHECK a3, 333
+
+  # Patch a function, call it, then patch it again and call it again
+test_3:
+  li gp, 3
+  la a0, func
+  lw a1, insn2
+  sw a1, 0(a0)
+  fence.i
+  li a3, 555
+  jal ra, func
+  TEST_CHECK a3, 1000
+
+test_4:
+  li gp, 4
+  lw a1, insn
+  sw a1, 0(a0)
+  fence.i
+  li a3, 111
+  jal ra, func
+  TEST_CHECK a3, 333
+  j 1f
+
+func:
+  addi a3, a3, 1
+  ret
+1:
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+  .balign 4
+insn:
+  addi a3, a3, 222
+insn2:
+  addi a3, a3, 445
+
+RVTEST_DATA_END
@@ -0,0 +1,74 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# jal.s
+#-----------------------------------------------------------------------------
+#
+# Test jal instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Test 2: Basic test
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  jal x4, target_2
+linkaddr_2:
+  nop
+  nop
+  j fail
+
+target_2:
+  la x2, linkaddr_2
+  bne x2, x4, fail
+
+  #-------------------------------------------------------------
+  # Test that the instructions after the jump are not executed
+  #-------------------------------------------------------------
+
+test_3:
+  li gp, 3
+  li x5, 1
+  jal ra, 1f
+linkaddr_3:
+  addi x5, x5, 1
+  addi x5, x5, 1
+  addi x5, x5, 1
+  addi x5, x5, 1
+1:
+  addi x5, x5, 1
+  addi x5, x5, 1
+  TEST_CHECK x5, 3
+  la x2, linkaddr_3
+  bne ra, x2, fail
+
+  #-------------------------------------------------------------
+  # Test jumping backwards
+  #-------------------------------------------------------------
+
+test_4:
+  li gp, 4
+  li x5, 0
+  j 2f
+1:
+  addi x5, x5, 1
+  j 3f
+2:
+  jal x0, 1b
+3:
+  TEST_CHECK x5, 1
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
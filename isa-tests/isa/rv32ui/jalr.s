@@ -0,0 +1,88 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# jalr.s
+#-----------------------------------------------------------------------------
+#
+# Test jalr instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Test 2: Basic test
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  li t0, 0
+  la t1, target_2
+  jalr t0, t1, 0
+linkaddr_2:
+  j fail
+
+target_2:
+  la t1, linkaddr_2
+  bne t0, t1, fail
+
+  #-------------------------------------------------------------
+  # Test 3: Check r0 target and that r31 is not modified
+  #-------------------------------------------------------------
+
+test_3:
+  li gp, 3
+  li t0, 0
+  la t1, target_3
+  jalr x0, t1, 0
+  j fail
+target_3:
+  bne t0, x0, fail
+
+  #-------------------------------------------------------------
+  # Test 4: rs1 = rd, the link is written after the target is read
+  #-------------------------------------------------------------
+
+test_4:
+  li gp, 4
+  la t0, target_4
+  jalr t0, t0, 0
+linkaddr_4:
+  j fail
+target_4:
+  la t1, linkaddr_4
+  bne t0, t1, fail
+
+  #-------------------------------------------------------------
+  # Test 5: negative offset
+  #-------------------------------------------------------------
+
+test_5:
+  li gp, 5
+  la t1, target_5
+  addi t1, t1, 16
+  jalr ra, t1, -16
+  j fail
+target_5:
+
+  #-------------------------------------------------------------
+  # Test 6: the lowest bit of the target is ignored
+  #-------------------------------------------------------------
+
+test_6:
+  li gp, 6
+  la t1, target_6
+  jalr ra, t1, 1
+  j fail
+target_6:
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,76 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lb.s
+#-----------------------------------------------------------------------------
+#
+# Test lb instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, lb, 0xffffffff, 0, tdat
+  TEST_LD_OP 3, lb, 0x00000000, 1, tdat
+  TEST_LD_OP 4, lb, 0xfffffff0, 2, tdat
+  TEST_LD_OP 5, lb, 0x0000000f, 3, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, lb, 0xffffffff, -3, tdat4
+  TEST_LD_OP 7, lb, 0x00000000, -2, tdat4
+  TEST_LD_OP 8, lb, 0xfffffff0, -1, tdat4
+  TEST_LD_OP 9, lb, 0x0000000f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  lb x5, 32(x1)
+  TEST_CHECK x5, 0xffffffff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  lb x5, 4(x1)
+  TEST_CHECK x5, 0x00000000
+
+  #-------------------------------------------------------------
+  # Test write-after-write hazard
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x3, tdat
+  lb x2, 0(x3)
+  li x2, 2
+  TEST_CHECK x2, 2
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .byte 0xff
+tdat2:  .byte 0x0
+tdat3:  .byte 0xf0
+tdat4:  .byte 0xf
+
+RVTEST_DATA_END
@@ -0,0 +1,76 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lbu.s
+#-----------------------------------------------------------------------------
+#
+# Test lbu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, lbu, 0x000000ff, 0, tdat
+  TEST_LD_OP 3, lbu, 0x00000000, 1, tdat
+  TEST_LD_OP 4, lbu, 0x000000f0, 2, tdat
+  TEST_LD_OP 5, lbu, 0x0000000f, 3, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, lbu, 0x000000ff, -3, tdat4
+  TEST_LD_OP 7, lbu, 0x00000000, -2, tdat4
+  TEST_LD_OP 8, lbu, 0x000000f0, -1, tdat4
+  TEST_LD_OP 9, lbu, 0x0000000f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  lbu x5, 32(x1)
+  TEST_CHECK x5, 0x000000ff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  lbu x5, 4(x1)
+  TEST_CHECK x5, 0x00000000
+
+  #-------------------------------------------------------------
+  # Test write-after-write hazard
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x3, tdat
+  lbu x2, 0(x3)
+  li x2, 2
+  TEST_CHECK x2, 2
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .byte 0xff
+tdat2:  .byte 0x0
+tdat3:  .byte 0xf0
+tdat4:  .byte 0xf
+
+RVTEST_DATA_END
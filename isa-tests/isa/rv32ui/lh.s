@@ -0,0 +1,76 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lh.s
+#-----------------------------------------------------------------------------
+#
+# Test lh instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, lh, 0x000000ff, 0, tdat
+  TEST_LD_OP 3, lh, 0xffffff00, 2, tdat
+  TEST_LD_OP 4, lh, 0x00000ff0, 4, tdat
+  TEST_LD_OP 5, lh, 0xfffff00f, 6, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, lh, 0x000000ff, -6, tdat4
+  TEST_LD_OP 7, lh, 0xffffff00, -4, tdat4
+  TEST_LD_OP 8, lh, 0x00000ff0, -2, tdat4
+  TEST_LD_OP 9, lh, 0xfffff00f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  lh x5, 32(x1)
+  TEST_CHECK x5, 0x000000ff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  lh x5, 5(x1)
+  TEST_CHECK x5, 0xffffff00
+
+  #-------------------------------------------------------------
+  # Test write-after-write hazard
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x3, tdat
+  lh x2, 0(x3)
+  li x2, 2
+  TEST_CHECK x2, 2
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .half 0xff
+tdat2:  .half 0xff00
+tdat3:  .half 0xff0
+tdat4:  .half 0xf00f
+
+RVTEST_DATA_END
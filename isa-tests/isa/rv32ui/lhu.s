@@ -0,0 +1,76 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lhu.s
+#-----------------------------------------------------------------------------
+#
+# Test lhu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, lhu, 0x000000ff, 0, tdat
+  TEST_LD_OP 3, lhu, 0x0000ff00, 2, tdat
+  TEST_LD_OP 4, lhu, 0x00000ff0, 4, tdat
+  TEST_LD_OP 5, lhu, 0x0000f00f, 6, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, lhu, 0x000000ff, -6, tdat4
+  TEST_LD_OP 7, lhu, 0x0000ff00, -4, tdat4
+  TEST_LD_OP 8, lhu, 0x00000ff0, -2, tdat4
+  TEST_LD_OP 9, lhu, 0x0000f00f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  lhu x5, 32(x1)
+  TEST_CHECK x5, 0x000000ff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  lhu x5, 5(x1)
+  TEST_CHECK x5, 0x0000ff00
+
+  #-------------------------------------------------------------
+  # Test write-after-write hazard
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x3, tdat
+  lhu x2, 0(x3)
+  li x2, 2
+  TEST_CHECK x2, 2
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .half 0xff
+tdat2:  .half 0xff00
+tdat3:  .half 0xff0
+tdat4:  .half 0xf00f
+
+RVTEST_DATA_END
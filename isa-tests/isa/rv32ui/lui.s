@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lui.s
+#-----------------------------------------------------------------------------
+#
+# Test lui instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+test_2:
+  li gp, 2
+  lui x1, 0x00000
+  TEST_CHECK x1, 0x00000000
+
+test_3:
+  li gp, 3
+  lui x1, 0xfffff
+  sra x1, x1, 1
+  TEST_CHECK x1, 0xfffff800
+
+test_4:
+  li gp, 4
+  lui x1, 0x7ffff
+  sra x1, x1, 20
+  TEST_CHECK x1, 0x000007ff
+
+test_5:
+  li gp, 5
+  lui x1, 0x80000
+  sra x1, x1, 20
+  TEST_CHECK x1, 0xfffff800
+
+test_6:
+  li gp, 6
+  lui x0, 0x80000
+  TEST_CHECK x0, 0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
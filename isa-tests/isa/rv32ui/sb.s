@@ -0,0 +1,91 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sb.s
+#-----------------------------------------------------------------------------
+#
+# Test sb instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 2, lb, sb, 0xffffffaa, 0, tdat
+  TEST_ST_OP 3, lb, sb, 0x00000000, 1, tdat
+  TEST_ST_OP 4, lb, sb, 0xffffffa0, 2, tdat
+  TEST_ST_OP 5, lb, sb, 0x0000000a, 3, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 6, lb, sb, 0x00000078, -3, tdat8
+  TEST_ST_OP 7, lb, sb, 0xffffff98, -2, tdat8
+  TEST_ST_OP 8, lb, sb, 0x00000010, -1, tdat8
+  TEST_ST_OP 9, lb, sb, 0x0000000f, 0, tdat8
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+test_10:
+  li gp, 10
+  la x1, tdat9
+  li x2, 0x12345678
+  addi x4, x1, -32
+  sb x2, 32(x4)
+  lb x5, 0(x1)
+  TEST_CHECK x5, 0x00000078
+
+test_11:
+  li gp, 11
+  la x1, tdat9
+  li x2, 0x58213098
+  addi x1, x1, -3
+  sb x2, 4(x1)
+  la x4, tdat10
+  lb x5, 0(x4)
+  TEST_CHECK x5, 0xffffff98
+
+  #-------------------------------------------------------------
+  # Neighbouring bytes are left alone
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x1, tdat11
+  li x2, -1
+  sw x2, 0(x1)
+  li x2, 0
+  sb x2, 0(x1)
+  lw x5, 0(x1)
+  TEST_CHECK x5, 0xffffff00
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .byte 0xef
+tdat2:  .byte 0xef
+tdat3:  .byte 0xef
+tdat4:  .byte 0xef
+tdat5:  .byte 0xef
+tdat6:  .byte 0xef
+tdat7:  .byte 0xef
+tdat8:  .byte 0xef
+tdat9:  .byte 0xef
+tdat10:  .byte 0xef
+  # Everything shares the code section, keep the data word sized
+  .zero 2
+tdat11: .word 0
+
+RVTEST_DATA_END
@@ -0,0 +1,90 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sh.s
+#-----------------------------------------------------------------------------
+#
+# Test sh instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 2, lh, sh, 0x000000aa, 0, tdat
+  TEST_ST_OP 3, lh, sh, 0xffffaa00, 2, tdat
+  TEST_ST_OP 4, lh, sh, 0x00000aa0, 4, tdat
+  TEST_ST_OP 5, lh, sh, 0xffffa00a, 6, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 6, lh, sh, 0x00005678, -6, tdat8
+  TEST_ST_OP 7, lh, sh, 0x00003098, -4, tdat8
+  TEST_ST_OP 8, lh, sh, 0x00000010, -2, tdat8
+  TEST_ST_OP 9, lh, sh, 0x00000f0f, 0, tdat8
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+test_10:
+  li gp, 10
+  la x1, tdat9
+  li x2, 0x12345678
+  addi x4, x1, -32
+  sh x2, 32(x4)
+  lh x5, 0(x1)
+  TEST_CHECK x5, 0x00005678
+
+test_11:
+  li gp, 11
+  la x1, tdat9
+  li x2, 0x58213098
+  addi x1, x1, -3
+  sh x2, 5(x1)
+  la x4, tdat10
+  lh x5, 0(x4)
+  TEST_CHECK x5, 0x00003098
+
+  #-------------------------------------------------------------
+  # Neighbouring bytes are left alone
+  #-------------------------------------------------------------
+
+test_12:
+  li gp, 12
+  la x1, tdat11
+  li x2, -1
+  sw x2, 0(x1)
+  li x2, 0
+  sh x2, 0(x1)
+  lw x5, 0(x1)
+  TEST_CHECK x5, 0xffff0000
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .half 0xbeef
+tdat2:  .half 0xbeef
+tdat3:  .half 0xbeef
+tdat4:  .half 0xbeef
+tdat5:  .half 0xbeef
+tdat6:  .half 0xbeef
+tdat7:  .half 0xbeef
+tdat8:  .half 0xbeef
+tdat9:  .half 0xbeef
+tdat10:  .half 0xbeef
+  .balign 4
+tdat11: .word 0
+
+RVTEST_DATA_END
@@ -0,0 +1,24 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# simple.s
+#-----------------------------------------------------------------------------
+#
+# This is the most basic self checking test. If your simulator does not
+# pass this, then there is little chance that it will pass any of the
+# more complicated self checking tests.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+RVTEST_PASS
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
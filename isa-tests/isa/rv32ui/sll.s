@@ -0,0 +1,68 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sll.s
+#-----------------------------------------------------------------------------
+#
+# Test sll instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sll, 0x00000001, 0x00000001, 0x00000000
+  TEST_RR_OP 3, sll, 0x00000002, 0x00000001, 0x00000001
+  TEST_RR_OP 4, sll, 0x00000080, 0x00000001, 0x00000007
+  TEST_RR_OP 5, sll, 0x00004000, 0x00000001, 0x0000000e
+  TEST_RR_OP 6, sll, 0x80000000, 0x00000001, 0x0000001f
+  TEST_RR_OP 7, sll, 0xffffffff, 0xffffffff, 0x00000000
+  TEST_RR_OP 8, sll, 0xfffffffe, 0xffffffff, 0x00000001
+  TEST_RR_OP 9, sll, 0xffffff80, 0xffffffff, 0x00000007
+  TEST_RR_OP 10, sll, 0xffffc000, 0xffffffff, 0x0000000e
+  TEST_RR_OP 11, sll, 0x80000000, 0xffffffff, 0x0000001f
+  TEST_RR_OP 12, sll, 0x21212121, 0x21212121, 0x00000000
+  TEST_RR_OP 13, sll, 0x42424242, 0x21212121, 0x00000001
+  TEST_RR_OP 14, sll, 0x90909080, 0x21212121, 0x00000007
+  TEST_RR_OP 15, sll, 0x48484000, 0x21212121, 0x0000000e
+  TEST_RR_OP 16, sll, 0x80000000, 0x21212121, 0x0000001f
+  TEST_RR_OP 17, sll, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 18, sll, 0x00000000, 0x80000000, 0x00000001
+  TEST_RR_OP 19, sll, 0x00000000, 0x80000000, 0x00000007
+  TEST_RR_OP 20, sll, 0x00000000, 0x80000000, 0x0000000e
+  TEST_RR_OP 21, sll, 0x00000000, 0x80000000, 0x0000001f
+  TEST_RR_OP 22, sll, 0x21212121, 0x21212121, 0xffffffc0
+  TEST_RR_OP 23, sll, 0x42424242, 0x21212121, 0xffffffc1
+  TEST_RR_OP 24, sll, 0x90909080, 0x21212121, 0xffffffc7
+  TEST_RR_OP 25, sll, 0x48484000, 0x21212121, 0xffffffce
+  TEST_RR_OP 26, sll, 0x80000000, 0x21212121, 0xffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 27, sll, 0x00000080, 0x00000001, 0x00000007
+  TEST_RR_SRC2_EQ_DEST 28, sll, 0x00000080, 0x00000001, 0x00000007
+  TEST_RR_SRC12_EQ_DEST 29, sll, 0x00000002, 0x00000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 30, sll, 0x00000000, 0x00000007
+  TEST_RR_ZEROSRC2 31, sll, 0x00000001, 0x00000001
+  TEST_RR_ZERODEST 32, sll, 0x00000001, 0x00000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
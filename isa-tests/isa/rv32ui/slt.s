@@ -0,0 +1,60 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# slt.s
+#-----------------------------------------------------------------------------
+#
+# Test slt instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, slt, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, slt, 0x00000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, slt, 0x00000001, 0x00000003, 0x00000007
+  TEST_RR_OP 5, slt, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 6, slt, 0x00000001, 0x80000000, 0x00000000
+  TEST_RR_OP 7, slt, 0x00000001, 0x80000000, 0xffff8000
+  TEST_RR_OP 8, slt, 0x00000001, 0x00000000, 0x00007fff
+  TEST_RR_OP 9, slt, 0x00000000, 0x7fffffff, 0x00000000
+  TEST_RR_OP 10, slt, 0x00000000, 0x7fffffff, 0x00007fff
+  TEST_RR_OP 11, slt, 0x00000001, 0x80000000, 0x00007fff
+  TEST_RR_OP 12, slt, 0x00000000, 0x7fffffff, 0xffff8000
+  TEST_RR_OP 13, slt, 0x00000000, 0x00000000, 0xffffffff
+  TEST_RR_OP 14, slt, 0x00000001, 0xffffffff, 0x00000001
+  TEST_RR_OP 15, slt, 0x00000000, 0xffffffff, 0xffffffff
+  TEST_RR_OP 16, slt, 0x00000001, 0x00000001, 0x7fffffff
+  TEST_RR_OP 17, slt, 0x00000000, 0x00000007, 0x00000003
+  TEST_RR_OP 18, slt, 0x00000001, 0xffffffff, 0x00000000
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 19, slt, 0x00000000, 0x0000000d, 0x0000000b
+  TEST_RR_SRC2_EQ_DEST 20, slt, 0x00000000, 0x0000000d, 0x0000000b
+  TEST_RR_SRC12_EQ_DEST 21, slt, 0x00000000, 0x0000000d
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 22, slt, 0x00000001, 0x0000000b
+  TEST_RR_ZEROSRC2 23, slt, 0x00000000, 0x0000000d
+  TEST_RR_ZERODEST 24, slt, 0x0000000d, 0x0000000b
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,56 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sltiu.s
+#-----------------------------------------------------------------------------
+#
+# Test sltiu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, sltiu, 0x00000000, 0x00000000, 0
+  TEST_IMM_OP 3, sltiu, 0x00000000, 0x00000001, 1
+  TEST_IMM_OP 4, sltiu, 0x00000001, 0x00000003, 7
+  TEST_IMM_OP 5, sltiu, 0x00000001, 0x00000000, -2048
+  TEST_IMM_OP 6, sltiu, 0x00000000, 0x80000000, 0
+  TEST_IMM_OP 7, sltiu, 0x00000001, 0x80000000, -2048
+  TEST_IMM_OP 8, sltiu, 0x00000001, 0x00000000, 2047
+  TEST_IMM_OP 9, sltiu, 0x00000000, 0x7fffffff, 0
+  TEST_IMM_OP 10, sltiu, 0x00000000, 0x7fffffff, 2047
+  TEST_IMM_OP 11, sltiu, 0x00000000, 0x80000000, 2047
+  TEST_IMM_OP 12, sltiu, 0x00000001, 0x7fffffff, -2048
+  TEST_IMM_OP 13, sltiu, 0x00000001, 0x00000000, -1
+  TEST_IMM_OP 14, sltiu, 0x00000000, 0xffffffff, 1
+  TEST_IMM_OP 15, sltiu, 0x00000000, 0xffffffff, -1
+  TEST_IMM_OP 16, sltiu, 0x00000000, 0x00000007, 3
+  TEST_IMM_OP 17, sltiu, 0x00000000, 0xffffffff, 0
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 18, sltiu, 0x00000000, 0x0000000d, 11
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 19, sltiu, 0x00000001, 11
+  TEST_IMM_ZERODEST 20, sltiu, 0x0000000d, 11
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
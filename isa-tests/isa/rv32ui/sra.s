@@ -0,0 +1,68 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sra.s
+#-----------------------------------------------------------------------------
+#
+# Test sra instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sra, 0x00000001, 0x00000001, 0x00000000
+  TEST_RR_OP 3, sra, 0x00000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, sra, 0x00000000, 0x00000001, 0x00000007
+  TEST_RR_OP 5, sra, 0x00000000, 0x00000001, 0x0000000e
+  TEST_RR_OP 6, sra, 0x00000000, 0x00000001, 0x0000001f
+  TEST_RR_OP 7, sra, 0xffffffff, 0xffffffff, 0x00000000
+  TEST_RR_OP 8, sra, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 9, sra, 0xffffffff, 0xffffffff, 0x00000007
+  TEST_RR_OP 10, sra, 0xffffffff, 0xffffffff, 0x0000000e
+  TEST_RR_OP 11, sra, 0xffffffff, 0xffffffff, 0x0000001f
+  TEST_RR_OP 12, sra, 0x21212121, 0x21212121, 0x00000000
+  TEST_RR_OP 13, sra, 0x10909090, 0x21212121, 0x00000001
+  TEST_RR_OP 14, sra, 0x00424242, 0x21212121, 0x00000007
+  TEST_RR_OP 15, sra, 0x00008484, 0x21212121, 0x0000000e
+  TEST_RR_OP 16, sra, 0x00000000, 0x21212121, 0x0000001f
+  TEST_RR_OP 17, sra, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 18, sra, 0xc0000000, 0x80000000, 0x00000001
+  TEST_RR_OP 19, sra, 0xff000000, 0x80000000, 0x00000007
+  TEST_RR_OP 20, sra, 0xfffe0000, 0x80000000, 0x0000000e
+  TEST_RR_OP 21, sra, 0xffffffff, 0x80000000, 0x0000001f
+  TEST_RR_OP 22, sra, 0x21212121, 0x21212121, 0xffffffc0
+  TEST_RR_OP 23, sra, 0x10909090, 0x21212121, 0xffffffc1
+  TEST_RR_OP 24, sra, 0x00424242, 0x21212121, 0xffffffc7
+  TEST_RR_OP 25, sra, 0x00008484, 0x21212121, 0xffffffce
+  TEST_RR_OP 26, sra, 0x00000000, 0x21212121, 0xffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 27, sra, 0x00000000, 0x00000001, 0x00000007
+  TEST_RR_SRC2_EQ_DEST 28, sra, 0x00000000, 0x00000001, 0x00000007
+  TEST_RR_SRC12_EQ_DEST 29, sra, 0x00000000, 0x00000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 30, sra, 0x00000000, 0x00000007
+  TEST_RR_ZEROSRC2 31, sra, 0x00000001, 0x00000001
+  TEST_RR_ZERODEST 32, sra, 0x00000001, 0x00000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,76 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sw.s
+#-----------------------------------------------------------------------------
+#
+# Test sw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 2, lw, sw, 0x00aa00aa, 0, tdat
+  TEST_ST_OP 3, lw, sw, 0xaa00aa00, 4, tdat
+  TEST_ST_OP 4, lw, sw, 0x0aa00aa0, 8, tdat
+  TEST_ST_OP 5, lw, sw, 0xa00aa00a, 12, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 6, lw, sw, 0x12345678, -12, tdat8
+  TEST_ST_OP 7, lw, sw, 0x58213098, -8, tdat8
+  TEST_ST_OP 8, lw, sw, 0x00000010, -4, tdat8
+  TEST_ST_OP 9, lw, sw, 0x0f0f0f0f, 0, tdat8
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+test_10:
+  li gp, 10
+  la x1, tdat9
+  li x2, 0x12345678
+  addi x4, x1, -32
+  sw x2, 32(x4)
+  lw x5, 0(x1)
+  TEST_CHECK x5, 0x12345678
+
+test_11:
+  li gp, 11
+  la x1, tdat9
+  li x2, 0x58213098
+  addi x1, x1, -3
+  sw x2, 7(x1)
+  la x4, tdat10
+  lw x5, 0(x4)
+  TEST_CHECK x5, 0x58213098
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .word 0xdeadbeef
+tdat2:  .word 0xdeadbeef
+tdat3:  .word 0xdeadbeef
+tdat4:  .word 0xdeadbeef
+tdat5:  .word 0xdeadbeef
+tdat6:  .word 0xdeadbeef
+tdat7:  .word 0xdeadbeef
+tdat8:  .word 0xdeadbeef
+tdat9:  .word 0xdeadbeef
+tdat10:  .word 0xdeadbeef
+  .balign 4
+tdat11: .word 0
+
+RVTEST_DATA_END
@@ -0,0 +1,45 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# xori.s
+#-----------------------------------------------------------------------------
+#
+# Test xori instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, xori, 0xff00f00f, 0x00ff0f00, -241
+  TEST_IMM_OP 3, xori, 0x0ff00f00, 0x0ff00ff0, 240
+  TEST_IMM_OP 4, xori, 0x00ff0ff0, 0x00ff08ff, 1807
+  TEST_IMM_OP 5, xori, 0xf00ff0ff, 0xf00ff00f, 240
+  TEST_IMM_OP 6, xori, 0xff00f80f, 0xff00ff00, 1807
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 7, xori, 0xff00fff0, 0xff00ff00, 240
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 8, xori, 0x000000f0, 240
+  TEST_IMM_ZERODEST 9, xori, 0xff00ff00, 240
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# div.s
+#-----------------------------------------------------------------------------
+#
+# Test div instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, div, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_OP 3, div, 0xfffffffd, 0xffffffec, 0x00000006
+  TEST_RR_OP 4, div, 0xfffffffd, 0x00000014, 0xfffffffa
+  TEST_RR_OP 5, div, 0x00000003, 0xffffffec, 0xfffffffa
+  TEST_RR_OP 6, div, 0x80000000, 0x80000000, 0x00000001
+  TEST_RR_OP 7, div, 0x80000000, 0x80000000, 0xffffffff
+  TEST_RR_OP 8, div, 0xffffffff, 0x80000000, 0x00000000
+  TEST_RR_OP 9, div, 0xffffffff, 0x00000001, 0x00000000
+  TEST_RR_OP 10, div, 0xffffffff, 0x00000000, 0x00000000
+  TEST_RR_OP 11, div, 0x00000000, 0x00000007, 0x80000000
+  TEST_RR_OP 12, div, 0x00000000, 0xffffffff, 0x00000002
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 13, div, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_SRC2_EQ_DEST 14, div, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_SRC12_EQ_DEST 15, div, 0x00000001, 0x00000014
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 16, div, 0x00000000, 0x00000006
+  TEST_RR_ZEROSRC2 17, div, 0xffffffff, 0x00000014
+  TEST_RR_ZERODEST 18, div, 0x00000014, 0x00000006
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# divu.s
+#-----------------------------------------------------------------------------
+#
+# Test divu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, divu, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_OP 3, divu, 0x2aaaaaa7, 0xffffffec, 0x00000006
+  TEST_RR_OP 4, divu, 0x00000000, 0x00000014, 0xfffffffa
+  TEST_RR_OP 5, divu, 0x00000000, 0xffffffec, 0xfffffffa
+  TEST_RR_OP 6, divu, 0x80000000, 0x80000000, 0x00000001
+  TEST_RR_OP 7, divu, 0x00000000, 0x80000000, 0xffffffff
+  TEST_RR_OP 8, divu, 0xffffffff, 0x80000000, 0x00000000
+  TEST_RR_OP 9, divu, 0xffffffff, 0x00000001, 0x00000000
+  TEST_RR_OP 10, divu, 0xffffffff, 0x00000000, 0x00000000
+  TEST_RR_OP 11, divu, 0x00000000, 0x00000007, 0x80000000
+  TEST_RR_OP 12, divu, 0x7fffffff, 0xffffffff, 0x00000002
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 13, divu, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_SRC2_EQ_DEST 14, divu, 0x00000003, 0x00000014, 0x00000006
+  TEST_RR_SRC12_EQ_DEST 15, divu, 0x00000001, 0x00000014
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 16, divu, 0x00000000, 0x00000006
+  TEST_RR_ZEROSRC2 17, divu, 0xffffffff, 0x00000014
+  TEST_RR_ZERODEST 18, divu, 0x00000014, 0x00000006
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# mul.s
+#-----------------------------------------------------------------------------
+#
+# Test mul instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, mul, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, mul, 0x00000001, 0x00000001, 0x00000001
+  TEST_RR_OP 4, mul, 0x00000015, 0x00000003, 0x00000007
+  TEST_RR_OP 5, mul, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 6, mul, 0x00000000, 0x80000000, 0x00000000
+  TEST_RR_OP 7, mul, 0x00000000, 0x80000000, 0xffff8000
+  TEST_RR_OP 8, mul, 0x0000ff7f, 0xaaaaaaab, 0x0002fe7d
+  TEST_RR_OP 9, mul, 0x0000ff7f, 0x0002fe7d, 0xaaaaaaab
+  TEST_RR_OP 10, mul, 0x00000000, 0xff000000, 0xff000000
+  TEST_RR_OP 11, mul, 0x00000001, 0xffffffff, 0xffffffff
+  TEST_RR_OP 12, mul, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 13, mul, 0xffffffff, 0x00000001, 0xffffffff
+  TEST_RR_OP 14, mul, 0x00000000, 0x80000000, 0x80000000
+  TEST_RR_OP 15, mul, 0x80000000, 0x7fffffff, 0x80000000
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, mul, 0x0000008f, 0x0000000d, 0x0000000b
+  TEST_RR_SRC2_EQ_DEST 17, mul, 0x0000008f, 0x0000000d, 0x0000000b
+  TEST_RR_SRC12_EQ_DEST 18, mul, 0x000000a9, 0x0000000d
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, mul, 0x00000000, 0x0000000b
+  TEST_RR_ZEROSRC2 20, mul, 0x00000000, 0x0000000d
+  TEST_RR_ZERODEST 21, mul, 0x0000000d, 0x0000000b
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# mulhu.s
+#-----------------------------------------------------------------------------
+#
+# Test mulhu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, mulhu, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, mulhu, 0x00000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, mulhu, 0x00000000, 0x00000003, 0x00000007
+  TEST_RR_OP 5, mulhu, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 6, mulhu, 0x00000000, 0x80000000, 0x00000000
+  TEST_RR_OP 7, mulhu, 0x7fffc000, 0x80000000, 0xffff8000
+  TEST_RR_OP 8, mulhu, 0x0001fefe, 0xaaaaaaab, 0x0002fe7d
+  TEST_RR_OP 9, mulhu, 0x0001fefe, 0x0002fe7d, 0xaaaaaaab
+  TEST_RR_OP 10, mulhu, 0xfe010000, 0xff000000, 0xff000000
+  TEST_RR_OP 11, mulhu, 0xfffffffe, 0xffffffff, 0xffffffff
+  TEST_RR_OP 12, mulhu, 0x00000000, 0xffffffff, 0x00000001
+  TEST_RR_OP 13, mulhu, 0x00000000, 0x00000001, 0xffffffff
+  TEST_RR_OP 14, mulhu, 0x40000000, 0x80000000, 0x80000000
+  TEST_RR_OP 15, mulhu, 0x3fffffff, 0x7fffffff, 0x80000000
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, mulhu, 0x00000000, 0x0000000d, 0x0000000b
+  TEST_RR_SRC2_EQ_DEST 17, mulhu, 0x00000000, 0x0000000d, 0x0000000b
+  TEST_RR_SRC12_EQ_DEST 18, mulhu, 0x00000000, 0x0000000d
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, mulhu, 0x00000000, 0x0000000b
+  TEST_RR_ZEROSRC2 20, mulhu, 0x00000000, 0x0000000d
+  TEST_RR_ZERODEST 21, mulhu, 0x0000000d, 0x0000000b
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
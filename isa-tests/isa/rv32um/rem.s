@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rem.s
+#-----------------------------------------------------------------------------
+#
+# Test rem instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, rem, 0x00000002, 0x00000014, 0x00000006
+  TEST_RR_OP 3, rem, 0xfffffffe, 0xffffffec, 0x00000006
+  TEST_RR_OP 4, rem, 0x00000002, 0x00000014, 0xfffffffa
+  TEST_RR_OP 5, rem, 0xfffffffe, 0xffffffec, 0xfffffffa
+  TEST_RR_OP 6, rem, 0x00000000, 0x80000000, 0x00000001
+  TEST_RR_OP 7, rem, 0x00000000, 0x80000000, 0xffffffff
+  TEST_RR_OP 8, rem, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 9, rem, 0x00000001, 0x00000001, 0x00000000
+  TEST_RR_OP 10, rem, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 11, rem, 0x00000007, 0x00000007, 0x80000000
+  TEST_RR_OP 12, rem, 0xffffffff, 0xffffffff, 0x00000002
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 13, rem, 0x00000002, 0x00000014, 0x00000006
+  TEST_RR_SRC2_EQ_DEST 14, rem, 0x00000002, 0x00000014, 0x00000006
+  TEST_RR_SRC12_EQ_DEST 15, rem, 0x00000000, 0x00000014
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 16, rem, 0x00000000, 0x00000006
+  TEST_RR_ZEROSRC2 17, rem, 0x00000014, 0x00000014
+  TEST_RR_ZERODEST 18, rem, 0x00000014, 0x00000006
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sh3add.s
+#-----------------------------------------------------------------------------
+#
+# Test sh3add instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sh3add, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, sh3add, 0x00000009, 0x00000001, 0x00000001
+  TEST_RR_OP 4, sh3add, 0x0000001f, 0x00000003, 0x00000007
+  TEST_RR_OP 5, sh3add, 0x00000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, sh3add, 0xffff8000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, sh3add, 0x00000017, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, sh3add, 0x00007fff, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, sh3add, 0xfffffff9, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, sh3add, 0x2c5f92b0, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, sh3add, 0x0717070f, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, sh3add, 0x0909092c, 0x21212121, 0x00000024
+  TEST_RR_OP 13, sh3add, 0x7ffffff8, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, sh3add, 0x2c5f92b0, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, sh3add, 0x2c5f92b0, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, sh3add, 0xa3d70a38, 0x12345678
+  TEST_RR_ZEROSRC1 17, sh3add, 0x9abcdef0, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, sh3add, 0x91a2b3c0, 0x12345678
+  TEST_RR_ZERODEST 19, sh3add, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
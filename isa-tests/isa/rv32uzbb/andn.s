@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# andn.s
+#-----------------------------------------------------------------------------
+#
+# Test andn instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, andn, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, andn, 0x00000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, andn, 0x00000000, 0x00000003, 0x00000007
+  TEST_RR_OP 5, andn, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, andn, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, andn, 0x7fffffe0, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, andn, 0x80000000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, andn, 0xfffffffe, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, andn, 0x00000008, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, andn, 0xf000f000, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, andn, 0x21212101, 0x21212121, 0x00000024
+  TEST_RR_OP 13, andn, 0x7fffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, andn, 0x00000008, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, andn, 0x00000008, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, andn, 0x00000000, 0x12345678
+  TEST_RR_ZEROSRC1 17, andn, 0x00000000, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, andn, 0x12345678, 0x12345678
+  TEST_RR_ZERODEST 19, andn, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# clz.s
+#-----------------------------------------------------------------------------
+#
+# Test clz instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, clz, 0x00000020, 0x00000000
+  TEST_R_OP 3, clz, 0x0000001f, 0x00000001
+  TEST_R_OP 4, clz, 0x00000018, 0x00000080
+  TEST_R_OP 5, clz, 0x00000010, 0x00008000
+  TEST_R_OP 6, clz, 0x00000000, 0x80000000
+  TEST_R_OP 7, clz, 0x00000001, 0x7fffffff
+  TEST_R_OP 8, clz, 0x00000000, 0xffffffff
+  TEST_R_OP 9, clz, 0x00000003, 0x12345678
+  TEST_R_OP 10, clz, 0x00000008, 0x00ff0f00
+  TEST_R_OP 11, clz, 0x00000000, 0xf0000001
+  TEST_R_OP 12, clz, 0x00000007, 0x01000000
+  TEST_R_OP 13, clz, 0x00000010, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, clz, 0x00000003, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
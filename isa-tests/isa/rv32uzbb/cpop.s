@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# cpop.s
+#-----------------------------------------------------------------------------
+#
+# Test cpop instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, cpop, 0x00000000, 0x00000000
+  TEST_R_OP 3, cpop, 0x00000001, 0x00000001
+  TEST_R_OP 4, cpop, 0x00000001, 0x00000080
+  TEST_R_OP 5, cpop, 0x00000001, 0x00008000
+  TEST_R_OP 6, cpop, 0x00000001, 0x80000000
+  TEST_R_OP 7, cpop, 0x0000001f, 0x7fffffff
+  TEST_R_OP 8, cpop, 0x00000020, 0xffffffff
+  TEST_R_OP 9, cpop, 0x0000000d, 0x12345678
+  TEST_R_OP 10, cpop, 0x0000000c, 0x00ff0f00
+  TEST_R_OP 11, cpop, 0x00000005, 0xf0000001
+  TEST_R_OP 12, cpop, 0x00000001, 0x01000000
+  TEST_R_OP 13, cpop, 0x0000000f, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, cpop, 0x0000000d, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
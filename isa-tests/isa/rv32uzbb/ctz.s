@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# ctz.s
+#-----------------------------------------------------------------------------
+#
+# Test ctz instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, ctz, 0x00000020, 0x00000000
+  TEST_R_OP 3, ctz, 0x00000000, 0x00000001
+  TEST_R_OP 4, ctz, 0x00000007, 0x00000080
+  TEST_R_OP 5, ctz, 0x0000000f, 0x00008000
+  TEST_R_OP 6, ctz, 0x0000001f, 0x80000000
+  TEST_R_OP 7, ctz, 0x00000000, 0x7fffffff
+  TEST_R_OP 8, ctz, 0x00000000, 0xffffffff
+  TEST_R_OP 9, ctz, 0x00000003, 0x12345678
+  TEST_R_OP 10, ctz, 0x00000008, 0x00ff0f00
+  TEST_R_OP 11, ctz, 0x00000000, 0xf0000001
+  TEST_R_OP 12, ctz, 0x00000018, 0x01000000
+  TEST_R_OP 13, ctz, 0x00000000, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, ctz, 0x00000003, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
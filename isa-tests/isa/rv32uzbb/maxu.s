@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# maxu.s
+#-----------------------------------------------------------------------------
+#
+# Test maxu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, maxu, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, maxu, 0x00000001, 0x00000001, 0x00000001
+  TEST_RR_OP 4, maxu, 0x00000007, 0x00000003, 0x00000007
+  TEST_RR_OP 5, maxu, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, maxu, 0xffff8000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, maxu, 0x7fffffff, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, maxu, 0x80000000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, maxu, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, maxu, 0x9abcdef0, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, maxu, 0xff00ff00, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, maxu, 0x21212121, 0x21212121, 0x00000024
+  TEST_RR_OP 13, maxu, 0xffffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, maxu, 0x9abcdef0, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, maxu, 0x9abcdef0, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, maxu, 0x12345678, 0x12345678
+  TEST_RR_ZEROSRC1 17, maxu, 0x9abcdef0, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, maxu, 0x12345678, 0x12345678
+  TEST_RR_ZERODEST 19, maxu, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# orc_b.s
+#-----------------------------------------------------------------------------
+#
+# Test orc.b instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, orc.b, 0x00000000, 0x00000000
+  TEST_R_OP 3, orc.b, 0x000000ff, 0x00000001
+  TEST_R_OP 4, orc.b, 0x000000ff, 0x00000080
+  TEST_R_OP 5, orc.b, 0x0000ff00, 0x00008000
+  TEST_R_OP 6, orc.b, 0xff000000, 0x80000000
+  TEST_R_OP 7, orc.b, 0xffffffff, 0x7fffffff
+  TEST_R_OP 8, orc.b, 0xffffffff, 0xffffffff
+  TEST_R_OP 9, orc.b, 0xffffffff, 0x12345678
+  TEST_R_OP 10, orc.b, 0x00ffff00, 0x00ff0f00
+  TEST_R_OP 11, orc.b, 0xff0000ff, 0xf0000001
+  TEST_R_OP 12, orc.b, 0xff000000, 0x01000000
+  TEST_R_OP 13, orc.b, 0x0000ffff, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, orc.b, 0xffffffff, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# orn.s
+#-----------------------------------------------------------------------------
+#
+# Test orn instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, orn, 0xffffffff, 0x00000000, 0x00000000
+  TEST_RR_OP 3, orn, 0xffffffff, 0x00000001, 0x00000001
+  TEST_RR_OP 4, orn, 0xfffffffb, 0x00000003, 0x00000007
+  TEST_RR_OP 5, orn, 0xffffffff, 0x80000000, 0x00000000
+  TEST_RR_OP 6, orn, 0x00007fff, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, orn, 0xffffffff, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, orn, 0xffff8000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, orn, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, orn, 0x7777777f, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, orn, 0xfff0fff0, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, orn, 0xfffffffb, 0x21212121, 0x00000024
+  TEST_RR_OP 13, orn, 0xffffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, orn, 0x7777777f, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, orn, 0x7777777f, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, orn, 0xffffffff, 0x12345678
+  TEST_RR_ZEROSRC1 17, orn, 0x6543210f, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, orn, 0xffffffff, 0x12345678
+  TEST_RR_ZERODEST 19, orn, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rev8.s
+#-----------------------------------------------------------------------------
+#
+# Test rev8 instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, rev8, 0x00000000, 0x00000000
+  TEST_R_OP 3, rev8, 0x01000000, 0x00000001
+  TEST_R_OP 4, rev8, 0x80000000, 0x00000080
+  TEST_R_OP 5, rev8, 0x00800000, 0x00008000
+  TEST_R_OP 6, rev8, 0x00000080, 0x80000000
+  TEST_R_OP 7, rev8, 0xffffff7f, 0x7fffffff
+  TEST_R_OP 8, rev8, 0xffffffff, 0xffffffff
+  TEST_R_OP 9, rev8, 0x78563412, 0x12345678
+  TEST_R_OP 10, rev8, 0x000fff00, 0x00ff0f00
+  TEST_R_OP 11, rev8, 0x010000f0, 0xf0000001
+  TEST_R_OP 12, rev8, 0x00000001, 0x01000000
+  TEST_R_OP 13, rev8, 0x7fff0000, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, rev8, 0x78563412, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
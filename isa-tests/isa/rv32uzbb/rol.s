@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rol.s
+#-----------------------------------------------------------------------------
+#
+# Test rol instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, rol, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, rol, 0x00000002, 0x00000001, 0x00000001
+  TEST_RR_OP 4, rol, 0x00000180, 0x00000003, 0x00000007
+  TEST_RR_OP 5, rol, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, rol, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, rol, 0xbfffffff, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, rol, 0x40000000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, rol, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, rol, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, rol, 0x7f807f80, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, rol, 0x12121212, 0x21212121, 0x00000024
+  TEST_RR_OP 13, rol, 0xffffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, rol, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, rol, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, rol, 0x78123456, 0x12345678
+  TEST_RR_ZEROSRC1 17, rol, 0x00000000, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, rol, 0x12345678, 0x12345678
+  TEST_RR_ZERODEST 19, rol, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# ror.s
+#-----------------------------------------------------------------------------
+#
+# Test ror instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, ror, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, ror, 0x80000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, ror, 0x06000000, 0x00000003, 0x00000007
+  TEST_RR_OP 5, ror, 0x80000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, ror, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, ror, 0xfffffffe, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, ror, 0x00000001, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, ror, 0xffffffff, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, ror, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, ror, 0xfe01fe01, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, ror, 0x12121212, 0x21212121, 0x00000024
+  TEST_RR_OP 13, ror, 0xffffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, ror, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, ror, 0x56781234, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, ror, 0x34567812, 0x12345678
+  TEST_RR_ZEROSRC1 17, ror, 0x00000000, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, ror, 0x12345678, 0x12345678
+  TEST_RR_ZERODEST 19, ror, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
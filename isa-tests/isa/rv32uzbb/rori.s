@@ -0,0 +1,39 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rori.s
+#-----------------------------------------------------------------------------
+#
+# Test rori instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, rori, 0x00000001, 0x00000001, 0
+  TEST_IMM_OP 3, rori, 0x80000000, 0x00000001, 1
+  TEST_IMM_OP 4, rori, 0x02000000, 0x00000001, 7
+  TEST_IMM_OP 5, rori, 0x00000001, 0x80000000, 31
+  TEST_IMM_OP 6, rori, 0x81234567, 0x12345678, 4
+  TEST_IMM_OP 7, rori, 0xf00ff00f, 0xff00ff00, 12
+  TEST_IMM_OP 8, rori, 0x12121212, 0x21212121, 20
+  TEST_IMM_OP 9, rori, 0xffffffff, 0xffffffff, 31
+
+  TEST_IMM_SRC1_EQ_DEST 10, rori, 0x81234567, 0x12345678, 4
+  TEST_IMM_ZEROSRC1 11, rori, 0x00000000, 4
+  TEST_IMM_ZERODEST 12, rori, 0x12345678, 4
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sext_b.s
+#-----------------------------------------------------------------------------
+#
+# Test sext.b instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, sext.b, 0x00000000, 0x00000000
+  TEST_R_OP 3, sext.b, 0x00000001, 0x00000001
+  TEST_R_OP 4, sext.b, 0xffffff80, 0x00000080
+  TEST_R_OP 5, sext.b, 0x00000000, 0x00008000
+  TEST_R_OP 6, sext.b, 0x00000000, 0x80000000
+  TEST_R_OP 7, sext.b, 0xffffffff, 0x7fffffff
+  TEST_R_OP 8, sext.b, 0xffffffff, 0xffffffff
+  TEST_R_OP 9, sext.b, 0x00000078, 0x12345678
+  TEST_R_OP 10, sext.b, 0x00000000, 0x00ff0f00
+  TEST_R_OP 11, sext.b, 0x00000001, 0xf0000001
+  TEST_R_OP 12, sext.b, 0x00000000, 0x01000000
+  TEST_R_OP 13, sext.b, 0x0000007f, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, sext.b, 0x00000078, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
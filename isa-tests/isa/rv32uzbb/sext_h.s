@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sext_h.s
+#-----------------------------------------------------------------------------
+#
+# Test sext.h instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, sext.h, 0x00000000, 0x00000000
+  TEST_R_OP 3, sext.h, 0x00000001, 0x00000001
+  TEST_R_OP 4, sext.h, 0x00000080, 0x00000080
+  TEST_R_OP 5, sext.h, 0xffff8000, 0x00008000
+  TEST_R_OP 6, sext.h, 0x00000000, 0x80000000
+  TEST_R_OP 7, sext.h, 0xffffffff, 0x7fffffff
+  TEST_R_OP 8, sext.h, 0xffffffff, 0xffffffff
+  TEST_R_OP 9, sext.h, 0x00005678, 0x12345678
+  TEST_R_OP 10, sext.h, 0x00000f00, 0x00ff0f00
+  TEST_R_OP 11, sext.h, 0x00000001, 0xf0000001
+  TEST_R_OP 12, sext.h, 0x00000000, 0x01000000
+  TEST_R_OP 13, sext.h, 0xffffff7f, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, sext.h, 0x00005678, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# xnor.s
+#-----------------------------------------------------------------------------
+#
+# Test xnor instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, xnor, 0xffffffff, 0x00000000, 0x00000000
+  TEST_RR_OP 3, xnor, 0xffffffff, 0x00000001, 0x00000001
+  TEST_RR_OP 4, xnor, 0xfffffffb, 0x00000003, 0x00000007
+  TEST_RR_OP 5, xnor, 0x7fffffff, 0x80000000, 0x00000000
+  TEST_RR_OP 6, xnor, 0x00007fff, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, xnor, 0x8000001f, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, xnor, 0x7fff8000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, xnor, 0x00000001, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, xnor, 0x77777777, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, xnor, 0x0ff00ff0, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, xnor, 0xdededefa, 0x21212121, 0x00000024
+  TEST_RR_OP 13, xnor, 0x80000000, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, xnor, 0x77777777, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, xnor, 0x77777777, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, xnor, 0xffffffff, 0x12345678
+  TEST_RR_ZEROSRC1 17, xnor, 0x6543210f, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, xnor, 0xedcba987, 0x12345678
+  TEST_RR_ZERODEST 19, xnor, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
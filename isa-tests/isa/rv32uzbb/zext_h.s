@@ -0,0 +1,41 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# zext_h.s
+#-----------------------------------------------------------------------------
+#
+# Test zext.h instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_R_OP 2, zext.h, 0x00000000, 0x00000000
+  TEST_R_OP 3, zext.h, 0x00000001, 0x00000001
+  TEST_R_OP 4, zext.h, 0x00000080, 0x00000080
+  TEST_R_OP 5, zext.h, 0x00008000, 0x00008000
+  TEST_R_OP 6, zext.h, 0x00000000, 0x80000000
+  TEST_R_OP 7, zext.h, 0x0000ffff, 0x7fffffff
+  TEST_R_OP 8, zext.h, 0x0000ffff, 0xffffffff
+  TEST_R_OP 9, zext.h, 0x00005678, 0x12345678
+  TEST_R_OP 10, zext.h, 0x00000f00, 0x00ff0f00
+  TEST_R_OP 11, zext.h, 0x00000001, 0xf0000001
+  TEST_R_OP 12, zext.h, 0x00000000, 0x01000000
+  TEST_R_OP 13, zext.h, 0x0000ff7f, 0x0000ff7f
+
+  TEST_R_SRC1_EQ_DEST 14, zext.h, 0x00005678, 0x12345678
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
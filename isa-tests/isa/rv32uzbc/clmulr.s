@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# clmulr.s
+#-----------------------------------------------------------------------------
+#
+# Test clmulr instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, clmulr, 0x00000000, 0x00000000, 0x00000000
+  TEST_RR_OP 3, clmulr, 0x00000000, 0x00000001, 0x00000001
+  TEST_RR_OP 4, clmulr, 0x00000000, 0x00000003, 0x00000007
+  TEST_RR_OP 5, clmulr, 0x00000000, 0x80000000, 0x00000000
+  TEST_RR_OP 6, clmulr, 0x00000000, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, clmulr, 0x0000000a, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, clmulr, 0x00007fff, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, clmulr, 0x00000001, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, clmulr, 0x110c1d28, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, clmulr, 0x0a000a00, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, clmulr, 0x00000009, 0x21212121, 0x00000024
+  TEST_RR_OP 13, clmulr, 0xffffffff, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, clmulr, 0x110c1d28, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, clmulr, 0x110c1d28, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, clmulr, 0x02080a20, 0x12345678
+  TEST_RR_ZEROSRC1 17, clmulr, 0x00000000, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, clmulr, 0x00000000, 0x12345678
+  TEST_RR_ZERODEST 19, clmulr, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# binv.s
+#-----------------------------------------------------------------------------
+#
+# Test binv instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV32U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, binv, 0x00000001, 0x00000000, 0x00000000
+  TEST_RR_OP 3, binv, 0x00000003, 0x00000001, 0x00000001
+  TEST_RR_OP 4, binv, 0x00000083, 0x00000003, 0x00000007
+  TEST_RR_OP 5, binv, 0x80000001, 0x80000000, 0x00000000
+  TEST_RR_OP 6, binv, 0x00000001, 0x00000000, 0xffff8000
+  TEST_RR_OP 7, binv, 0xffffffff, 0x7fffffff, 0x0000001f
+  TEST_RR_OP 8, binv, 0x00000000, 0x80000000, 0x00007fff
+  TEST_RR_OP 9, binv, 0xfffffffd, 0xffffffff, 0x00000001
+  TEST_RR_OP 10, binv, 0x12355678, 0x12345678, 0x9abcdef0
+  TEST_RR_OP 11, binv, 0xff007f00, 0xff00ff00, 0x0f0f0f0f
+  TEST_RR_OP 12, binv, 0x21212131, 0x21212121, 0x00000024
+  TEST_RR_OP 13, binv, 0xfffffffe, 0xffffffff, 0x80000000
+
+  TEST_RR_SRC1_EQ_DEST 14, binv, 0x12355678, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC2_EQ_DEST 15, binv, 0x12355678, 0x12345678, 0x9abcdef0
+  TEST_RR_SRC12_EQ_DEST 16, binv, 0x13345678, 0x12345678
+  TEST_RR_ZEROSRC1 17, binv, 0x00010000, 0x9abcdef0
+  TEST_RR_ZEROSRC2 18, binv, 0x12345679, 0x12345678
+  TEST_RR_ZERODEST 19, binv, 0x12345678, 0x9abcdef0
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
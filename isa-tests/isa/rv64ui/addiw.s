@@ -0,0 +1,54 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# addiw.s
+#-----------------------------------------------------------------------------
+#
+# Test addiw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, addiw, 0x0000000000000000, 0x0000000000000000, 0
+  TEST_IMM_OP 3, addiw, 0x0000000000000002, 0x0000000000000001, 1
+  TEST_IMM_OP 4, addiw, 0x000000000000000a, 0x0000000000000003, 7
+  TEST_IMM_OP 5, addiw, 0xfffffffffffff800, 0x0000000000000000, -2048
+  TEST_IMM_OP 6, addiw, 0xffffffff80000000, 0xffffffff80000000, 0
+  TEST_IMM_OP 7, addiw, 0x000000007ffff800, 0xffffffff80000000, -2048
+  TEST_IMM_OP 8, addiw, 0x00000000000007ff, 0x0000000000000000, 2047
+  TEST_IMM_OP 9, addiw, 0x000000007fffffff, 0x000000007fffffff, 0
+  TEST_IMM_OP 10, addiw, 0xffffffff800007fe, 0x000000007fffffff, 2047
+  TEST_IMM_OP 11, addiw, 0xffffffff800007ff, 0xffffffff80000000, 2047
+  TEST_IMM_OP 12, addiw, 0x000000007ffff7ff, 0x000000007fffffff, -2048
+  TEST_IMM_OP 13, addiw, 0xffffffffffffffff, 0x0000000000000000, -1
+  TEST_IMM_OP 14, addiw, 0x0000000000000000, 0x00000000ffffffff, 1
+  TEST_IMM_OP 15, addiw, 0xffffffff9abcdf00, 0x123456789abcdef0, 16
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 16, addiw, 0x000000000000000a, 0x0000000000000003, 7
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 17, addiw, 0x0000000000000007, 7
+  TEST_IMM_ZERODEST 18, addiw, 0x0000000000000003, 7
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# addw.s
+#-----------------------------------------------------------------------------
+#
+# Test addw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, addw, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 3, addw, 0x0000000000000002, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, addw, 0x000000000000000a, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_OP 5, addw, 0xffffffffffff8000, 0x0000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 6, addw, 0x0000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 7, addw, 0xffffffffffff8000, 0x8000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 8, addw, 0x0000000000000000, 0x7fffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 9, addw, 0x0000000000000000, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 10, addw, 0xffffffff80000000, 0x000000007fffffff, 0x0000000000000001
+  TEST_RR_OP 11, addw, 0x000000007fffffff, 0x0000000080000000, 0x00000000ffffffff
+  TEST_RR_OP 12, addw, 0x000000007fffffff, 0xffffffff80000000, 0x00000000ffffffff
+  TEST_RR_OP 13, addw, 0x0000000022222211, 0x123456789abcdef0, 0x0fedcba987654321
+  TEST_RR_OP 14, addw, 0xfffffffffffffffd, 0xfffffffffffffffe, 0xffffffffffffffff
+  TEST_RR_OP 15, addw, 0xfffffffffffffffe, 0x00000000ffffffff, 0x00000000ffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, addw, 0x000000000000000a, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, addw, 0x000000000000000a, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, addw, 0x0000000000000006, 0x0000000000000003
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, addw, 0x0000000000000007, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, addw, 0x0000000000000003, 0x0000000000000003
+  TEST_RR_ZERODEST 21, addw, 0x0000000000000003, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
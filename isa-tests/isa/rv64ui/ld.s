@@ -0,0 +1,65 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# ld.s
+#-----------------------------------------------------------------------------
+#
+# Test ld instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, ld, 0x00ff00ff00ff00ff, 0, tdat
+  TEST_LD_OP 3, ld, 0xff00ff00ff00ff00, 8, tdat
+  TEST_LD_OP 4, ld, 0x0ff00ff00ff00ff0, 16, tdat
+  TEST_LD_OP 5, ld, 0xf00ff00ff00ff00f, 24, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, ld, 0x00ff00ff00ff00ff, -24, tdat4
+  TEST_LD_OP 7, ld, 0xff00ff00ff00ff00, -16, tdat4
+  TEST_LD_OP 8, ld, 0x0ff00ff00ff00ff0, -8, tdat4
+  TEST_LD_OP 9, ld, 0xf00ff00ff00ff00f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  ld x5, 32(x1)
+  TEST_CHECK x5, 0x00ff00ff00ff00ff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  ld x5, 11(x1)
+  TEST_CHECK x5, 0xff00ff00ff00ff00
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .dword 0xff00ff00ff00ff
+tdat2:  .dword 0xff00ff00ff00ff00
+tdat3:  .dword 0xff00ff00ff00ff0
+tdat4:  .dword 0xf00ff00ff00ff00f
+
+RVTEST_DATA_END
@@ -0,0 +1,65 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# lwu.s
+#-----------------------------------------------------------------------------
+#
+# Test lwu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 2, lwu, 0x0000000000ff00ff, 0, tdat
+  TEST_LD_OP 3, lwu, 0x00000000ff00ff00, 4, tdat
+  TEST_LD_OP 4, lwu, 0x000000000ff00ff0, 8, tdat
+  TEST_LD_OP 5, lwu, 0x00000000f00ff00f, 12, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_LD_OP 6, lwu, 0x0000000000ff00ff, -12, tdat4
+  TEST_LD_OP 7, lwu, 0x00000000ff00ff00, -8, tdat4
+  TEST_LD_OP 8, lwu, 0x000000000ff00ff0, -4, tdat4
+  TEST_LD_OP 9, lwu, 0x00000000f00ff00f, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Base address tests
+  #-------------------------------------------------------------
+
+  # Test with a negative base
+test_10:
+  li gp, 10
+  la x1, tdat
+  addi x1, x1, -32
+  lwu x5, 32(x1)
+  TEST_CHECK x5, 0x0000000000ff00ff
+
+  # Test with unaligned base
+test_11:
+  li gp, 11
+  la x1, tdat
+  addi x1, x1, -3
+  lwu x5, 7(x1)
+  TEST_CHECK x5, 0x00000000ff00ff00
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .word 0xff00ff
+tdat2:  .word 0xff00ff00
+tdat3:  .word 0xff00ff0
+tdat4:  .word 0xf00ff00f
+
+RVTEST_DATA_END
@@ -0,0 +1,59 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sd.s
+#-----------------------------------------------------------------------------
+#
+# Test sd instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Basic tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 2, ld, sd, 0x00aa00aa00aa00aa, 0, tdat
+  TEST_ST_OP 3, ld, sd, 0xaa00aa00aa00aa00, 8, tdat
+  TEST_ST_OP 4, ld, sd, 0x0aa00aa00aa00aa0, 16, tdat
+  TEST_ST_OP 5, ld, sd, 0xa00aa00aa00aa00a, 24, tdat
+
+  #-------------------------------------------------------------
+  # Negative offset tests
+  #-------------------------------------------------------------
+
+  TEST_ST_OP 6, ld, sd, 0xff55ff55ff55ff55, -24, tdat4
+  TEST_ST_OP 7, ld, sd, 0x55ff55ff55ff55ff, -16, tdat4
+  TEST_ST_OP 8, ld, sd, 0xf55ff55ff55ff55f, -8, tdat4
+  TEST_ST_OP 9, ld, sd, 0x5ff55ff55ff55ff5, 0, tdat4
+
+  #-------------------------------------------------------------
+  # Word stores leave the upper half alone
+  #-------------------------------------------------------------
+
+test_10:
+  li gp, 10
+  la x1, tdat
+  li x2, 0x1122334455667788
+  sd x2, 0(x1)
+  li x3, 0xffffffffaabbccdd
+  sw x3, 0(x1)
+  ld x14, 0(x1)
+  TEST_CHECK x14, 0x11223344aabbccdd
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+tdat:
+tdat1:  .dword 0xdeadbeefdeadbeef
+tdat2:  .dword 0xdeadbeefdeadbeef
+tdat3:  .dword 0xdeadbeefdeadbeef
+tdat4:  .dword 0xdeadbeefdeadbeef
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sll.s
+#-----------------------------------------------------------------------------
+#
+# Test sll instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sll, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, sll, 0x0000000000000002, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, sll, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, sll, 0x0000000080000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, sll, 0xfffffffffffffffe, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, sll, 0xffffffff80000000, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, sll, 0x48487fffe0000000, 0x21212121ffff8000, 0x000000000000000e
+  TEST_RR_OP 9, sll, 0x0000000000000000, 0x8000000000000000, 0x000000000000003f
+  TEST_RR_OP 10, sll, 0x0000000080000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 11, sll, 0x0000000100000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 12, sll, 0xc000000000000000, 0xffffffff80000000, 0x000000000000001f
+  TEST_RR_OP 13, sll, 0x9abcdef000000000, 0x0123456789abcdef, 0x0000000000000024
+  TEST_RR_OP 14, sll, 0x0000000021212121, 0x0000000021212121, 0xffffffffffffffc0
+  TEST_RR_OP 15, sll, 0x8000000000000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, sll, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, sll, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, sll, 0x0000000000000002, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, sll, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, sll, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 21, sll, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,49 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# slli.s
+#-----------------------------------------------------------------------------
+#
+# Test slli instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, slli, 0x0000000000000001, 0x0000000000000001, 0
+  TEST_IMM_OP 3, slli, 0x0000000000000002, 0x0000000000000001, 1
+  TEST_IMM_OP 4, slli, 0x0000000000000080, 0x0000000000000001, 7
+  TEST_IMM_OP 5, slli, 0x0000000080000000, 0x0000000000000001, 31
+  TEST_IMM_OP 6, slli, 0x0000000100000000, 0x0000000000000001, 32
+  TEST_IMM_OP 7, slli, 0x8000000000000000, 0x0000000000000001, 63
+  TEST_IMM_OP 8, slli, 0xffffffffffffc000, 0xffffffffffffffff, 14
+  TEST_IMM_OP 9, slli, 0x2121210000000000, 0x0000000021212121, 40
+  TEST_IMM_OP 10, slli, 0x0000000000000002, 0x8000000000000001, 1
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 11, slli, 0x0000000000000080, 0x0000000000000001, 7
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 12, slli, 0x0000000000000000, 7
+  TEST_IMM_ZERODEST 13, slli, 0x0000000000000001, 7
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,49 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# slliw.s
+#-----------------------------------------------------------------------------
+#
+# Test slliw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, slliw, 0x0000000000000001, 0x0000000000000001, 0
+  TEST_IMM_OP 3, slliw, 0x0000000000000002, 0x0000000000000001, 1
+  TEST_IMM_OP 4, slliw, 0xffffffff80000000, 0x0000000000000001, 31
+  TEST_IMM_OP 5, slliw, 0xfffffffffffffffe, 0xffffffffffffffff, 1
+  TEST_IMM_OP 6, slliw, 0xffffffff80000000, 0xffffffffffffffff, 31
+  TEST_IMM_OP 7, slliw, 0x0000000000000000, 0x0000000080000000, 1
+  TEST_IMM_OP 8, slliw, 0x0000000000000000, 0x0000000080000000, 31
+  TEST_IMM_OP 9, slliw, 0x0000000048484000, 0x1234567821212121, 14
+  TEST_IMM_OP 10, slliw, 0x0000000012121210, 0xffffffff21212121, 4
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 11, slliw, 0xffffffff80000000, 0x0000000000000001, 31
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 12, slliw, 0x0000000000000000, 31
+  TEST_IMM_ZERODEST 13, slliw, 0x0000000000000001, 31
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,56 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sllw.s
+#-----------------------------------------------------------------------------
+#
+# Test sllw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sllw, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, sllw, 0x0000000000000002, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, sllw, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, sllw, 0xffffffff80000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, sllw, 0xfffffffffffffffe, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, sllw, 0xffffffff80000000, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, sllw, 0xffffffff80000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 9, sllw, 0x0000000000000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 10, sllw, 0x0000000000000000, 0x0000000080000000, 0x000000000000001f
+  TEST_RR_OP 11, sllw, 0x0000000048484000, 0x0000000021212121, 0x000000000000000e
+  TEST_RR_OP 12, sllw, 0x0000000012121210, 0x1234567821212121, 0x0000000000000004
+  TEST_RR_OP 13, sllw, 0x0000000012121210, 0xffffffff21212121, 0xffffffffffffffe4
+  TEST_RR_OP 14, sllw, 0xffffffff80000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 15, sllw, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 16, sllw, 0x0000000000000080, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 17, sllw, 0x0000000000000002, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 18, sllw, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 19, sllw, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 20, sllw, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sra.s
+#-----------------------------------------------------------------------------
+#
+# Test sra instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sra, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, sra, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, sra, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, sra, 0x0000000000000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, sra, 0xffffffffffffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, sra, 0xffffffffffffffff, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, sra, 0x000084848487fffe, 0x21212121ffff8000, 0x000000000000000e
+  TEST_RR_OP 9, sra, 0xffffffffffffffff, 0x8000000000000000, 0x000000000000003f
+  TEST_RR_OP 10, sra, 0x0000000080000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 11, sra, 0x0000000040000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 12, sra, 0xffffffffffffffff, 0xffffffff80000000, 0x000000000000001f
+  TEST_RR_OP 13, sra, 0x0000000000123456, 0x0123456789abcdef, 0x0000000000000024
+  TEST_RR_OP 14, sra, 0x0000000021212121, 0x0000000021212121, 0xffffffffffffffc0
+  TEST_RR_OP 15, sra, 0x0000000000000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, sra, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, sra, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, sra, 0x0000000000000000, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, sra, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, sra, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 21, sra, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
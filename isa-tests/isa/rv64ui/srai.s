@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# srai.s
+#-----------------------------------------------------------------------------
+#
+# Test srai instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, srai, 0x8000000000000000, 0x8000000000000000, 0
+  TEST_IMM_OP 3, srai, 0xc000000000000000, 0x8000000000000000, 1
+  TEST_IMM_OP 4, srai, 0xffffffffffffffff, 0x8000000000000000, 63
+  TEST_IMM_OP 5, srai, 0xffffffffffffffff, 0xffffffff80000000, 32
+  TEST_IMM_OP 6, srai, 0x0000000001234567, 0x0123456789abcdef, 32
+  TEST_IMM_OP 7, srai, 0x0000000000000001, 0x7fffffffffffffff, 62
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 8, srai, 0xffffffffffffffff, 0x8000000000000000, 63
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 9, srai, 0x0000000000000000, 63
+  TEST_IMM_ZERODEST 10, srai, 0x8000000000000000, 63
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,56 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sraw.s
+#-----------------------------------------------------------------------------
+#
+# Test sraw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sraw, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, sraw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, sraw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, sraw, 0x0000000000000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, sraw, 0xffffffffffffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, sraw, 0xffffffffffffffff, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, sraw, 0xffffffff80000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 9, sraw, 0xffffffffc0000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 10, sraw, 0xffffffffffffffff, 0x0000000080000000, 0x000000000000001f
+  TEST_RR_OP 11, sraw, 0x0000000000008484, 0x0000000021212121, 0x000000000000000e
+  TEST_RR_OP 12, sraw, 0x0000000002121212, 0x1234567821212121, 0x0000000000000004
+  TEST_RR_OP 13, sraw, 0x0000000002121212, 0xffffffff21212121, 0xffffffffffffffe4
+  TEST_RR_OP 14, sraw, 0x0000000000000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 15, sraw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 16, sraw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 17, sraw, 0x0000000000000000, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 18, sraw, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 19, sraw, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 20, sraw, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# srl.s
+#-----------------------------------------------------------------------------
+#
+# Test srl instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, srl, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, srl, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, srl, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, srl, 0x0000000000000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, srl, 0x7fffffffffffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, srl, 0x00000001ffffffff, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, srl, 0x000084848487fffe, 0x21212121ffff8000, 0x000000000000000e
+  TEST_RR_OP 9, srl, 0x0000000000000001, 0x8000000000000000, 0x000000000000003f
+  TEST_RR_OP 10, srl, 0x0000000080000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 11, srl, 0x0000000040000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 12, srl, 0x00000001ffffffff, 0xffffffff80000000, 0x000000000000001f
+  TEST_RR_OP 13, srl, 0x0000000000123456, 0x0123456789abcdef, 0x0000000000000024
+  TEST_RR_OP 14, srl, 0x0000000021212121, 0x0000000021212121, 0xffffffffffffffc0
+  TEST_RR_OP 15, srl, 0x0000000000000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, srl, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, srl, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, srl, 0x0000000000000000, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, srl, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, srl, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 21, srl, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
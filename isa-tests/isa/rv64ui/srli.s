@@ -0,0 +1,46 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# srli.s
+#-----------------------------------------------------------------------------
+#
+# Test srli instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, srli, 0x8000000000000000, 0x8000000000000000, 0
+  TEST_IMM_OP 3, srli, 0x4000000000000000, 0x8000000000000000, 1
+  TEST_IMM_OP 4, srli, 0x0000000000000001, 0x8000000000000000, 63
+  TEST_IMM_OP 5, srli, 0x000000007fffffff, 0xffffffffffffffff, 33
+  TEST_IMM_OP 6, srli, 0x0000000001234567, 0x0123456789abcdef, 32
+  TEST_IMM_OP 7, srli, 0x0000000000008484, 0x0000000021212121, 14
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 8, srli, 0x0000000000000001, 0x8000000000000000, 63
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 9, srli, 0x0000000000000000, 63
+  TEST_IMM_ZERODEST 10, srli, 0x8000000000000000, 63
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
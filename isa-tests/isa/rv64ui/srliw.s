@@ -0,0 +1,49 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# srliw.s
+#-----------------------------------------------------------------------------
+#
+# Test srliw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_OP 2, srliw, 0x0000000000000001, 0x0000000000000001, 0
+  TEST_IMM_OP 3, srliw, 0x0000000000000000, 0x0000000000000001, 1
+  TEST_IMM_OP 4, srliw, 0x0000000000000000, 0x0000000000000001, 31
+  TEST_IMM_OP 5, srliw, 0x000000007fffffff, 0xffffffffffffffff, 1
+  TEST_IMM_OP 6, srliw, 0x0000000000000001, 0xffffffffffffffff, 31
+  TEST_IMM_OP 7, srliw, 0x0000000040000000, 0x0000000080000000, 1
+  TEST_IMM_OP 8, srliw, 0x0000000000000001, 0x0000000080000000, 31
+  TEST_IMM_OP 9, srliw, 0x0000000000008484, 0x1234567821212121, 14
+  TEST_IMM_OP 10, srliw, 0x0000000002121212, 0xffffffff21212121, 4
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_SRC1_EQ_DEST 11, srliw, 0x0000000000000000, 0x0000000000000001, 31
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_IMM_ZEROSRC1 12, srliw, 0x0000000000000000, 31
+  TEST_IMM_ZERODEST 13, srliw, 0x0000000000000001, 31
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
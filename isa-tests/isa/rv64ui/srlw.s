@@ -0,0 +1,56 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# srlw.s
+#-----------------------------------------------------------------------------
+#
+# Test srlw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, srlw, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 3, srlw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, srlw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_OP 5, srlw, 0x0000000000000000, 0x0000000000000001, 0x000000000000001f
+  TEST_RR_OP 6, srlw, 0x000000007fffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 7, srlw, 0x0000000000000001, 0xffffffffffffffff, 0x000000000000001f
+  TEST_RR_OP 8, srlw, 0xffffffff80000000, 0x0000000080000000, 0x0000000000000000
+  TEST_RR_OP 9, srlw, 0x0000000040000000, 0x0000000080000000, 0x0000000000000001
+  TEST_RR_OP 10, srlw, 0x0000000000000001, 0x0000000080000000, 0x000000000000001f
+  TEST_RR_OP 11, srlw, 0x0000000000008484, 0x0000000021212121, 0x000000000000000e
+  TEST_RR_OP 12, srlw, 0x0000000002121212, 0x1234567821212121, 0x0000000000000004
+  TEST_RR_OP 13, srlw, 0x0000000002121212, 0xffffffff21212121, 0xffffffffffffffe4
+  TEST_RR_OP 14, srlw, 0x0000000000000000, 0x0000000021212121, 0xffffffffffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 15, srlw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 16, srlw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 17, srlw, 0x0000000000000000, 0x0000000000000001
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 18, srlw, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 19, srlw, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_ZERODEST 20, srlw, 0x0000000000000001, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
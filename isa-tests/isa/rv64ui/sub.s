@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# sub.s
+#-----------------------------------------------------------------------------
+#
+# Test sub instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, sub, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 3, sub, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, sub, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_OP 5, sub, 0x0000000000008000, 0x0000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 6, sub, 0x8000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 7, sub, 0x8000000000008000, 0x8000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 8, sub, 0x7ffffffffffffffe, 0x7fffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 9, sub, 0xfffffffffffffffe, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 10, sub, 0x000000007ffffffe, 0x000000007fffffff, 0x0000000000000001
+  TEST_RR_OP 11, sub, 0xffffffff80000001, 0x0000000080000000, 0x00000000ffffffff
+  TEST_RR_OP 12, sub, 0xfffffffe80000001, 0xffffffff80000000, 0x00000000ffffffff
+  TEST_RR_OP 13, sub, 0x02468acf13579bcf, 0x123456789abcdef0, 0x0fedcba987654321
+  TEST_RR_OP 14, sub, 0xffffffffffffffff, 0xfffffffffffffffe, 0xffffffffffffffff
+  TEST_RR_OP 15, sub, 0x0000000000000000, 0x00000000ffffffff, 0x00000000ffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, sub, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, sub, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, sub, 0x0000000000000000, 0x0000000000000003
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, sub, 0xfffffffffffffff9, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, sub, 0x0000000000000003, 0x0000000000000003
+  TEST_RR_ZERODEST 21, sub, 0x0000000000000003, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,57 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# subw.s
+#-----------------------------------------------------------------------------
+#
+# Test subw instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64U
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, subw, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 3, subw, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, subw, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_OP 5, subw, 0x0000000000008000, 0x0000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 6, subw, 0x0000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 7, subw, 0x0000000000008000, 0x8000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 8, subw, 0xfffffffffffffffe, 0x7fffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 9, subw, 0xfffffffffffffffe, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 10, subw, 0x000000007ffffffe, 0x000000007fffffff, 0x0000000000000001
+  TEST_RR_OP 11, subw, 0xffffffff80000001, 0x0000000080000000, 0x00000000ffffffff
+  TEST_RR_OP 12, subw, 0xffffffff80000001, 0xffffffff80000000, 0x00000000ffffffff
+  TEST_RR_OP 13, subw, 0x0000000013579bcf, 0x123456789abcdef0, 0x0fedcba987654321
+  TEST_RR_OP 14, subw, 0xffffffffffffffff, 0xfffffffffffffffe, 0xffffffffffffffff
+  TEST_RR_OP 15, subw, 0x0000000000000000, 0x00000000ffffffff, 0x00000000ffffffff
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 16, subw, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 17, subw, 0xfffffffffffffffc, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 18, subw, 0x0000000000000000, 0x0000000000000003
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 19, subw, 0xfffffffffffffff9, 0x0000000000000007
+  TEST_RR_ZEROSRC2 20, subw, 0x0000000000000003, 0x0000000000000003
+  TEST_RR_ZERODEST 21, subw, 0x0000000000000003, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,60 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# mul.s
+#-----------------------------------------------------------------------------
+#
+# Test mul instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, mul, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 3, mul, 0x0000000000000001, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, mul, 0x0000000000000015, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_OP 5, mul, 0x0000000000000000, 0x0000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 6, mul, 0x0000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 7, mul, 0x0000000000000000, 0x8000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 8, mul, 0x7fffffffffffffff, 0x7fffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 9, mul, 0xffffffffffffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 10, mul, 0x000000007fffffff, 0x000000007fffffff, 0x0000000000000001
+  TEST_RR_OP 11, mul, 0x7fffffff80000000, 0x0000000080000000, 0x00000000ffffffff
+  TEST_RR_OP 12, mul, 0x8000000080000000, 0xffffffff80000000, 0x00000000ffffffff
+  TEST_RR_OP 13, mul, 0x2236d88fe5618cf0, 0x123456789abcdef0, 0x0fedcba987654321
+  TEST_RR_OP 14, mul, 0x0000000000000002, 0xfffffffffffffffe, 0xffffffffffffffff
+  TEST_RR_OP 15, mul, 0xfffffffe00000001, 0x00000000ffffffff, 0x00000000ffffffff
+  TEST_RR_OP 16, mul, 0x000000000000ff7f, 0xaaaaaaaaaaaaaaab, 0x000000000002fe7d
+  TEST_RR_OP 17, mul, 0x0000000000000000, 0xff00000000000000, 0xff00000000000000
+  TEST_RR_OP 18, mul, 0xffffffffffffca00, 0x0000000000007e00, 0xb6db6db6db6db6db
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 19, mul, 0x0000000000000015, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 20, mul, 0x0000000000000015, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 21, mul, 0x0000000000000009, 0x0000000000000003
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 22, mul, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 23, mul, 0x0000000000000000, 0x0000000000000003
+  TEST_RR_ZERODEST 24, mul, 0x0000000000000003, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
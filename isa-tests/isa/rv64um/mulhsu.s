@@ -0,0 +1,60 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# mulhsu.s
+#-----------------------------------------------------------------------------
+#
+# Test mulhsu instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, mulhsu, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 3, mulhsu, 0x0000000000000000, 0x0000000000000001, 0x0000000000000001
+  TEST_RR_OP 4, mulhsu, 0x0000000000000000, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_OP 5, mulhsu, 0x0000000000000000, 0x0000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 6, mulhsu, 0x0000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 7, mulhsu, 0x8000000000004000, 0x8000000000000000, 0xffffffffffff8000
+  TEST_RR_OP 8, mulhsu, 0x0000000000000000, 0x7fffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 9, mulhsu, 0xffffffffffffffff, 0xffffffffffffffff, 0x0000000000000001
+  TEST_RR_OP 10, mulhsu, 0x0000000000000000, 0x000000007fffffff, 0x0000000000000001
+  TEST_RR_OP 11, mulhsu, 0x0000000000000000, 0x0000000080000000, 0x00000000ffffffff
+  TEST_RR_OP 12, mulhsu, 0xffffffffffffffff, 0xffffffff80000000, 0x00000000ffffffff
+  TEST_RR_OP 13, mulhsu, 0x0121fa00ad77d742, 0x123456789abcdef0, 0x0fedcba987654321
+  TEST_RR_OP 14, mulhsu, 0xfffffffffffffffe, 0xfffffffffffffffe, 0xffffffffffffffff
+  TEST_RR_OP 15, mulhsu, 0x0000000000000000, 0x00000000ffffffff, 0x00000000ffffffff
+  TEST_RR_OP 16, mulhsu, 0xffffffffffff0081, 0xaaaaaaaaaaaaaaab, 0x000000000002fe7d
+  TEST_RR_OP 17, mulhsu, 0xff01000000000000, 0xff00000000000000, 0xff00000000000000
+  TEST_RR_OP 18, mulhsu, 0x00000000000059ff, 0x0000000000007e00, 0xb6db6db6db6db6db
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 19, mulhsu, 0x0000000000000000, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC2_EQ_DEST 20, mulhsu, 0x0000000000000000, 0x0000000000000003, 0x0000000000000007
+  TEST_RR_SRC12_EQ_DEST 21, mulhsu, 0x0000000000000000, 0x0000000000000003
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 22, mulhsu, 0x0000000000000000, 0x0000000000000007
+  TEST_RR_ZEROSRC2 23, mulhsu, 0x0000000000000000, 0x0000000000000003
+  TEST_RR_ZERODEST 24, mulhsu, 0x0000000000000003, 0x0000000000000007
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
@@ -0,0 +1,56 @@
+# See LICENSE for license details.
+
+#*****************************************************************************
+# rem.s
+#-----------------------------------------------------------------------------
+#
+# Test rem instruction.
+#
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_RV64M
+RVTEST_CODE_BEGIN
+
+  #-------------------------------------------------------------
+  # Arithmetic tests
+  #-------------------------------------------------------------
+
+  TEST_RR_OP 2, rem, 0x0000000000000002, 0x0000000000000014, 0x0000000000000006
+  TEST_RR_OP 3, rem, 0xfffffffffffffffe, 0xffffffffffffffec, 0x0000000000000006
+  TEST_RR_OP 4, rem, 0x0000000000000002, 0x0000000000000014, 0xfffffffffffffffa
+  TEST_RR_OP 5, rem, 0xfffffffffffffffe, 0xffffffffffffffec, 0xfffffffffffffffa
+  TEST_RR_OP 6, rem, 0x0000000000000000, 0x8000000000000000, 0x0000000000000001
+  TEST_RR_OP 7, rem, 0x0000000000000000, 0x8000000000000000, 0xffffffffffffffff
+  TEST_RR_OP 8, rem, 0x8000000000000000, 0x8000000000000000, 0x0000000000000000
+  TEST_RR_OP 9, rem, 0x0000000000000001, 0x0000000000000001, 0x0000000000000000
+  TEST_RR_OP 10, rem, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000
+  TEST_RR_OP 11, rem, 0x000000000000def0, 0x123456789abcdef0, 0x0000000000010000
+  TEST_RR_OP 12, rem, 0x0000000000000000, 0xffffffff80000000, 0xffffffffffffffff
+  TEST_RR_OP 13, rem, 0x0000000000000000, 0x0000000080000000, 0xffffffffffffffff
+  TEST_RR_OP 14, rem, 0xffffffff00000014, 0xffffffff00000014, 0x0000000100000006
+
+  #-------------------------------------------------------------
+  # Source/Destination tests
+  #-------------------------------------------------------------
+
+  TEST_RR_SRC1_EQ_DEST 15, rem, 0x0000000000000002, 0x0000000000000014, 0xfffffffffffffffa
+  TEST_RR_SRC2_EQ_DEST 16, rem, 0x0000000000000002, 0x0000000000000014, 0xfffffffffffffffa
+  TEST_RR_SRC12_EQ_DEST 17, rem, 0x0000000000000000, 0x0000000000000014
+
+  #-------------------------------------------------------------
+  # Zero register tests
+  #-------------------------------------------------------------
+
+  TEST_RR_ZEROSRC1 18, rem, 0x0000000000000000, 0xfffffffffffffffa
+  TEST_RR_ZEROSRC2 19, rem, 0x0000000000000014, 0x0000000000000014
+  TEST_RR_ZERODEST 20, rem, 0x0000000000000014, 0xfffffffffffffffa
+
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+
+RVTEST_DATA_END
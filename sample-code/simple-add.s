@@ -1,2 +1,3 @@
 addi s0, zero, 1
 addi s1, s0, 2
+ebreak
@@ -0,0 +1,547 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Two-pass assembler
+ *
+ * Takes GNU-style RV32IM assembly and produces an `Elf`, which the VM loads
+ * directly or which is written out as an ELF executable or a flat binary.
+ *
+ * The first pass splits each line into labels and a directive or an
+ * instruction, and works out how many bytes it takes, so every label gets its
+ * address. The second pass evaluates the operands, now that every symbol is
+ * known, and encodes them.
+ *
+ * .text is placed at the base address and .data right after it. Execution
+ * starts at _start if there's one, at the base address otherwise.
+ */
+
+mod expr;
+mod instructions;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::cpu::Xlen;
+use crate::elf::{Elf, Segment, Symbol, SymbolTable};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct AsmError {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+const TEXT: usize = 0;
+const DATA: usize = 1;
+
+const NOP: u32 = 0x0000_0013;
+
+const TOO_LARGE: &str = "section does not fit in the 32-bit address space";
+
+enum Item {
+    Instruction { mnemonic: String, operands: Vec<String>, length: usize },
+    // .word, .half and .byte, evaluated in the second pass
+    Values { width: usize, values: Vec<String> },
+    Bytes(Vec<u8>),
+}
+
+struct Statement {
+    line: usize,
+    section: usize,
+    offset: u32,
+    item: Item,
+}
+
+// Where a symbol is, before the sections are placed
+#[derive(Clone, Copy)]
+enum Definition {
+    Label(usize, u32),
+    Constant(i64),
+}
+
+#[derive(Default)]
+struct Assembler {
+    section: usize,
+    sizes: [u32; 2],
+    // Largest alignment asked for in each section
+    alignments: [u32; 2],
+    symbols: HashMap<String, Definition>,
+    // .equ whose value needs labels, in the order they appear
+    deferred: Vec<(usize, String, String)>,
+    statements: Vec<Statement>,
+}
+
+// Cuts a comment off, unless the '#' is in a string or a character
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            _ if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+// Splits operands on the commas outside of parentheses and strings
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = Vec::new();
+    let (mut depth, mut quote, mut escaped, mut start) = (0, None, false, 0);
+    for (i, c) in text.char_indices() {
+        match quote {
+            _ if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    operands.push(text[start..i].trim().to_string());
+                    start = i + 1;
+                }
+                _ => {}
+            },
+        }
+    }
+    let last = text[start..].trim();
+    if !last.is_empty() || !operands.is_empty() {
+        operands.push(last.to_string());
+    }
+    operands
+}
+
+// The label at the start of a line, and what follows it
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let (label, rest) = text.split_once(':')?;
+    let mut chars = label.chars();
+    let valid = chars.next().is_some_and(expr::is_symbol_start) && chars.all(expr::is_symbol_char);
+    valid.then_some((label, rest.trim_start()))
+}
+
+// The bytes of a string literal, escapes included
+fn parse_string(text: &str) -> Result<Vec<u8>, String> {
+    let inner = text.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a string, got '{}'", text))?;
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next() {
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('r') => b'\r',
+            Some('0') => 0,
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&digits, 16).map_err(|_| format!("bad escape '\\x{}'", digits))?
+            }
+            Some(c @ ('\\' | '"' | '\'')) => c as u8,
+            other => return Err(format!("bad escape '\\{}'", other.map(String::from).unwrap_or_default())),
+        });
+    }
+    Ok(bytes)
+}
+
+impl Assembler {
+    fn define(&mut self, name: &str, definition: Definition) -> Result<(), String> {
+        if self.symbols.insert(name.to_string(), definition).is_some() {
+            return Err(format!("symbol '{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    // The value of an expression, if it only needs the constants defined so far
+    fn constant(&self, text: &str) -> Option<i64> {
+        expr::evaluate(text, &|name| match self.symbols.get(name) {
+            Some(Definition::Constant(value)) => Some(*value),
+            _ => None,
+        }).ok()
+    }
+
+    // Like `constant`, but an expression that can't be evaluated is an error
+    fn require_constant(&self, text: &str) -> Result<i64, String> {
+        self.constant(text).ok_or_else(|| format!("'{}' must be a constant known at this point", text))
+    }
+
+    // The offset `size` more bytes would end at in the current section
+    fn end_after(&self, size: u32) -> Result<u32, String> {
+        self.sizes[self.section].checked_add(size).ok_or_else(|| TOO_LARGE.to_string())
+    }
+
+    fn push(&mut self, line: usize, item: Item, size: u32) -> Result<(), String> {
+        let end = self.end_after(size)?;
+        self.statements.push(Statement { line, section: self.section, offset: self.sizes[self.section], item });
+        self.sizes[self.section] = end;
+        Ok(())
+    }
+
+    fn align(&mut self, line: usize, alignment: u32) -> Result<(), String> {
+        if !alignment.is_power_of_two() {
+            return Err(format!("alignment {} is not a power of two", alignment));
+        }
+        self.alignments[self.section] = self.alignments[self.section].max(alignment);
+        let offset = self.sizes[self.section];
+        let padding = (offset.checked_next_multiple_of(alignment).ok_or(TOO_LARGE)? - offset) as usize;
+        // Code is padded with NOPs, in case execution runs into the padding
+        let bytes = if self.section == TEXT && offset.is_multiple_of(4) {
+            NOP.to_le_bytes().repeat(padding / 4)
+        } else {
+            vec![0; padding]
+        };
+        self.push(line, Item::Bytes(bytes), padding as u32)
+    }
+
+    fn directive(&mut self, line: usize, name: &str, operands: Vec<String>) -> Result<(), String> {
+        match name {
+            ".text" => self.section = TEXT,
+            ".data" | ".rodata" | ".bss" => self.section = DATA,
+            ".section" => {
+                let section = operands.first().ok_or(".section needs a name")?;
+                self.section = if section.starts_with(".text") { TEXT } else { DATA };
+            }
+            ".globl" | ".global" | ".local" | ".type" | ".size" | ".file" | ".ident" | ".option" | ".attribute" => {}
+            ".equ" | ".set" => {
+                let [name, value] = &operands[..] else {
+                    return Err(format!("{} takes a name and a value", name));
+                };
+                match self.constant(value) {
+                    Some(value) => self.define(name, Definition::Constant(value))?,
+                    None => self.deferred.push((line, name.clone(), value.clone())),
+                }
+            }
+            ".word" | ".long" | ".4byte" | ".half" | ".short" | ".2byte" | ".byte" => {
+                let width = match name {
+                    ".byte" => 1,
+                    ".half" | ".short" | ".2byte" => 2,
+                    _ => 4,
+                };
+                let size = (width * operands.len()) as u32;
+                self.push(line, Item::Values { width, values: operands }, size)?;
+            }
+            ".ascii" | ".asciz" | ".string" => {
+                let mut bytes = Vec::new();
+                for operand in &operands {
+                    bytes.extend(parse_string(operand)?);
+                    if name != ".ascii" {
+                        bytes.push(0);
+                    }
+                }
+                let size = bytes.len() as u32;
+                self.push(line, Item::Bytes(bytes), size)?;
+            }
+            ".zero" | ".space" | ".skip" => {
+                let (size, fill) = match &operands[..] {
+                    [size] => (self.require_constant(size)?, 0),
+                    [size, fill] => (self.require_constant(size)?, self.require_constant(fill)?),
+                    _ => return Err(format!("{} takes a size and an optional fill value", name)),
+                };
+                let size = u32::try_from(size).map_err(|_| format!("invalid size {}", size))?;
+                // Checked before the bytes are allocated
+                self.end_after(size)?;
+                self.push(line, Item::Bytes(vec![fill as u8; size as usize]), size)?;
+            }
+            ".align" | ".p2align" | ".balign" => {
+                let value = self.require_constant(operands.first().ok_or(format!("{} needs an alignment", name))?)?;
+                let alignment = match name {
+                    ".balign" => value,
+                    _ if (0..32).contains(&value) => 1 << value,
+                    _ => return Err(format!("invalid alignment {}", value)),
+                };
+                self.align(line, alignment as u32)?;
+            }
+            _ => return Err(format!("unknown directive '{}'", name)),
+        }
+        Ok(())
+    }
+
+    // First pass, which lays out the sections
+    fn statement(&mut self, line: usize, text: &str) -> Result<(), String> {
+        let mut rest = strip_comment(text).trim();
+        while let Some((label, after)) = split_label(rest) {
+            self.define(label, Definition::Label(self.section, self.sizes[self.section]))?;
+            rest = after;
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        let (head, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let head = head.to_ascii_lowercase();
+        let operands = split_operands(tail);
+        if head.starts_with('.') {
+            return self.directive(line, &head, operands);
+        }
+
+        let borrowed: Vec<&str> = operands.iter().map(String::as_str).collect();
+        let length = instructions::length(&head, &borrowed, &|text| self.constant(text));
+        self.push(line, Item::Instruction { mnemonic: head, operands, length }, 4 * length as u32)
+    }
+
+    // Second pass, once the sections are placed at `bases`
+    fn encode(&self, bases: [u32; 2], symbols: &HashMap<&str, i64>) -> Result<[Vec<u8>; 2], AsmError> {
+        let mut sections = [Vec::new(), Vec::new()];
+        for statement in &self.statements {
+            let address = bases[statement.section].wrapping_add(statement.offset);
+            let lookup = |name: &str| match name {
+                "." => Some(address as i64),
+                _ => symbols.get(name).copied(),
+            };
+            let error = |message| AsmError { line: statement.line, message };
+            let output = &mut sections[statement.section];
+
+            match &statement.item {
+                Item::Instruction { mnemonic, operands, length } => {
+                    let operands: Vec<&str> = operands.iter().map(String::as_str).collect();
+                    let encoded = instructions::encode(mnemonic, &operands, *length, address, &lookup).map_err(error)?;
+                    debug_assert_eq!(encoded.len(), *length, "{} changed size between the passes", mnemonic);
+                    output.extend(encoded.iter().flat_map(|instruction| instruction.to_le_bytes()));
+                }
+                Item::Values { width, values } => {
+                    for value in values {
+                        let number = expr::evaluate(value, &lookup).map_err(|e| error(e.to_string()))?;
+                        let bits = 8 * *width as u32;
+                        if number < -(1 << (bits - 1)) || number >= 1 << bits {
+                            return Err(error(format!("value {} does not fit in {} bytes", number, width)));
+                        }
+                        output.extend_from_slice(&number.to_le_bytes()[..*width]);
+                    }
+                }
+                Item::Bytes(bytes) => output.extend_from_slice(bytes),
+            }
+        }
+        Ok(sections)
+    }
+}
+
+// Assembles `source` for the text section to start at `base`
+pub(crate) fn assemble(source: &str, base: u32) -> Result<Elf, AsmError> {
+    let mut assembler = Assembler::default();
+    for (number, text) in source.lines().enumerate() {
+        assembler.statement(number + 1, text).map_err(|message| AsmError { line: number + 1, message })?;
+    }
+
+    // .data goes after .text, and both have to end by the top of the address space
+    let data_alignment = assembler.alignments[DATA].max(4);
+    let data_base = base.checked_add(assembler.sizes[TEXT]).and_then(|end| end.checked_next_multiple_of(data_alignment));
+    let Some(data_base) = data_base.filter(|data_base| data_base.checked_add(assembler.sizes[DATA]).is_some()) else {
+        return Err(AsmError { line: source.lines().count(), message: TOO_LARGE.to_string() });
+    };
+    let bases = [base, data_base];
+    let mut symbols: HashMap<&str, i64> = assembler.symbols.iter()
+        .map(|(name, definition)| (name.as_str(), match *definition {
+            Definition::Label(section, offset) => bases[section].wrapping_add(offset) as i64,
+            Definition::Constant(value) => value,
+        }))
+        .collect();
+    for (line, name, value) in &assembler.deferred {
+        let error = |message| AsmError { line: *line, message };
+        let value = expr::evaluate(value, &|name| symbols.get(name).copied()).map_err(|e| error(e.to_string()))?;
+        if symbols.insert(name.as_str(), value).is_some() {
+            return Err(error(format!("symbol '{}' is already defined", name)));
+        }
+    }
+
+    let sections = assembler.encode(bases, &symbols)?;
+    let segments = sections.into_iter().zip(bases)
+        .filter(|(data, _)| !data.is_empty())
+        .map(|(data, vaddr)| Segment { vaddr, mem_size: data.len() as u32, data })
+        .collect();
+    let labels = assembler.symbols.iter()
+        .filter_map(|(name, definition)| match definition {
+            Definition::Label(..) => Some(Symbol { name: name.clone(), value: symbols[name.as_str()] as u32, size: 0 }),
+            Definition::Constant(_) => None,
+        })
+        .collect();
+    let entry = symbols.get("_start").map_or(base, |&start| start as u32);
+    Ok(Elf { xlen: Xlen::Rv32, entry, segments, symbols: SymbolTable::new(labels) })
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::asm::*;
+    use crate::cpu::{Disassembler, StopReason, CPU};
+
+    fn words(source: &str, base: u32) -> Vec<u32> {
+        let elf = assemble(source, base).unwrap();
+        elf.segments[0].data.chunks(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect()
+    }
+
+    fn error(source: &str) -> AsmError {
+        match assemble(source, 0) {
+            Err(e) => e,
+            Ok(_) => panic!("'{}' should not assemble", source),
+        }
+    }
+
+    fn error_line(source: &str) -> usize {
+        error(source).line
+    }
+
+    #[test]
+    fn test_sample() {
+        let source = std::fs::read_to_string("sample-code/simple-add.s").unwrap();
+        let binary = std::fs::read("sample-code/simple-add.bin").unwrap();
+        assert_eq!(assemble(&source, 0x4).unwrap().to_flat(), binary);
+    }
+
+    #[test]
+    fn test_labels() {
+        let source = "
+            _start: beq a0, a1, forward  # comment
+            back:   addi a0, a0, 1
+                    jal ra, back
+            forward:
+                    bne a0, zero, back";
+        let elf = assemble(source, 0x1000).unwrap();
+        assert_eq!(elf.entry, 0x1000);
+        assert_eq!(elf.symbols.lookup("forward"), Some(0x100C));
+        assert_eq!(words(source, 0x1000), vec![0x00B50663, 0x00150513, 0xFFDFF0EF, 0xFE051CE3]);
+    }
+
+    #[test]
+    fn test_relocations() {
+        let source = "
+            lui  t0, %hi(value)
+            addi t0, t0, %lo(value)
+            lw   t1, %lo(value)(t0)
+            la   a0, value
+            .data
+            value: .word 1";
+        let elf = assemble(source, 0x8000_07EC).unwrap();
+        assert_eq!(elf.symbols.lookup("value"), Some(0x8000_0800), "Data follows the code");
+        // 0x80000800 has bit 11 set, so %lo is negative and %hi rounds up
+        assert_eq!(words(source, 0x8000_07EC), vec![0x800012B7, 0x80028293, 0x8002A303, 0x00000517, 0x00850513]);
+    }
+
+    #[test]
+    fn test_directives() {
+        let source = r##"
+            .equ SIZE, 3
+            .set LATER, end - start
+            .text
+            nop
+            .align 3
+            .data
+            start:
+            .byte 1, 'A', SIZE
+            .half -1
+            .balign 4
+            .word start, LATER
+            .ascii "a\"\x41"
+            .asciz "#"
+            .zero SIZE, 0xEE
+            end:"##;
+        let elf = assemble(source, 0x100).unwrap();
+        assert_eq!(elf.segments[0].data, [0x13, 0, 0, 0, 0x13, 0, 0, 0], "Code is padded with NOPs");
+        assert_eq!(elf.segments[1].vaddr, 0x108);
+        assert_eq!(elf.segments[1].data, [1, 0x41, 3, 0xFF, 0xFF, 0, 0, 0, 0x08, 0x01, 0, 0, 24, 0, 0, 0,
+            b'a', b'"', 0x41, b'#', 0, 0xEE, 0xEE, 0xEE]);
+    }
+
+    #[test]
+    fn test_li() {
+        assert_eq!(words("li a0, -2048", 0), vec![0x80000513]);
+        assert_eq!(words("li a0, 0x12345000", 0), vec![0x12345537]);
+        assert_eq!(words("li a0, 0xFFFFFFFF", 0), vec![0xFFF00513]);
+        assert_eq!(words("li a0, 0x12345FFF", 0), vec![0x12346537, 0xFFF50513]);
+        // Unknown in the first pass, so it gets the long form even though one instruction would do
+        assert_eq!(words("li a0, SMALL\n.equ SMALL, 1", 0), vec![0x00000537, 0x00150513]);
+    }
+
+    #[test]
+    fn test_pseudo() {
+        let symbols = SymbolTable::default();
+        let disassembler = Disassembler::new(Xlen::Rv32, true, &symbols);
+        for text in ["nop", "mv\ta0,a1", "not\ta0,a1", "neg\ta0,a1", "seqz\ta0,a1", "snez\ta0,a1", "sltz\ta0,a1",
+            "sgtz\ta0,a1", "beqz\ta0,8", "bnez\ta0,8", "blez\ta0,8", "bgez\ta0,8", "bltz\ta0,8", "bgtz\ta0,8",
+            "j\t8", "jal\t8", "jr\ta0", "jalr\ta0", "ret", "csrr\ta0,mstatus", "csrw\tmtvec,a0", "csrs\tmie,a0",
+            "csrc\tmip,a0", "csrwi\tmscratch,3", "csrsi\tsie,1", "csrci\tsip,2", "fence", "fence.i", "ecall",
+            "ebreak", "mret", "sret", "wfi", "sfence.vma", "srai\ta0,a1,0x1f", "mulhsu\ta0,a1,a2",
+            "lhu\ta0,-2(sp)", "sb\ta0,2047(s0)", "auipc\ta0,0xfffff", "csrrwi\ta0,0x7c0,31", "fence\tr,w"] {
+            let word = words(text, 0)[0];
+            assert_eq!(disassembler.disassemble(word, 0), text, "0x{:08x}", word);
+        }
+        assert_eq!(words("bgt a0, a1, 8", 0), words("blt a1, a0, 8", 0));
+        assert_eq!(words("bleu a0, a1, 8", 0), words("bgeu a1, a0, 8", 0));
+        assert_eq!(words("jalr ra, 4(a0)", 0), words("jalr ra, a0, 4", 0));
+        assert_eq!(words("call 0x1000", 0x800), vec![0x00001097, 0x800080E7]);
+        assert_eq!(words("tail 0", 0x10), vec![0x00000317, 0xFF030067]);
+        assert_eq!(words("ADD x1, fp, x0", 0), words("add ra, s0, zero", 0), "Mnemonics are case insensitive");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("nop\n  frob a0"), AsmError { line: 2, message: "unknown instruction 'frob'".to_string() });
+        assert_eq!(error_line("nop\nnop\naddi a0, a0, 2048"), 3);
+        assert_eq!(error_line("slli a0, a0, 32"), 1);
+        assert_eq!(error_line("add a0, a1"), 1);
+        assert_eq!(error_line("add a0, a1, x32"), 1);
+        assert_eq!(error_line("beq a0, a1, 3"), 1);
+        assert_eq!(error_line("beq a0, a1, 4096"), 1);
+        assert_eq!(error_line("nop\nj nowhere"), 2);
+        assert_eq!(error_line("a: nop\na: nop"), 2);
+        assert_eq!(error_line(".data\n.byte 256"), 2);
+        assert_eq!(error_line(".align 3\n.balign 3"), 2);
+        assert_eq!(error_line(".bogus"), 1);
+        assert_eq!(error_line(".ascii \"open"), 1);
+        assert_eq!(error_line("nop\n.equ X, missing"), 2);
+        // Sections end by the top of the address space
+        assert_eq!(error("nop\n.zero 0xFFFFFFFE"), AsmError { line: 2, message: TOO_LARGE.to_string() });
+        assert_eq!(assemble("nop\nnop", 0xFFFF_FFFC).err(), Some(AsmError { line: 2, message: TOO_LARGE.to_string() }));
+        assert_eq!(assemble(".data\n.word 0\n.text\nnop", 0xFFFF_FFF8).err(),
+            Some(AsmError { line: 4, message: TOO_LARGE.to_string() }));
+    }
+
+    #[test]
+    fn test_elf() {
+        let elf = assemble("nop\n_start: ebreak\n.data\ntohost: .word 0", 0x8000_0000).unwrap();
+        let parsed = Elf::parse(&elf.to_bytes()).unwrap();
+        assert_eq!(parsed.entry, 0x8000_0004);
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[1].vaddr, 0x8000_0008);
+        assert_eq!(parsed.segments[0].data, elf.segments[0].data);
+        assert_eq!(parsed.symbols.lookup("tohost"), Some(0x8000_0008));
+    }
+
+    #[test]
+    fn test_run() {
+        let source = "
+            _start: li   a0, 10
+                    li   a1, 0
+            loop:   add  a1, a1, a0
+                    addi a0, a0, -1
+                    bnez a0, loop
+                    slli a1, a1, 1
+                    ori  a1, a1, 1
+                    la   t0, tohost
+                    sw   a1, 0(t0)
+                    sw   zero, 4(t0)
+                    j    .
+            .data
+            tohost:   .word 0, 0
+            fromhost: .word 0, 0";
+        let mut cpu = CPU::new();
+        let elf = assemble(source, 0x1000).unwrap();
+        let entry = elf.entry;
+        cpu.load_elf(elf).unwrap();
+        assert_eq!(cpu.run(entry as u64, Some(1000)), StopReason::Exit(55));
+    }
+}
@@ -0,0 +1,258 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Operand expressions
+ *
+ * Numbers (decimal, 0x hex, 0b binary, 'c' characters), symbols, `.` for the
+ * current address, the usual C operators with C precedence, and the %hi and
+ * %lo relocations. Everything is evaluated on 64 bits, the caller checks the
+ * result fits wherever it goes.
+ */
+
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub(super) enum ExprError {
+    // The symbol isn't known yet, it may be in the second pass
+    Undefined(String),
+    Syntax(String),
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprError::Undefined(name) => write!(f, "undefined symbol '{}'", name),
+            ExprError::Syntax(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// Upper 20 bits of a value, rounded so that adding the sign-extended %lo gives it back
+pub(super) fn hi(value: i64) -> i64 {
+    ((value + 0x800) >> 12) & 0xF_FFFF
+}
+
+// Lower 12 bits of a value, sign-extended
+pub(super) fn lo(value: i64) -> i64 {
+    ((value & 0xFFF) ^ 0x800) - 0x800
+}
+
+pub(super) fn is_symbol_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.' || c == '$'
+}
+
+pub(super) fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+// Binary operators, from the loosest to the tightest binding
+const OPERATORS: [&[&str]; 6] = [&["|"], &["^"], &["&"], &["<<", ">>"], &["+", "-"], &["*", "/", "%"]];
+
+struct Parser<'a, 'b> {
+    text: &'a str,
+    position: usize,
+    lookup: &'b dyn Fn(&str) -> Option<i64>,
+}
+
+impl<'a> Parser<'a, '_> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    // Consumes `token` if it's next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_spaces();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ExprError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(ExprError::Syntax(format!("expected '{}' in '{}'", token, self.text)))
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<i64, ExprError> {
+        if level == OPERATORS.len() {
+            return self.unary();
+        }
+        let mut value = self.binary(level + 1)?;
+        'outer: loop {
+            for &operator in OPERATORS[level] {
+                if self.eat(operator) {
+                    let rhs = self.binary(level + 1)?;
+                    value = match operator {
+                        "|" => value | rhs,
+                        "^" => value ^ rhs,
+                        "&" => value & rhs,
+                        "<<" => value.wrapping_shl(rhs as u32),
+                        ">>" => value.wrapping_shr(rhs as u32),
+                        "+" => value.wrapping_add(rhs),
+                        "-" => value.wrapping_sub(rhs),
+                        "*" => value.wrapping_mul(rhs),
+                        _ if rhs == 0 => return Err(ExprError::Syntax(format!("division by zero in '{}'", self.text))),
+                        "/" => value / rhs,
+                        _ => value % rhs,
+                    };
+                    continue 'outer;
+                }
+            }
+            return Ok(value);
+        }
+    }
+
+    fn unary(&mut self) -> Result<i64, ExprError> {
+        if self.eat("-") {
+            return Ok(self.unary()?.wrapping_neg());
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("~") {
+            return Ok(!self.unary()?);
+        }
+        if self.eat("(") {
+            let value = self.binary(0)?;
+            self.expect(")")?;
+            return Ok(value);
+        }
+        for (name, relocation) in [("%hi", hi as fn(i64) -> i64), ("%lo", lo)] {
+            if self.eat(name) {
+                self.expect("(")?;
+                let value = self.binary(0)?;
+                self.expect(")")?;
+                return Ok(relocation(value));
+            }
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<i64, ExprError> {
+        self.skip_spaces();
+        let rest = self.rest();
+        let Some(first) = rest.chars().next() else {
+            return Err(ExprError::Syntax(format!("missing operand in '{}'", self.text)));
+        };
+
+        if first == '\'' {
+            let mut chars = rest[1..].chars();
+            let value = match chars.next() {
+                Some('\\') => match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('0') => '\0',
+                    Some(c) => c,
+                    None => '\\',
+                },
+                Some(c) => c,
+                None => '\'',
+            };
+            if chars.next() != Some('\'') {
+                return Err(ExprError::Syntax(format!("bad character constant in '{}'", self.text)));
+            }
+            self.position = self.text.len() - chars.as_str().len();
+            return Ok(value as i64);
+        }
+
+        if first.is_ascii_digit() {
+            let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            let literal = rest[..len].replace('_', "");
+            self.position += len;
+            let parsed = if let Some(hex) = literal.strip_prefix("0x").or_else(|| literal.strip_prefix("0X")) {
+                i64::from_str_radix(hex, 16)
+            } else if let Some(binary) = literal.strip_prefix("0b").or_else(|| literal.strip_prefix("0B")) {
+                i64::from_str_radix(binary, 2)
+            } else {
+                literal.parse()
+            };
+            return parsed.map_err(|_| ExprError::Syntax(format!("bad number '{}'", literal)));
+        }
+
+        if is_symbol_start(first) {
+            let len = rest.find(|c: char| !is_symbol_char(c)).unwrap_or(rest.len());
+            let name = &rest[..len];
+            self.position += len;
+            return (self.lookup)(name).ok_or_else(|| ExprError::Undefined(name.to_string()));
+        }
+
+        Err(ExprError::Syntax(format!("unexpected '{}' in '{}'", first, self.text)))
+    }
+}
+
+// Evaluates `text`, `lookup` gives the value of symbols (and of `.`)
+pub(super) fn evaluate(text: &str, lookup: &dyn Fn(&str) -> Option<i64>) -> Result<i64, ExprError> {
+    let mut parser = Parser { text, position: 0, lookup };
+    let value = parser.binary(0)?;
+    parser.skip_spaces();
+    if !parser.rest().is_empty() {
+        return Err(ExprError::Syntax(format!("unexpected '{}' in '{}'", parser.rest(), text)));
+    }
+    Ok(value)
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::asm::expr::*;
+
+    fn eval(text: &str) -> Result<i64, ExprError> {
+        evaluate(text, &|name| match name {
+            "label" => Some(0x8000_1234),
+            "." => Some(0x100),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(eval("42"), Ok(42));
+        assert_eq!(eval("-42"), Ok(-42));
+        assert_eq!(eval("0x7FF"), Ok(0x7FF));
+        assert_eq!(eval("0b101"), Ok(5));
+        assert_eq!(eval("1_000"), Ok(1000));
+        assert_eq!(eval("'a'"), Ok(97));
+        assert_eq!(eval("'\\n'"), Ok(10));
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("1 << 4 | 1"), Ok(17));
+        assert_eq!(eval("~0 & 0xFF"), Ok(0xFF));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3), "Operators are left associative");
+        assert_eq!(eval("label - . + 4"), Ok(0x8000_1138));
+    }
+
+    #[test]
+    fn test_relocations() {
+        assert_eq!(eval("%hi(label)"), Ok(0x80001));
+        assert_eq!(eval("%lo(label)"), Ok(0x234));
+        // A negative %lo borrows from %hi
+        assert_eq!(eval("%hi(0x12345FFF)"), Ok(0x12346));
+        assert_eq!(eval("%lo(0x12345FFF)"), Ok(-1));
+        assert_eq!(eval("%lo(label + 4)"), Ok(0x238));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("missing + 1"), Err(ExprError::Undefined("missing".to_string())));
+        assert!(matches!(eval("1 +"), Err(ExprError::Syntax(_))));
+        assert!(matches!(eval("(1"), Err(ExprError::Syntax(_))));
+        assert!(matches!(eval("1 2"), Err(ExprError::Syntax(_))));
+        assert!(matches!(eval("0xZZ"), Err(ExprError::Syntax(_))));
+        assert!(matches!(eval("1 / 0"), Err(ExprError::Syntax(_))));
+    }
+}
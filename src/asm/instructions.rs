@@ -0,0 +1,388 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Instruction encoding
+ *
+ * RV32I and M, the CSR instructions, the privileged ones and the usual
+ * pseudo-instructions. Most pseudo-instructions are a base instruction with
+ * some of its operands fixed, and are rewritten into it. li, la, call and
+ * tail are a pair of instructions, li only when its value doesn't fit in a
+ * single one.
+ */
+
+use crate::asm::expr;
+use crate::cpu::opcodes::*;
+use crate::cpu::{csr_number, REG_ALIASES, REG_RA, REG_T1, REG_ZERO};
+
+// Where the operands go, along with the fields that tell the instruction apart
+#[derive(Clone, Copy)]
+enum Format {
+    R(u16),        // rd, rs1, rs2. funct7 and funct3
+    I(u8),         // rd, rs1, imm. funct3 of OP-IMM
+    Shift(u8, u8), // rd, rs1, shamt. funct7 and funct3
+    Load(u8),      // rd, offset(rs1)
+    Store(u8),     // rs2, offset(rs1)
+    Branch(u8),    // rs1, rs2, target
+    Upper(u8),     // rd, imm. The opcode
+    Jal,           // rd, target
+    Jalr,          // rd, offset(rs1)
+    Csr(u8),       // rd, csr, rs1
+    CsrImm(u8),    // rd, csr, uimm
+    Fence,         // pred, succ
+    SfenceVma,     // rs1, rs2
+    Fixed(u32),    // No operands at all
+}
+
+const fn system(funct12: u16) -> Format {
+    Format::Fixed((funct12 as u32) << 20 | OP::E_C as u32)
+}
+
+const INSTRUCTIONS: [(&str, Format); 54] = [
+    ("add", Format::R(F73_ADD)),
+    ("sub", Format::R(F73_SUB)),
+    ("sll", Format::R(F73_SLL)),
+    ("slt", Format::R(F73_SLT)),
+    ("sltu", Format::R(F73_SLTU)),
+    ("xor", Format::R(F73_XOR)),
+    ("srl", Format::R(F73_SRL)),
+    ("sra", Format::R(F73_SRA)),
+    ("or", Format::R(F73_OR)),
+    ("and", Format::R(F73_AND)),
+    ("mul", Format::R(F73_MUL)),
+    ("mulh", Format::R(F73_MULH)),
+    ("mulhsu", Format::R(F73_MULHSU)),
+    ("mulhu", Format::R(F73_MULHU)),
+    ("div", Format::R(F73_DIV)),
+    ("divu", Format::R(F73_DIVU)),
+    ("rem", Format::R(F73_REM)),
+    ("remu", Format::R(F73_REMU)),
+    ("addi", Format::I(F3::ADDI)),
+    ("slti", Format::I(F3::SLTI)),
+    ("sltiu", Format::I(F3::SLTIU)),
+    ("xori", Format::I(F3::XORI)),
+    ("ori", Format::I(F3::ORI)),
+    ("andi", Format::I(F3::ANDI)),
+    ("slli", Format::Shift(0, F3::SLLI)),
+    ("srli", Format::Shift(F7_SRLI, F3::SRLI_SRAI)),
+    ("srai", Format::Shift(F7_SRAI, F3::SRLI_SRAI)),
+    ("lb", Format::Load(F3::LB)),
+    ("lh", Format::Load(F3::LH)),
+    ("lw", Format::Load(F3::LW)),
+    ("lbu", Format::Load(F3::LBU)),
+    ("lhu", Format::Load(F3::LHU)),
+    ("sb", Format::Store(F3::SB)),
+    ("sh", Format::Store(F3::SH)),
+    ("sw", Format::Store(F3::SW)),
+    ("beq", Format::Branch(F3::BEQ)),
+    ("bne", Format::Branch(F3::BNE)),
+    ("blt", Format::Branch(F3::BLT)),
+    ("bge", Format::Branch(F3::BGE)),
+    ("bltu", Format::Branch(F3::BLTU)),
+    ("bgeu", Format::Branch(F3::BGEU)),
+    ("lui", Format::Upper(OP::LUI)),
+    ("auipc", Format::Upper(OP::AUIPC)),
+    ("jal", Format::Jal),
+    ("jalr", Format::Jalr),
+    ("csrrw", Format::Csr(F3::CSRRW)),
+    ("csrrs", Format::Csr(F3::CSRRS)),
+    ("csrrc", Format::Csr(F3::CSRRC)),
+    ("csrrwi", Format::CsrImm(F3::CSRRWI)),
+    ("csrrsi", Format::CsrImm(F3::CSRRSI)),
+    ("csrrci", Format::CsrImm(F3::CSRRCI)),
+    ("fence", Format::Fence),
+    ("sfence.vma", Format::SfenceVma),
+    ("fence.i", Format::Fixed((F3::FENCE_I as u32) << 12 | OP::FENCE as u32)),
+];
+
+const PRIVILEGED: [(&str, Format); 5] = [
+    ("ecall", system(F12::ECALL)),
+    ("ebreak", system(F12::EBREAK)),
+    ("sret", system(F12::SRET)),
+    ("mret", system(F12::MRET)),
+    ("wfi", system(F12::WFI)),
+];
+
+fn r_type(funct73: u16, rd: u8, rs1: u8, rs2: u8) -> u32 {
+    ((funct73 >> 3) as u32) << 25 | (rs2 as u32) << 20 | (rs1 as u32) << 15 | ((funct73 & 0x7) as u32) << 12
+        | (rd as u32) << 7 | OP::ALU as u32
+}
+
+fn i_type(imm: i64, rs1: u8, funct3: u8, rd: u8, opcode: u8) -> u32 {
+    (imm as u32 & 0xFFF) << 20 | (rs1 as u32) << 15 | (funct3 as u32) << 12 | (rd as u32) << 7 | opcode as u32
+}
+
+fn s_type(imm: i64, rs2: u8, rs1: u8, funct3: u8) -> u32 {
+    let imm = imm as u32;
+    (imm >> 5 & 0x7F) << 25 | (rs2 as u32) << 20 | (rs1 as u32) << 15 | (funct3 as u32) << 12
+        | (imm & 0x1F) << 7 | OP::STORE as u32
+}
+
+fn b_type(offset: i64, rs1: u8, rs2: u8, funct3: u8) -> u32 {
+    let imm = offset as u32;
+    (imm >> 12 & 0x1) << 31 | (imm >> 5 & 0x3F) << 25 | (rs2 as u32) << 20 | (rs1 as u32) << 15
+        | (funct3 as u32) << 12 | (imm >> 1 & 0xF) << 8 | (imm >> 11 & 0x1) << 7 | OP::BRANCH as u32
+}
+
+fn u_type(imm: i64, rd: u8, opcode: u8) -> u32 {
+    (imm as u32) << 12 | (rd as u32) << 7 | opcode as u32
+}
+
+fn j_type(offset: i64, rd: u8) -> u32 {
+    let imm = offset as u32;
+    (imm >> 20 & 0x1) << 31 | (imm >> 1 & 0x3FF) << 21 | (imm >> 11 & 0x1) << 20 | (imm >> 12 & 0xFF) << 12
+        | (rd as u32) << 7 | OP::JAL as u32
+}
+
+fn fits_12_bits(value: i64) -> bool {
+    (-2048..=2047).contains(&value)
+}
+
+// Whether li of `value` takes a single instruction: an ADDI, or a LUI when the low bits are zero.
+// Registers are 32 bits wide, so 0xFFFFFFFF is -1 and fits an ADDI.
+fn single_li(value: i64) -> bool {
+    let value = value as i32 as i64;
+    fits_12_bits(value) || expr::lo(value) == 0
+}
+
+// The number of instructions a line assembles to, known in the first pass.
+// `constant` evaluates an expression if it only uses symbols defined so far.
+pub(super) fn length(mnemonic: &str, operands: &[&str], constant: &dyn Fn(&str) -> Option<i64>) -> usize {
+    match mnemonic {
+        "li" if operands.len() == 2 => match constant(operands[1]) {
+            Some(value) if single_li(value) => 1,
+            _ => 2,
+        },
+        "la" | "lla" | "call" | "tail" => 2,
+        _ => 1,
+    }
+}
+
+// The base instruction a single instruction pseudo-instruction stands for, with its operands
+fn expand<'a>(mnemonic: &str, operands: &[&'a str]) -> Option<(&'static str, Vec<&'a str>)> {
+    let expanded = match (mnemonic, operands) {
+        ("nop", []) => ("addi", vec!["zero", "zero", "0"]),
+        ("mv", &[rd, rs]) => ("addi", vec![rd, rs, "0"]),
+        ("not", &[rd, rs]) => ("xori", vec![rd, rs, "-1"]),
+        ("neg", &[rd, rs]) => ("sub", vec![rd, "zero", rs]),
+        ("seqz", &[rd, rs]) => ("sltiu", vec![rd, rs, "1"]),
+        ("snez", &[rd, rs]) => ("sltu", vec![rd, "zero", rs]),
+        ("sltz", &[rd, rs]) => ("slt", vec![rd, rs, "zero"]),
+        ("sgtz", &[rd, rs]) => ("slt", vec![rd, "zero", rs]),
+        ("beqz", &[rs, target]) => ("beq", vec![rs, "zero", target]),
+        ("bnez", &[rs, target]) => ("bne", vec![rs, "zero", target]),
+        ("bltz", &[rs, target]) => ("blt", vec![rs, "zero", target]),
+        ("bgez", &[rs, target]) => ("bge", vec![rs, "zero", target]),
+        ("blez", &[rs, target]) => ("bge", vec!["zero", rs, target]),
+        ("bgtz", &[rs, target]) => ("blt", vec!["zero", rs, target]),
+        // The comparisons missing from the base set are the others with the operands swapped
+        ("bgt", &[rs, rt, target]) => ("blt", vec![rt, rs, target]),
+        ("ble", &[rs, rt, target]) => ("bge", vec![rt, rs, target]),
+        ("bgtu", &[rs, rt, target]) => ("bltu", vec![rt, rs, target]),
+        ("bleu", &[rs, rt, target]) => ("bgeu", vec![rt, rs, target]),
+        ("j", &[target]) => ("jal", vec!["zero", target]),
+        ("jal", &[target]) => ("jal", vec!["ra", target]),
+        ("jr", &[rs]) => ("jalr", vec!["zero", rs, "0"]),
+        ("jalr", &[rs]) => ("jalr", vec!["ra", rs, "0"]),
+        ("ret", []) => ("jalr", vec!["zero", "ra", "0"]),
+        ("csrr", &[rd, csr]) => ("csrrs", vec![rd, csr, "zero"]),
+        ("csrw", &[csr, rs]) => ("csrrw", vec!["zero", csr, rs]),
+        ("csrs", &[csr, rs]) => ("csrrs", vec!["zero", csr, rs]),
+        ("csrc", &[csr, rs]) => ("csrrc", vec!["zero", csr, rs]),
+        ("csrwi", &[csr, imm]) => ("csrrwi", vec!["zero", csr, imm]),
+        ("csrsi", &[csr, imm]) => ("csrrsi", vec!["zero", csr, imm]),
+        ("csrci", &[csr, imm]) => ("csrrci", vec!["zero", csr, imm]),
+        ("fence", []) => ("fence", vec!["iorw", "iorw"]),
+        ("sfence.vma", []) => ("sfence.vma", vec!["zero", "zero"]),
+        ("sfence.vma", &[rs]) => ("sfence.vma", vec![rs, "zero"]),
+        _ => return None,
+    };
+    Some(expanded)
+}
+
+// Evaluates operands of the instruction at `pc`
+struct Operands<'a> {
+    pc: u32,
+    lookup: &'a dyn Fn(&str) -> Option<i64>,
+}
+
+impl Operands<'_> {
+    fn register(&self, text: &str) -> Result<u8, String> {
+        let number = match text {
+            "fp" => Some(8),
+            _ => match text.strip_prefix('x').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if n < 32 => Some(n),
+                _ => REG_ALIASES.iter().position(|&alias| alias == text).map(|n| n as u8),
+            },
+        };
+        number.ok_or_else(|| format!("invalid register '{}'", text))
+    }
+
+    fn value(&self, text: &str) -> Result<i64, String> {
+        expr::evaluate(text, self.lookup).map_err(|e| e.to_string())
+    }
+
+    fn immediate(&self, text: &str, min: i64, max: i64) -> Result<i64, String> {
+        let value = self.value(text)?;
+        if value < min || value > max {
+            return Err(format!("immediate {} out of range [{}, {}]", value, min, max));
+        }
+        Ok(value)
+    }
+
+    // Offset from the instruction to a branch or jump target, which takes `bits` bits
+    fn offset(&self, text: &str, bits: u32) -> Result<i64, String> {
+        let offset = self.value(text)? - self.pc as i64;
+        let limit = 1i64 << (bits - 1);
+        if offset < -limit || offset >= limit {
+            return Err(format!("target {} is out of reach", text));
+        }
+        if offset % 2 != 0 {
+            return Err(format!("target {} is misaligned", text));
+        }
+        Ok(offset)
+    }
+
+    // offset(rs1), the offset being optional
+    fn memory(&self, text: &str) -> Result<(i64, u8), String> {
+        let open = text.rfind('(').filter(|_| text.ends_with(')'))
+            .ok_or_else(|| format!("expected offset(register), got '{}'", text))?;
+        let offset = text[..open].trim();
+        let offset = if offset.is_empty() { 0 } else { self.immediate(offset, -2048, 2047)? };
+        Ok((offset, self.register(text[open + 1..text.len() - 1].trim())?))
+    }
+
+    fn csr(&self, text: &str) -> Result<u16, String> {
+        match csr_number(text) {
+            Some(number) => Ok(number),
+            None => Ok(self.immediate(text, 0, 0xFFF)? as u16),
+        }
+    }
+
+    // A FENCE predecessor or successor set, as a subset of "iorw"
+    fn fence_set(&self, text: &str) -> Result<u32, String> {
+        text.chars().try_fold(0, |set, c| match "iorw".find(c) {
+            Some(bit) => Ok(set | 0x8 >> bit),
+            None => Err(format!("invalid fence set '{}'", text)),
+        })
+    }
+}
+
+fn check_count(mnemonic: &str, operands: &[&str], count: usize) -> Result<(), String> {
+    if operands.len() != count {
+        return Err(format!("{} takes {} operands, got {}", mnemonic, count, operands.len()));
+    }
+    Ok(())
+}
+
+// Encodes a line into the `length` instructions the first pass made room for
+pub(super) fn encode(mnemonic: &str, operands: &[&str], length: usize, pc: u32, lookup: &dyn Fn(&str) -> Option<i64>)
+    -> Result<Vec<u32>, String> {
+    let ops = Operands { pc, lookup };
+    match mnemonic {
+        "li" => {
+            check_count(mnemonic, operands, 2)?;
+            let rd = ops.register(operands[0])?;
+            let value = ops.immediate(operands[1], i32::MIN as i64, u32::MAX as i64)? as i32 as i64;
+            let (hi, lo) = (expr::hi(value), expr::lo(value));
+            return Ok(match length {
+                1 if fits_12_bits(value) => vec![i_type(value, 0, F3::ADDI, rd, OP::ALUI)],
+                1 => vec![u_type(hi, rd, OP::LUI)],
+                _ => vec![u_type(hi, rd, OP::LUI), i_type(lo, rd, F3::ADDI, rd, OP::ALUI)],
+            });
+        }
+        // PC-relative, so the program works wherever it's loaded
+        "la" | "lla" | "call" | "tail" => {
+            let (rd, target, scratch) = match (mnemonic, operands) {
+                ("call", &[target]) => (REG_RA, target, REG_RA),
+                ("tail", &[target]) => (REG_ZERO, target, REG_T1),
+                (_, &[rd, target]) if mnemonic.ends_with("la") => {
+                    let rd = ops.register(rd)?;
+                    (rd, target, rd)
+                }
+                _ => return Err(format!("wrong number of operands for {}", mnemonic)),
+            };
+            let delta = ops.value(target)? - pc as i64;
+            if delta < i32::MIN as i64 || delta > i32::MAX as i64 {
+                return Err(format!("target {} is out of reach", target));
+            }
+            let second = if mnemonic.ends_with("la") {
+                i_type(expr::lo(delta), scratch, F3::ADDI, rd, OP::ALUI)
+            } else {
+                i_type(expr::lo(delta), scratch, 0, rd, OP::JALR)
+            };
+            return Ok(vec![u_type(expr::hi(delta), scratch, OP::AUIPC), second]);
+        }
+        _ => {}
+    }
+
+    let (mnemonic, operands) = match expand(mnemonic, operands) {
+        Some((base, operands)) => (base, operands),
+        None => (mnemonic, operands.to_vec()),
+    };
+    let format = INSTRUCTIONS.iter().chain(PRIVILEGED.iter())
+        .find(|(name, _)| *name == mnemonic)
+        .map(|(_, format)| *format)
+        .ok_or_else(|| format!("unknown instruction '{}'", mnemonic))?;
+
+    let count = match format {
+        Format::R(_) | Format::I(_) | Format::Shift(..) | Format::Branch(_) | Format::Csr(_) | Format::CsrImm(_) => 3,
+        Format::Load(_) | Format::Store(_) | Format::Upper(_) | Format::Jal | Format::Fence | Format::SfenceVma => 2,
+        // Both "jalr rd, offset(rs1)" and "jalr rd, rs1, offset"
+        Format::Jalr => if operands.len() == 3 { 3 } else { 2 },
+        Format::Fixed(_) => 0,
+    };
+    check_count(mnemonic, &operands, count)?;
+
+    let instruction = match format {
+        Format::R(funct73) => r_type(funct73, ops.register(operands[0])?, ops.register(operands[1])?, ops.register(operands[2])?),
+        Format::I(funct3) => {
+            let imm = ops.immediate(operands[2], -2048, 2047)?;
+            i_type(imm, ops.register(operands[1])?, funct3, ops.register(operands[0])?, OP::ALUI)
+        }
+        Format::Shift(funct7, funct3) => {
+            let shamt = ops.immediate(operands[2], 0, 31)? | (funct7 as i64) << 5;
+            i_type(shamt, ops.register(operands[1])?, funct3, ops.register(operands[0])?, OP::ALUI)
+        }
+        Format::Load(funct3) => {
+            let (offset, rs1) = ops.memory(operands[1])?;
+            i_type(offset, rs1, funct3, ops.register(operands[0])?, OP::LOAD)
+        }
+        Format::Store(funct3) => {
+            let (offset, rs1) = ops.memory(operands[1])?;
+            s_type(offset, ops.register(operands[0])?, rs1, funct3)
+        }
+        Format::Branch(funct3) => {
+            let offset = ops.offset(operands[2], 13)?;
+            b_type(offset, ops.register(operands[0])?, ops.register(operands[1])?, funct3)
+        }
+        Format::Upper(opcode) => u_type(ops.immediate(operands[1], 0, 0xF_FFFF)?, ops.register(operands[0])?, opcode),
+        Format::Jal => j_type(ops.offset(operands[1], 21)?, ops.register(operands[0])?),
+        Format::Jalr => {
+            let (offset, rs1) = if operands.len() == 3 {
+                (ops.immediate(operands[2], -2048, 2047)?, ops.register(operands[1])?)
+            } else if operands[1].ends_with(')') {
+                ops.memory(operands[1])?
+            } else {
+                (0, ops.register(operands[1])?)
+            };
+            i_type(offset, rs1, 0, ops.register(operands[0])?, OP::JALR)
+        }
+        Format::Csr(funct3) => {
+            let csr = ops.csr(operands[1])? as i64;
+            i_type(csr, ops.register(operands[2])?, funct3, ops.register(operands[0])?, OP::E_C)
+        }
+        Format::CsrImm(funct3) => {
+            let csr = ops.csr(operands[1])? as i64;
+            i_type(csr, ops.immediate(operands[2], 0, 31)? as u8, funct3, ops.register(operands[0])?, OP::E_C)
+        }
+        Format::Fence => {
+            let sets = ops.fence_set(operands[0])? << 4 | ops.fence_set(operands[1])?;
+            i_type(sets as i64, 0, F3::FENCE, 0, OP::FENCE)
+        }
+        Format::SfenceVma => {
+            let (rs1, rs2) = (ops.register(operands[0])?, ops.register(operands[1])?);
+            r_type((F7_SFENCE_VMA as u16) << 3, 0, rs1, rs2) & !(OP::ALU as u32) | OP::E_C as u32
+        }
+        Format::Fixed(instruction) => instruction,
+    };
+    Ok(vec![instruction])
+}
@@ -0,0 +1,366 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Command line flag parsing
+ *
+ * We only have a handful of flags, so they're parsed by hand instead of
+ * pulling in a dependency. Every numeric value can be given either in
+ * decimal or in hex with a 0x prefix.
+ */
+
+pub(crate) const USAGE: &str = "\
+Usage: tiny-vm [OPTIONS] <IMAGE>
+       tiny-vm --isa-tests <DIR>
+       tiny-vm --assemble <SOURCE> --output <FILE> [--elf] [--load-addr <ADDR>]
+
+Runs a RISC-V program. IMAGE can be an ELF32 or ELF64 executable, a flat
+binary or RV32IM assembly source (.s), which is assembled at the load address
+first.
+
+Options:
+      --xlen <32|64>       Register width, RV32 or RV64 [default: ELF class, or 32]
+      --load-addr <ADDR>   Address flat binaries are loaded at [default: 0x4]
+      --entry <ADDR>       Address execution starts at [default: ELF entry point or load address]
+      --ram-base <ADDR>    Address the first byte of memory is mapped at [default: 0x0]
+      --memory <MB>        Memory size in MiB [default: 2]
+      --tohost <ADDR>      Address of the HTIF tohost register [default: ELF tohost symbol]
+      --fromhost <ADDR>    Address of the HTIF fromhost register [default: ELF fromhost symbol]
+      --uart <ADDR>        Address the 16550 UART is mapped at [default: 0x10000000]
+      --clint <ADDR>       Address the CLINT timer is mapped at [default: 0x02000000]
+      --plic <ADDR>        Address the PLIC interrupt controller is mapped at [default: 0x0c000000]
+      --mtime <SOURCE>     What mtime counts: instructions, or host (wall clock at 10 MHz) [default: instructions]
+      --page-bits <BITS>   Number of address bits used as page offset [default: 8]
+      --max-insts <N>      Stop after executing N instructions
+      --no-gui             Run headless, without opening the control panel
+      --dump-regs          Print the register file once execution stops
+      --tlb-stats          Print the TLB hit and miss counts once execution stops
+      --trace <FILE>       Log every instruction to FILE (- for stderr), in the format of spike --log-commits
+      --trace-range <START>:<END>
+                           Only log the instructions with their address in [START, END)
+      --gdb <PORT>         Wait for GDB to connect on localhost:PORT and let it drive the program
+      --isa-tests <DIR>    Run every rv32u{i,m,a,c,f,d,zb*}-p-* and rv64u{i,m}-p-* test in DIR and report the results
+      --assemble <SOURCE>  Assemble SOURCE for the load address instead of running anything
+  -o, --output <FILE>      Where --assemble writes its output
+      --elf                Make --assemble write an ELF executable rather than a flat binary
+  -h, --help               Print this help";
+
+pub(crate) const DEFAULT_LOAD_ADDR: u32 = 0x4;
+pub(crate) const DEFAULT_MEMSIZE_MB: usize = crate::cpu::MEMSIZE_MB;
+const MAX_MEMSIZE_MB: usize = 4095;
+pub(crate) const DEFAULT_PAGE_OFFSET_BITS: usize = crate::cpu::PAGE_OFFSET_BITS;
+pub(crate) const DEFAULT_UART_BASE: u32 = crate::cpu::UART_BASE;
+pub(crate) const DEFAULT_CLINT_BASE: u32 = crate::cpu::CLINT_BASE;
+pub(crate) const DEFAULT_PLIC_BASE: u32 = crate::cpu::PLIC_BASE;
+
+use crate::cpu::{TimeSource, Xlen};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Args {
+    pub(crate) image: String,
+    pub(crate) xlen: Option<Xlen>,
+    pub(crate) load_addr: u32,
+    pub(crate) entry: Option<u32>,
+    pub(crate) ram_base: u32,
+    pub(crate) memsize_mb: usize,
+    pub(crate) page_offset_bits: usize,
+    pub(crate) tohost: Option<u32>,
+    pub(crate) fromhost: Option<u32>,
+    pub(crate) uart: u32,
+    pub(crate) clint: u32,
+    pub(crate) plic: u32,
+    pub(crate) mtime: TimeSource,
+    pub(crate) max_instructions: Option<u64>,
+    pub(crate) no_gui: bool,
+    pub(crate) dump_regs: bool,
+    pub(crate) tlb_stats: bool,
+    pub(crate) trace: Option<String>,
+    pub(crate) trace_range: Option<(u32, u32)>,
+    pub(crate) gdb: Option<u16>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct AssembleArgs {
+    pub(crate) source: String,
+    pub(crate) output: String,
+    pub(crate) load_addr: u32,
+    pub(crate) elf: bool,
+}
+
+// What the caller should do after parsing
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Run(Args),
+    IsaTests(String),
+    Assemble(AssembleArgs),
+    Help,
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u64, String> {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+        None => value.replace('_', "").parse::<u64>(),
+    };
+    parsed.map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
+
+fn parse_u32(flag: &str, value: &str) -> Result<u32, String> {
+    u32::try_from(parse_number(flag, value)?)
+        .map_err(|_| format!("value '{}' for {} does not fit in 32 bits", value, flag))
+}
+
+impl Command {
+    // Parses the arguments, without the program name
+    pub(crate) fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut image = None;
+        let mut isa_tests = None;
+        let mut assemble = None;
+        let mut output = None;
+        let mut elf = false;
+        let mut parsed = Args {
+            image: String::new(),
+            xlen: None,
+            load_addr: DEFAULT_LOAD_ADDR,
+            entry: None,
+            ram_base: 0,
+            memsize_mb: DEFAULT_MEMSIZE_MB,
+            page_offset_bits: DEFAULT_PAGE_OFFSET_BITS,
+            tohost: None,
+            fromhost: None,
+            uart: DEFAULT_UART_BASE,
+            clint: DEFAULT_CLINT_BASE,
+            plic: DEFAULT_PLIC_BASE,
+            mtime: TimeSource::Instructions,
+            max_instructions: None,
+            no_gui: false,
+            dump_regs: false,
+            tlb_stats: false,
+            trace: None,
+            trace_range: None,
+            gdb: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Both "--flag value" and "--flag=value" are accepted
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let takes_value = matches!(flag.as_str(),
+                "--xlen" | "--load-addr" | "--entry" | "--ram-base" | "--memory" | "--page-bits" | "--tohost" | "--fromhost" | "--uart" | "--clint" | "--plic" | "--mtime"
+                | "--max-insts" | "--isa-tests" | "--assemble" | "-o" | "--output" | "--trace" | "--trace-range" | "--gdb");
+            let value = if takes_value {
+                match inline_value {
+                    Some(value) => value,
+                    None => args.next().ok_or_else(|| format!("missing value for {}", flag))?,
+                }
+            } else {
+                if inline_value.is_some() {
+                    return Err(format!("{} does not take a value", flag));
+                }
+                String::new()
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "--xlen" => parsed.xlen = match value.as_str() {
+                    "32" => Some(Xlen::Rv32),
+                    "64" => Some(Xlen::Rv64),
+                    _ => return Err(format!("invalid value '{}' for {}, expected 32 or 64", value, flag)),
+                },
+                "--load-addr" => parsed.load_addr = parse_u32(&flag, &value)?,
+                "--entry" => parsed.entry = Some(parse_u32(&flag, &value)?),
+                "--ram-base" => parsed.ram_base = parse_u32(&flag, &value)?,
+                "--memory" => parsed.memsize_mb = parse_number(&flag, &value)? as usize,
+                "--page-bits" => parsed.page_offset_bits = parse_number(&flag, &value)? as usize,
+                "--tohost" => parsed.tohost = Some(parse_u32(&flag, &value)?),
+                "--fromhost" => parsed.fromhost = Some(parse_u32(&flag, &value)?),
+                "--uart" => parsed.uart = parse_u32(&flag, &value)?,
+                "--clint" => parsed.clint = parse_u32(&flag, &value)?,
+                "--plic" => parsed.plic = parse_u32(&flag, &value)?,
+                "--mtime" => parsed.mtime = match value.as_str() {
+                    "instructions" => TimeSource::Instructions,
+                    "host" => TimeSource::Host,
+                    _ => return Err(format!("invalid value '{}' for {}, expected instructions or host", value, flag)),
+                },
+                "--max-insts" => parsed.max_instructions = Some(parse_number(&flag, &value)?),
+                "--no-gui" => parsed.no_gui = true,
+                "--dump-regs" => parsed.dump_regs = true,
+                "--tlb-stats" => parsed.tlb_stats = true,
+                "--trace" => parsed.trace = Some(value),
+                "--trace-range" => {
+                    let (start, end) = value.split_once(':')
+                        .ok_or_else(|| format!("invalid value '{}' for {}, expected <START>:<END>", value, flag))?;
+                    let (start, end) = (parse_u32(&flag, start)?, parse_u32(&flag, end)?);
+                    if start >= end {
+                        return Err(format!("empty range '{}' for {}", value, flag));
+                    }
+                    parsed.trace_range = Some((start, end));
+                }
+                "--gdb" => parsed.gdb = Some(u16::try_from(parse_number(&flag, &value)?)
+                    .map_err(|_| format!("invalid port '{}' for {}", value, flag))?),
+                "--isa-tests" => isa_tests = Some(value),
+                "--assemble" => assemble = Some(value),
+                "-o" | "--output" => output = Some(value),
+                "--elf" => elf = true,
+                _ if flag.starts_with('-') && flag != "-" => return Err(format!("unknown option '{}'", flag)),
+                _ => {
+                    if image.is_some() {
+                        return Err(format!("unexpected argument '{}'", arg));
+                    }
+                    image = Some(arg);
+                }
+            }
+        }
+
+        if let Some(dir) = isa_tests {
+            return Ok(Command::IsaTests(dir));
+        }
+        if let Some(source) = assemble {
+            let output = output.ok_or("--assemble needs --output")?;
+            return Ok(Command::Assemble(AssembleArgs { source, output, load_addr: parsed.load_addr, elf }));
+        }
+        if output.is_some() || elf {
+            return Err("--output and --elf only go with --assemble".to_string());
+        }
+        // The bus maps regions of up to 4 GiB - 1 bytes, so the whole address space is out of reach
+        if parsed.memsize_mb == 0 || parsed.memsize_mb > MAX_MEMSIZE_MB {
+            return Err(format!("memory size must be between 1 and {} MiB, got {}", MAX_MEMSIZE_MB, parsed.memsize_mb));
+        }
+        if parsed.ram_base as u64 + (parsed.memsize_mb as u64) * 1024 * 1024 > 1 << 32 {
+            return Err("memory does not fit in the 32-bit address space".to_string());
+        }
+        if parsed.trace_range.is_some() && parsed.trace.is_none() {
+            return Err("--trace-range needs --trace".to_string());
+        }
+        if parsed.fromhost.is_some() && parsed.tohost.is_none() {
+            return Err("--fromhost needs --tohost".to_string());
+        }
+        // Pages can't be larger than the smallest memory we allow (1 MiB)
+        if parsed.page_offset_bits > 20 {
+            return Err(format!("page offset bits must be at most 20, got {}", parsed.page_offset_bits));
+        }
+        parsed.image = image.ok_or("missing image path")?;
+        Ok(Command::Run(parsed))
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cli::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    fn parse_args(args: &[&str]) -> Args {
+        match parse(args) {
+            Ok(Command::Run(args)) => args,
+            other => panic!("Expected arguments, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let args = parse_args(&["prog.bin"]);
+        assert_eq!(args.image, "prog.bin");
+        assert_eq!(args.xlen, None);
+        assert_eq!(args.load_addr, DEFAULT_LOAD_ADDR);
+        assert_eq!(args.entry, None);
+        assert_eq!(args.ram_base, 0);
+        assert_eq!(args.memsize_mb, DEFAULT_MEMSIZE_MB);
+        assert_eq!(args.page_offset_bits, DEFAULT_PAGE_OFFSET_BITS);
+        assert_eq!(args.tohost, None);
+        assert_eq!(args.fromhost, None);
+        assert_eq!(args.uart, DEFAULT_UART_BASE);
+        assert_eq!(args.clint, DEFAULT_CLINT_BASE);
+        assert_eq!(args.plic, DEFAULT_PLIC_BASE);
+        assert_eq!(args.mtime, TimeSource::Instructions);
+        assert_eq!(args.max_instructions, None);
+        assert!(!args.no_gui);
+        assert!(!args.dump_regs);
+        assert!(!args.tlb_stats);
+        assert_eq!(args.trace, None);
+        assert_eq!(args.trace_range, None);
+        assert_eq!(args.gdb, None);
+    }
+
+    #[test]
+    fn test_all_flags() {
+        let args = parse_args(&["--xlen", "64", "--load-addr", "0x100", "--entry=0x104", "--ram-base", "0x8000_0000", "--memory", "4",
+            "--page-bits", "12", "--tohost", "0x1000", "--fromhost", "0x1040", "--uart", "0x2000_0000",
+            "--clint", "0x3000_0000", "--plic", "0x4000_0000", "--mtime", "host",
+            "--max-insts", "1_000", "--no-gui", "--dump-regs", "--tlb-stats", "--trace", "-", "--trace-range=0x100:0x200",
+            "--gdb", "1234", "prog.elf"]);
+        assert_eq!(args.image, "prog.elf");
+        assert_eq!(args.xlen, Some(Xlen::Rv64));
+        assert_eq!(args.load_addr, 0x100);
+        assert_eq!(args.entry, Some(0x104));
+        assert_eq!(args.ram_base, 0x8000_0000);
+        assert_eq!(args.memsize_mb, 4);
+        assert_eq!(args.page_offset_bits, 12);
+        assert_eq!(args.tohost, Some(0x1000));
+        assert_eq!(args.fromhost, Some(0x1040));
+        assert_eq!(args.uart, 0x2000_0000);
+        assert_eq!(args.clint, 0x3000_0000);
+        assert_eq!(args.plic, 0x4000_0000);
+        assert_eq!(args.mtime, TimeSource::Host);
+        assert_eq!(args.max_instructions, Some(1000));
+        assert!(args.no_gui);
+        assert!(args.dump_regs);
+        assert!(args.tlb_stats);
+        assert_eq!(args.trace, Some("-".to_string()));
+        assert_eq!(args.trace_range, Some((0x100, 0x200)));
+        assert_eq!(args.gdb, Some(1234));
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(parse(&["--no-gui", "-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_isa_tests() {
+        assert_eq!(parse(&["--isa-tests", "isa-tests/isa"]), Ok(Command::IsaTests("isa-tests/isa".to_string())));
+        assert!(parse(&["--isa-tests"]).is_err());
+    }
+
+    #[test]
+    fn test_assemble() {
+        assert_eq!(parse(&["--assemble", "prog.s", "-o", "prog.bin"]), Ok(Command::Assemble(AssembleArgs {
+            source: "prog.s".to_string(), output: "prog.bin".to_string(), load_addr: DEFAULT_LOAD_ADDR, elf: false,
+        })));
+        assert_eq!(parse(&["--elf", "--load-addr=0x8000_0000", "--assemble=prog.s", "--output", "prog.elf"]),
+            Ok(Command::Assemble(AssembleArgs {
+                source: "prog.s".to_string(), output: "prog.elf".to_string(), load_addr: 0x8000_0000, elf: true,
+            })));
+        assert!(parse(&["--assemble", "prog.s"]).is_err());
+        assert!(parse(&["prog.bin", "--elf"]).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["a.bin", "b.bin"]).is_err());
+        assert!(parse(&["--bogus", "a.bin"]).is_err());
+        assert!(parse(&["a.bin", "--entry"]).is_err());
+        assert!(parse(&["a.bin", "--entry", "0x1_0000_0000"]).is_err());
+        assert!(parse(&["a.bin", "--memory", "two"]).is_err());
+        assert!(parse(&["a.bin", "--memory", "0"]).is_err());
+        assert!(parse(&["a.bin", "--memory", "4096"]).is_err());
+        assert!(parse(&["a.bin", "--memory", "4095"]).is_ok());
+        assert!(parse(&["a.bin", "--ram-base", "0x10_0000", "--memory", "4095"]).is_ok());
+        assert!(parse(&["a.bin", "--ram-base", "0x10_0001", "--memory", "4095"]).is_err());
+        assert!(parse(&["a.bin", "--ram-base", "0xFFF0_0000", "--memory", "2"]).is_err());
+        assert!(parse(&["a.bin", "--no-gui=yes"]).is_err());
+        assert!(parse(&["a.bin", "--fromhost", "0x1040"]).is_err());
+        assert!(parse(&["a.bin", "--mtime", "sundial"]).is_err());
+        assert!(parse(&["a.bin", "--trace-range", "0x100:0x200"]).is_err());
+        assert!(parse(&["a.bin", "--trace", "-", "--trace-range", "0x100"]).is_err());
+        assert!(parse(&["a.bin", "--trace", "-", "--trace-range", "0x200:0x100"]).is_err());
+        assert!(parse(&["a.bin", "--gdb", "65536"]).is_err());
+        assert!(parse(&["a.bin", "--xlen", "128"]).is_err());
+        assert!(parse(&["a.bin", "--xlen=32"]).is_ok());
+    }
+}
@@ -1,57 +1,332 @@
 // RISC-V Tiny VM - Ivi Ballou / Amechania
 
 mod register;
-mod opcodes;
-mod memory;
+pub(crate) mod opcodes;
+mod compressed;
+mod decode;
+mod disasm;
+mod bus;
 mod instruction;
+mod csr;
+mod trap;
+mod mmu;
+mod pmp;
+mod softfloat;
+mod trace;
+mod debug;
 
 use crate::cpu::register::*;
-use crate::cpu::memory::Memory;
-const MEMSIZE_MB: usize = 2;
+pub(crate) use crate::cpu::register::{FREG_ALIASES, REG_ALIASES, REG_RA, REG_T1, REG_ZERO};
+use crate::cpu::bus::{Bus, BusError};
+use crate::cpu::bus::htif::Htif;
+pub(crate) use crate::cpu::bus::uart::{Uart, UART_BASE, UART_IRQ};
+use crate::cpu::bus::uart::UART_SIZE;
+pub(crate) use crate::cpu::bus::clint::{Clint, TimeSource, CLINT_BASE};
+use crate::cpu::bus::clint::CLINT_SIZE;
+pub(crate) use crate::cpu::bus::plic::{Plic, PLIC_BASE};
+use crate::cpu::bus::plic::PLIC_SIZE;
+use crate::cpu::csr::{MIP_MEIP, MIP_MSIP, MIP_MTIP};
+use crate::cpu::csr::Csr;
+use crate::cpu::trace::Trace;
+pub(crate) use crate::cpu::csr::{csr_name, csr_number};
+use crate::cpu::mmu::Mmu;
+pub(crate) use crate::cpu::trap::Exception;
+pub(crate) use crate::cpu::disasm::Disassembler;
+pub(crate) use crate::cpu::debug::MemoryAccess;
+use crate::elf::{Elf, ElfError, SymbolTable};
+pub(crate) const MEMSIZE_MB: usize = 2;
 const MEMSIZE: usize = MEMSIZE_MB*1024*1024; // 2MB
+pub(crate) const PAGE_OFFSET_BITS: usize = 8;
+
+// Width of the integer registers, picked when the CPU is created
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Xlen {
+    Rv32,
+    Rv64,
+}
+
+impl Xlen {
+    pub(crate) fn bits(self) -> u32 {
+        match self {
+            Xlen::Rv32 => 32,
+            Xlen::Rv64 => 64,
+        }
+    }
+
+    // Sign-extends the lower XLEN bits of `value`, the way registers hold them
+    pub(crate) fn sext(self, value: u64) -> u64 {
+        match self {
+            Xlen::Rv32 => value as i32 as u64,
+            Xlen::Rv64 => value,
+        }
+    }
+
+    // Zero-extends the lower XLEN bits of `value`, the way addresses and CSRs hold them
+    pub(crate) fn zext(self, value: u64) -> u64 {
+        match self {
+            Xlen::Rv32 => value as u32 as u64,
+            Xlen::Rv64 => value,
+        }
+    }
+}
+
+// Why `run` returned
+#[derive(Debug, PartialEq)]
+pub(crate) enum StopReason {
+    Exit(u32), // Exit code the program passed to the HTIF
+    InstructionLimit,
+    Fault(Fault),
+}
+
+// An exception that stopped execution, along with where it happened
+#[derive(Debug, PartialEq)]
+pub(crate) struct Fault {
+    pub(crate) exception: Exception,
+    pub(crate) pc: u64,
+    pub(crate) instruction: Option<u32>, // As fetched, None if the fetch itself faulted
+}
 
 pub struct CPU {
-    pc: u32,
+    xlen: Xlen,
+    pc: u64,
+    pub(crate) instret: u64, // Number of instructions retired
     pub(crate) registers: Register,
-    pub(crate) memory: Memory,
+    pub(crate) fregisters: FloatRegister,
+    pub(crate) csr: Csr,
+    pub(crate) mmu: Mmu,
+    pub(crate) memory: Bus,
+    pub(crate) symbols: SymbolTable,
     instruction: u32,
-    opcode: u8,
+    raw_instruction: u32, // The instruction as fetched, before a compressed one is expanded
+    compressed: bool, // The instruction was a 16-bit one, expanded into `instruction`
+    waiting: bool, // Stopped on WFI until an interrupt is pending
+    reservation: Option<(u32, u32)>, // Physical address and size of what LR reserved
+    trace: Option<Trace>,
 }
 
 #[allow(dead_code)]
 impl CPU {
     pub fn new() -> Self {
-        Self {
-        pc: 4,
+        Self::with_xlen(Xlen::Rv32)
+    }
+
+    pub(crate) fn with_xlen(xlen: Xlen) -> Self {
+        Self::with_memory(xlen, 0, MEMSIZE, PAGE_OFFSET_BITS).expect("the default RAM fits in the address space")
+    }
+
+    // CPU with `memsize` bytes of RAM at `ram_base`, which must fit below 4 GiB.
+    // Physical addresses are 32 bits wide whatever XLEN is.
+    pub(crate) fn with_memory(xlen: Xlen, ram_base: u32, memsize: usize, page_offset_bits: usize) -> Result<Self, BusError> {
+        Ok(Self {
+            xlen,
+            pc: 4,
+            instret: 0,
             registers: Register::new(),
-            memory: Memory::new(MEMSIZE, 8),
+            fregisters: FloatRegister::new(),
+            csr: Csr::with_xlen(xlen),
+            mmu: Mmu::new(),
+            memory: Bus::with_ram(ram_base, memsize, page_offset_bits)?,
+            symbols: SymbolTable::default(),
             instruction: 0,
-            opcode: 0,
-        }
+            raw_instruction: 0,
+            compressed: false,
+            waiting: false,
+            reservation: None,
+            trace: None,
+        })
     }
     
-    fn get_pc(&self) -> u32 {
+    pub(crate) fn xlen(&self) -> Xlen {
+        self.xlen
+    }
+
+    pub(crate) fn get_pc(&self) -> u64 {
         self.pc
     }
 
-    fn fetch_inst(&mut self) {
-        self.instruction = self.memory.get_u32(self.pc);
-        self.opcode = (self.instruction & 0x7F) as u8;
+    pub(crate) fn set_pc(&mut self, pc: u64) {
+        self.pc = self.xlen.zext(pc);
+    }
+
+    // Fetches the instruction at pc, expanding a compressed one to its 32-bit form.
+    // With C on instructions are only halfword aligned, so a 32-bit one at pc % 4 == 2
+    // is fetched in two parcels, it may well straddle a page.
+    fn fetch_inst(&mut self) -> Result<(), Exception> {
+        let aligned = self.pc & 0x3 == 0;
+        let first = if aligned { self.fetch_u32(self.pc)? } else { self.fetch_u16(self.pc)? as u32 };
+        self.compressed = compressed::is_compressed(first as u16);
+        if self.compressed {
+            // Reported as is if it turns out to be illegal
+            self.instruction = first & 0xFFFF;
+            self.raw_instruction = self.instruction;
+            if !self.csr.has_compressed() {
+                return Err(Exception::IllegalInstruction(self.instruction));
+            }
+            self.instruction = compressed::expand(first as u16, self.xlen).ok_or(Exception::IllegalInstruction(self.instruction))?;
+        } else if aligned {
+            self.instruction = first;
+            self.raw_instruction = first;
+        } else {
+            self.instruction = first | (self.fetch_u16(self.xlen.zext(self.pc.wrapping_add(2)))? as u32) << 16;
+            self.raw_instruction = self.instruction;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load_image(&mut self, offset: u32, program: &[u8]) -> Result<(), Exception> {
+        self.memory.load_image(offset, program)
+    }
+
+    // Maps every loadable segment into memory, points the PC at the entry point
+    // and keeps the symbols around, so addresses can be resolved to names.
+    // Programs with a tohost symbol get an HTIF to talk to the host. The ELF class has
+    // to match XLEN.
+    pub(crate) fn load_elf(&mut self, elf: Elf) -> Result<(), ElfError> {
+        if elf.xlen != self.xlen {
+            return Err(ElfError::WrongXlen { elf: elf.xlen, cpu: self.xlen });
+        }
+        for segment in &elf.segments {
+            self.memory.load_segment(segment).map_err(|_| ElfError::OutOfMemory(segment.vaddr))?;
+        }
+        if let Some(tohost) = elf.symbols.lookup("tohost") {
+            self.attach_htif(tohost, elf.symbols.lookup("fromhost"));
+        }
+        self.pc = elf.entry as u64;
+        self.symbols = elf.symbols;
+        Ok(())
+    }
+
+    // Maps the HTIF tohost (and fromhost, if there is one) registers at the given addresses
+    pub(crate) fn attach_htif(&mut self, tohost: u32, fromhost: Option<u32>) {
+        self.memory.attach_htif(Htif::new(tohost, fromhost));
+    }
+
+    // Maps a UART at `base`, its interrupt line goes to PLIC source `irq`
+    pub(crate) fn attach_uart(&mut self, base: u32, uart: Uart, irq: u32) -> Result<(), BusError> {
+        self.memory.attach_with_irq(base, UART_SIZE, Box::new(uart), irq)
+    }
+
+    // Maps a CLINT at `base`, its timer and software interrupts go to mip
+    pub(crate) fn attach_clint(&mut self, base: u32, clint: Clint) -> Result<(), BusError> {
+        self.memory.attach(base, CLINT_SIZE, Box::new(clint))
     }
 
-    pub(crate) fn load_image(&mut self, offset: u32, program: &Vec<u8>) {
-        self.memory.load_image(offset, program);
+    // Maps a PLIC at `base`, its M-mode context drives mip.MEIP and its S-mode context mip.SEIP
+    pub(crate) fn attach_plic(&mut self, base: u32, plic: Plic) -> Result<(), BusError> {
+        self.memory.attach(base, PLIC_SIZE, Box::new(plic))
+    }
+
+    // Samples the interrupt lines of the devices into mip
+    fn update_interrupts(&mut self) {
+        if let Some(clint) = self.memory.device::<Clint>() {
+            let (msip, mtip) = (clint.msip(), clint.mtip());
+            self.csr.set_pending(MIP_MSIP, msip);
+            self.csr.set_pending(MIP_MTIP, mtip);
+        }
+        self.memory.route_irqs();
+        if let Some(plic) = self.memory.device::<Plic>() {
+            let (meip, seip) = (plic.irq(0), plic.irq(1));
+            self.csr.set_pending(MIP_MEIP, meip);
+            self.csr.set_supervisor_external(seip);
+        }
     }
 
-    pub(crate) fn run(&mut self, start: u32) {
-        self.pc = start;
+    // Executes a single instruction, taking the trap if it raises an exception,
+    // and lets the devices on the bus advance. A pending interrupt is taken first,
+    // the instruction is then the first one of its handler. While waiting on WFI
+    // only the devices advance. Returns the fault if there's no handler to trap to.
+    pub(crate) fn step(&mut self) -> Result<(), Fault> {
+        self.update_interrupts();
+        if self.waiting {
+            if !self.csr.has_pending_interrupt() {
+                self.memory.tick();
+                return Ok(());
+            }
+            self.waiting = false;
+        }
+        // Without a handler to go to, an interrupt stays pending
+        let interrupt = self.csr.interrupt_to_take().filter(|interrupt| self.csr.has_trap_handler(interrupt.cause(), true));
+        if let Some(interrupt) = interrupt {
+            self.take_interrupt(interrupt);
+        }
+
+        let pc = self.pc;
+        self.trace_begin();
+        let result = self.fetch_inst().and_then(|_| self.exec_inst());
+        self.memory.tick();
+        self.trace_step(pc, &result);
+        match result {
+            Ok(()) => {
+                self.instret += 1;
+                Ok(())
+            }
+            // Before software has set up a handler, the trap would jump to wherever
+            // the trap vector comes out of reset. Stop and report it instead
+            Err(exception) if !self.csr.has_trap_handler(exception.cause(), false) => {
+                // Nothing has been fetched if the fetch itself faulted
+                let instruction = match exception {
+                    Exception::InstructionAccessFault(_) | Exception::InstructionPageFault(_) => None,
+                    _ => Some(self.raw_instruction),
+                };
+                Err(Fault { exception, pc, instruction })
+            }
+            Err(exception) => {
+                self.take_exception(exception);
+                Ok(())
+            }
+        }
+    }
+
+    // Runs from `start` until the program exits, or until `max_instructions` have been executed
+    pub(crate) fn run(&mut self, start: u64, max_instructions: Option<u64>) -> StopReason {
+        self.set_pc(start);
+        let mut executed: u64 = 0;
         loop {
-            self.fetch_inst();
-            if self.exec_inst() {
-                return;
+            if max_instructions.is_some_and(|max| executed >= max) {
+                return StopReason::InstructionLimit;
+            }
+            executed += 1;
+            if let Err(fault) = self.step() {
+                return StopReason::Fault(fault);
+            }
+            if let Some(code) = self.memory.exit_code() {
+                return StopReason::Exit(code);
             }
         }
     }
+
+    // Prints the hit and miss counts of both TLBs
+    pub(crate) fn dump_tlb_stats(&self) {
+        for (name, tlb) in [("itlb", &self.mmu.fetch), ("dtlb", &self.mmu.data)] {
+            let lookups = tlb.hits() + tlb.misses();
+            let rate = if lookups == 0 { 0.0 } else { tlb.hits() as f64 * 100.0 / lookups as f64 };
+            println!("{} {:>12} hits {:>12} misses ({:.2}% hit rate)", name, tlb.hits(), tlb.misses(), rate);
+        }
+    }
+
+    // Prints the PC and every register, four per line, then the FP registers if the FPU is on
+    pub(crate) fn dump_registers(&self) {
+        let width = self.xlen.bits() as usize / 4;
+        println!("pc   0x{:0width$x}", self.pc);
+        for row in 0..8 {
+            let line: Vec<String> = (0..4).map(|col| {
+                let reg = row * 4 + col;
+                let value = self.xlen.zext(self.registers.get_x(reg as u8));
+                format!("x{:<2} {:>4} 0x{:0width$x}", reg, REG_ALIASES[reg], value)
+            }).collect();
+            println!("{}", line.join("   "));
+        }
+        if !self.csr.fp_enabled() {
+            return;
+        }
+        for row in 0..8 {
+            let line: Vec<String> = (0..4).map(|col| {
+                let reg = row * 4 + col;
+                format!("f{:<2} {:>4} 0x{:016x}", reg, FREG_ALIASES[reg], self.fregisters.get_f64(reg as u8))
+            }).collect();
+            println!("{}", line.join("   "));
+        }
+    }
 }
 
 ///// TESTS /////
@@ -59,6 +334,7 @@ impl CPU {
 #[allow(non_snake_case)]
 mod tests {
     use crate::cpu::*;
+    use crate::cpu::csr::*;
     use crate::cpu::opcodes::*;
 
     #[test]
@@ -66,14 +342,286 @@ mod tests {
         let mut cpu = CPU::new();
         cpu.pc = 0x10;
         let instruction = 0xA51E9F80 | OP::JAL as u32;
-        cpu.memory.set_u32(cpu.pc, instruction);
+        cpu.memory.set_u32(cpu.pc as u32, instruction).unwrap();
 
         // Fetch instruction
-        cpu.fetch_inst();
+        cpu.fetch_inst().unwrap();
 
         // Verify results
         assert_eq!(cpu.instruction, instruction);
-        assert_eq!(cpu.opcode, OP::JAL);
+        assert!(matches!(decode::decode(cpu.instruction, Xlen::Rv32), Some(decode::Instruction::Jal { .. })));
+    }
+
+    #[test]
+    fn test_run_compressed() {
+        let mut cpu = CPU::new();
+        // c.li a0, 1; addi s0, s0, 1; c.jal 4; c.nop; c.add s0, a0
+        cpu.memory.set_u16(0x10, 0x4505).unwrap();
+        cpu.memory.set_u16(0x12, 0x0413).unwrap();
+        cpu.memory.set_u16(0x14, 0x0014).unwrap();
+        cpu.memory.set_u16(0x16, 0x2011).unwrap();
+        cpu.memory.set_u16(0x18, 0x0001).unwrap();
+        cpu.memory.set_u16(0x1A, 0x942A).unwrap();
+        assert_eq!(cpu.run(0x10, Some(4)), StopReason::InstructionLimit);
+        assert_eq!(cpu.pc, 0x1C);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0x18, "c.jal links to the next halfword");
+        assert_eq!(cpu.registers.get_register(REG_S0), 2);
     }
-}
 
+    #[test]
+    fn test_run_compressed_without_c() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MISA, 0).unwrap();
+        cpu.memory.set_u16(0x10, 0x4505).unwrap();
+        let expected = Fault {
+            exception: Exception::IllegalInstruction(0x4505),
+            pc: 0x10,
+            instruction: Some(0x4505),
+        };
+        assert_eq!(cpu.run(0x10, None), StopReason::Fault(expected));
+    }
+
+    #[test]
+    fn test_run_limit() {
+        let mut cpu = CPU::new();
+        // Ten times addi s0, s0, 1
+        for i in 0..10 {
+            cpu.memory.set_u32(0x10 + i * 4, 0x00140413).unwrap();
+        }
+        assert_eq!(cpu.run(0x10, Some(5)), StopReason::InstructionLimit);
+        assert_eq!(cpu.instret, 5);
+        assert_eq!(cpu.registers.get_register(REG_S0), 5);
+    }
+
+    #[test]
+    fn test_run_exit() {
+        let mut cpu = CPU::new();
+        cpu.attach_htif(0x100, Some(0x140));
+        // li a0, 7; sw a0, 0x100(zero); sw zero, 0x104(zero)
+        cpu.memory.set_u32(0x10, 0x00700513).unwrap();
+        cpu.memory.set_u32(0x14, 0x10A02023).unwrap();
+        cpu.memory.set_u32(0x18, 0x10002223).unwrap();
+        assert_eq!(cpu.run(0x10, Some(5)), StopReason::Exit(3));
+        assert_eq!(cpu.instret, 3);
+    }
+
+    #[test]
+    fn test_run_exit_rv64() {
+        let mut cpu = CPU::with_xlen(Xlen::Rv64);
+        cpu.attach_htif(0x100, None);
+        // li a0, 7; sd a0, 0x100(zero): the whole of tohost in one store
+        cpu.memory.set_u32(0x10, 0x00700513).unwrap();
+        cpu.memory.set_u32(0x14, 0x10A03023).unwrap();
+        assert_eq!(cpu.run(0x10, Some(5)), StopReason::Exit(3));
+    }
+
+    #[test]
+    fn test_load_elf_wrong_xlen() {
+        let elf = Elf { xlen: Xlen::Rv64, entry: 0x10, segments: Vec::new(), symbols: SymbolTable::default() };
+        assert_eq!(CPU::new().load_elf(elf).err(), Some(ElfError::WrongXlen { elf: Xlen::Rv64, cpu: Xlen::Rv32 }));
+        let elf = Elf { xlen: Xlen::Rv64, entry: 0x10, segments: Vec::new(), symbols: SymbolTable::default() };
+        let mut cpu = CPU::with_xlen(Xlen::Rv64);
+        cpu.load_elf(elf).unwrap();
+        assert_eq!(cpu.get_pc(), 0x10);
+    }
+
+    // CPU with a CLINT whose timer fires once mtime reaches `deadline`
+    fn setup_timer(deadline: u32) -> CPU {
+        let mut cpu = CPU::new();
+        cpu.attach_clint(CLINT_BASE, Clint::new(TimeSource::Instructions)).unwrap();
+        cpu.memory.set_u32(CLINT_BASE + 0x4000, deadline).unwrap();
+        cpu.memory.set_u32(CLINT_BASE + 0x4004, 0).unwrap();
+        cpu.csr.write(MIE, MIP_MTIP).unwrap();
+        // Ten times addi s0, s0, 1, then the handler does addi s1, s1, 1
+        for i in 0..10 {
+            cpu.memory.set_u32(0x10 + i * 4, 0x00140413).unwrap();
+        }
+        cpu.memory.set_u32(0x100, 0x00148493).unwrap();
+        cpu.csr.write(MTVEC, 0x100).unwrap();
+        cpu
+    }
+
+    #[test]
+    fn test_timer_interrupt() {
+        let mut cpu = setup_timer(5);
+        cpu.csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        assert_eq!(cpu.run(0x10, Some(6)), StopReason::InstructionLimit);
+        // mtime reached 5 after the fifth instruction, the sixth one is the handler's
+        assert_eq!(cpu.registers.get_register(REG_S0), 5);
+        assert_eq!(cpu.registers.get_register(REG_S1), 1);
+        assert_eq!(cpu.csr.read(MCAUSE), Some(0x8000_0007));
+        assert_eq!(cpu.csr.read(MEPC), Some(0x24));
+        assert_eq!(cpu.csr.read(MSTATUS), Some(MSTATUS_MPIE | MSTATUS_MPP), "Interrupts should be off in the handler");
+        assert_eq!(cpu.pc, 0x104);
+    }
+
+    #[test]
+    fn test_timer_interrupt_disabled() {
+        let mut cpu = setup_timer(5);
+        assert_eq!(cpu.run(0x10, Some(10)), StopReason::InstructionLimit);
+        assert_eq!(cpu.registers.get_register(REG_S0), 10);
+        assert_eq!(cpu.registers.get_register(REG_S1), 0);
+        assert_eq!(cpu.csr.read(MIP), Some(MIP_MTIP), "The interrupt should still be pending");
+    }
+
+    #[test]
+    fn test_software_interrupt() {
+        let mut cpu = setup_timer(u32::MAX);
+        cpu.csr.write(MIE, MIP_MSIP).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        // sw s0, 0(s2) with s2 pointing at msip
+        cpu.registers.set_register(REG_S2, CLINT_BASE);
+        cpu.registers.set_register(REG_S0, 1);
+        cpu.memory.set_u32(0x10, 0x00892023).unwrap();
+        assert_eq!(cpu.run(0x10, Some(2)), StopReason::InstructionLimit);
+        assert_eq!(cpu.csr.read(MCAUSE), Some(0x8000_0003));
+        assert_eq!(cpu.csr.read(MEPC), Some(0x14));
+        assert_eq!(cpu.registers.get_register(REG_S1), 1);
+    }
+
+    #[test]
+    fn test_wfi_wakeup() {
+        let mut cpu = setup_timer(10);
+        // wfi, with the timer enabled in mie but interrupts globally off
+        cpu.memory.set_u32(0x10, 0x10500073).unwrap();
+        assert_eq!(cpu.run(0x10, Some(10)), StopReason::InstructionLimit);
+        assert!(cpu.waiting);
+        assert_eq!(cpu.registers.get_register(REG_S0), 0);
+        // The timer fires at the end of the tenth step, so the eleventh one runs the addi
+        assert_eq!(cpu.run(0x14, Some(1)), StopReason::InstructionLimit);
+        assert!(!cpu.waiting);
+        assert_eq!(cpu.registers.get_register(REG_S0), 1);
+        assert_eq!(cpu.instret, 2);
+    }
+
+    #[test]
+    fn test_wfi_nothing_enabled() {
+        let mut cpu = setup_timer(10);
+        cpu.csr.write(MIE, 0).unwrap();
+        cpu.memory.set_u32(0x10, 0x10500073).unwrap();
+        assert_eq!(cpu.run(0x10, Some(2)), StopReason::InstructionLimit);
+        assert_eq!(cpu.registers.get_register(REG_S0), 1, "WFI should not wait for what can't happen");
+    }
+
+    #[test]
+    fn test_external_interrupt() {
+        let mut cpu = setup_timer(u32::MAX);
+        cpu.attach_plic(PLIC_BASE, Plic::new()).unwrap();
+        cpu.attach_uart(UART_BASE, Uart::new(Box::new(std::io::sink())), UART_IRQ).unwrap();
+        cpu.memory.set_u32(PLIC_BASE + UART_IRQ * 4, 1).unwrap();
+        cpu.memory.set_u32(PLIC_BASE + 0x2000, 1 << UART_IRQ).unwrap();
+        cpu.csr.write(MIE, MIP_MEIP).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        assert_eq!(cpu.run(0x10, Some(1)), StopReason::InstructionLimit);
+        assert_eq!(cpu.registers.get_register(REG_S1), 0);
+
+        // Enabling the THRE interrupt raises the UART's line straight away
+        cpu.memory.set_u8(UART_BASE + 1, 0x02).unwrap();
+        assert_eq!(cpu.run(0x14, Some(1)), StopReason::InstructionLimit);
+        assert_eq!(cpu.csr.read(MCAUSE), Some(0x8000_000B));
+        assert_eq!(cpu.csr.read(MEPC), Some(0x14));
+        assert_eq!(cpu.registers.get_register(REG_S1), 1);
+        assert_eq!(cpu.memory.get_u32(PLIC_BASE + 0x20_0004), Ok(UART_IRQ), "The handler claims the UART");
+    }
+
+    #[test]
+    fn test_run_zero_is_illegal() {
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(0x10, 0x00140413).unwrap();
+        let expected = Fault {
+            exception: Exception::IllegalInstruction(0),
+            pc: 0x14,
+            instruction: Some(0),
+        };
+        assert_eq!(cpu.run(0x10, Some(5)), StopReason::Fault(expected));
+        assert_eq!(cpu.instret, 1);
+    }
+
+    #[test]
+    fn test_handler_at_zero() {
+        // A handler at 0 is as good as any once mtvec has been written
+        let mut cpu = CPU::new();
+        cpu.csr.write(MTVEC, 0).unwrap();
+        assert_eq!(cpu.run(0x10, Some(1)), StopReason::InstructionLimit);
+        assert_eq!(cpu.pc, 0);
+        assert_eq!(cpu.csr.read(MCAUSE), Some(2));
+        assert_eq!(cpu.csr.read(MEPC), Some(0x10));
+    }
+
+    #[test]
+    fn test_interrupt_without_handler() {
+        let mut cpu = setup_timer(2);
+        cpu.csr = Csr::with_xlen(Xlen::Rv32);
+        cpu.csr.write(MIE, MIP_MTIP).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        assert_eq!(cpu.run(0x10, Some(10)), StopReason::InstructionLimit);
+        assert_eq!(cpu.registers.get_register(REG_S0), 10, "mtvec was never written, so the interrupt is not taken");
+        assert_eq!(cpu.csr.read(MIP).map(|mip| mip & MIP_MTIP), Some(MIP_MTIP), "It stays pending");
+    }
+
+    #[test]
+    fn test_run_illegal_instruction() {
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(0x10, 0x00140413).unwrap();
+        cpu.memory.set_u32(0x14, 0xFFFFFFFF).unwrap();
+        let expected = Fault {
+            exception: Exception::IllegalInstruction(0xFFFFFFFF),
+            pc: 0x14,
+            instruction: Some(0xFFFFFFFF),
+        };
+        assert_eq!(cpu.run(0x10, None), StopReason::Fault(expected));
+    }
+
+    #[test]
+    fn test_run_access_fault() {
+        let mut cpu = CPU::new();
+        // lw s0, 0(s1) with s1 pointing past the end of memory
+        cpu.registers.set_register(REG_S1, MEMSIZE as u32);
+        cpu.memory.set_u32(0x10, 0x0004A403).unwrap();
+        let expected = Fault {
+            exception: Exception::LoadAccessFault(MEMSIZE as u64),
+            pc: 0x10,
+            instruction: Some(0x0004A403),
+        };
+        assert_eq!(cpu.run(0x10, None), StopReason::Fault(expected));
+
+        let expected = Fault {
+            exception: Exception::InstructionAccessFault(MEMSIZE as u64),
+            pc: MEMSIZE as u64,
+            instruction: None,
+        };
+        assert_eq!(cpu.run(MEMSIZE as u64, None), StopReason::Fault(expected));
+    }
+
+    #[test]
+    fn test_run_compressed_fault() {
+        // c.lw a0, 0(a1) with a1 pointing past the end of memory: the fault has the parcel as fetched
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(11, MEMSIZE as u32);
+        cpu.memory.set_u16(0x10, 0x4188).unwrap();
+        let expected = Fault {
+            exception: Exception::LoadAccessFault(MEMSIZE as u64),
+            pc: 0x10,
+            instruction: Some(0x4188),
+        };
+        assert_eq!(cpu.run(0x10, None), StopReason::Fault(expected));
+    }
+
+    #[test]
+    fn test_run_page_fault() {
+        // S-mode with an empty root page table: the first fetch faults, leaving nothing to report
+        let mut cpu = CPU::new();
+        cpu.csr.write(crate::cpu::pmp::PMPADDR0, 0xFFFF_FFFF).unwrap();
+        cpu.csr.write(crate::cpu::pmp::PMPCFG0, 0x1F).unwrap();
+        cpu.csr.write(SATP, SATP_MODE_SV32 | 1).unwrap();
+        cpu.csr.write(MSTATUS, 1 << 11).unwrap();
+        cpu.csr.leave_trap();
+        cpu.instruction = 0x0004A403;
+        let expected = Fault {
+            exception: Exception::InstructionPageFault(0x4000),
+            pc: 0x4000,
+            instruction: None,
+        };
+        assert_eq!(cpu.run(0x4000, None), StopReason::Fault(expected));
+    }
+}
@@ -0,0 +1,554 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* System bus
+ *
+ * Everything the CPU can load from or store to is a device mapped at some
+ * address range on the bus, RAM included. The bus finds the device an
+ * access falls in and hands it the offset into its range; accesses to
+ * unmapped addresses, or that the device refuses, become access faults.
+ *
+ * Devices can be given an interrupt line when they're attached, a source
+ * of the PLIC. The lines are routed to it once per instruction.
+ *
+ * The HTIF is the exception: its registers normally sit in the middle of
+ * RAM, and its syscall proxy needs to read the program's memory, so it's
+ * checked before the devices instead of being one of them.
+ */
+
+use std::any::Any;
+use std::fmt;
+use crate::cpu::bus::ram::{Ram, Page};
+use crate::cpu::bus::htif::Htif;
+use crate::cpu::bus::plic::{Plic, NUM_SOURCES};
+use crate::cpu::trap::Exception;
+use crate::elf::Segment;
+
+pub mod ram;
+pub mod htif;
+pub mod uart;
+pub mod clint;
+pub mod plic;
+
+// A memory mapped device. Offsets are relative to where the device is mapped,
+// returning None makes the access fault. Only the byte accessors are needed,
+// wider accesses are split into little endian bytes unless overridden.
+pub(crate) trait Device: Any {
+    fn read_u8(&mut self, offset: u32) -> Option<u8>;
+    fn write_u8(&mut self, offset: u32, value: u8) -> Option<()>;
+
+    fn read_u16(&mut self, offset: u32) -> Option<u16> {
+        Some(u16::from_le_bytes([self.read_u8(offset)?, self.read_u8(offset + 1)?]))
+    }
+
+    fn write_u16(&mut self, offset: u32, value: u16) -> Option<()> {
+        for (i, byte) in value.to_le_bytes().iter().enumerate() {
+            self.write_u8(offset + i as u32, *byte)?;
+        }
+        Some(())
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Option<u32> {
+        let mut bytes = [0u8; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.read_u8(offset + i as u32)?;
+        }
+        Some(u32::from_le_bytes(bytes))
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) -> Option<()> {
+        for (i, byte) in value.to_le_bytes().iter().enumerate() {
+            self.write_u8(offset + i as u32, *byte)?;
+        }
+        Some(())
+    }
+
+    // Called once per executed instruction, for devices that need a notion of time
+    fn tick(&mut self) {}
+
+    // State of the device's interrupt line
+    fn irq(&self) -> bool {
+        false
+    }
+
+    // Accesses for a debugger, which mustn't disturb the device. Only plain
+    // memory can do without side effects, so the default is to refuse them.
+    fn peek_u8(&self, _offset: u32) -> Option<u8> {
+        None
+    }
+
+    fn poke_u8(&mut self, _offset: u32, _value: u8) -> Option<()> {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum BusError {
+    Empty,
+    OutOfRange(u32), // Base of a region that runs past the end of the address space
+    Overlap(u32), // Base of the region already mapped there
+    InvalidIrq(u32),
+}
+
+impl fmt::Display for BusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BusError::Empty => write!(f, "device has no address range"),
+            BusError::OutOfRange(base) => write!(f, "device at 0x{:08x} does not fit in the address space", base),
+            BusError::Overlap(base) => write!(f, "device overlaps the one at 0x{:08x}", base),
+            BusError::InvalidIrq(irq) => write!(f, "interrupt line {} does not exist", irq),
+        }
+    }
+}
+
+// Output that can still be read once it's been handed to a device, for the tests
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedOutput(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedOutput {
+    pub(crate) fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+#[cfg(test)]
+impl std::io::Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Region {
+    base: u32,
+    size: u32,
+    device: Box<dyn Device>,
+    irq: Option<u32>, // PLIC source the device's interrupt line goes to
+}
+
+impl Region {
+    // Offset of an access of `size` bytes at `address`, if it fits entirely in this region
+    fn offset(&self, address: u32, size: u32) -> Option<u32> {
+        let offset = address.checked_sub(self.base)?;
+        (offset as u64 + size as u64 <= self.size as u64).then_some(offset)
+    }
+}
+
+pub(crate) struct Bus {
+    regions: Vec<Region>,
+    htif: Option<Htif>,
+}
+
+impl Bus {
+    pub(crate) fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+            htif: None,
+        }
+    }
+
+    // Bus with `memsize` bytes of RAM mapped at `ram_base`. The range is checked
+    // before the RAM is allocated.
+    pub(crate) fn with_ram(ram_base: u32, memsize: usize, page_offset_bits: usize) -> Result<Self, BusError> {
+        let size = u32::try_from(memsize).map_err(|_| BusError::OutOfRange(ram_base))?;
+        if size == 0 {
+            return Err(BusError::Empty);
+        }
+        if ram_base as u64 + size as u64 > 1 << 32 {
+            return Err(BusError::OutOfRange(ram_base));
+        }
+        let mut bus = Self::new();
+        bus.attach(ram_base, size, Box::new(Ram::new(memsize, page_offset_bits)))?;
+        Ok(bus)
+    }
+
+    // Maps `device` at [base, base + size)
+    pub(crate) fn attach(&mut self, base: u32, size: u32, device: Box<dyn Device>) -> Result<(), BusError> {
+        self.attach_region(Region { base, size, device, irq: None })
+    }
+
+    // Same as attach, with the device's interrupt line going to PLIC source `irq`
+    pub(crate) fn attach_with_irq(&mut self, base: u32, size: u32, device: Box<dyn Device>, irq: u32)
+        -> Result<(), BusError> {
+        if irq == 0 || irq as usize >= NUM_SOURCES {
+            return Err(BusError::InvalidIrq(irq));
+        }
+        self.attach_region(Region { base, size, device, irq: Some(irq) })
+    }
+
+    fn attach_region(&mut self, region: Region) -> Result<(), BusError> {
+        let (base, size) = (region.base, region.size);
+        if size == 0 {
+            return Err(BusError::Empty);
+        }
+        let end = base as u64 + size as u64;
+        if end > 1 << 32 {
+            return Err(BusError::OutOfRange(base));
+        }
+        if let Some(region) = self.regions.iter()
+            .find(|region| (base as u64) < region.base as u64 + region.size as u64 && end > region.base as u64) {
+            return Err(BusError::Overlap(region.base));
+        }
+        self.regions.push(region);
+        Ok(())
+    }
+
+    // First device of type T on the bus
+    pub(crate) fn device<T: Device>(&self) -> Option<&T> {
+        self.regions.iter().find_map(|region| (region.device.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub(crate) fn device_mut<T: Device>(&mut self) -> Option<&mut T> {
+        self.regions.iter_mut().find_map(|region| (region.device.as_mut() as &mut dyn Any).downcast_mut::<T>())
+    }
+
+    // Lets every device advance by one instruction
+    pub(crate) fn tick(&mut self) {
+        for region in &mut self.regions {
+            region.device.tick();
+        }
+    }
+
+    // Hands the interrupt lines of the devices over to the PLIC, if there is one
+    pub(crate) fn route_irqs(&mut self) {
+        let lines = self.regions.iter()
+            .filter_map(|region| region.irq.filter(|_| region.device.irq()))
+            .fold(0u128, |lines, irq| lines | 1 << irq);
+        if let Some(plic) = self.device_mut::<Plic>() {
+            plic.set_lines(lines);
+        }
+    }
+
+    // From now on, accesses to the HTIF registers go to `htif` instead of the devices
+    pub(crate) fn attach_htif(&mut self, htif: Htif) {
+        self.htif = Some(htif);
+    }
+
+    // Exit code, once the program has asked the HTIF to exit
+    pub(crate) fn exit_code(&self) -> Option<u32> {
+        self.htif.as_ref().and_then(|htif| htif.exit_code())
+    }
+
+    fn htif_read(&self, address: u32, size: u32) -> Option<u32> {
+        self.htif.as_ref().and_then(|htif| htif.read(address, size))
+    }
+
+    // Returns false if `address` isn't an HTIF register
+    fn htif_write(&mut self, address: u32, size: u32, value: u32) -> bool {
+        let command = match &mut self.htif {
+            Some(htif) if htif.contains(address) => htif.write(address, size, value),
+            _ => return false,
+        };
+        if let Some(command) = command {
+            // Taken out for the duration of the command, so it can access the rest of the bus
+            let mut htif = self.htif.take().unwrap();
+            htif.execute(command, self);
+            self.htif = Some(htif);
+        }
+        true
+    }
+
+    // Device an access of `size` bytes at `address` goes to, and the offset into it
+    fn locate(&mut self, address: u32, size: u32) -> Option<(&mut dyn Device, u32)> {
+        self.regions.iter_mut()
+            .find_map(|region| region.offset(address, size).map(|offset| (region.device.as_mut(), offset)))
+    }
+
+    // Reads `size` bytes for a debugger, without side effects. That's RAM and
+    // the HTIF registers, the other devices can't be peeked at.
+    pub(crate) fn peek(&self, address: u32, size: u32) -> Option<u32> {
+        if let Some(value) = self.htif_read(address, size) {
+            return Some(value);
+        }
+        let (region, offset) = self.regions.iter()
+            .find_map(|region| region.offset(address, size).map(|offset| (region, offset)))?;
+        (0..size).rev().try_fold(0, |value, i| Some(value << 8 | region.device.peek_u8(offset + i)? as u32))
+    }
+
+    // Writes a byte for a debugger, to RAM only. Writing the HTIF registers
+    // would run a command, writing other devices could do anything.
+    pub(crate) fn poke_u8(&mut self, address: u32, value: u8) -> Option<()> {
+        if self.htif.as_ref().is_some_and(|htif| htif.contains(address)) {
+            return None;
+        }
+        let (device, offset) = self.locate(address, 1)?;
+        device.poke_u8(offset, value)
+    }
+
+    // Pages of the RAM, for the memory viewer
+    pub(crate) fn get_memory(&self) -> &[Page] {
+        self.device::<Ram>().map_or(&[], |ram| ram.get_memory())
+    }
+
+    // Sets a byte on the bus
+    pub fn set_u8(&mut self, address: u32, value: u8) -> Result<(), Exception> {
+        if self.htif_write(address, 1, value as u32) {
+            return Ok(());
+        }
+        self.locate(address, 1)
+            .and_then(|(device, offset)| device.write_u8(offset, value))
+            .ok_or(Exception::StoreAccessFault(address as u64))
+    }
+
+    // Gets a byte from the bus
+    pub fn get_u8(&mut self, address: u32) -> Result<u8, Exception> {
+        if let Some(value) = self.htif_read(address, 1) {
+            return Ok(value as u8);
+        }
+        self.locate(address, 1)
+            .and_then(|(device, offset)| device.read_u8(offset))
+            .ok_or(Exception::LoadAccessFault(address as u64))
+    }
+
+    // Stores a half word on the bus, the device decides how to split it
+    pub fn set_u16(&mut self, address: u32, value: u16) -> Result<(), Exception> {
+        if self.htif_write(address, 2, value as u32) {
+            return Ok(());
+        }
+        self.locate(address, 2)
+            .and_then(|(device, offset)| device.write_u16(offset, value))
+            .ok_or(Exception::StoreAccessFault(address as u64))
+    }
+
+    // Gets a half word from the bus
+    pub fn get_u16(&mut self, address: u32) -> Result<u16, Exception> {
+        if let Some(value) = self.htif_read(address, 2) {
+            return Ok(value as u16);
+        }
+        self.locate(address, 2)
+            .and_then(|(device, offset)| device.read_u16(offset))
+            .ok_or(Exception::LoadAccessFault(address as u64))
+    }
+
+    // Stores a word on the bus, the device decides how to split it
+    pub fn set_u32(&mut self, address: u32, value: u32) -> Result<(), Exception> {
+        if self.htif_write(address, 4, value) {
+            return Ok(());
+        }
+        self.locate(address, 4)
+            .and_then(|(device, offset)| device.write_u32(offset, value))
+            .ok_or(Exception::StoreAccessFault(address as u64))
+    }
+
+    // Gets a word from the bus
+    pub fn get_u32(&mut self, address: u32) -> Result<u32, Exception> {
+        if let Some(value) = self.htif_read(address, 4) {
+            return Ok(value);
+        }
+        self.locate(address, 4)
+            .and_then(|(device, offset)| device.read_u32(offset))
+            .ok_or(Exception::LoadAccessFault(address as u64))
+    }
+
+    // Fetches an instruction word. Same as get_u32, but faults as an instruction fetch
+    pub fn fetch_u32(&mut self, address: u32) -> Result<u32, Exception> {
+        self.locate(address, 4)
+            .and_then(|(device, offset)| device.read_u32(offset))
+            .ok_or(Exception::InstructionAccessFault(address as u64))
+    }
+
+    // Fetches an instruction parcel, half of a 32-bit instruction or a whole compressed one
+    pub fn fetch_u16(&mut self, address: u32) -> Result<u16, Exception> {
+        self.locate(address, 2)
+            .and_then(|(device, offset)| device.read_u16(offset))
+            .ok_or(Exception::InstructionAccessFault(address as u64))
+    }
+
+    pub fn load_image(&mut self, offset: u32, image: &[u8]) -> Result<(), Exception> {
+        for (i, byte) in image.iter().enumerate() {
+            let address = offset.checked_add(i as u32).ok_or(Exception::StoreAccessFault(offset as u64))?;
+            self.set_u8(address, *byte)?;
+        }
+        Ok(())
+    }
+
+    // Loads an ELF segment, zero-filling the part that isn't backed by the file (.bss)
+    pub fn load_segment(&mut self, segment: &Segment) -> Result<(), Exception> {
+        self.load_image(segment.vaddr, &segment.data)?;
+        for i in segment.data.len() as u32..segment.mem_size {
+            self.set_u8(segment.vaddr + i, 0)?;
+        }
+        Ok(())
+    }
+}
+
+///// TESTS /////
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::{*};
+    use crate::cpu::bus::plic::PLIC_SIZE;
+
+    // Device that logs every access and refuses writes to its last byte
+    #[derive(Default)]
+    struct Probe {
+        accesses: Vec<(char, u32)>,
+        ticks: u32,
+    }
+
+    impl Device for Probe {
+        fn read_u8(&mut self, offset: u32) -> Option<u8> {
+            self.accesses.push(('r', offset));
+            Some(offset as u8)
+        }
+
+        fn write_u8(&mut self, offset: u32, _value: u8) -> Option<()> {
+            self.accesses.push(('w', offset));
+            (offset != 0xF).then_some(())
+        }
+
+        fn tick(&mut self) {
+            self.ticks += 1;
+        }
+    }
+
+    // Device with nothing but an interrupt line
+    struct Line(bool);
+
+    impl Device for Line {
+        fn read_u8(&mut self, _offset: u32) -> Option<u8> {
+            None
+        }
+
+        fn write_u8(&mut self, _offset: u32, _value: u8) -> Option<()> {
+            None
+        }
+
+        fn irq(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_set_get_u8() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.set_u8(10, 0xFF).unwrap();
+        assert_eq!(memory.get_u8(10), Ok(0xFF));
+    }
+
+    #[test]
+    fn test_set_get_u16() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.set_u16(10, 0xFFFF).unwrap();
+        assert_eq!(memory.get_u16(10), Ok(0xFFFF));
+    }
+
+    #[test]
+    fn test_set_get_u32() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.set_u32(10, 0xFFFFFFFF).unwrap();
+        assert_eq!(memory.get_u32(10), Ok(0xFFFFFFFF));
+    }
+
+    #[test]
+    fn test_load_segment() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.set_u32(0x104, 0xFFFFFFFF).unwrap();
+        memory.load_segment(&Segment { vaddr: 0x100, mem_size: 8, data: vec![1, 2, 3, 4] }).unwrap();
+        assert_eq!(memory.get_u32(0x100), Ok(0x04030201));
+        assert_eq!(memory.get_u32(0x104), Ok(0));
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        assert_eq!(memory.get_u32(0x400), Err(Exception::LoadAccessFault(0x400)));
+        assert_eq!(memory.set_u8(0x400, 0), Err(Exception::StoreAccessFault(0x400)));
+        assert_eq!(memory.fetch_u32(0x3FE), Err(Exception::InstructionAccessFault(0x3FE)));
+        assert!(memory.load_image(0x3FE, &[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_ram_base() {
+        let mut memory = Bus::with_ram(0x8000_0000, 1024, 8).unwrap();
+        memory.set_u32(0x8000_0000, 0xFEDCBA98).unwrap();
+        assert_eq!(memory.get_u32(0x8000_0000), Ok(0xFEDCBA98));
+        assert_eq!(memory.get_memory()[0].get_u8(0), 0x98);
+        assert_eq!(memory.get_u8(0x7FFF_FFFF), Err(Exception::LoadAccessFault(0x7FFF_FFFF)));
+        assert_eq!(memory.get_u16(0x7FFF_FFFF), Err(Exception::LoadAccessFault(0x7FFF_FFFF)));
+        assert_eq!(memory.get_u8(0x8000_0400), Err(Exception::LoadAccessFault(0x8000_0400)));
+    }
+
+    #[test]
+    fn test_ram_range() {
+        assert_eq!(Bus::with_ram(0, 1 << 32, 8).err(), Some(BusError::OutOfRange(0)), "4 GiB needs a 33 bit size");
+        assert_eq!(Bus::with_ram(0xFFFF_F000, 0x2000, 8).err(), Some(BusError::OutOfRange(0xFFFF_F000)));
+        assert_eq!(Bus::with_ram(0, 0, 8).err(), Some(BusError::Empty));
+        assert!(Bus::with_ram(0xFFFF_F000, 0x1000, 8).is_ok());
+    }
+
+    #[test]
+    fn test_attach() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        assert_eq!(memory.attach(0x3F0, 0x10, Box::new(Probe::default())), Err(BusError::Overlap(0)));
+        assert_eq!(memory.attach(0x1000, 0, Box::new(Probe::default())), Err(BusError::Empty));
+        assert_eq!(memory.attach(0xFFFF_FFF0, 0x20, Box::new(Probe::default())), Err(BusError::OutOfRange(0xFFFF_FFF0)));
+        assert_eq!(memory.attach(0x400, 0x10, Box::new(Probe::default())), Ok(()));
+        assert_eq!(memory.attach(0x300, 0x200, Box::new(Probe::default())), Err(BusError::Overlap(0)));
+        assert_eq!(memory.attach(0x40F, 1, Box::new(Probe::default())), Err(BusError::Overlap(0x400)));
+        assert_eq!(memory.attach(0xFFFF_FFF0, 0x10, Box::new(Probe::default())), Ok(()));
+    }
+
+    #[test]
+    fn test_device_routing() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.attach(0x1000, 0x10, Box::new(Probe::default())).unwrap();
+        // Wide accesses are split into bytes, at offsets relative to the device
+        assert_eq!(memory.get_u32(0x1004), Ok(0x07060504));
+        assert_eq!(memory.set_u16(0x1008, 0), Ok(()));
+        assert_eq!(memory.set_u8(0x100F, 0), Err(Exception::StoreAccessFault(0x100F)));
+        // Accesses that run past the end of the device don't reach it
+        assert_eq!(memory.get_u32(0x100E), Err(Exception::LoadAccessFault(0x100E)));
+        assert_eq!(memory.get_u32(0x3FE), Err(Exception::LoadAccessFault(0x3FE)));
+        assert_eq!(memory.fetch_u32(0x1000), Ok(0x03020100));
+
+        let probe = memory.device::<Probe>().unwrap();
+        assert_eq!(probe.accesses[..7], [('r', 4), ('r', 5), ('r', 6), ('r', 7), ('w', 8), ('w', 9), ('w', 0xF)]);
+        assert_eq!(memory.device::<Ram>().unwrap().size(), 1024);
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut memory = Bus::new();
+        memory.attach(0, 0x10, Box::new(Probe::default())).unwrap();
+        memory.tick();
+        memory.tick();
+        assert_eq!(memory.device::<Probe>().unwrap().ticks, 2);
+        memory.device_mut::<Probe>().unwrap().ticks = 0;
+        assert_eq!(memory.device::<Probe>().unwrap().ticks, 0);
+        assert!(memory.get_memory().is_empty(), "No RAM on this bus");
+    }
+
+    #[test]
+    fn test_irq_routing() {
+        let mut memory = Bus::new();
+        memory.attach(0x0, 0x100, Box::new(Probe::default())).unwrap();
+        memory.attach(0x1000, PLIC_SIZE, Box::new(Plic::new())).unwrap();
+        assert_eq!(memory.attach_with_irq(0x200, 0x10, Box::new(Line(true)), 0), Err(BusError::InvalidIrq(0)));
+        assert_eq!(memory.attach_with_irq(0x200, 0x10, Box::new(Line(true)), NUM_SOURCES as u32),
+            Err(BusError::InvalidIrq(NUM_SOURCES as u32)));
+        memory.attach_with_irq(0x200, 0x10, Box::new(Line(true)), 3).unwrap();
+        memory.attach_with_irq(0x300, 0x10, Box::new(Line(false)), 4).unwrap();
+        memory.route_irqs();
+        // Pending bits of sources 0 to 31
+        assert_eq!(memory.get_u32(0x1000 + 0x1000), Ok(1 << 3));
+    }
+
+    #[test]
+    fn test_htif() {
+        let mut memory = Bus::with_ram(0, 1024, 8).unwrap();
+        memory.attach_htif(Htif::new(0x100, Some(0x140)));
+        memory.set_u32(0x100, 0x7).unwrap();
+        assert_eq!(memory.get_u32(0x100), Ok(0x7));
+        assert_eq!(memory.device_mut::<Ram>().unwrap().read_u32(0x100), Some(0), "HTIF registers should not be backed by memory");
+        assert_eq!(memory.exit_code(), None);
+        memory.set_u32(0x104, 0).unwrap();
+        assert_eq!(memory.exit_code(), Some(3));
+    }
+}
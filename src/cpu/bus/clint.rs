@@ -0,0 +1,246 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Core-local interruptor (CLINT)
+ *
+ * SiFive layout, as found on QEMU's virt board, for our single hart:
+ *
+ *   0x0000 msip      bit 0 raises a machine software interrupt
+ *   0x4000 mtimecmp  64 bit, a timer interrupt is pending while mtime >= mtimecmp
+ *   0xBFF8 mtime     64 bit, free running
+ *
+ * mtime either counts retired instructions, which keeps runs reproducible,
+ * or follows the host's wall clock at 10 MHz, QEMU's timebase.
+ */
+
+use std::time::Instant;
+use crate::cpu::bus::Device;
+
+// Size of the address range the CLINT is mapped over
+pub(crate) const CLINT_SIZE: u32 = 0x1_0000;
+// Standard location, same as QEMU's virt board
+pub(crate) const CLINT_BASE: u32 = 0x0200_0000;
+
+// mtime frequency when following the host clock
+pub(crate) const HOST_TIMEBASE_HZ: u64 = 10_000_000;
+// The host clock is only read every so many instructions
+const HOST_POLL_INTERVAL: u32 = 64;
+
+const MSIP: u32 = 0x0000;
+const MTIMECMP: u32 = 0x4000;
+const MTIME: u32 = 0xBFF8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TimeSource {
+    Instructions, // One tick per instruction
+    Host, // Wall clock, at HOST_TIMEBASE_HZ
+}
+
+pub(crate) struct Clint {
+    source: TimeSource,
+    msip: bool,
+    mtimecmp: u64,
+    mtime: u64, // As of the last tick
+    start: Instant, // When mtime was last set, for the host clock
+    start_mtime: u64,
+    ticks: u32,
+}
+
+#[allow(dead_code)]
+impl Clint {
+    pub(crate) fn new(source: TimeSource) -> Self {
+        Self {
+            source,
+            msip: false,
+            // Nothing pending until software sets up a deadline
+            mtimecmp: u64::MAX,
+            mtime: 0,
+            start: Instant::now(),
+            start_mtime: 0,
+            ticks: 0,
+        }
+    }
+
+    // Machine software interrupt line, mip.MSIP
+    pub(crate) fn msip(&self) -> bool {
+        self.msip
+    }
+
+    // Machine timer interrupt line, mip.MTIP
+    pub(crate) fn mtip(&self) -> bool {
+        self.mtime >= self.mtimecmp
+    }
+
+    pub(crate) fn mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    fn host_mtime(&self) -> u64 {
+        let elapsed = self.start.elapsed().as_nanos() as u64;
+        self.start_mtime.wrapping_add(elapsed / (1_000_000_000 / HOST_TIMEBASE_HZ))
+    }
+
+    // mtime as of right now, rather than as of the last tick
+    fn current_mtime(&mut self) -> u64 {
+        if self.source == TimeSource::Host {
+            self.mtime = self.host_mtime();
+        }
+        self.mtime
+    }
+
+    fn set_mtime(&mut self, value: u64) {
+        self.mtime = value;
+        self.start = Instant::now();
+        self.start_mtime = value;
+    }
+
+    // Which register `offset` falls in, and where it starts
+    fn locate(offset: u32, size: u32) -> Option<(u32, u32)> {
+        let (start, length) = match offset {
+            MSIP..=0x0003 => (MSIP, 4),
+            MTIMECMP..=0x4007 => (MTIMECMP, 8),
+            MTIME..=0xBFFF => (MTIME, 8),
+            _ => return None,
+        };
+        // Accesses can't straddle two registers
+        (offset + size <= start + length).then_some((start, offset - start))
+    }
+
+    fn read(&mut self, offset: u32, size: u32) -> Option<u32> {
+        let (register, shift) = Self::locate(offset, size)?;
+        let value = match register {
+            MSIP => self.msip as u64,
+            MTIMECMP => self.mtimecmp,
+            _ => self.current_mtime(),
+        };
+        let mask = (1u64 << (size * 8)) - 1;
+        Some(((value >> (shift * 8)) & mask) as u32)
+    }
+
+    fn write(&mut self, offset: u32, size: u32, value: u32) -> Option<()> {
+        let (register, shift) = Self::locate(offset, size)?;
+        let mask = ((1u64 << (size * 8)) - 1) << (shift * 8);
+        let merge = |old: u64| (old & !mask) | (((value as u64) << (shift * 8)) & mask);
+        match register {
+            // Only bit 0 is implemented, the rest reads as zero
+            MSIP => self.msip = merge(self.msip as u64) & 1 != 0,
+            MTIMECMP => self.mtimecmp = merge(self.mtimecmp),
+            _ => {
+                let mtime = merge(self.current_mtime());
+                self.set_mtime(mtime);
+            }
+        }
+        Some(())
+    }
+}
+
+impl Device for Clint {
+    fn read_u8(&mut self, offset: u32) -> Option<u8> {
+        self.read(offset, 1).map(|value| value as u8)
+    }
+
+    fn write_u8(&mut self, offset: u32, value: u8) -> Option<()> {
+        self.write(offset, 1, value as u32)
+    }
+
+    fn read_u16(&mut self, offset: u32) -> Option<u16> {
+        self.read(offset, 2).map(|value| value as u16)
+    }
+
+    fn write_u16(&mut self, offset: u32, value: u16) -> Option<()> {
+        self.write(offset, 2, value as u32)
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Option<u32> {
+        self.read(offset, 4)
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) -> Option<()> {
+        self.write(offset, 4, value)
+    }
+
+    fn tick(&mut self) {
+        match self.source {
+            TimeSource::Instructions => self.mtime = self.mtime.wrapping_add(1),
+            TimeSource::Host => {
+                self.ticks += 1;
+                if self.ticks >= HOST_POLL_INTERVAL {
+                    self.ticks = 0;
+                    self.mtime = self.host_mtime();
+                }
+            }
+        }
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::clint::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_msip() {
+        let mut clint = Clint::new(TimeSource::Instructions);
+        assert!(!clint.msip());
+        clint.write_u32(MSIP, 0xFFFF_FFFF).unwrap();
+        assert!(clint.msip());
+        assert_eq!(clint.read_u32(MSIP), Some(1));
+        clint.write_u8(MSIP, 0).unwrap();
+        assert!(!clint.msip());
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut clint = Clint::new(TimeSource::Instructions);
+        assert!(!clint.mtip(), "Nothing should be pending out of reset");
+        clint.write_u32(MTIMECMP, 3).unwrap();
+        clint.write_u32(MTIMECMP + 4, 0).unwrap();
+        clint.tick();
+        clint.tick();
+        assert!(!clint.mtip());
+        clint.tick();
+        assert!(clint.mtip());
+        assert_eq!(clint.read_u32(MTIME), Some(3));
+        // Moving the deadline is how the interrupt is acknowledged
+        clint.write_u32(MTIMECMP, 10).unwrap();
+        assert!(!clint.mtip());
+    }
+
+    #[test]
+    fn test_mtime_64bit() {
+        let mut clint = Clint::new(TimeSource::Instructions);
+        clint.write_u32(MTIME, 0xFFFF_FFFF).unwrap();
+        clint.write_u32(MTIME + 4, 0x1).unwrap();
+        clint.tick();
+        assert_eq!(clint.read_u32(MTIME), Some(0));
+        assert_eq!(clint.read_u32(MTIME + 4), Some(2));
+        assert_eq!(clint.read_u16(MTIME + 4), Some(2));
+        assert_eq!(clint.read_u8(MTIMECMP + 7), Some(0xFF));
+        assert_eq!(clint.mtime(), 0x2_0000_0000);
+    }
+
+    #[test]
+    fn test_invalid_access() {
+        let mut clint = Clint::new(TimeSource::Instructions);
+        assert_eq!(clint.read_u32(0x8), None);
+        assert_eq!(clint.read_u32(MTIMECMP + 6), None, "Accesses can't straddle registers");
+        assert_eq!(clint.write_u32(0xBFF0, 0), None);
+    }
+
+    #[test]
+    fn test_host_clock() {
+        let mut clint = Clint::new(TimeSource::Host);
+        clint.write_u32(MTIMECMP, 1000).unwrap();
+        clint.write_u32(MTIMECMP + 4, 0).unwrap();
+        thread::sleep(Duration::from_millis(1));
+        // 1 ms is 10000 ticks at 10 MHz, but we only look at the clock every so often
+        assert!(!clint.mtip());
+        for _ in 0..HOST_POLL_INTERVAL {
+            clint.tick();
+        }
+        assert!(clint.mtip());
+        assert!(clint.read_u32(MTIME).unwrap() >= 10_000);
+    }
+}
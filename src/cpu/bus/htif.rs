@@ -0,0 +1,268 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Berkeley Host-Target Interface (HTIF)
+ *
+ * The program talks to the host through two 64 bit registers, tohost and
+ * fromhost, usually found through the symbols of the same name. A command
+ * written to tohost is laid out as
+ *
+ *   63..56 device | 55..48 command | 47..0 payload
+ *
+ * Device 0 is the syscall proxy: an odd payload means exit with code
+ * payload >> 1, an even one is the address of eight 64 bit words holding
+ * the syscall number and its arguments. Device 1 is the console, where
+ * command 1 writes the character in the payload.
+ *
+ * On RV32 the registers are written one word at a time, so a command is
+ * only picked up once the upper word of tohost has been written.
+ */
+
+use std::io::{self, Write};
+use crate::cpu::bus::Bus;
+
+const REGISTER_SIZE: u32 = 8;
+
+const DEV_SYSCALL: u8 = 0;
+const DEV_CONSOLE: u8 = 1;
+const CONSOLE_PUTCHAR: u8 = 1;
+
+// Syscall numbers, as used by riscv-pk and newlib
+const SYS_WRITE: u64 = 64;
+const SYS_EXIT: u64 = 93;
+
+const EBADF: i64 = 9;
+const EFAULT: i64 = 14;
+const ENOSYS: i64 = 38;
+
+pub(crate) struct Htif {
+    tohost_address: u32,
+    fromhost_address: Option<u32>,
+    tohost: u64,
+    fromhost: u64,
+    exit_code: Option<u32>,
+    console: Box<dyn Write + Send>,
+}
+
+#[allow(dead_code)]
+impl Htif {
+    pub(crate) fn new(tohost_address: u32, fromhost_address: Option<u32>) -> Self {
+        Self::with_console(tohost_address, fromhost_address, Box::new(io::stdout()))
+    }
+
+    // Console output goes to `console` instead of stdout
+    pub(crate) fn with_console(tohost_address: u32, fromhost_address: Option<u32>, console: Box<dyn Write + Send>) -> Self {
+        Self {
+            tohost_address,
+            fromhost_address,
+            tohost: 0,
+            fromhost: 0,
+            exit_code: None,
+            console,
+        }
+    }
+
+    // Exit code, once the program has asked to exit
+    pub(crate) fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    // Which register `address` falls in, and the byte offset into it
+    fn locate(&self, address: u32) -> Option<(bool, u32)> {
+        let offset = address.wrapping_sub(self.tohost_address);
+        if offset < REGISTER_SIZE {
+            return Some((true, offset));
+        }
+        let offset = address.wrapping_sub(self.fromhost_address?);
+        (offset < REGISTER_SIZE).then_some((false, offset))
+    }
+
+    pub(crate) fn contains(&self, address: u32) -> bool {
+        self.locate(address).is_some()
+    }
+
+    // Reads `size` bytes at `address`. None if it isn't one of our registers
+    pub(crate) fn read(&self, address: u32, size: u32) -> Option<u32> {
+        let (is_tohost, offset) = self.locate(address)?;
+        let register = if is_tohost { self.tohost } else { self.fromhost };
+        let mask = (1u64 << (size * 8)) - 1;
+        Some(((register >> (offset * 8)) & mask) as u32)
+    }
+
+    // Writes `size` bytes at `address`. Returns the command to execute,
+    // if this write completed one.
+    pub(crate) fn write(&mut self, address: u32, size: u32, value: u32) -> Option<u64> {
+        let (is_tohost, offset) = self.locate(address)?;
+        let register = if is_tohost { &mut self.tohost } else { &mut self.fromhost };
+        let mask = ((1u64 << (size * 8)) - 1) << (offset * 8);
+        *register = (*register & !mask) | (((value as u64) << (offset * 8)) & mask);
+
+        let writes_upper_byte = offset + size >= REGISTER_SIZE;
+        (is_tohost && writes_upper_byte && self.tohost != 0).then_some(self.tohost)
+    }
+
+    // Runs a command written to tohost. `bus` gives access to the program's memory,
+    // for the syscall arguments.
+    pub(crate) fn execute(&mut self, command: u64, bus: &mut Bus) {
+        let device = (command >> 56) as u8;
+        let cmd = (command >> 48) as u8;
+        let payload = command & 0xFFFF_FFFF_FFFF;
+        // The host always clears tohost once it has seen the command
+        self.tohost = 0;
+
+        match (device, cmd) {
+            (DEV_SYSCALL, 0) if payload & 1 == 1 => self.exit_code = Some((payload >> 1) as u32),
+            (DEV_SYSCALL, 0) => {
+                self.syscall(payload as u32, bus);
+                self.respond(device, cmd, 1);
+            }
+            (DEV_CONSOLE, CONSOLE_PUTCHAR) => {
+                let _ = self.console.write_all(&[payload as u8]);
+                let _ = self.console.flush();
+                self.respond(device, cmd, 0);
+            }
+            // Unknown devices and commands are dropped
+            _ => {}
+        }
+    }
+
+    fn respond(&mut self, device: u8, cmd: u8, payload: u64) {
+        if self.fromhost_address.is_some() {
+            self.fromhost = ((device as u64) << 56) | ((cmd as u64) << 48) | payload;
+        }
+    }
+
+    // Proxies the syscall described at `magic_mem`, the result goes back in its first word
+    fn syscall(&mut self, magic_mem: u32, bus: &mut Bus) {
+        let mut args = [0u64; 8];
+        for (i, arg) in args.iter_mut().enumerate() {
+            let address = magic_mem.wrapping_add(i as u32 * 8);
+            match (bus.get_u32(address), bus.get_u32(address.wrapping_add(4))) {
+                (Ok(low), Ok(high)) => *arg = (high as u64) << 32 | low as u64,
+                _ => return,
+            }
+        }
+
+        let result = match args[0] {
+            SYS_WRITE => self.sys_write(args[1], args[2] as u32, args[3] as u32, bus),
+            SYS_EXIT => {
+                self.exit_code = Some(args[1] as u32);
+                0
+            }
+            _ => -ENOSYS,
+        };
+        let _ = bus.set_u32(magic_mem, result as u32);
+        let _ = bus.set_u32(magic_mem.wrapping_add(4), (result >> 32) as u32);
+    }
+
+    fn sys_write(&mut self, fd: u64, buffer: u32, length: u32, bus: &mut Bus) -> i64 {
+        if fd != 1 && fd != 2 {
+            return -EBADF;
+        }
+        let mut data = Vec::with_capacity(length as usize);
+        for i in 0..length {
+            match bus.get_u8(buffer.wrapping_add(i)) {
+                Ok(byte) => data.push(byte),
+                Err(_) => return -EFAULT,
+            }
+        }
+        let _ = self.console.write_all(&data);
+        let _ = self.console.flush();
+        length as i64
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::htif::*;
+    use crate::cpu::bus::SharedOutput;
+
+    fn setup() -> (Htif, Bus, SharedOutput) {
+        let console = SharedOutput::default();
+        let htif = Htif::with_console(0x100, Some(0x140), Box::new(console.clone()));
+        (htif, Bus::with_ram(0, 1024, 8).unwrap(), console)
+    }
+
+    // Writes a 64 bit command the way an RV32 program does, low word first
+    fn send(htif: &mut Htif, bus: &mut Bus, command: u64) {
+        assert_eq!(htif.write(0x100, 4, command as u32), None);
+        if let Some(command) = htif.write(0x104, 4, (command >> 32) as u32) {
+            htif.execute(command, bus);
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let (htif, _, _) = setup();
+        assert!(htif.contains(0x100));
+        assert!(htif.contains(0x107));
+        assert!(!htif.contains(0x108));
+        assert!(htif.contains(0x140));
+        assert!(!htif.contains(0xFF));
+    }
+
+    #[test]
+    fn test_exit() {
+        let (mut htif, mut bus, _) = setup();
+        assert_eq!(htif.exit_code(), None);
+        send(&mut htif, &mut bus, (21 << 1) | 1);
+        assert_eq!(htif.exit_code(), Some(21));
+        assert_eq!(htif.read(0x100, 4), Some(0), "tohost should be cleared");
+    }
+
+    #[test]
+    fn test_needs_upper_word() {
+        let (mut htif, _, _) = setup();
+        assert_eq!(htif.write(0x100, 4, 1), None);
+        assert_eq!(htif.read(0x100, 4), Some(1));
+        assert_eq!(htif.exit_code(), None);
+        assert_eq!(htif.write(0x104, 4, 0), Some(1));
+    }
+
+    #[test]
+    fn test_putchar() {
+        let (mut htif, mut bus, console) = setup();
+        send(&mut htif, &mut bus, (1 << 56) | (1 << 48) | b'A' as u64);
+        send(&mut htif, &mut bus, (1 << 56) | (1 << 48) | b'\n' as u64);
+        assert_eq!(console.text(), "A\n");
+        assert_eq!(htif.read(0x144, 4), Some(0x0101_0000), "fromhost should acknowledge");
+        assert_eq!(htif.exit_code(), None);
+    }
+
+    #[test]
+    fn test_syscall_write() {
+        let (mut htif, mut bus, console) = setup();
+        for (i, byte) in b"hello".iter().enumerate() {
+            bus.set_u8(0x200 + i as u32, *byte).unwrap();
+        }
+        // magic_mem: which, fd, buffer, length
+        bus.set_u32(0x180, SYS_WRITE as u32).unwrap();
+        bus.set_u32(0x188, 1).unwrap();
+        bus.set_u32(0x190, 0x200).unwrap();
+        bus.set_u32(0x198, 5).unwrap();
+        send(&mut htif, &mut bus, 0x180);
+
+        assert_eq!(console.text(), "hello");
+        assert_eq!(bus.get_u32(0x180), Ok(5), "Result should be the number of bytes written");
+        assert_eq!(htif.read(0x140, 4), Some(1));
+        // The program clears fromhost once it has seen the response
+        htif.write(0x140, 4, 0);
+        assert_eq!(htif.read(0x140, 4), Some(0));
+    }
+
+    #[test]
+    fn test_syscall_exit_and_unknown() {
+        let (mut htif, mut bus, _) = setup();
+        bus.set_u32(0x180, 1234).unwrap();
+        send(&mut htif, &mut bus, 0x180);
+        assert_eq!(bus.get_u32(0x180), Ok(-ENOSYS as u32));
+        assert_eq!(bus.get_u32(0x184), Ok(0xFFFF_FFFF));
+
+        bus.set_u32(0x180, SYS_EXIT as u32).unwrap();
+        bus.set_u32(0x184, 0).unwrap();
+        bus.set_u32(0x188, 3).unwrap();
+        send(&mut htif, &mut bus, 0x180);
+        assert_eq!(htif.exit_code(), Some(3));
+    }
+}
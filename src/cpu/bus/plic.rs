@@ -0,0 +1,296 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Platform-level interrupt controller (PLIC)
+ *
+ * Same layout as on QEMU's virt board, with two contexts for our single
+ * hart: context 0 is its M-mode, context 1 its S-mode.
+ *
+ *   0x000000 + 4 * source           priority (0 never interrupts)
+ *   0x001000 + 4 * word             pending bits, read only
+ *   0x002000 + 0x80 * context       enable bits
+ *   0x200000 + 0x1000 * context     priority threshold
+ *   0x200004 + 0x1000 * context     claim (read) / complete (write)
+ *
+ * Sources are level triggered: a source is pending while its device holds
+ * its line up, unless it has been claimed and not completed yet. Source 0
+ * doesn't exist. Registers are 32 bit wide and only accessed as such.
+ */
+
+use crate::cpu::bus::Device;
+
+// Size of the address range the PLIC is mapped over
+pub(crate) const PLIC_SIZE: u32 = 0x0400_0000;
+// Where QEMU's virt board has it
+pub(crate) const PLIC_BASE: u32 = 0x0C00_0000;
+
+// Interrupt sources, including the nonexistent source 0
+pub(crate) const NUM_SOURCES: usize = 96;
+pub(crate) const NUM_CONTEXTS: usize = 2;
+// Priorities and thresholds are 3 bits wide
+const PRIORITY_MASK: u32 = 0x7;
+
+const PRIORITY_BASE: u32 = 0x0000;
+const PENDING_BASE: u32 = 0x1000;
+const ENABLE_BASE: u32 = 0x2000;
+const ENABLE_STRIDE: u32 = 0x80;
+const CONTEXT_BASE: u32 = 0x20_0000;
+const CONTEXT_STRIDE: u32 = 0x1000;
+
+// Number of 32 bit words holding one bit per source
+const SOURCE_WORDS: u32 = (NUM_SOURCES as u32).div_ceil(32);
+// Every source but 0
+const SOURCE_MASK: u128 = ((1u128 << NUM_SOURCES) - 1) & !1;
+
+pub(crate) struct Plic {
+    priority: [u32; NUM_SOURCES],
+    enable: [u128; NUM_CONTEXTS],
+    threshold: [u32; NUM_CONTEXTS],
+    lines: u128, // Level of each source's interrupt line
+    claimed: u128, // Claimed, but not completed yet
+}
+
+enum Register {
+    Priority(usize),
+    Pending(u32),
+    Enable(usize, u32),
+    Threshold(usize),
+    Claim(usize),
+}
+
+#[allow(dead_code)]
+impl Plic {
+    pub(crate) fn new() -> Self {
+        Self {
+            priority: [0; NUM_SOURCES],
+            enable: [0; NUM_CONTEXTS],
+            threshold: [0; NUM_CONTEXTS],
+            lines: 0,
+            claimed: 0,
+        }
+    }
+
+    // Sets the level of every source's line at once, bit n being source n
+    pub(crate) fn set_lines(&mut self, lines: u128) {
+        self.lines = lines & SOURCE_MASK;
+    }
+
+    pub(crate) fn set_line(&mut self, source: u32, level: bool) {
+        if level {
+            self.set_lines(self.lines | 1 << source);
+        } else {
+            self.set_lines(self.lines & !(1 << source));
+        }
+    }
+
+    fn pending(&self) -> u128 {
+        self.lines & !self.claimed
+    }
+
+    // Pending source `context` should be interrupted for: the highest priority one
+    // above the threshold, lowest id first on ties
+    fn best(&self, context: usize) -> Option<u32> {
+        let candidates = self.pending() & self.enable[context];
+        (1..NUM_SOURCES)
+            .filter(|&source| candidates & (1 << source) != 0)
+            .filter(|&source| self.priority[source] > self.threshold[context])
+            .max_by_key(|&source| (self.priority[source], std::cmp::Reverse(source)))
+            .map(|source| source as u32)
+    }
+
+    // Interrupt line going to `context`: mip.MEIP for context 0, mip.SEIP for context 1
+    pub(crate) fn irq(&self, context: usize) -> bool {
+        self.best(context).is_some()
+    }
+
+    fn claim(&mut self, context: usize) -> u32 {
+        match self.best(context) {
+            Some(source) => {
+                self.claimed |= 1 << source;
+                source
+            }
+            None => 0,
+        }
+    }
+
+    fn complete(&mut self, context: usize, source: u32) {
+        // Completions for sources the context can't see are ignored
+        if (source as usize) < NUM_SOURCES && self.enable[context] & (1 << source) != 0 {
+            self.claimed &= !(1 << source);
+        }
+    }
+
+    fn decode(offset: u32) -> Option<Register> {
+        if offset & 0x3 != 0 {
+            return None;
+        }
+        let register = match offset {
+            PRIORITY_BASE..PENDING_BASE => {
+                let source = (offset / 4) as usize;
+                (source < NUM_SOURCES).then_some(Register::Priority(source))?
+            }
+            PENDING_BASE..ENABLE_BASE => {
+                let word = (offset - PENDING_BASE) / 4;
+                (word < SOURCE_WORDS).then_some(Register::Pending(word))?
+            }
+            ENABLE_BASE..CONTEXT_BASE => {
+                let context = ((offset - ENABLE_BASE) / ENABLE_STRIDE) as usize;
+                let word = (offset - ENABLE_BASE) % ENABLE_STRIDE / 4;
+                (context < NUM_CONTEXTS && word < SOURCE_WORDS).then_some(Register::Enable(context, word))?
+            }
+            _ => {
+                let context = ((offset - CONTEXT_BASE) / CONTEXT_STRIDE) as usize;
+                if context >= NUM_CONTEXTS {
+                    return None;
+                }
+                match (offset - CONTEXT_BASE) % CONTEXT_STRIDE {
+                    0 => Register::Threshold(context),
+                    4 => Register::Claim(context),
+                    _ => return None,
+                }
+            }
+        };
+        Some(register)
+    }
+}
+
+impl Device for Plic {
+    // Byte and half word accesses aren't supported
+    fn read_u8(&mut self, _offset: u32) -> Option<u8> {
+        None
+    }
+
+    fn write_u8(&mut self, _offset: u32, _value: u8) -> Option<()> {
+        None
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Option<u32> {
+        let value = match Self::decode(offset)? {
+            Register::Priority(source) => self.priority[source],
+            Register::Pending(word) => (self.pending() >> (word * 32)) as u32,
+            Register::Enable(context, word) => (self.enable[context] >> (word * 32)) as u32,
+            Register::Threshold(context) => self.threshold[context],
+            Register::Claim(context) => self.claim(context),
+        };
+        Some(value)
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) -> Option<()> {
+        match Self::decode(offset)? {
+            // Source 0 doesn't exist, its priority stays 0
+            Register::Priority(0) => {}
+            Register::Priority(source) => self.priority[source] = value & PRIORITY_MASK,
+            Register::Pending(_) => {}
+            Register::Enable(context, word) => {
+                let mask = 0xFFFF_FFFFu128 << (word * 32);
+                let bits = ((value as u128) << (word * 32)) & SOURCE_MASK;
+                self.enable[context] = (self.enable[context] & !mask) | bits;
+            }
+            Register::Threshold(context) => self.threshold[context] = value & PRIORITY_MASK,
+            Register::Claim(context) => self.complete(context, value),
+        }
+        Some(())
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::plic::*;
+
+    const UART: u32 = 10;
+    const DISK: u32 = 40;
+
+    fn enable(plic: &mut Plic, context: u32, source: u32) {
+        let offset = ENABLE_BASE + context * ENABLE_STRIDE + source / 32 * 4;
+        let value = plic.read_u32(offset).unwrap() | 1 << (source % 32);
+        plic.write_u32(offset, value).unwrap();
+    }
+
+    fn claim(plic: &mut Plic, context: u32) -> u32 {
+        plic.read_u32(CONTEXT_BASE + context * CONTEXT_STRIDE + 4).unwrap()
+    }
+
+    fn complete(plic: &mut Plic, context: u32, source: u32) {
+        plic.write_u32(CONTEXT_BASE + context * CONTEXT_STRIDE + 4, source).unwrap();
+    }
+
+    #[test]
+    fn test_claim_complete() {
+        let mut plic = Plic::new();
+        plic.write_u32(UART * 4, 1).unwrap();
+        enable(&mut plic, 0, UART);
+        assert!(!plic.irq(0));
+
+        plic.set_line(UART, true);
+        assert!(plic.irq(0));
+        assert!(!plic.irq(1), "Context 1 doesn't have the source enabled");
+        assert_eq!(plic.read_u32(PENDING_BASE), Some(1 << UART));
+        assert_eq!(claim(&mut plic, 0), UART);
+        assert!(!plic.irq(0), "Claimed sources aren't pending");
+        assert_eq!(plic.read_u32(PENDING_BASE), Some(0));
+        assert_eq!(claim(&mut plic, 0), 0);
+
+        // Still high after completion, so pending again
+        complete(&mut plic, 0, UART);
+        assert!(plic.irq(0));
+        plic.set_line(UART, false);
+        assert!(!plic.irq(0));
+    }
+
+    #[test]
+    fn test_priority_and_threshold() {
+        let mut plic = Plic::new();
+        plic.write_u32(UART * 4, 2).unwrap();
+        plic.write_u32(DISK * 4, 5).unwrap();
+        enable(&mut plic, 0, UART);
+        enable(&mut plic, 0, DISK);
+        plic.set_lines(1 << UART | 1 << DISK);
+        assert_eq!(plic.read_u32(PENDING_BASE + 4), Some(1 << (DISK - 32)));
+
+        plic.write_u32(CONTEXT_BASE, 5).unwrap();
+        assert!(!plic.irq(0), "Priorities must be above the threshold");
+        plic.write_u32(CONTEXT_BASE, 1).unwrap();
+        assert_eq!(claim(&mut plic, 0), DISK);
+        assert_eq!(claim(&mut plic, 0), UART);
+
+        // Same priority, lowest id wins
+        plic.write_u32(DISK * 4, 2).unwrap();
+        complete(&mut plic, 0, UART);
+        complete(&mut plic, 0, DISK);
+        assert_eq!(claim(&mut plic, 0), UART);
+    }
+
+    #[test]
+    fn test_zero_priority() {
+        let mut plic = Plic::new();
+        enable(&mut plic, 0, UART);
+        plic.set_line(UART, true);
+        assert!(!plic.irq(0), "Priority 0 never interrupts");
+    }
+
+    #[test]
+    fn test_warl() {
+        let mut plic = Plic::new();
+        plic.write_u32(0, 7).unwrap();
+        assert_eq!(plic.read_u32(0), Some(0), "Source 0 doesn't exist");
+        plic.write_u32(UART * 4, 0xFF).unwrap();
+        assert_eq!(plic.read_u32(UART * 4), Some(7));
+        plic.write_u32(ENABLE_BASE, 0xFFFF_FFFF).unwrap();
+        assert_eq!(plic.read_u32(ENABLE_BASE), Some(0xFFFF_FFFE));
+        plic.write_u32(CONTEXT_BASE + CONTEXT_STRIDE, 0xFF).unwrap();
+        assert_eq!(plic.read_u32(CONTEXT_BASE + CONTEXT_STRIDE), Some(7));
+    }
+
+    #[test]
+    fn test_invalid_access() {
+        let mut plic = Plic::new();
+        assert_eq!(plic.read_u8(0), None);
+        assert_eq!(plic.read_u32(2), None);
+        assert_eq!(plic.read_u32(NUM_SOURCES as u32 * 4), None);
+        assert_eq!(plic.read_u32(PENDING_BASE + SOURCE_WORDS * 4), None);
+        assert_eq!(plic.read_u32(ENABLE_BASE + NUM_CONTEXTS as u32 * ENABLE_STRIDE), None);
+        assert_eq!(plic.read_u32(CONTEXT_BASE + 8), None);
+        assert_eq!(plic.read_u32(CONTEXT_BASE + NUM_CONTEXTS as u32 * CONTEXT_STRIDE), None);
+    }
+}
@@ -0,0 +1,156 @@
+pub use crate::cpu::bus::ram::page::Page;
+use crate::cpu::bus::Device;
+
+mod page;
+
+// Main memory, split into pages. Addresses are offsets from wherever the
+// bus maps it.
+pub(crate) struct Ram {
+    page_table: Vec<Page>,
+    page_offset_bits: usize, // Number of lower bits in the global address used for page offset
+    page_mask: usize, // We calculate the mask once :3
+    num_pages: usize,
+}
+
+impl Ram {
+    pub(crate) fn get_memory(&self) -> &[Page] {
+        &self.page_table
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.num_pages << self.page_offset_bits
+    }
+}
+
+impl Ram {
+    pub(crate) fn new(memsize: usize, page_offset_bits: usize) -> Self {
+        let page_size = 1 << page_offset_bits;
+        let num_pages = memsize / page_size;
+        let mut page_table: Vec<Page> = Vec::with_capacity(num_pages);
+        for _ in 0..num_pages {
+            page_table.push(Page::new(page_size));
+        }
+
+        Self {
+            page_table,
+            page_offset_bits,
+            page_mask: page_size - 1,
+            num_pages,
+        }
+    }
+
+    // Splits an offset into page index and page offset.
+    // Returns None if the access is out of bounds or crosses into the next page.
+    fn locate(&self, offset: u32, size: usize) -> Option<(usize, u32)> {
+        let page_index = (offset >> self.page_offset_bits) as usize;
+        let page_offset = offset as usize & self.page_mask;
+        if page_index >= self.num_pages || page_offset + size > self.page_mask + 1 {
+            return None;
+        }
+        Some((page_index, page_offset as u32))
+    }
+
+    // Makes sure every byte of an access that straddles two pages exists
+    fn check_range(&self, offset: u32, size: u32) -> bool {
+        (offset as u64 + size as u64) <= self.size() as u64
+    }
+}
+
+impl Device for Ram {
+    fn write_u8(&mut self, offset: u32, value: u8) -> Option<()> {
+        let (page_index, page_offset) = self.locate(offset, 1)?;
+        self.page_table[page_index].set_u8(page_offset, value);
+        Some(())
+    }
+
+    fn read_u8(&mut self, offset: u32) -> Option<u8> {
+        let (page_index, page_offset) = self.locate(offset, 1)?;
+        Some(self.page_table[page_index].get_u8(page_offset))
+    }
+
+    fn write_u16(&mut self, offset: u32, value: u16) -> Option<()> {
+        match self.locate(offset, 2) {
+            Some((page_index, page_offset)) => self.page_table[page_index].set_u16(page_offset, value),
+            None if self.check_range(offset, 2) => {
+                for (i, byte) in value.to_le_bytes().iter().enumerate() {
+                    self.write_u8(offset + i as u32, *byte)?;
+                }
+            }
+            None => return None,
+        }
+        Some(())
+    }
+
+    fn read_u16(&mut self, offset: u32) -> Option<u16> {
+        match self.locate(offset, 2) {
+            Some((page_index, page_offset)) => Some(self.page_table[page_index].get_u16(page_offset)),
+            None if self.check_range(offset, 2) => {
+                Some(u16::from_le_bytes([self.read_u8(offset)?, self.read_u8(offset + 1)?]))
+            }
+            None => None,
+        }
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) -> Option<()> {
+        match self.locate(offset, 4) {
+            Some((page_index, page_offset)) => self.page_table[page_index].set_u32(page_offset, value),
+            None if self.check_range(offset, 4) => {
+                for (i, byte) in value.to_le_bytes().iter().enumerate() {
+                    self.write_u8(offset + i as u32, *byte)?;
+                }
+            }
+            None => return None,
+        }
+        Some(())
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Option<u32> {
+        match self.locate(offset, 4) {
+            Some((page_index, page_offset)) => Some(self.page_table[page_index].get_u32(page_offset)),
+            None if self.check_range(offset, 4) => {
+                let mut bytes = [0u8; 4];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = self.read_u8(offset + i as u32)?;
+                }
+                Some(u32::from_le_bytes(bytes))
+            }
+            None => None,
+        }
+    }
+
+    fn peek_u8(&self, offset: u32) -> Option<u8> {
+        let (page_index, page_offset) = self.locate(offset, 1)?;
+        Some(self.page_table[page_index].get_u8(page_offset))
+    }
+
+    fn poke_u8(&mut self, offset: u32, value: u8) -> Option<()> {
+        self.write_u8(offset, value)
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::ram::*;
+
+    #[test]
+    fn test_cross_page() {
+        let mut ram = Ram::new(1024, 8);
+        ram.write_u32(0xFE, 0xFEDCBA98).unwrap();
+        assert_eq!(ram.read_u32(0xFE), Some(0xFEDCBA98));
+        assert_eq!(ram.read_u16(0xFF), Some(0xDCBA));
+        assert_eq!(ram.read_u8(0x100), Some(0xDC));
+        assert_eq!(ram.get_memory()[1].get_u8(0), 0xDC);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut ram = Ram::new(1024, 8);
+        assert_eq!(ram.read_u8(1024), None);
+        assert_eq!(ram.read_u32(1022), None);
+        assert_eq!(ram.write_u16(1023, 0), None);
+        assert_eq!(ram.write_u32(0xFFFF_FFFE, 0), None);
+        assert_eq!(ram.read_u8(1023), Some(0));
+    }
+}
@@ -8,7 +8,7 @@ impl Page {
         Self { page }
     }
 
-    pub fn get_page(&self) -> &Box<[u8]> {
+    pub fn get_page(&self) -> &[u8] {
         &self.page
     }
 
@@ -65,7 +65,7 @@ impl Page {
 #[cfg(test)]
 #[allow(non_snake_case)]
 mod tests {
-    use crate::cpu::memory::mmu::page::{*};
+    use crate::cpu::bus::ram::page::{*};
 
     #[test]
     fn test_set_get_u8() {
@@ -0,0 +1,345 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* NS16550A UART
+ *
+ * Same register layout as the UART on QEMU's virt board: eight byte wide
+ * registers, with the divisor latch (DLL/DLM) replacing RBR/THR and IER
+ * while LCR.DLAB is set.
+ *
+ *   0 RBR/THR/DLL  1 IER/DLM  2 IIR/FCR  3 LCR  4 MCR  5 LSR  6 MSR  7 SCR
+ *
+ * Transmitting is instantaneous, so THR is always empty again by the time
+ * the program looks. Received bytes come from a channel, usually fed by a
+ * thread reading the host's stdin, and are pulled into the 16 byte receive
+ * FIFO as the VM runs. The baud rate and line settings are kept but have no
+ * effect.
+ */
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::mpsc::Receiver;
+use crate::cpu::bus::Device;
+
+// Size of the address range the UART is mapped over
+pub(crate) const UART_SIZE: u32 = 0x100;
+// Where QEMU's virt board has it, and its PLIC source there
+pub(crate) const UART_BASE: u32 = 0x1000_0000;
+pub(crate) const UART_IRQ: u32 = 10;
+
+const FIFO_SIZE: usize = 16;
+// The input channel is only polled every so many instructions
+const POLL_INTERVAL: u32 = 256;
+
+const RBR_THR: u32 = 0;
+const IER: u32 = 1;
+const IIR_FCR: u32 = 2;
+const LCR: u32 = 3;
+const MCR: u32 = 4;
+const LSR: u32 = 5;
+const MSR: u32 = 6;
+const SCR: u32 = 7;
+
+const IER_RDA: u8 = 0x01; // Received data available
+const IER_THRE: u8 = 0x02; // Transmitter holding register empty
+
+const IIR_NONE: u8 = 0x01;
+const IIR_THRE: u8 = 0x02;
+const IIR_RDA: u8 = 0x04;
+const IIR_FIFO_ENABLED: u8 = 0xC0;
+
+const FCR_ENABLE: u8 = 0x01;
+const FCR_CLEAR_RX: u8 = 0x02;
+
+const LCR_DLAB: u8 = 0x80;
+
+const LSR_DR: u8 = 0x01; // Data ready
+const LSR_THRE: u8 = 0x20;
+const LSR_TEMT: u8 = 0x40; // Transmitter empty
+
+// Carrier detect, data set ready and clear to send: there's always someone on the other end
+const MSR_CONNECTED: u8 = 0xB0;
+
+pub(crate) struct Uart {
+    ier: u8,
+    lcr: u8,
+    mcr: u8,
+    scr: u8,
+    dll: u8,
+    dlm: u8,
+    fifo_enabled: bool,
+    thre_pending: bool, // THRE interrupt, until IIR is read or THR written
+    rx: VecDeque<u8>,
+    input: Option<Receiver<u8>>,
+    output: Box<dyn Write>,
+    ticks: u32,
+}
+
+#[allow(dead_code)]
+impl Uart {
+    pub(crate) fn new(output: Box<dyn Write>) -> Self {
+        Self {
+            ier: 0,
+            lcr: 0,
+            mcr: 0,
+            scr: 0,
+            dll: 0,
+            dlm: 0,
+            fifo_enabled: false,
+            thre_pending: false,
+            rx: VecDeque::with_capacity(FIFO_SIZE),
+            input: None,
+            output,
+            ticks: 0,
+        }
+    }
+
+    // Bytes sent on `input` end up in the receive FIFO
+    pub(crate) fn with_input(output: Box<dyn Write>, input: Receiver<u8>) -> Self {
+        Self {
+            input: Some(input),
+            ..Self::new(output)
+        }
+    }
+
+    // Pulls whatever is waiting on the input channel into the receive FIFO
+    fn poll_input(&mut self) {
+        if let Some(input) = &self.input {
+            while self.rx.len() < FIFO_SIZE {
+                match input.try_recv() {
+                    Ok(byte) => self.rx.push_back(byte),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    fn dlab(&self) -> bool {
+        self.lcr & LCR_DLAB != 0
+    }
+
+    // Highest priority pending interrupt, as reported by IIR
+    fn pending(&self) -> u8 {
+        if self.ier & IER_RDA != 0 && !self.rx.is_empty() {
+            IIR_RDA
+        } else if self.ier & IER_THRE != 0 && self.thre_pending {
+            IIR_THRE
+        } else {
+            IIR_NONE
+        }
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        let _ = self.output.write_all(&[byte]);
+        let _ = self.output.flush();
+        self.thre_pending = true;
+    }
+
+    fn read_register(&mut self, offset: u32) -> Option<u8> {
+        let value = match offset {
+            RBR_THR if self.dlab() => self.dll,
+            RBR_THR => {
+                let byte = self.rx.pop_front().unwrap_or(0);
+                self.poll_input();
+                byte
+            }
+            IER if self.dlab() => self.dlm,
+            IER => self.ier,
+            IIR_FCR => {
+                let pending = self.pending();
+                // Reading IIR is what acknowledges a THRE interrupt
+                if pending == IIR_THRE {
+                    self.thre_pending = false;
+                }
+                pending | if self.fifo_enabled { IIR_FIFO_ENABLED } else { 0 }
+            }
+            LCR => self.lcr,
+            MCR => self.mcr,
+            LSR => LSR_THRE | LSR_TEMT | if self.rx.is_empty() { 0 } else { LSR_DR },
+            MSR => MSR_CONNECTED,
+            SCR => self.scr,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn write_register(&mut self, offset: u32, value: u8) -> Option<()> {
+        match offset {
+            RBR_THR if self.dlab() => self.dll = value,
+            RBR_THR => self.transmit(value),
+            IER if self.dlab() => self.dlm = value,
+            IER => {
+                // Enabling the THRE interrupt while THR is empty raises it straight away
+                if value & IER_THRE != 0 && self.ier & IER_THRE == 0 {
+                    self.thre_pending = true;
+                }
+                self.ier = value & 0x0F;
+            }
+            IIR_FCR => {
+                self.fifo_enabled = value & FCR_ENABLE != 0;
+                if value & FCR_CLEAR_RX != 0 {
+                    self.rx.clear();
+                }
+            }
+            LCR => self.lcr = value,
+            MCR => self.mcr = value & 0x1F,
+            LSR | MSR => {} // Read only
+            SCR => self.scr = value,
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+impl Device for Uart {
+    fn read_u8(&mut self, offset: u32) -> Option<u8> {
+        self.read_register(offset)
+    }
+
+    fn write_u8(&mut self, offset: u32, value: u8) -> Option<()> {
+        self.write_register(offset, value)
+    }
+
+    // Wider accesses only touch the register they point at, splitting them
+    // into bytes would pop the receive FIFO and acknowledge interrupts behind
+    // the program's back.
+    fn read_u16(&mut self, offset: u32) -> Option<u16> {
+        self.read_register(offset).map(|value| value as u16)
+    }
+
+    fn write_u16(&mut self, offset: u32, value: u16) -> Option<()> {
+        self.write_register(offset, value as u8)
+    }
+
+    fn read_u32(&mut self, offset: u32) -> Option<u32> {
+        self.read_register(offset).map(|value| value as u32)
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) -> Option<()> {
+        self.write_register(offset, value as u8)
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+        if self.ticks >= POLL_INTERVAL {
+            self.ticks = 0;
+            self.poll_input();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.pending() != IIR_NONE
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::bus::uart::*;
+    use crate::cpu::bus::SharedOutput;
+    use std::sync::mpsc::{self, Sender};
+
+    fn setup() -> (Uart, SharedOutput, Sender<u8>) {
+        let output = SharedOutput::default();
+        let (sender, receiver) = mpsc::channel();
+        (Uart::with_input(Box::new(output.clone()), receiver), output, sender)
+    }
+
+    fn tick(uart: &mut Uart) {
+        for _ in 0..POLL_INTERVAL {
+            uart.tick();
+        }
+    }
+
+    #[test]
+    fn test_transmit() {
+        let (mut uart, output, _) = setup();
+        assert_eq!(uart.read_u8(LSR), Some(LSR_THRE | LSR_TEMT));
+        for byte in b"hi\n" {
+            uart.write_u8(RBR_THR, *byte).unwrap();
+        }
+        assert_eq!(output.text(), "hi\n");
+    }
+
+    #[test]
+    fn test_receive() {
+        let (mut uart, _, input) = setup();
+        for byte in 0..20 {
+            input.send(byte).unwrap();
+        }
+        assert_eq!(uart.read_u8(LSR).unwrap() & LSR_DR, 0, "Input is only picked up as the VM runs");
+        tick(&mut uart);
+        assert_eq!(uart.rx.len(), FIFO_SIZE);
+        for byte in 0..20 {
+            assert_eq!(uart.read_u8(LSR).unwrap() & LSR_DR, LSR_DR);
+            assert_eq!(uart.read_u8(RBR_THR), Some(byte));
+        }
+        assert_eq!(uart.read_u8(LSR).unwrap() & LSR_DR, 0);
+        assert_eq!(uart.read_u8(RBR_THR), Some(0));
+    }
+
+    #[test]
+    fn test_divisor_latch() {
+        let (mut uart, output, _) = setup();
+        uart.write_u8(IER, 0x01).unwrap();
+        uart.write_u8(LCR, LCR_DLAB | 0x03).unwrap();
+        uart.write_u8(RBR_THR, 0x03).unwrap();
+        uart.write_u8(IER, 0x00).unwrap();
+        assert_eq!(uart.read_u8(RBR_THR), Some(0x03));
+        assert_eq!(uart.read_u8(IER), Some(0x00));
+        uart.write_u8(LCR, 0x03).unwrap();
+        assert_eq!(uart.read_u8(IER), Some(0x01));
+        assert_eq!(uart.read_u8(LCR), Some(0x03));
+        assert!(output.text().is_empty(), "Divisor writes should not transmit");
+    }
+
+    #[test]
+    fn test_interrupts() {
+        let (mut uart, _, input) = setup();
+        assert!(!uart.irq());
+        assert_eq!(uart.read_u8(IIR_FCR), Some(IIR_NONE));
+
+        // THRE fires as soon as it's enabled, and reading IIR acknowledges it
+        uart.write_u8(IER, IER_THRE).unwrap();
+        assert!(uart.irq());
+        assert_eq!(uart.read_u8(IIR_FCR), Some(IIR_THRE));
+        assert!(!uart.irq());
+        uart.write_u8(RBR_THR, b'x').unwrap();
+        assert!(uart.irq());
+
+        // Received data takes priority, and stays pending until the FIFO is drained
+        uart.write_u8(IER, IER_RDA | IER_THRE).unwrap();
+        uart.write_u8(IIR_FCR, FCR_ENABLE).unwrap();
+        input.send(b'a').unwrap();
+        tick(&mut uart);
+        assert_eq!(uart.read_u8(IIR_FCR), Some(IIR_FIFO_ENABLED | IIR_RDA));
+        assert!(uart.irq());
+        assert_eq!(uart.read_u8(RBR_THR), Some(b'a'));
+        assert_eq!(uart.read_u8(IIR_FCR), Some(IIR_FIFO_ENABLED | IIR_THRE));
+        assert_eq!(uart.read_u8(IIR_FCR), Some(IIR_FIFO_ENABLED | IIR_NONE));
+        assert!(!uart.irq());
+    }
+
+    #[test]
+    fn test_fifo_clear() {
+        let (mut uart, _, input) = setup();
+        input.send(b'a').unwrap();
+        tick(&mut uart);
+        uart.write_u8(IIR_FCR, FCR_ENABLE | FCR_CLEAR_RX).unwrap();
+        assert_eq!(uart.read_u8(LSR).unwrap() & LSR_DR, 0);
+    }
+
+    #[test]
+    fn test_wide_access() {
+        let (mut uart, output, input) = setup();
+        input.send(b'a').unwrap();
+        tick(&mut uart);
+        uart.write_u8(SCR, 0x5A).unwrap();
+        assert_eq!(uart.read_u32(SCR), Some(0x5A));
+        // A word read of RBR doesn't touch IER, IIR and LCR
+        assert_eq!(uart.read_u32(RBR_THR), Some(b'a' as u32));
+        uart.write_u32(RBR_THR, 0x4342_4100 | b'z' as u32).unwrap();
+        assert_eq!(output.text(), "z");
+        assert_eq!(uart.read_u8(8), None);
+    }
+}
@@ -0,0 +1,423 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* 16-bit compressed (C extension) instructions
+ *
+ * Any instruction whose lowest two bits aren't 11 is a 16-bit parcel, the two
+ * bits being its quadrant. Every compressed instruction is a shorter encoding
+ * of a 32-bit one, so they're expanded on fetch and executed as such.
+ *
+ * The 3-bit register fields (rd', rs1', rs2') only reach x8-x15.
+ *
+ * RV64 reuses a few encodings: C.FLW, C.FSW, C.FLWSP and C.FSWSP become C.LD,
+ * C.SD, C.LDSP and C.SDSP, and C.JAL becomes C.ADDIW. Shifts get a 6-bit shamt.
+ */
+
+use crate::cpu::Xlen;
+use crate::cpu::opcodes::*;
+use crate::cpu::register::REG_RA;
+use crate::cpu::register::REG_SP;
+
+const QUADRANT: u16 = 0x3;
+
+// Bits of the parcel, shifted down to bit 0
+fn bits(parcel: u16, high: u32, low: u32) -> u32 {
+    (parcel as u32 >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+// Sign-extends the lowest `bits` bits of value to the full 32 bits
+fn sign_extend(value: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+// Full register number of a 3-bit register field
+fn reg_prime(field: u32) -> u32 {
+    field + 8
+}
+
+// 32-bit encodings, one per instruction format
+fn i_type(imm: u32, rs1: u32, funct3: u8, rd: u32, opcode: u8) -> u32 {
+    (imm & 0xFFF) << 20 | rs1 << 15 | (funct3 as u32) << 12 | rd << 7 | opcode as u32
+}
+
+fn r_type(funct7: u8, rs2: u32, rs1: u32, funct3: u8, rd: u32, opcode: u8) -> u32 {
+    (funct7 as u32) << 25 | rs2 << 20 | rs1 << 15 | (funct3 as u32) << 12 | rd << 7 | opcode as u32
+}
+
+fn s_type(imm: u32, rs2: u32, rs1: u32, funct3: u8, opcode: u8) -> u32 {
+    (imm >> 5 & 0x7F) << 25 | rs2 << 20 | rs1 << 15 | (funct3 as u32) << 12 | (imm & 0x1F) << 7 | opcode as u32
+}
+
+fn b_type(imm: u32, rs2: u32, rs1: u32, funct3: u8) -> u32 {
+    (imm >> 12 & 0x1) << 31 | (imm >> 5 & 0x3F) << 25 | rs2 << 20 | rs1 << 15 | (funct3 as u32) << 12
+        | (imm >> 1 & 0xF) << 8 | (imm >> 11 & 0x1) << 7 | OP::BRANCH as u32
+}
+
+fn j_type(imm: u32, rd: u32) -> u32 {
+    (imm >> 20 & 0x1) << 31 | (imm >> 1 & 0x3FF) << 21 | (imm >> 11 & 0x1) << 20 | (imm >> 12 & 0xFF) << 12
+        | rd << 7 | OP::JAL as u32
+}
+
+// Offset of C.J and C.JAL
+fn jump_offset(parcel: u16) -> u32 {
+    let imm = bits(parcel, 12, 12) << 11
+        | bits(parcel, 11, 11) << 4
+        | bits(parcel, 10, 9) << 8
+        | bits(parcel, 8, 8) << 10
+        | bits(parcel, 7, 7) << 6
+        | bits(parcel, 6, 6) << 7
+        | bits(parcel, 5, 3) << 1
+        | bits(parcel, 2, 2) << 5;
+    sign_extend(imm, 12)
+}
+
+// Offset of C.BEQZ and C.BNEZ
+fn branch_offset(parcel: u16) -> u32 {
+    let imm = bits(parcel, 12, 12) << 8
+        | bits(parcel, 11, 10) << 3
+        | bits(parcel, 6, 5) << 6
+        | bits(parcel, 4, 3) << 1
+        | bits(parcel, 2, 2) << 5;
+    sign_extend(imm, 9)
+}
+
+// The 6-bit immediate most quadrant 1 and 2 instructions have, bit 12 being its top bit
+fn imm6(parcel: u16) -> u32 {
+    sign_extend(bits(parcel, 12, 12) << 5 | bits(parcel, 6, 2), 6)
+}
+
+// Whether the lowest two bits make this the first parcel of a 16-bit instruction
+pub(crate) fn is_compressed(parcel: u16) -> bool {
+    parcel & QUADRANT != QUADRANT
+}
+
+// The 32-bit instruction a compressed one stands for, None if it's reserved
+// or not defined for this XLEN
+pub(crate) fn expand(parcel: u16, xlen: Xlen) -> Option<u32> {
+    let rv64 = xlen == Xlen::Rv64;
+    let funct3 = bits(parcel, 15, 13);
+    let rd = bits(parcel, 11, 7); // Also rs1
+    let rs2 = bits(parcel, 6, 2);
+    // shamt[5] is bit 12, which must be zero on RV32
+    let shamt = bits(parcel, 12, 12) << 5 | rs2;
+    let rd_prime = reg_prime(bits(parcel, 4, 2)); // Also rs2'
+    let rs1_prime = reg_prime(bits(parcel, 9, 7)); // Also rd'
+    let sp = REG_SP as u32;
+
+    let instruction = match (parcel & QUADRANT, funct3) {
+        // C.ADDI4SPN: addi rd', sp, nzuimm. This also makes the all zero parcel illegal
+        (0b00, 0b000) => {
+            let imm = bits(parcel, 12, 11) << 4 | bits(parcel, 10, 7) << 6 | bits(parcel, 6, 6) << 2 | bits(parcel, 5, 5) << 3;
+            if imm == 0 {
+                return None;
+            }
+            i_type(imm, sp, F3::ADDI, rd_prime, OP::ALUI)
+        }
+        // C.FLD: fld rd', uimm(rs1')
+        (0b00, 0b001) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 5) << 6;
+            i_type(imm, rs1_prime, F3::FLD, rd_prime, OP::LOAD_FP)
+        }
+        // C.LW: lw rd', uimm(rs1')
+        (0b00, 0b010) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 6) << 2 | bits(parcel, 5, 5) << 6;
+            i_type(imm, rs1_prime, F3::LW, rd_prime, OP::LOAD)
+        }
+        // C.LD: ld rd', uimm(rs1')
+        (0b00, 0b011) if rv64 => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 5) << 6;
+            i_type(imm, rs1_prime, F3::LD, rd_prime, OP::LOAD)
+        }
+        // C.FLW: flw rd', uimm(rs1')
+        (0b00, 0b011) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 6) << 2 | bits(parcel, 5, 5) << 6;
+            i_type(imm, rs1_prime, F3::FLW, rd_prime, OP::LOAD_FP)
+        }
+        // C.FSD: fsd rs2', uimm(rs1')
+        (0b00, 0b101) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 5) << 6;
+            s_type(imm, rd_prime, rs1_prime, F3::FSD, OP::STORE_FP)
+        }
+        // C.SW: sw rs2', uimm(rs1')
+        (0b00, 0b110) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 6) << 2 | bits(parcel, 5, 5) << 6;
+            s_type(imm, rd_prime, rs1_prime, F3::SW, OP::STORE)
+        }
+        // C.SD: sd rs2', uimm(rs1')
+        (0b00, 0b111) if rv64 => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 5) << 6;
+            s_type(imm, rd_prime, rs1_prime, F3::SD, OP::STORE)
+        }
+        // C.FSW: fsw rs2', uimm(rs1')
+        (0b00, 0b111) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 6, 6) << 2 | bits(parcel, 5, 5) << 6;
+            s_type(imm, rd_prime, rs1_prime, F3::FSW, OP::STORE_FP)
+        }
+        // C.ADDI: addi rd, rd, imm. C.NOP with rd = 0
+        (0b01, 0b000) => i_type(imm6(parcel), rd, F3::ADDI, rd, OP::ALUI),
+        // C.ADDIW: addiw rd, rd, imm, rd can't be zero
+        (0b01, 0b001) if rv64 => {
+            if rd == 0 {
+                return None;
+            }
+            i_type(imm6(parcel), rd, F3::ADDI, rd, OP::ALUI_W)
+        }
+        // C.JAL: jal ra, offset
+        (0b01, 0b001) => j_type(jump_offset(parcel), REG_RA as u32),
+        // C.LI: addi rd, zero, imm
+        (0b01, 0b010) => i_type(imm6(parcel), 0, F3::ADDI, rd, OP::ALUI),
+        // C.ADDI16SP: addi sp, sp, nzimm
+        (0b01, 0b011) if rd == sp => {
+            let imm = bits(parcel, 12, 12) << 9
+                | bits(parcel, 6, 6) << 4
+                | bits(parcel, 5, 5) << 6
+                | bits(parcel, 4, 3) << 7
+                | bits(parcel, 2, 2) << 5;
+            if imm == 0 {
+                return None;
+            }
+            i_type(sign_extend(imm, 10), sp, F3::ADDI, sp, OP::ALUI)
+        }
+        // C.LUI: lui rd, nzimm
+        (0b01, 0b011) => {
+            let imm = imm6(parcel);
+            if imm == 0 {
+                return None;
+            }
+            imm << 12 | rd << 7 | OP::LUI as u32
+        }
+        (0b01, 0b100) => match bits(parcel, 11, 10) {
+            // C.SRLI, C.SRAI: srli/srai rd', rd', shamt
+            0b00 | 0b01 if shamt > 31 && !rv64 => return None,
+            0b00 => i_type(shamt, rs1_prime, F3::SRLI_SRAI, rs1_prime, OP::ALUI),
+            0b01 => i_type((F7_SRAI as u32) << 5 | shamt, rs1_prime, F3::SRLI_SRAI, rs1_prime, OP::ALUI),
+            // C.ANDI: andi rd', rd', imm
+            0b10 => i_type(imm6(parcel), rs1_prime, F3::ANDI, rs1_prime, OP::ALUI),
+            // C.SUB, C.XOR, C.OR, C.AND: op rd', rd', rs2'. C.SUBW and C.ADDW, with bit 12 set, are RV64 only
+            _ => {
+                let (funct7, funct3, opcode) = match (bits(parcel, 12, 12), bits(parcel, 6, 5)) {
+                    (0, 0b00) => (F7_SUB, F3::ADD_SUB, OP::ALU),
+                    (0, 0b01) => (0, F3::XOR, OP::ALU),
+                    (0, 0b10) => (0, F3::OR, OP::ALU),
+                    (0, 0b11) => (0, F3::AND, OP::ALU),
+                    (1, 0b00) if rv64 => (F7_SUB, F3::ADD_SUB, OP::ALU_W),
+                    (1, 0b01) if rv64 => (F7_ADD, F3::ADD_SUB, OP::ALU_W),
+                    _ => return None,
+                };
+                r_type(funct7, rd_prime, rs1_prime, funct3, rs1_prime, opcode)
+            }
+        },
+        // C.J: jal zero, offset
+        (0b01, 0b101) => j_type(jump_offset(parcel), 0),
+        // C.BEQZ, C.BNEZ: beq/bne rs1', zero, offset
+        (0b01, 0b110) => b_type(branch_offset(parcel), 0, rs1_prime, F3::BEQ),
+        (0b01, 0b111) => b_type(branch_offset(parcel), 0, rs1_prime, F3::BNE),
+        // C.SLLI: slli rd, rd, shamt
+        (0b10, 0b000) => {
+            if shamt > 31 && !rv64 {
+                return None;
+            }
+            i_type(shamt, rd, F3::SLLI, rd, OP::ALUI)
+        }
+        // C.FLDSP: fld rd, uimm(sp)
+        (0b10, 0b001) => {
+            let imm = bits(parcel, 12, 12) << 5 | bits(parcel, 6, 5) << 3 | bits(parcel, 4, 2) << 6;
+            i_type(imm, sp, F3::FLD, rd, OP::LOAD_FP)
+        }
+        // C.LWSP: lw rd, uimm(sp), rd can't be zero
+        (0b10, 0b010) => {
+            if rd == 0 {
+                return None;
+            }
+            let imm = bits(parcel, 12, 12) << 5 | bits(parcel, 6, 4) << 2 | bits(parcel, 3, 2) << 6;
+            i_type(imm, sp, F3::LW, rd, OP::LOAD)
+        }
+        // C.LDSP: ld rd, uimm(sp), rd can't be zero
+        (0b10, 0b011) if rv64 => {
+            if rd == 0 {
+                return None;
+            }
+            let imm = bits(parcel, 12, 12) << 5 | bits(parcel, 6, 5) << 3 | bits(parcel, 4, 2) << 6;
+            i_type(imm, sp, F3::LD, rd, OP::LOAD)
+        }
+        // C.FLWSP: flw rd, uimm(sp), unlike C.LWSP f0 is fine
+        (0b10, 0b011) => {
+            let imm = bits(parcel, 12, 12) << 5 | bits(parcel, 6, 4) << 2 | bits(parcel, 3, 2) << 6;
+            i_type(imm, sp, F3::FLW, rd, OP::LOAD_FP)
+        }
+        (0b10, 0b100) => match (bits(parcel, 12, 12), rd, rs2) {
+            (0, 0, 0) => return None,
+            // C.JR: jalr zero, 0(rs1)
+            (0, _, 0) => i_type(0, rd, 0, 0, OP::JALR),
+            // C.MV: add rd, zero, rs2
+            (0, _, _) => r_type(F7_ADD, rs2, 0, F3::ADD_SUB, rd, OP::ALU),
+            // C.EBREAK
+            (_, 0, 0) => i_type(F12::EBREAK as u32, 0, F3::ECALL_EBREAK, 0, OP::E_C),
+            // C.JALR: jalr ra, 0(rs1)
+            (_, _, 0) => i_type(0, rd, 0, REG_RA as u32, OP::JALR),
+            // C.ADD: add rd, rd, rs2
+            _ => r_type(F7_ADD, rs2, rd, F3::ADD_SUB, rd, OP::ALU),
+        },
+        // C.FSDSP: fsd rs2, uimm(sp)
+        (0b10, 0b101) => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 9, 7) << 6;
+            s_type(imm, rs2, sp, F3::FSD, OP::STORE_FP)
+        }
+        // C.SWSP: sw rs2, uimm(sp)
+        (0b10, 0b110) => {
+            let imm = bits(parcel, 12, 9) << 2 | bits(parcel, 8, 7) << 6;
+            s_type(imm, rs2, sp, F3::SW, OP::STORE)
+        }
+        // C.SDSP: sd rs2, uimm(sp)
+        (0b10, 0b111) if rv64 => {
+            let imm = bits(parcel, 12, 10) << 3 | bits(parcel, 9, 7) << 6;
+            s_type(imm, rs2, sp, F3::SD, OP::STORE)
+        }
+        // C.FSWSP: fsw rs2, uimm(sp)
+        (0b10, 0b111) => {
+            let imm = bits(parcel, 12, 9) << 2 | bits(parcel, 8, 7) << 6;
+            s_type(imm, rs2, sp, F3::FSW, OP::STORE_FP)
+        }
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::compressed::*;
+
+    // Encodings from llvm-mc, compressed next to the instruction it expands to
+    fn check(cases: &[(u16, u32, &str)]) {
+        check_xlen(Xlen::Rv32, cases);
+    }
+
+    fn check_xlen(xlen: Xlen, cases: &[(u16, u32, &str)]) {
+        for (parcel, expected, asm) in cases {
+            assert!(is_compressed(*parcel));
+            assert_eq!(expand(*parcel, xlen), Some(*expected), "{} expanded wrong", asm);
+        }
+    }
+
+    #[test]
+    fn test_quadrant0() {
+        check(&[
+            (0x0040, 0x00410413, "c.addi4spn s0, sp, 4"),
+            (0x1ff4, 0x3fc10693, "c.addi4spn a3, sp, 1020"),
+            (0x4180, 0x0005a403, "c.lw s0, 0(a1)"),
+            (0x5ffc, 0x07c7a783, "c.lw a5, 124(a5)"),
+            (0xc2c0, 0x0086a223, "c.sw s0, 4(a3)"),
+            (0xdffc, 0x06f7ae23, "c.sw a5, 124(a5)"),
+            (0x2180, 0x0005b407, "c.fld fs0, 0(a1)"),
+            (0x3ffc, 0x0f87b787, "c.fld fa5, 248(a5)"),
+            (0x61c0, 0x0045a407, "c.flw fs0, 4(a1)"),
+            (0x7ffc, 0x07c7a787, "c.flw fa5, 124(a5)"),
+            (0xa680, 0x0086b427, "c.fsd fs0, 8(a3)"),
+            (0xbffc, 0x0ef7bc27, "c.fsd fa5, 248(a5)"),
+            (0xe2c0, 0x0086a227, "c.fsw fs0, 4(a3)"),
+            (0xfffc, 0x06f7ae27, "c.fsw fa5, 124(a5)"),
+        ]);
+    }
+
+    #[test]
+    fn test_quadrant1() {
+        check(&[
+            (0x0001, 0x00000013, "c.nop"),
+            (0x147d, 0xfff40413, "c.addi s0, -1"),
+            (0x2001, 0x000000ef, "c.jal 0"),
+            (0x2ffd, 0x7fe000ef, "c.jal 2046"),
+            (0x3001, 0x801ff0ef, "c.jal -2048"),
+            (0x4505, 0x00100513, "c.li a0, 1"),
+            (0x557d, 0xfff00513, "c.li a0, -1"),
+            (0x7101, 0xe0010113, "c.addi16sp sp, -512"),
+            (0x6141, 0x01010113, "c.addi16sp sp, 16"),
+            (0x6505, 0x00001537, "c.lui a0, 1"),
+            (0x757d, 0xfffff537, "c.lui a0, 0xfffff"),
+            (0x807d, 0x01f45413, "c.srli s0, 31"),
+            (0x8405, 0x40145413, "c.srai s0, 1"),
+            (0x99f1, 0xffc5f593, "c.andi a1, -4"),
+            (0x8c05, 0x40940433, "c.sub s0, s1"),
+            (0x8c25, 0x00944433, "c.xor s0, s1"),
+            (0x8c45, 0x00946433, "c.or s0, s1"),
+            (0x8c65, 0x00947433, "c.and s0, s1"),
+            (0xa001, 0x0000006f, "c.j 0"),
+            (0xbffd, 0xfffff06f, "c.j -2"),
+            (0xc001, 0x00040063, "c.beqz s0, 0"),
+            (0xfc7d, 0xfe041fe3, "c.bnez s0, -2"),
+            (0xd001, 0xf00400e3, "c.beqz s0, -256"),
+            (0xcc7d, 0x0e040f63, "c.beqz s0, 254"),
+        ]);
+    }
+
+    #[test]
+    fn test_quadrant2() {
+        check(&[
+            (0x047e, 0x01f41413, "c.slli s0, 31"),
+            (0x4402, 0x00012403, "c.lwsp s0, 0(sp)"),
+            (0x54fe, 0x0fc12483, "c.lwsp s1, 252(sp)"),
+            (0x8402, 0x00040067, "c.jr s0"),
+            (0x8426, 0x00900433, "c.mv s0, s1"),
+            (0x9002, 0x00100073, "c.ebreak"),
+            (0x9402, 0x000400e7, "c.jalr s0"),
+            (0x9426, 0x00940433, "c.add s0, s1"),
+            (0xc022, 0x00812023, "c.swsp s0, 0(sp)"),
+            (0xdfa6, 0x0e912e23, "c.swsp s1, 252(sp)"),
+            (0x2402, 0x00013407, "c.fldsp fs0, 0(sp)"),
+            (0x307e, 0x1f813007, "c.fldsp ft0, 504(sp)"),
+            (0x6402, 0x00012407, "c.flwsp fs0, 0(sp)"),
+            (0x707e, 0x0fc12007, "c.flwsp ft0, 252(sp)"),
+            (0xa022, 0x00813027, "c.fsdsp fs0, 0(sp)"),
+            (0xbfa6, 0x1e913c27, "c.fsdsp fs1, 504(sp)"),
+            (0xe022, 0x00812027, "c.fswsp fs0, 0(sp)"),
+            (0xffa6, 0x0e912e27, "c.fswsp fs1, 252(sp)"),
+        ]);
+    }
+
+    #[test]
+    fn test_rv64() {
+        check_xlen(Xlen::Rv64, &[
+            (0x6580, 0x0085b403, "c.ld s0, 8(a1)"),
+            (0x7ffc, 0x0f87b783, "c.ld a5, 248(a5)"),
+            (0xe680, 0x0086b423, "c.sd s0, 8(a3)"),
+            (0x357d, 0xfff5051b, "c.addiw a0, -1"),
+            (0x247d, 0x01f4041b, "c.addiw s0, 31"),
+            (0x907d, 0x03f45413, "c.srli s0, 63"),
+            (0x9401, 0x42045413, "c.srai s0, 32"),
+            (0x147e, 0x03f41413, "c.slli s0, 63"),
+            (0x9c05, 0x4094043b, "c.subw s0, s1"),
+            (0x9c25, 0x0094043b, "c.addw s0, s1"),
+            (0x6402, 0x00013403, "c.ldsp s0, 0(sp)"),
+            (0x70fe, 0x1f813083, "c.ldsp ra, 504(sp)"),
+            (0xe022, 0x00813023, "c.sdsp s0, 0(sp)"),
+            (0xffa6, 0x1e913c23, "c.sdsp s1, 504(sp)"),
+            // The rest expands the same
+            (0x4505, 0x00100513, "c.li a0, 1"),
+            (0x3ffc, 0x0f87b787, "c.fld fa5, 248(a5)"),
+        ]);
+        assert_eq!(expand(0x2001, Xlen::Rv64), None, "c.addiw to x0");
+        assert_eq!(expand(0x6002, Xlen::Rv64), None, "c.ldsp to x0");
+        assert_eq!(expand(0x9c45, Xlen::Rv64), None, "Reserved");
+    }
+
+    #[test]
+    fn test_illegal() {
+        assert_eq!(expand(0x0000, Xlen::Rv32), None, "The all zero parcel is illegal");
+        assert_eq!(expand(0x6101, Xlen::Rv32), None, "c.addi16sp with a zero immediate");
+        assert_eq!(expand(0x6401, Xlen::Rv32), None, "c.lui with a zero immediate");
+        assert_eq!(expand(0x4002, Xlen::Rv32), None, "c.lwsp to x0");
+        assert_eq!(expand(0x8002, Xlen::Rv32), None, "c.jr x0");
+        assert_eq!(expand(0x1002, Xlen::Rv32), None, "c.slli with shamt[5] set is RV64 only");
+        assert_eq!(expand(0x9001, Xlen::Rv32), None, "c.srli with shamt[5] set is RV64 only");
+        assert_eq!(expand(0x9c05, Xlen::Rv32), None, "c.subw is RV64 only");
+        assert_eq!(expand(0x8000, Xlen::Rv32), None, "Reserved");
+    }
+
+    #[test]
+    fn test_is_compressed() {
+        assert!(is_compressed(0x0001));
+        assert!(is_compressed(0x0002));
+        assert!(!is_compressed(0x0013));
+    }
+}
@@ -0,0 +1,897 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Control and status registers, and the privilege mode they go with
+ *
+ * Most of these are WARL (write any, read legal): writes are accepted,
+ * but only the bits we implement stick. Reads and writes of CSRs we don't
+ * implement return None, which the instruction turns into an illegal
+ * instruction exception.
+ *
+ * The supervisor registers sstatus, sie and sip aren't registers of their
+ * own, they are restricted views of mstatus, mie and mip. Likewise fflags
+ * and frm are the two fields of fcsr.
+ */
+
+use crate::cpu::Xlen;
+use crate::cpu::trap::Interrupt;
+use crate::cpu::pmp::{Pmp, PMPCFG0, PMPCFG3, PMPADDR0, PMPADDR15};
+
+// Floating point
+pub(crate) const FFLAGS: u16 = 0x001;
+pub(crate) const FRM: u16 = 0x002;
+pub(crate) const FCSR: u16 = 0x003;
+
+// Supervisor trap setup
+pub(crate) const SSTATUS: u16 = 0x100;
+pub(crate) const SIE: u16 = 0x104;
+pub(crate) const STVEC: u16 = 0x105;
+
+// Supervisor trap handling
+pub(crate) const SSCRATCH: u16 = 0x140;
+pub(crate) const SEPC: u16 = 0x141;
+pub(crate) const SCAUSE: u16 = 0x142;
+pub(crate) const STVAL: u16 = 0x143;
+pub(crate) const SIP: u16 = 0x144;
+
+// Supervisor protection and translation
+pub(crate) const SATP: u16 = 0x180;
+
+// Machine information registers (read only)
+pub(crate) const MVENDORID: u16 = 0xF11;
+pub(crate) const MARCHID: u16 = 0xF12;
+pub(crate) const MIMPID: u16 = 0xF13;
+pub(crate) const MHARTID: u16 = 0xF14;
+
+// Machine trap setup
+pub(crate) const MSTATUS: u16 = 0x300;
+pub(crate) const MISA: u16 = 0x301;
+pub(crate) const MEDELEG: u16 = 0x302;
+pub(crate) const MIDELEG: u16 = 0x303;
+pub(crate) const MIE: u16 = 0x304;
+pub(crate) const MTVEC: u16 = 0x305;
+pub(crate) const MSTATUSH: u16 = 0x310;
+
+// Machine trap handling
+pub(crate) const MSCRATCH: u16 = 0x340;
+pub(crate) const MEPC: u16 = 0x341;
+pub(crate) const MCAUSE: u16 = 0x342;
+pub(crate) const MTVAL: u16 = 0x343;
+pub(crate) const MIP: u16 = 0x344;
+
+// Names of the registers above, for the disassembler and the assembler
+const CSR_NAMES: [(u16, &str); 28] = [
+    (FFLAGS, "fflags"), (FRM, "frm"), (FCSR, "fcsr"),
+    (SSTATUS, "sstatus"), (SIE, "sie"), (STVEC, "stvec"),
+    (SSCRATCH, "sscratch"), (SEPC, "sepc"), (SCAUSE, "scause"), (STVAL, "stval"), (SIP, "sip"),
+    (SATP, "satp"),
+    (MVENDORID, "mvendorid"), (MARCHID, "marchid"), (MIMPID, "mimpid"), (MHARTID, "mhartid"),
+    (MSTATUS, "mstatus"), (MISA, "misa"), (MEDELEG, "medeleg"), (MIDELEG, "mideleg"), (MIE, "mie"),
+    (MTVEC, "mtvec"), (MSTATUSH, "mstatush"),
+    (MSCRATCH, "mscratch"), (MEPC, "mepc"), (MCAUSE, "mcause"), (MTVAL, "mtval"), (MIP, "mip"),
+];
+
+// The name of a CSR we implement, the PMP ones included
+pub(crate) fn csr_name(csr: u16) -> Option<String> {
+    match csr {
+        PMPCFG0..=PMPCFG3 => Some(format!("pmpcfg{}", csr - PMPCFG0)),
+        PMPADDR0..=PMPADDR15 => Some(format!("pmpaddr{}", csr - PMPADDR0)),
+        _ => CSR_NAMES.iter().find(|(number, _)| *number == csr).map(|(_, name)| name.to_string()),
+    }
+}
+
+// The number of a CSR we implement, by name
+pub(crate) fn csr_number(name: &str) -> Option<u16> {
+    let numbered = |prefix: &str, first: u16, last: u16| {
+        let index: u16 = name.strip_prefix(prefix)?.parse().ok()?;
+        (index <= last - first).then_some(first + index)
+    };
+    numbered("pmpcfg", PMPCFG0, PMPCFG3)
+        .or_else(|| numbered("pmpaddr", PMPADDR0, PMPADDR15))
+        .or_else(|| CSR_NAMES.iter().find(|(_, n)| *n == name).map(|(number, _)| *number))
+}
+
+// mstatus fields
+pub(crate) const MSTATUS_SIE: u64 = 1 << 1;
+pub(crate) const MSTATUS_MIE: u64 = 1 << 3;
+pub(crate) const MSTATUS_SPIE: u64 = 1 << 5;
+pub(crate) const MSTATUS_MPIE: u64 = 1 << 7;
+pub(crate) const MSTATUS_SPP: u64 = 1 << 8;
+pub(crate) const MSTATUS_MPP: u64 = 0x3 << 11;
+pub(crate) const MSTATUS_FS: u64 = 0x3 << 13;
+pub(crate) const MSTATUS_MPRV: u64 = 1 << 17;
+pub(crate) const MSTATUS_SUM: u64 = 1 << 18;
+pub(crate) const MSTATUS_MXR: u64 = 1 << 19;
+pub(crate) const MSTATUS_TVM: u64 = 1 << 20;
+pub(crate) const MSTATUS_TW: u64 = 1 << 21;
+pub(crate) const MSTATUS_TSR: u64 = 1 << 22;
+pub(crate) const MSTATUS_SD: u64 = 1 << 31; // Bit 63 on RV64
+// RV64 only: XLEN in U and S-mode, fixed to the machine's own
+pub(crate) const MSTATUS_UXL: u64 = 0x3 << 32;
+pub(crate) const MSTATUS_SXL: u64 = 0x3 << 34;
+const MSTATUS_MPP_SHIFT: u64 = 11;
+
+// mstatus.FS states
+pub(crate) const FS_OFF: u64 = 0;
+pub(crate) const FS_INITIAL: u64 = 1 << 13;
+pub(crate) const FS_DIRTY: u64 = 3 << 13;
+
+// fcsr fields
+const FFLAGS_MASK: u32 = 0x1F;
+const FRM_MASK: u32 = 0x7;
+const FRM_SHIFT: u32 = 5;
+
+// mtvec/stvec modes
+pub(crate) const MTVEC_MODE_DIRECT: u64 = 0;
+pub(crate) const MTVEC_MODE_VECTORED: u64 = 1;
+
+// mie/mip fields
+pub(crate) const MIP_SSIP: u64 = 1 << 1;
+pub(crate) const MIP_MSIP: u64 = 1 << 3;
+pub(crate) const MIP_STIP: u64 = 1 << 5;
+pub(crate) const MIP_MTIP: u64 = 1 << 7;
+pub(crate) const MIP_SEIP: u64 = 1 << 9;
+pub(crate) const MIP_MEIP: u64 = 1 << 11;
+
+// satp fields
+pub(crate) const SATP_MODE_SV32: u64 = 1 << 31;
+
+// misa: MXL in the top two bits, 1 for 32 bit and 2 for 64 bit, plus one bit per extension letter
+const MISA_MXL_32: u64 = 1 << 30;
+const MISA_MXL_64: u64 = 2 << 62;
+const fn misa_ext(ext: u8) -> u64 {
+    1 << (ext - b'A')
+}
+// The only extension that can be turned off, taking IALIGN from 16 to 32
+pub(crate) const MISA_C: u64 = misa_ext(b'C');
+
+const MSTATUS_MASK: u64 = MSTATUS_SIE | MSTATUS_MIE | MSTATUS_SPIE | MSTATUS_MPIE | MSTATUS_SPP | MSTATUS_MPP
+    | MSTATUS_FS | MSTATUS_MPRV | MSTATUS_SUM | MSTATUS_MXR | MSTATUS_TVM | MSTATUS_TW | MSTATUS_TSR;
+const SSTATUS_MASK: u64 = MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_FS | MSTATUS_SUM | MSTATUS_MXR;
+const S_INTERRUPTS: u64 = MIP_SSIP | MIP_STIP | MIP_SEIP;
+const MIE_MASK: u64 = S_INTERRUPTS | MIP_MSIP | MIP_MTIP | MIP_MEIP;
+// Software can raise the supervisor interrupts in mip, the machine ones are driven by devices
+const MIP_WRITABLE: u64 = S_INTERRUPTS;
+// Every exception but ecall from M-mode, which couldn't be delegated anywhere
+const MEDELEG_MASK: u64 = 0xB3FF & !(1 << 11);
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub(crate) enum Privilege {
+    User = 0,
+    Supervisor = 1,
+    Machine = 3,
+}
+
+impl Privilege {
+    // From the two bit encoding used by MPP, None for the reserved value
+    fn from_bits(bits: u64) -> Option<Self> {
+        match bits {
+            0 => Some(Privilege::User),
+            1 => Some(Privilege::Supervisor),
+            3 => Some(Privilege::Machine),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct Csr {
+    xlen: Xlen,
+    privilege: Privilege,
+    mstatus: u64,
+    misa: u64,
+    medeleg: u64,
+    mideleg: u64,
+    mie: u64,
+    mip: u64,
+    seip: bool, // External interrupt line of the supervisor, ORed into mip.SEIP
+    mtvec: u64,
+    mscratch: u64,
+    mepc: u64,
+    mcause: u64,
+    mtval: u64,
+    stvec: u64,
+    sscratch: u64,
+    sepc: u64,
+    scause: u64,
+    stval: u64,
+    satp: u64,
+    pmp: Pmp,
+    fflags: u32,
+    frm: u32,
+    // Whether software has written mtvec/stvec. Until it has, a trap has no handler to go to.
+    mtvec_written: bool,
+    stvec_written: bool,
+}
+
+// Applies the WARL rules of mtvec/stvec: modes 2 and 3 are reserved, keep the old mode
+fn legal_tvec(old: u64, value: u64) -> u64 {
+    let mode = match value & 0x3 {
+        MTVEC_MODE_DIRECT | MTVEC_MODE_VECTORED => value & 0x3,
+        _ => old & 0x3,
+    };
+    (value & !0x3) | mode
+}
+
+impl Csr {
+    pub(crate) fn with_xlen(xlen: Xlen) -> Self {
+        let (mxl, xl) = match xlen {
+            Xlen::Rv32 => (MISA_MXL_32, 0),
+            // U and S-mode run at 64 bits too
+            Xlen::Rv64 => (MISA_MXL_64, 2 << 32 | 2 << 34),
+        };
+        Self {
+            xlen,
+            // Harts come out of reset in M-mode. The FPU starts out on, so that
+            // programs built for hard float run without boot code enabling it.
+            privilege: Privilege::Machine,
+            mstatus: FS_INITIAL | xl,
+            // B stands for Zba, Zbb and Zbs. Zbc has no bit of its own.
+            misa: mxl | misa_ext(b'A') | misa_ext(b'B') | MISA_C | misa_ext(b'D') | misa_ext(b'F') | misa_ext(b'I')
+                | misa_ext(b'M') | misa_ext(b'S') | misa_ext(b'U'),
+            medeleg: 0,
+            mideleg: 0,
+            mie: 0,
+            mip: 0,
+            seip: false,
+            mtvec: 0,
+            mscratch: 0,
+            mepc: 0,
+            mcause: 0,
+            mtval: 0,
+            stvec: 0,
+            sscratch: 0,
+            sepc: 0,
+            scause: 0,
+            stval: 0,
+            satp: 0,
+            pmp: Pmp::new(),
+            fflags: 0,
+            frm: 0,
+            mtvec_written: false,
+            stvec_written: false,
+        }
+    }
+
+    pub(crate) fn privilege(&self) -> Privilege {
+        self.privilege
+    }
+
+    // Mode mret goes back to
+    pub(crate) fn mpp(&self) -> Privilege {
+        Privilege::from_bits((self.mstatus & MSTATUS_MPP) >> MSTATUS_MPP_SHIFT).unwrap_or(Privilege::User)
+    }
+
+    pub(crate) fn pmp(&self) -> &Pmp {
+        &self.pmp
+    }
+
+    // Whether an mstatus bit is set
+    pub(crate) fn status(&self, mask: u64) -> bool {
+        self.mstatus & mask != 0
+    }
+
+    // Whether FP instructions and CSRs are available, which they aren't while mstatus.FS is Off
+    pub(crate) fn fp_enabled(&self) -> bool {
+        self.mstatus & MSTATUS_FS != FS_OFF
+    }
+
+    // Any change to the FP state, registers or fcsr, marks it Dirty
+    pub(crate) fn set_fp_dirty(&mut self) {
+        self.mstatus |= FS_DIRTY;
+    }
+
+    // The dynamic rounding mode
+    pub(crate) fn frm(&self) -> u32 {
+        self.frm
+    }
+
+    // Raises the exception flags of an FP instruction, they stay set until software clears them
+    pub(crate) fn accrue_fp_flags(&mut self, flags: u32) {
+        self.fflags |= flags & FFLAGS_MASK;
+        self.set_fp_dirty();
+    }
+
+    // SD summarizes whether there's any dirty state to save, FS being the only one here.
+    // It's the top bit, whatever XLEN is.
+    fn mstatus(&self) -> u64 {
+        if self.mstatus & MSTATUS_FS == FS_DIRTY { self.mstatus | self.sd() } else { self.mstatus }
+    }
+
+    fn sd(&self) -> u64 {
+        match self.xlen {
+            Xlen::Rv32 => MSTATUS_SD,
+            Xlen::Rv64 => MSTATUS_SD << 32,
+        }
+    }
+
+    // CSRs with address bits 11:10 set to 0b11 are read only
+    pub(crate) fn is_read_only(address: u16) -> bool {
+        (address >> 10) & 0x3 == 0x3
+    }
+
+    // Whether the current privilege mode may access the CSR at `address`. Bits 9:8
+    // of the address give the lowest mode allowed, and satp is off limits to
+    // S-mode while mstatus.TVM is set. The FP CSRs go away with the FPU.
+    pub(crate) fn is_accessible(&self, address: u16) -> bool {
+        let required = ((address >> 8) & 0x3) as u8;
+        if (self.privilege as u8) < required {
+            return false;
+        }
+        if (FFLAGS..=FCSR).contains(&address) && !self.fp_enabled() {
+            return false;
+        }
+        !(address == SATP && self.privilege == Privilege::Supervisor && self.status(MSTATUS_TVM))
+    }
+
+    // Whether the C extension is on, allowing 16-bit instructions and 2 byte aligned jumps
+    pub(crate) fn has_compressed(&self) -> bool {
+        self.misa & MISA_C != 0
+    }
+
+    // xepc as seen by software and xRET, bit 1 is masked while C is off
+    fn epc(&self, epc: u64) -> u64 {
+        if self.has_compressed() { epc } else { epc & !0x3 }
+    }
+
+    fn mip(&self) -> u64 {
+        if self.seip { self.mip | MIP_SEIP } else { self.mip }
+    }
+
+    pub(crate) fn read(&self, address: u16) -> Option<u64> {
+        let value = match address {
+            FFLAGS => self.fflags as u64,
+            FRM => self.frm as u64,
+            FCSR => (self.frm << FRM_SHIFT | self.fflags) as u64,
+            SSTATUS => self.mstatus() & (SSTATUS_MASK | MSTATUS_UXL | self.sd()),
+            SIE => self.mie & self.mideleg,
+            STVEC => self.stvec,
+            SSCRATCH => self.sscratch,
+            SEPC => self.epc(self.sepc),
+            SCAUSE => self.scause,
+            STVAL => self.stval,
+            SIP => self.mip() & self.mideleg,
+            SATP => self.satp,
+            MVENDORID | MARCHID | MIMPID | MHARTID => 0,
+            MSTATUS => self.mstatus(),
+            MSTATUSH if self.xlen == Xlen::Rv32 => 0,
+            MISA => self.misa,
+            MEDELEG => self.medeleg,
+            MIDELEG => self.mideleg,
+            MIE => self.mie,
+            MIP => self.mip(),
+            MTVEC => self.mtvec,
+            MSCRATCH => self.mscratch,
+            MEPC => self.epc(self.mepc),
+            MCAUSE => self.mcause,
+            MTVAL => self.mtval,
+            PMPCFG0..=PMPCFG3 if self.xlen == Xlen::Rv64 => return self.read_pmpcfg64(address),
+            PMPCFG0..=PMPCFG3 | PMPADDR0..=PMPADDR15 => return self.pmp.read(address).map(u64::from),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Writing to a read only CSR is the caller's problem, here it just doesn't stick
+    pub(crate) fn write(&mut self, address: u16, value: u64) -> Option<()> {
+        let value = self.xlen.zext(value);
+        match address {
+            FFLAGS => self.fflags = value as u32 & FFLAGS_MASK,
+            FRM => self.frm = value as u32 & FRM_MASK,
+            FCSR => {
+                self.fflags = value as u32 & FFLAGS_MASK;
+                self.frm = (value as u32 >> FRM_SHIFT) & FRM_MASK;
+            }
+            SSTATUS => self.write_mstatus((self.mstatus & !SSTATUS_MASK) | (value & SSTATUS_MASK)),
+            // Only the delegated interrupts are visible to S-mode
+            SIE => self.mie = (self.mie & !self.mideleg) | (value & self.mideleg & MIE_MASK),
+            STVEC => {
+                self.stvec = legal_tvec(self.stvec, value);
+                self.stvec_written = true;
+            }
+            SSCRATCH => self.sscratch = value,
+            SEPC => self.sepc = value & !0x1,
+            SCAUSE => self.scause = value,
+            STVAL => self.stval = value,
+            // S-mode can only clear its own software interrupt
+            SIP => {
+                let mask = MIP_SSIP & self.mideleg;
+                self.mip = (self.mip & !mask) | (value & mask);
+            }
+            SATP => self.write_satp(value),
+            MVENDORID | MARCHID | MIMPID | MHARTID => {}
+            MSTATUS => self.write_mstatus(value),
+            // Only little endian, so MBE is hardwired to 0
+            MSTATUSH if self.xlen == Xlen::Rv32 => {}
+            // Only C can be turned off. Whether it's safe to do so depends on the
+            // alignment of the next instruction, which inst_csr checks
+            MISA => self.misa = (self.misa & !MISA_C) | (value & MISA_C),
+            MEDELEG => self.medeleg = value & MEDELEG_MASK,
+            // Only the supervisor interrupts can be delegated
+            MIDELEG => self.mideleg = value & S_INTERRUPTS,
+            MIE => self.mie = value & MIE_MASK,
+            MIP => self.mip = (self.mip & !MIP_WRITABLE) | (value & MIP_WRITABLE),
+            MTVEC => {
+                self.mtvec = legal_tvec(self.mtvec, value);
+                self.mtvec_written = true;
+            }
+            MSCRATCH => self.mscratch = value,
+            // Instructions are at least 2 byte aligned, so is mepc
+            MEPC => self.mepc = value & !0x1,
+            MCAUSE => self.mcause = value,
+            MTVAL => self.mtval = value,
+            PMPCFG0..=PMPCFG3 if self.xlen == Xlen::Rv64 => return self.write_pmpcfg64(address, value),
+            // pmpaddr holds bits 33:2 on RV64 too, the physical addresses past those don't exist
+            PMPCFG0..=PMPCFG3 | PMPADDR0..=PMPADDR15 => return self.pmp.write(address, value as u32),
+            _ => return None,
+        }
+        if (FFLAGS..=FCSR).contains(&address) {
+            self.set_fp_dirty();
+        }
+        Some(())
+    }
+
+    fn write_mstatus(&mut self, value: u64) {
+        let mut mstatus = (value & MSTATUS_MASK) | (self.mstatus & (MSTATUS_UXL | MSTATUS_SXL));
+        // MPP is WARL too, the reserved encoding keeps the old mode
+        if Privilege::from_bits((mstatus & MSTATUS_MPP) >> MSTATUS_MPP_SHIFT).is_none() {
+            mstatus = (mstatus & !MSTATUS_MPP) | (self.mstatus & MSTATUS_MPP);
+        }
+        self.mstatus = mstatus;
+    }
+    // Sv32 is the only translation mode, and it's RV32 only. RV64 has satp stuck
+    // in Bare mode: writes selecting any other mode are ignored, as they are for
+    // modes an implementation doesn't support, and the other fields read as zero.
+    fn write_satp(&mut self, value: u64) {
+        if self.xlen == Xlen::Rv32 {
+            self.satp = value;
+        }
+    }
+
+    // On RV64 the odd pmpcfg registers are gone, the even ones hold eight entries each
+    fn read_pmpcfg64(&self, address: u16) -> Option<u64> {
+        if !(address - PMPCFG0).is_multiple_of(2) {
+            return None;
+        }
+        let low = self.pmp.read(address)? as u64;
+        let high = self.pmp.read(address + 1)? as u64;
+        Some(high << 32 | low)
+    }
+
+    fn write_pmpcfg64(&mut self, address: u16, value: u64) -> Option<()> {
+        if !(address - PMPCFG0).is_multiple_of(2) {
+            return None;
+        }
+        self.pmp.write(address, value as u32)?;
+        self.pmp.write(address + 1, (value >> 32) as u32)
+    }
+}
+
+// Trap entry and exit
+impl Csr {
+    // Whether a trap with this cause is handled in S-mode. Traps are only
+    // delegated away from M-mode, never taken from M-mode down to S-mode.
+    fn is_delegated(&self, cause: u32, interrupt: bool) -> bool {
+        let deleg = if interrupt { self.mideleg } else { self.medeleg };
+        self.privilege != Privilege::Machine && cause < 32 && deleg & (1 << cause) != 0
+    }
+
+    // Saves the trap state, switches to the mode handling the trap and returns
+    // the address of its handler. In vectored mode interrupts jump to
+    // BASE + 4 * cause, exceptions always go to BASE.
+    pub(crate) fn enter_trap(&mut self, pc: u64, cause: u32, tval: u64, interrupt: bool) -> u64 {
+        // The interrupt bit is the top one, whatever XLEN is
+        let cause_value = if interrupt { cause as u64 | self.sd() } else { cause as u64 };
+        let tvec = if self.is_delegated(cause, interrupt) {
+            self.sepc = pc & !0x1;
+            self.scause = cause_value;
+            self.stval = tval;
+
+            // SPIE <- SIE, SIE <- 0, SPP <- previous mode (S or U)
+            let sie = self.status(MSTATUS_SIE);
+            self.mstatus &= !(MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP);
+            if sie {
+                self.mstatus |= MSTATUS_SPIE;
+            }
+            if self.privilege == Privilege::Supervisor {
+                self.mstatus |= MSTATUS_SPP;
+            }
+            self.privilege = Privilege::Supervisor;
+            self.stvec
+        } else {
+            self.mepc = pc & !0x1;
+            self.mcause = cause_value;
+            self.mtval = tval;
+
+            // MPIE <- MIE, MIE <- 0, MPP <- previous mode
+            let mie = self.status(MSTATUS_MIE);
+            self.mstatus &= !(MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_MPP);
+            if mie {
+                self.mstatus |= MSTATUS_MPIE;
+            }
+            self.mstatus |= (self.privilege as u64) << MSTATUS_MPP_SHIFT;
+            self.privilege = Privilege::Machine;
+            self.mtvec
+        };
+
+        let base = tvec & !0x3;
+        if interrupt && tvec & 0x3 == MTVEC_MODE_VECTORED {
+            base.wrapping_add(4 * cause as u64)
+        } else {
+            base
+        }
+    }
+
+    // MRET: restores the state saved by enter_trap, returns the address to go back to
+    pub(crate) fn leave_trap(&mut self) -> u64 {
+        // MIE <- MPIE, MPIE <- 1, mode <- MPP, MPP <- U
+        let mpie = self.status(MSTATUS_MPIE);
+        let mpp = self.mpp();
+        self.mstatus &= !(MSTATUS_MIE | MSTATUS_MPP);
+        if mpie {
+            self.mstatus |= MSTATUS_MIE;
+        }
+        self.mstatus |= MSTATUS_MPIE;
+        // Leaving M-mode turns MPRV off
+        if mpp != Privilege::Machine {
+            self.mstatus &= !MSTATUS_MPRV;
+        }
+        self.privilege = mpp;
+        self.epc(self.mepc)
+    }
+
+    // SRET: same as leave_trap, for traps taken in S-mode
+    pub(crate) fn leave_supervisor_trap(&mut self) -> u64 {
+        // SIE <- SPIE, SPIE <- 1, mode <- SPP, SPP <- U
+        let spie = self.status(MSTATUS_SPIE);
+        let spp = if self.status(MSTATUS_SPP) { Privilege::Supervisor } else { Privilege::User };
+        self.mstatus &= !(MSTATUS_SIE | MSTATUS_SPP | MSTATUS_MPRV);
+        if spie {
+            self.mstatus |= MSTATUS_SIE;
+        }
+        self.mstatus |= MSTATUS_SPIE;
+        self.privilege = spp;
+        self.epc(self.sepc)
+    }
+
+    // Whether software has set up mtvec, or stvec if the trap is delegated. Any
+    // value counts once written, a handler at address 0 included.
+    pub(crate) fn has_trap_handler(&self, cause: u32, interrupt: bool) -> bool {
+        if self.is_delegated(cause, interrupt) { self.stvec_written } else { self.mtvec_written }
+    }
+}
+
+// Interrupts
+impl Csr {
+    // Drives the mip bits in `mask` from a device's interrupt line
+    pub(crate) fn set_pending(&mut self, mask: u64, pending: bool) {
+        if pending {
+            self.mip |= mask;
+        } else {
+            self.mip &= !mask;
+        }
+    }
+
+    // Drives the supervisor external interrupt line. Unlike the machine lines it
+    // doesn't replace mip.SEIP, which M-mode software can also set.
+    pub(crate) fn set_supervisor_external(&mut self, pending: bool) {
+        self.seip = pending;
+    }
+
+    // Whether any interrupt is both pending and enabled in mie, regardless of
+    // the global enables. That's what wakes up WFI.
+    pub(crate) fn has_pending_interrupt(&self) -> bool {
+        self.mip() & self.mie != 0
+    }
+
+    // Whether anything could ever wake up WFI
+    pub(crate) fn has_enabled_interrupt(&self) -> bool {
+        self.mie != 0
+    }
+
+    // Interrupt to take before the next instruction, if any. Interrupts handled
+    // in M-mode go first, then the ones delegated to S-mode, each in priority
+    // order: external, software, timer. An interrupt is only taken when it's
+    // globally enabled for the mode handling it, or when running in a lower mode.
+    pub(crate) fn interrupt_to_take(&self) -> Option<Interrupt> {
+        const PRIORITY: [Interrupt; 6] = [
+            Interrupt::MachineExternal, Interrupt::MachineSoftware, Interrupt::MachineTimer,
+            Interrupt::SupervisorExternal, Interrupt::SupervisorSoftware, Interrupt::SupervisorTimer,
+        ];
+        let pending = self.mip() & self.mie;
+        if pending == 0 {
+            return None;
+        }
+        let machine_enabled = self.privilege < Privilege::Machine || self.status(MSTATUS_MIE);
+        let supervisor_enabled = self.privilege < Privilege::Supervisor
+            || (self.privilege == Privilege::Supervisor && self.status(MSTATUS_SIE));
+        let mut enabled = 0;
+        if machine_enabled {
+            enabled |= pending & !self.mideleg;
+        }
+        if supervisor_enabled {
+            enabled |= pending & self.mideleg;
+        }
+        let delegated = PRIORITY.into_iter().filter(|interrupt| self.mideleg & interrupt.mask() != 0);
+        let machine = PRIORITY.into_iter().filter(|interrupt| self.mideleg & interrupt.mask() == 0);
+        machine.chain(delegated).find(|interrupt| enabled & interrupt.mask() != 0)
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::csr::*;
+
+    #[test]
+    fn test_read_only() {
+        assert!(Csr::is_read_only(MHARTID));
+        assert!(Csr::is_read_only(MVENDORID));
+        assert!(!Csr::is_read_only(MSTATUS));
+    }
+
+    #[test]
+    fn test_unknown() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        assert_eq!(csr.read(0x7FF), None);
+        assert_eq!(csr.write(0x7FF, 1), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(csr_name(MSTATUS).as_deref(), Some("mstatus"));
+        assert_eq!(csr_name(PMPADDR0 + 15).as_deref(), Some("pmpaddr15"));
+        assert_eq!(csr_name(0x7FF), None);
+        assert_eq!(csr_number("mtvec"), Some(MTVEC));
+        assert_eq!(csr_number("pmpcfg3"), Some(PMPCFG3));
+        assert_eq!(csr_number("pmpcfg4"), None);
+        assert_eq!(csr_number("cycle"), None);
+    }
+
+    #[test]
+    fn test_misa() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        let misa = csr.read(MISA).unwrap();
+        assert_eq!(misa >> 30, 1, "MXL should report 32 bits");
+        assert_eq!(misa & 0x3FF_FFFF, 1 | (1 << 1) | (1 << 2) | (1 << 3) | (1 << 5) | (1 << 8) | (1 << 12) | (1 << 18) | (1 << 20),
+            "Only A, B, C, D, F, I, M, S and U should be set");
+        csr.write(MISA, 0).unwrap();
+        assert_eq!(csr.read(MISA), Some(misa & !MISA_C), "Only C can be turned off");
+        assert!(!csr.has_compressed());
+        csr.write(MISA, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MISA), Some(misa));
+    }
+
+    #[test]
+    fn test_mstatus_warl() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MSTATUS, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MSTATUS), Some(0x807E_79AA), "SD should be set with FS Dirty");
+        csr.write(MSTATUS, 0).unwrap();
+        assert_eq!(csr.read(MSTATUS), Some(0));
+        csr.write(MSTATUS, 1 << 11).unwrap();
+        assert_eq!(csr.read(MSTATUS), Some(1 << 11), "MPP should accept S");
+        csr.write(MSTATUS, 2 << 11).unwrap();
+        assert_eq!(csr.read(MSTATUS), Some(1 << 11), "Reserved MPP should be ignored");
+    }
+
+    #[test]
+    fn test_sstatus_view() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MSTATUS, MSTATUS_MIE | MSTATUS_MPP | MSTATUS_SIE).unwrap();
+        assert_eq!(csr.read(SSTATUS), Some(MSTATUS_SIE));
+        csr.write(SSTATUS, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(SSTATUS), Some(MSTATUS_SD | MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_FS | MSTATUS_SUM | MSTATUS_MXR));
+        assert!(csr.status(MSTATUS_MIE), "M-mode bits must not change through sstatus");
+        assert_eq!(csr.read(MSTATUS).unwrap() & MSTATUS_MPP, MSTATUS_MPP);
+        assert!(!csr.status(MSTATUS_TSR));
+    }
+
+    #[test]
+    fn test_mie_mip_warl() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MIE, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MIE), Some(0xAAA));
+        csr.write(MIP, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MIP), Some(MIP_SSIP | MIP_STIP | MIP_SEIP), "Machine bits are driven by devices");
+        csr.write(MIDELEG, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MIDELEG), Some(MIP_SSIP | MIP_STIP | MIP_SEIP));
+        csr.write(MEDELEG, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MEDELEG), Some(0xB3FF & !(1 << 11)));
+    }
+
+    #[test]
+    fn test_sie_sip_view() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MIE, MIP_MTIP | MIP_STIP).unwrap();
+        csr.write(MIP, MIP_STIP | MIP_SSIP).unwrap();
+        assert_eq!(csr.read(SIE), Some(0), "Nothing is delegated");
+        assert_eq!(csr.read(SIP), Some(0));
+
+        csr.write(MIDELEG, MIP_SSIP | MIP_STIP).unwrap();
+        assert_eq!(csr.read(SIE), Some(MIP_STIP));
+        assert_eq!(csr.read(SIP), Some(MIP_SSIP | MIP_STIP));
+        csr.write(SIE, 0xFFFF_FFFF).unwrap();
+        assert_eq!(csr.read(MIE), Some(MIP_MTIP | MIP_STIP | MIP_SSIP));
+        csr.write(SIP, 0).unwrap();
+        assert_eq!(csr.read(MIP), Some(MIP_STIP), "Only SSIP is writable through sip");
+    }
+
+    #[test]
+    fn test_seip() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.set_supervisor_external(true);
+        assert_eq!(csr.read(MIP), Some(MIP_SEIP));
+        csr.write(MIP, 0).unwrap();
+        assert_eq!(csr.read(MIP), Some(MIP_SEIP), "Software can't clear the external line");
+        csr.set_supervisor_external(false);
+        csr.write(MIP, MIP_SEIP).unwrap();
+        assert_eq!(csr.read(MIP), Some(MIP_SEIP));
+    }
+
+    #[test]
+    fn test_accessible() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        assert!(csr.is_accessible(MSTATUS));
+        assert!(csr.is_accessible(SATP));
+        csr.privilege = Privilege::Supervisor;
+        assert!(!csr.is_accessible(MSTATUS));
+        assert!(!csr.is_accessible(MHARTID));
+        assert!(csr.is_accessible(SSTATUS));
+        assert!(csr.is_accessible(SATP));
+        csr.write(MSTATUS, MSTATUS_TVM).unwrap();
+        assert!(!csr.is_accessible(SATP), "mstatus.TVM traps satp accesses");
+        csr.privilege = Privilege::User;
+        assert!(!csr.is_accessible(SSTATUS));
+    }
+
+    #[test]
+    fn test_mtvec_warl() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MTVEC, 0x8000_0101).unwrap();
+        assert_eq!(csr.read(MTVEC), Some(0x8000_0101));
+        csr.write(MTVEC, 0x8000_0202).unwrap();
+        assert_eq!(csr.read(MTVEC), Some(0x8000_0201), "Reserved mode should be ignored");
+    }
+
+    #[test]
+    fn test_mepc_alignment() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MEPC, 0x1003).unwrap();
+        assert_eq!(csr.read(MEPC), Some(0x1002));
+        csr.write(MISA, 0).unwrap();
+        assert_eq!(csr.read(MEPC), Some(0x1000), "Bit 1 is masked without C");
+        assert_eq!(csr.leave_trap(), 0x1000);
+    }
+
+    #[test]
+    fn test_enter_trap() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MTVEC, 0x100 | MTVEC_MODE_DIRECT).unwrap();
+        csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        assert_eq!(csr.enter_trap(0x40, 2, 0xFFFF_FFFF, false), 0x100);
+        assert_eq!(csr.read(MEPC), Some(0x40));
+        assert_eq!(csr.read(MCAUSE), Some(2));
+        assert_eq!(csr.read(MTVAL), Some(0xFFFF_FFFF));
+        assert_eq!(csr.read(MSTATUS), Some(MSTATUS_MPIE | MSTATUS_MPP));
+    }
+
+    #[test]
+    fn test_enter_trap_vectored() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MTVEC, 0x100 | MTVEC_MODE_VECTORED).unwrap();
+        assert_eq!(csr.enter_trap(0x40, 11, 0, false), 0x100, "Exceptions always go to BASE");
+        assert_eq!(csr.enter_trap(0x40, 7, 0, true), 0x11C);
+        assert_eq!(csr.read(MCAUSE), Some(0x8000_0007));
+    }
+
+    #[test]
+    fn test_leave_trap() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        csr.enter_trap(0x40, 3, 0x40, false);
+        assert_eq!(csr.leave_trap(), 0x40);
+        assert_eq!(csr.privilege(), Privilege::Machine);
+        assert_eq!(csr.read(MSTATUS), Some(MSTATUS_MIE | MSTATUS_MPIE), "MPP should be back to U");
+    }
+
+    #[test]
+    fn test_leave_trap_to_user() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MSTATUS, MSTATUS_MPRV).unwrap();
+        csr.leave_trap();
+        assert_eq!(csr.privilege(), Privilege::User);
+        assert!(!csr.status(MSTATUS_MPRV), "Leaving M-mode clears MPRV");
+
+        // Back to M-mode, MPP remembers where we came from
+        csr.write(MTVEC, 0x100).unwrap();
+        assert_eq!(csr.enter_trap(0x40, 8, 0, false), 0x100);
+        assert_eq!(csr.privilege(), Privilege::Machine);
+        assert_eq!(csr.read(MSTATUS).unwrap() & MSTATUS_MPP, 0);
+    }
+
+    #[test]
+    fn test_delegation() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MTVEC, 0x100).unwrap();
+        csr.write(STVEC, 0x200 | MTVEC_MODE_VECTORED).unwrap();
+        csr.write(MEDELEG, 1 << 8).unwrap();
+        csr.write(MIDELEG, MIP_STIP).unwrap();
+
+        // Never delegated away from M-mode
+        assert_eq!(csr.enter_trap(0x40, 8, 0, false), 0x100);
+        assert_eq!(csr.privilege(), Privilege::Machine);
+
+        csr.privilege = Privilege::User;
+        csr.write(MSTATUS, MSTATUS_SIE).unwrap();
+        assert_eq!(csr.enter_trap(0x44, 8, 0, false), 0x200);
+        assert_eq!(csr.privilege(), Privilege::Supervisor);
+        assert_eq!(csr.read(SEPC), Some(0x44));
+        assert_eq!(csr.read(SCAUSE), Some(8));
+        assert_eq!(csr.read(SSTATUS), Some(MSTATUS_SPIE), "SPP should be U");
+        assert_eq!(csr.read(MEPC), Some(0x40), "M-mode state must not change");
+
+        // Interrupts from S-mode into S-mode, vectored
+        assert_eq!(csr.enter_trap(0x80, 5, 0, true), 0x214);
+        assert_eq!(csr.read(SCAUSE), Some(0x8000_0005));
+        assert_eq!(csr.read(SSTATUS), Some(MSTATUS_SPP));
+
+        // Not delegated, so up to M-mode
+        assert_eq!(csr.enter_trap(0x84, 2, 0x13, false), 0x100);
+        assert_eq!(csr.read(MSTATUS).unwrap() & MSTATUS_MPP, 1 << 11);
+        assert_eq!(csr.read(MTVAL), Some(0x13));
+    }
+
+    #[test]
+    fn test_leave_supervisor_trap() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(SEPC, 0x300).unwrap();
+        csr.write(MSTATUS, MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_MPRV).unwrap();
+        assert_eq!(csr.leave_supervisor_trap(), 0x300);
+        assert_eq!(csr.privilege(), Privilege::Supervisor);
+        assert_eq!(csr.read(MSTATUS), Some(MSTATUS_SIE | MSTATUS_SPIE));
+        csr.leave_supervisor_trap();
+        assert_eq!(csr.privilege(), Privilege::User);
+    }
+
+    #[test]
+    fn test_interrupt_priority() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.set_pending(MIP_MSIP | MIP_MTIP | MIP_MEIP, true);
+        assert_eq!(csr.read(MIP), Some(MIP_MSIP | MIP_MTIP | MIP_MEIP));
+        assert!(!csr.has_pending_interrupt(), "Nothing is enabled in mie");
+        csr.write(MIE, MIP_MSIP | MIP_MTIP | MIP_MEIP).unwrap();
+        assert!(csr.has_pending_interrupt());
+        assert_eq!(csr.interrupt_to_take(), None, "mstatus.MIE is clear");
+
+        csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::MachineExternal));
+        csr.set_pending(MIP_MEIP, false);
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::MachineSoftware));
+        csr.set_pending(MIP_MSIP, false);
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::MachineTimer));
+        csr.write(MIE, MIP_MSIP).unwrap();
+        assert_eq!(csr.interrupt_to_take(), None);
+    }
+
+    #[test]
+    fn test_supervisor_interrupts() {
+        let mut csr = Csr::with_xlen(Xlen::Rv32);
+        csr.write(MIDELEG, MIP_SSIP | MIP_STIP).unwrap();
+        csr.write(MIE, MIP_SSIP | MIP_STIP | MIP_MTIP).unwrap();
+        csr.write(MIP, MIP_SSIP | MIP_STIP).unwrap();
+        assert_eq!(csr.interrupt_to_take(), None, "Delegated interrupts aren't taken in M-mode");
+
+        csr.privilege = Privilege::Supervisor;
+        assert_eq!(csr.interrupt_to_take(), None, "sstatus.SIE is clear");
+        csr.write(MSTATUS, MSTATUS_SIE).unwrap();
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::SupervisorSoftware));
+
+        csr.privilege = Privilege::User;
+        csr.write(MSTATUS, 0).unwrap();
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::SupervisorSoftware), "Always enabled from a lower mode");
+
+        // Machine interrupts come first, whatever the mode
+        csr.set_pending(MIP_MTIP, true);
+        assert_eq!(csr.interrupt_to_take(), Some(Interrupt::MachineTimer));
+    }
+}
@@ -0,0 +1,165 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Debugger support
+ *
+ * What a debugger needs to know about an instruction before it runs. The GDB
+ * stub uses it to stop on a watchpoint before the access rather than after,
+ * so looking ahead must leave no trace: the instruction is peeked at, through
+ * the TLB without counting in its statistics and from memory without device
+ * side effects, and fetched for real when it actually executes.
+ */
+
+use crate::cpu::compressed;
+use crate::cpu::decode::{self, AmoOp, Instruction, LoadOp, StoreOp};
+use crate::cpu::mmu::Access;
+use crate::cpu::softfloat::F64;
+use crate::cpu::CPU;
+
+// A data access, at a virtual address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MemoryAccess {
+    pub(crate) address: u64,
+    pub(crate) size: u32,
+    pub(crate) read: bool,
+    pub(crate) write: bool,
+}
+
+impl CPU {
+    // The byte at virtual address `address` as a load would see it, for a debugger
+    pub(crate) fn peek_u8(&mut self, address: u64) -> Option<u8> {
+        let physical = self.peek_physical(address, 1, Access::Load)?;
+        self.memory.peek(physical, 1).map(|byte| byte as u8)
+    }
+
+    // Writes the byte at virtual address `address` for a debugger. Translated as
+    // a load, so it can patch pages the program can't write to.
+    pub(crate) fn poke_u8(&mut self, address: u64, value: u8) -> Option<()> {
+        let physical = self.peek_physical(address, 1, Access::Load)?;
+        self.memory.poke_u8(physical, value)
+    }
+
+    // The 16-bit parcel at virtual address `address`, fetched without side effects
+    fn peek_u16(&mut self, address: u64) -> Option<u16> {
+        let physical = self.peek_physical(address, 2, Access::Fetch)?;
+        self.memory.peek(physical, 2).map(|parcel| parcel as u16)
+    }
+
+    // The instruction at pc, expanded if it's compressed
+    fn peek_instruction(&mut self) -> Option<u32> {
+        let first = self.peek_u16(self.pc)?;
+        if compressed::is_compressed(first) {
+            return self.csr.has_compressed().then(|| compressed::expand(first, self.xlen)).flatten();
+        }
+        Some(first as u32 | (self.peek_u16(self.xlen.zext(self.pc.wrapping_add(2)))? as u32) << 16)
+    }
+
+    // The memory access the instruction at pc is about to make, if it makes one.
+    // None too if it can't be fetched, the fault will come up when it's executed.
+    pub(crate) fn next_access(&mut self) -> Option<MemoryAccess> {
+        let base = |cpu: &CPU, rs1: u8, offset: u32| {
+            cpu.xlen.zext(cpu.registers.get_x(rs1).wrapping_add(offset as i32 as u64))
+        };
+        let access = match decode::decode(self.peek_instruction()?, self.xlen)? {
+            Instruction::Load { op, rs1, offset, .. } => {
+                let size = match op {
+                    LoadOp::Lb | LoadOp::Lbu => 1,
+                    LoadOp::Lh | LoadOp::Lhu => 2,
+                    LoadOp::Lw | LoadOp::Lwu => 4,
+                    LoadOp::Ld => 8,
+                };
+                MemoryAccess { address: base(self, rs1, offset), size, read: true, write: false }
+            }
+            Instruction::Store { op, rs1, offset, .. } => {
+                let size = match op {
+                    StoreOp::Sb => 1,
+                    StoreOp::Sh => 2,
+                    StoreOp::Sw => 4,
+                    StoreOp::Sd => 8,
+                };
+                MemoryAccess { address: base(self, rs1, offset), size, read: false, write: true }
+            }
+            // AMOs both read and write, LR only reads and SC only writes
+            Instruction::Amo { op, doubleword, rs1, .. } => MemoryAccess {
+                address: base(self, rs1, 0),
+                size: if doubleword { 8 } else { 4 },
+                read: op != AmoOp::Sc,
+                write: op != AmoOp::Lr,
+            },
+            Instruction::LoadFp { format, rs1, offset, .. } => MemoryAccess {
+                address: base(self, rs1, offset),
+                size: if format == F64 { 8 } else { 4 },
+                read: true,
+                write: false,
+            },
+            Instruction::StoreFp { format, rs1, offset, .. } => MemoryAccess {
+                address: base(self, rs1, offset),
+                size: if format == F64 { 8 } else { 4 },
+                read: false,
+                write: true,
+            },
+            _ => return None,
+        };
+        Some(access)
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::*;
+    use crate::cpu::csr::{MSTATUS, SATP, SATP_MODE_SV32};
+    use crate::cpu::debug::MemoryAccess;
+    use crate::cpu::mmu::{PTE_R, PTE_V, PTE_X};
+    use crate::cpu::pmp::{PMPADDR0, PMPCFG0};
+
+    fn cpu_with(instruction: u32) -> CPU {
+        let mut cpu = CPU::new();
+        cpu.load_image(0, &instruction.to_le_bytes()).unwrap();
+        cpu.set_pc(0);
+        cpu.registers.set_register(11, 0x100);
+        cpu
+    }
+
+    #[test]
+    fn test_next_access() {
+        // lbu a0, -2(a1)
+        assert_eq!(cpu_with(0xffe5c503).next_access(), Some(MemoryAccess { address: 0xFE, size: 1, read: true, write: false }));
+        // sw a0, -4(a1)
+        assert_eq!(cpu_with(0xfea5ae23).next_access(), Some(MemoryAccess { address: 0xFC, size: 4, read: false, write: true }));
+        // amoswap.w.aq a0, a1, (a0), with a0 = 0
+        assert_eq!(cpu_with(0x0cb5252f).next_access(), Some(MemoryAccess { address: 0, size: 4, read: true, write: true }));
+        // fld fa0, -1(a1)
+        assert_eq!(cpu_with(0xfff5b507).next_access(), Some(MemoryAccess { address: 0xFF, size: 8, read: true, write: false }));
+        // addi a0, a0, 1
+        assert_eq!(cpu_with(0x00150513).next_access(), None);
+    }
+
+    #[test]
+    fn test_next_access_compressed() {
+        // c.sw a0, 4(a1)
+        let mut cpu = cpu_with(0xc1c8);
+        (cpu.instruction, cpu.raw_instruction) = (0x00150513, 0x00150513);
+        assert_eq!(cpu.next_access(), Some(MemoryAccess { address: 0x104, size: 4, read: false, write: true }));
+        assert_eq!((cpu.instruction, cpu.raw_instruction), (0x00150513, 0x00150513), "The instruction being executed stays as it is");
+        assert_eq!(cpu.mmu.fetch.misses(), 0);
+    }
+
+    #[test]
+    fn test_peek_poke() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(PMPADDR0, 0xFFFF_FFFF).unwrap();
+        cpu.csr.write(PMPCFG0, 0x1F).unwrap();
+        // A read-only superpage at 0x4000_0000 over the RAM, in S-mode
+        cpu.memory.set_u32(0x1000 + (0x4000_0000 >> 22) * 4, PTE_V | PTE_R | PTE_X).unwrap();
+        cpu.csr.write(SATP, SATP_MODE_SV32 | 1).unwrap();
+        cpu.csr.write(MSTATUS, 1 << 11).unwrap();
+        cpu.csr.leave_trap();
+        cpu.memory.set_u8(0x100, 0xAB).unwrap();
+
+        assert_eq!(cpu.peek_u8(0x4000_0100), Some(0xAB));
+        assert_eq!(cpu.peek_u8(0x100), None, "Nothing is mapped there");
+        assert_eq!(cpu.poke_u8(0x4000_0101, 0xCD), Some(()), "Read-only pages can be patched");
+        assert_eq!(cpu.memory.get_u8(0x101), Ok(0xCD));
+    }
+}
@@ -0,0 +1,666 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Instruction decoder
+ *
+ * Turns a 32-bit instruction (compressed ones are expanded before getting here)
+ * into an `Instruction`, with its register fields pulled out and its immediate
+ * reassembled and sign-extended. Every reserved encoding is rejected here, so
+ * execution only has to deal with what depends on the hart's state: privilege,
+ * CSR permissions, mstatus.FS and the dynamic rounding mode.
+ *
+ * The RV64 only instructions are decoded when asked for XLEN = 64, and are
+ * illegal otherwise.
+ */
+
+use crate::cpu::Xlen;
+use crate::cpu::opcodes::*;
+use crate::cpu::softfloat::{Format, F32, F64};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BranchOp { Beq, Bne, Blt, Bge, Bltu, Bgeu }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LoadOp { Lb, Lh, Lw, Lbu, Lhu, Lwu, Ld }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum StoreOp { Sb, Sh, Sw, Sd }
+
+// Operations on two values, the second being rs2 for OP and the immediate for OP-IMM.
+// Only a subset has an immediate form. The .UW ones are RV64 only.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AluOp {
+    Add, Sub, Sll, Slt, Sltu, Xor, Srl, Sra, Or, And,
+    Mul, Mulh, Mulhsu, Mulhu, Div, Divu, Rem, Remu,
+    Sh1add, Sh2add, Sh3add, AddUw, Sh1addUw, Sh2addUw, Sh3addUw, SllUw,
+    Andn, Orn, Xnor, Min, Minu, Max, Maxu, Rol, Ror,
+    Clmul, Clmulh, Clmulr,
+    Bclr, Bext, Binv, Bset,
+}
+
+// Zbb instructions with a single source register
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum UnaryOp { Clz, Ctz, Cpop, SextB, SextH, ZextH, OrcB, Rev8 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AmoOp { Lr, Sc, Swap, Add, Xor, And, Or, Min, Max, Minu, Maxu }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CsrOp { Rw, Rs, Rc }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FmaOp { Fmadd, Fmsub, Fnmsub, Fnmadd }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FpOp {
+    Fadd, Fsub, Fmul, Fdiv, Fsqrt,
+    Fsgnj, Fsgnjn, Fsgnjx, Fmin, Fmax,
+    FcvtFmt, // From the other format, FCVT.S.D or FCVT.D.S
+    Feq, Flt, Fle,
+    FcvtW, FcvtWu, FcvtFromW, FcvtFromWu,
+    FmvXW, Fclass, FmvWX,
+    // RV64 only
+    FcvtL, FcvtLu, FcvtFromL, FcvtFromLu, FmvXD, FmvDX,
+}
+
+impl FpOp {
+    // Whether funct3 holds a rounding mode, the others use it as an opcode
+    pub(crate) fn rounds(self) -> bool {
+        matches!(self, FpOp::Fadd | FpOp::Fsub | FpOp::Fmul | FpOp::Fdiv | FpOp::Fsqrt
+            | FpOp::FcvtFmt | FpOp::FcvtW | FpOp::FcvtWu | FpOp::FcvtFromW | FpOp::FcvtFromWu
+            | FpOp::FcvtL | FpOp::FcvtLu | FpOp::FcvtFromL | FpOp::FcvtFromLu)
+    }
+}
+
+// A decoded instruction. Immediates and offsets are sign-extended to 32 bits, and from
+// there to XLEN when executed, except for shift amounts and the CSR immediate, which are
+// zero-extended. OpImmW, OpW and UnaryW are the RV64 word instructions: they work on the
+// lower 32 bits and sign-extend the result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Instruction {
+    Lui { rd: u8, imm: u32 },
+    Auipc { rd: u8, imm: u32 },
+    Jal { rd: u8, offset: u32 },
+    Jalr { rd: u8, rs1: u8, offset: u32 },
+    Branch { op: BranchOp, rs1: u8, rs2: u8, offset: u32 },
+    Load { op: LoadOp, rd: u8, rs1: u8, offset: u32 },
+    Store { op: StoreOp, rs1: u8, rs2: u8, offset: u32 },
+    OpImm { op: AluOp, rd: u8, rs1: u8, imm: u32 },
+    Op { op: AluOp, rd: u8, rs1: u8, rs2: u8 },
+    Unary { op: UnaryOp, rd: u8, rs1: u8 },
+    OpImmW { op: AluOp, rd: u8, rs1: u8, imm: u32 },
+    OpW { op: AluOp, rd: u8, rs1: u8, rs2: u8 },
+    UnaryW { op: UnaryOp, rd: u8, rs1: u8 },
+    Fence { pred: u8, succ: u8 },
+    FenceI,
+    Amo { op: AmoOp, doubleword: bool, aq: bool, rl: bool, rd: u8, rs1: u8, rs2: u8 },
+    Csr { op: CsrOp, rd: u8, rs1: u8, csr: u16 },
+    CsrImm { op: CsrOp, rd: u8, uimm: u8, csr: u16 },
+    Ecall,
+    Ebreak,
+    Mret,
+    Sret,
+    Wfi,
+    SfenceVma { rs1: u8, rs2: u8 },
+    LoadFp { format: Format, rd: u8, rs1: u8, offset: u32 },
+    StoreFp { format: Format, rs1: u8, rs2: u8, offset: u32 },
+    Fma { op: FmaOp, format: Format, rm: u8, rd: u8, rs1: u8, rs2: u8, rs3: u8 },
+    OpFp { op: FpOp, format: Format, rm: u8, rd: u8, rs1: u8, rs2: u8 },
+}
+
+// Sign-extends the lowest `bits` bits of value to the full 32 bits
+fn sign_extend(value: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+fn rd(raw: u32) -> u8 {
+    ((raw & MASK::RD) >> 7) as u8
+}
+
+fn rs1(raw: u32) -> u8 {
+    ((raw & MASK::RS1) >> 15) as u8
+}
+
+fn rs2(raw: u32) -> u8 {
+    ((raw & MASK::RS2) >> 20) as u8
+}
+
+fn funct3(raw: u32) -> u8 {
+    ((raw & MASK::F3) >> 12) as u8
+}
+
+fn funct7(raw: u32) -> u8 {
+    ((raw & MASK::F7) >> 25) as u8
+}
+
+// Immediates of each format
+fn imm_i(raw: u32) -> u32 {
+    sign_extend((raw & MASK::LOAD_IMM) >> 20, 12)
+}
+
+fn imm_s(raw: u32) -> u32 {
+    let imm_11_5 = (raw & MASK::STORE_IMM_11_5) >> 25;
+    let imm_4_0 = (raw & MASK::STORE_IMM_4_0) >> 7;
+    sign_extend(imm_11_5 << 5 | imm_4_0, 12)
+}
+
+fn imm_b(raw: u32) -> u32 {
+    let imm_12 = ((raw & MASK::BRANCH_IMM_12) >> 31) << 12;
+    let imm_11 = ((raw & MASK::BRANCH_IMM_11) >> 7) << 11;
+    let imm_10_5 = ((raw & MASK::BRANCH_IMM_10_5) >> 25) << 5;
+    let imm_4_1 = ((raw & MASK::BRANCH_IMM_4_1) >> 8) << 1;
+    sign_extend(imm_12 | imm_11 | imm_10_5 | imm_4_1, 13)
+}
+
+fn imm_j(raw: u32) -> u32 {
+    let imm_20 = (raw & MASK::JAL_IMM_20) >> 11;
+    let imm_10_1 = (raw & MASK::JAL_IMM_10_1) >> 20;
+    let imm_11 = (raw & MASK::JAL_IMM_11) >> 9;
+    let imm_19_12 = raw & MASK::JAL_IMM_19_12;
+    sign_extend(imm_20 | imm_19_12 | imm_11 | imm_10_1, 21)
+}
+
+// The format in bits 26:25, None for the unsupported H and Q
+fn fp_format(raw: u32) -> Option<Format> {
+    match ((raw & MASK::FMT) >> 25) as u8 {
+        FMT_S => Some(F32),
+        FMT_D => Some(F64),
+        _ => None,
+    }
+}
+
+// Decodes a 32-bit instruction, None if it's illegal
+pub(crate) fn decode(raw: u32, xlen: Xlen) -> Option<Instruction> {
+    let (rd, rs1, rs2, funct3) = (rd(raw), rs1(raw), rs2(raw), funct3(raw));
+    let rv64 = xlen == Xlen::Rv64;
+    let instruction = match (raw & MASK::OP) as u8 {
+        // LUI and AUIPC immediates are already in place, the low 12 bits are zero
+        OP::LUI => Instruction::Lui { rd, imm: raw & MASK::LUI_IMM },
+        OP::AUIPC => Instruction::Auipc { rd, imm: raw & MASK::AUIPC_IMM },
+        OP::JAL => Instruction::Jal { rd, offset: imm_j(raw) },
+        OP::JALR if funct3 == 0 => Instruction::Jalr { rd, rs1, offset: imm_i(raw) },
+        OP::BRANCH => {
+            let op = match funct3 {
+                F3::BEQ => BranchOp::Beq,
+                F3::BNE => BranchOp::Bne,
+                F3::BLT => BranchOp::Blt,
+                F3::BGE => BranchOp::Bge,
+                F3::BLTU => BranchOp::Bltu,
+                F3::BGEU => BranchOp::Bgeu,
+                _ => return None,
+            };
+            Instruction::Branch { op, rs1, rs2, offset: imm_b(raw) }
+        }
+        OP::LOAD => {
+            let op = match funct3 {
+                F3::LB => LoadOp::Lb,
+                F3::LH => LoadOp::Lh,
+                F3::LW => LoadOp::Lw,
+                F3::LBU => LoadOp::Lbu,
+                F3::LHU => LoadOp::Lhu,
+                F3::LWU if rv64 => LoadOp::Lwu,
+                F3::LD if rv64 => LoadOp::Ld,
+                _ => return None,
+            };
+            Instruction::Load { op, rd, rs1, offset: imm_i(raw) }
+        }
+        OP::STORE => {
+            let op = match funct3 {
+                F3::SB => StoreOp::Sb,
+                F3::SH => StoreOp::Sh,
+                F3::SW => StoreOp::Sw,
+                F3::SD if rv64 => StoreOp::Sd,
+                _ => return None,
+            };
+            Instruction::Store { op, rs1, rs2, offset: imm_s(raw) }
+        }
+        OP::ALUI => decode_op_imm(raw, xlen)?,
+        OP::ALU => decode_op(raw, xlen)?,
+        OP::ALUI_W if rv64 => decode_op_imm_w(raw)?,
+        OP::ALU_W if rv64 => decode_op_w(raw)?,
+        OP::FENCE => match funct3 {
+            F3::FENCE => Instruction::Fence { pred: (raw >> 24 & 0xF) as u8, succ: (raw >> 20 & 0xF) as u8 },
+            F3::FENCE_I => Instruction::FenceI,
+            _ => return None,
+        },
+        OP::AMO => decode_amo(raw, xlen)?,
+        OP::E_C => decode_system(raw)?,
+        OP::LOAD_FP | OP::STORE_FP => {
+            let format = match funct3 {
+                F3::FLW => F32,
+                F3::FLD => F64,
+                _ => return None,
+            };
+            if raw & MASK::OP == OP::LOAD_FP as u32 {
+                Instruction::LoadFp { format, rd, rs1, offset: imm_i(raw) }
+            } else {
+                Instruction::StoreFp { format, rs1, rs2, offset: imm_s(raw) }
+            }
+        }
+        opcode @ (OP::MADD | OP::MSUB | OP::NMSUB | OP::NMADD) => {
+            let op = match opcode {
+                OP::MADD => FmaOp::Fmadd,
+                OP::MSUB => FmaOp::Fmsub,
+                OP::NMSUB => FmaOp::Fnmsub,
+                _ => FmaOp::Fnmadd,
+            };
+            let rs3 = ((raw & MASK::RS3) >> 27) as u8;
+            Instruction::Fma { op, format: fp_format(raw)?, rm: funct3, rd, rs1, rs2, rs3 }
+        }
+        OP::OP_FP => decode_op_fp(raw, xlen)?,
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+// Shifts and the Zbs single bit instructions take a 5-bit shamt, the upper 7 bits
+// of the immediate acting as a funct7. The unary Zbb instructions also have the shamt fixed.
+// On RV64 the shamt is 6 bits wide, taking over the lowest bit of funct7.
+fn decode_op_imm(raw: u32, xlen: Xlen) -> Option<Instruction> {
+    let (rd, rs1) = (rd(raw), rs1(raw));
+    let (funct7, shamt, shamt_rev8) = match xlen {
+        Xlen::Rv32 => (funct7(raw), rs2(raw) as u32, SHAMT_REV8),
+        Xlen::Rv64 => (funct7(raw) & !0x1, (raw >> 20) & 0x3F, SHAMT_REV8_64),
+    };
+    let imm = imm_i(raw);
+    let (op, imm) = match funct3(raw) {
+        F3::ADDI => (AluOp::Add, imm),
+        F3::SLTI => (AluOp::Slt, imm),
+        // The immediate is still sign extended, but compared as unsigned
+        F3::SLTIU => (AluOp::Sltu, imm),
+        F3::XORI => (AluOp::Xor, imm),
+        F3::ORI => (AluOp::Or, imm),
+        F3::ANDI => (AluOp::And, imm),
+        F3::SLLI => match funct7 {
+            0 => (AluOp::Sll, shamt),
+            F7_BCLR => (AluOp::Bclr, shamt),
+            F7_BINV => (AluOp::Binv, shamt),
+            F7_BSET => (AluOp::Bset, shamt),
+            F7_ROTATE => {
+                let op = match shamt {
+                    SHAMT_CLZ => UnaryOp::Clz,
+                    SHAMT_CTZ => UnaryOp::Ctz,
+                    SHAMT_CPOP => UnaryOp::Cpop,
+                    SHAMT_SEXT_B => UnaryOp::SextB,
+                    SHAMT_SEXT_H => UnaryOp::SextH,
+                    _ => return None,
+                };
+                return Some(Instruction::Unary { op, rd, rs1 });
+            }
+            _ => return None,
+        },
+        F3::SRLI_SRAI => match funct7 {
+            F7_SRLI => (AluOp::Srl, shamt),
+            F7_SRAI => (AluOp::Sra, shamt),
+            F7_ROTATE => (AluOp::Ror, shamt),
+            F7_BCLR => (AluOp::Bext, shamt),
+            F7_BSET if shamt == SHAMT_ORC_B => return Some(Instruction::Unary { op: UnaryOp::OrcB, rd, rs1 }),
+            F7_BINV if shamt == shamt_rev8 => return Some(Instruction::Unary { op: UnaryOp::Rev8, rd, rs1 }),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Instruction::OpImm { op, rd, rs1, imm })
+}
+
+fn decode_op(raw: u32, xlen: Xlen) -> Option<Instruction> {
+    let (rd, rs1, rs2) = (rd(raw), rs1(raw), rs2(raw));
+    let funct73 = ((funct7(raw) as u16) << 3) | funct3(raw) as u16;
+    let op = match funct73 {
+        F73_ADD => AluOp::Add,
+        F73_SUB => AluOp::Sub,
+        F73_SLL => AluOp::Sll,
+        F73_SLT => AluOp::Slt,
+        F73_SLTU => AluOp::Sltu,
+        F73_XOR => AluOp::Xor,
+        F73_SRL => AluOp::Srl,
+        F73_SRA => AluOp::Sra,
+        F73_OR => AluOp::Or,
+        F73_AND => AluOp::And,
+        F73_MUL => AluOp::Mul,
+        F73_MULH => AluOp::Mulh,
+        F73_MULHSU => AluOp::Mulhsu,
+        F73_MULHU => AluOp::Mulhu,
+        F73_DIV => AluOp::Div,
+        F73_DIVU => AluOp::Divu,
+        F73_REM => AluOp::Rem,
+        F73_REMU => AluOp::Remu,
+        F73_SH1ADD => AluOp::Sh1add,
+        F73_SH2ADD => AluOp::Sh2add,
+        F73_SH3ADD => AluOp::Sh3add,
+        F73_ANDN => AluOp::Andn,
+        F73_ORN => AluOp::Orn,
+        F73_XNOR => AluOp::Xnor,
+        F73_MIN => AluOp::Min,
+        F73_MINU => AluOp::Minu,
+        F73_MAX => AluOp::Max,
+        F73_MAXU => AluOp::Maxu,
+        F73_ROL => AluOp::Rol,
+        F73_ROR => AluOp::Ror,
+        F73_CLMUL => AluOp::Clmul,
+        F73_CLMULH => AluOp::Clmulh,
+        F73_CLMULR => AluOp::Clmulr,
+        F73_BCLR => AluOp::Bclr,
+        F73_BEXT => AluOp::Bext,
+        F73_BINV => AluOp::Binv,
+        F73_BSET => AluOp::Bset,
+        // ZEXT.H moves to OP-32 on RV64
+        F73_ZEXT_H if rs2 == 0 && xlen == Xlen::Rv32 => return Some(Instruction::Unary { op: UnaryOp::ZextH, rd, rs1 }),
+        _ => return None,
+    };
+    Some(Instruction::Op { op, rd, rs1, rs2 })
+}
+
+// OP-IMM-32: ADDIW and the word shifts, which keep a 5-bit shamt. SLLI.UW is the
+// odd one out, with a 6-bit shamt and a full width result.
+fn decode_op_imm_w(raw: u32) -> Option<Instruction> {
+    let (rd, rs1, funct7) = (rd(raw), rs1(raw), funct7(raw));
+    let shamt = rs2(raw) as u32;
+    let (op, imm) = match funct3(raw) {
+        F3::ADDI => (AluOp::Add, imm_i(raw)),
+        F3::SLLI => match funct7 {
+            0 => (AluOp::Sll, shamt),
+            F7_ROTATE => {
+                let op = match shamt {
+                    SHAMT_CLZ => UnaryOp::Clz,
+                    SHAMT_CTZ => UnaryOp::Ctz,
+                    SHAMT_CPOP => UnaryOp::Cpop,
+                    _ => return None,
+                };
+                return Some(Instruction::UnaryW { op, rd, rs1 });
+            }
+            _ if funct7 & !0x1 == F7_ADD_UW => {
+                return Some(Instruction::OpImm { op: AluOp::SllUw, rd, rs1, imm: (raw >> 20) & 0x3F });
+            }
+            _ => return None,
+        },
+        F3::SRLI_SRAI => match funct7 {
+            F7_SRLI => (AluOp::Srl, shamt),
+            F7_SRAI => (AluOp::Sra, shamt),
+            F7_ROTATE => (AluOp::Ror, shamt),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Instruction::OpImmW { op, rd, rs1, imm })
+}
+
+// OP-32: the word ALU and M instructions. The Zba .UW ones and ZEXT.H live here too,
+// though their results are full width.
+fn decode_op_w(raw: u32) -> Option<Instruction> {
+    let (rd, rs1, rs2) = (rd(raw), rs1(raw), rs2(raw));
+    let funct73 = ((funct7(raw) as u16) << 3) | funct3(raw) as u16;
+    let op = match funct73 {
+        F73_ADD => AluOp::Add,
+        F73_SUB => AluOp::Sub,
+        F73_SLL => AluOp::Sll,
+        F73_SRL => AluOp::Srl,
+        F73_SRA => AluOp::Sra,
+        F73_MULW => AluOp::Mul,
+        F73_DIVW => AluOp::Div,
+        F73_DIVUW => AluOp::Divu,
+        F73_REMW => AluOp::Rem,
+        F73_REMUW => AluOp::Remu,
+        F73_ROL => AluOp::Rol,
+        F73_ROR => AluOp::Ror,
+        F73_ADD_UW => return Some(Instruction::Op { op: AluOp::AddUw, rd, rs1, rs2 }),
+        F73_SH1ADD => return Some(Instruction::Op { op: AluOp::Sh1addUw, rd, rs1, rs2 }),
+        F73_SH2ADD => return Some(Instruction::Op { op: AluOp::Sh2addUw, rd, rs1, rs2 }),
+        F73_SH3ADD => return Some(Instruction::Op { op: AluOp::Sh3addUw, rd, rs1, rs2 }),
+        F73_ZEXT_H if rs2 == 0 => return Some(Instruction::Unary { op: UnaryOp::ZextH, rd, rs1 }),
+        _ => return None,
+    };
+    Some(Instruction::OpW { op, rd, rs1, rs2 })
+}
+
+// Doubleword atomics are RV64 only
+fn decode_amo(raw: u32, xlen: Xlen) -> Option<Instruction> {
+    let (rd, rs1, rs2) = (rd(raw), rs1(raw), rs2(raw));
+    let doubleword = match funct3(raw) {
+        F3::AMO_W => false,
+        F3::AMO_D if xlen == Xlen::Rv64 => true,
+        _ => return None,
+    };
+    let op = match ((raw & MASK::AMO_F5) >> 27) as u8 {
+        // rs2 must be zero
+        F5::LR if rs2 == 0 => AmoOp::Lr,
+        F5::SC => AmoOp::Sc,
+        F5::AMOSWAP => AmoOp::Swap,
+        F5::AMOADD => AmoOp::Add,
+        F5::AMOXOR => AmoOp::Xor,
+        F5::AMOAND => AmoOp::And,
+        F5::AMOOR => AmoOp::Or,
+        F5::AMOMIN => AmoOp::Min,
+        F5::AMOMAX => AmoOp::Max,
+        F5::AMOMINU => AmoOp::Minu,
+        F5::AMOMAXU => AmoOp::Maxu,
+        _ => return None,
+    };
+    let aq = raw & MASK::AMO_AQ != 0;
+    let rl = raw & MASK::AMO_RL != 0;
+    Some(Instruction::Amo { op, doubleword, aq, rl, rd, rs1, rs2 })
+}
+
+// The CSR instructions, and under funct3 = 0 the privileged ones
+fn decode_system(raw: u32) -> Option<Instruction> {
+    let (rd, rs1, rs2) = (rd(raw), rs1(raw), rs2(raw));
+    let csr = ((raw & MASK::CSR) >> 20) as u16;
+    let instruction = match funct3(raw) {
+        F3::ECALL_EBREAK => {
+            // SFENCE.VMA only has funct7 fixed, rs2 and rs1 hold the ASID and address to flush
+            if funct7(raw) == F7_SFENCE_VMA {
+                return (rd == 0).then_some(Instruction::SfenceVma { rs1, rs2 });
+            }
+            // None of the others use rd or rs1, they must be zero
+            if rd != 0 || rs1 != 0 {
+                return None;
+            }
+            match csr {
+                F12::ECALL => Instruction::Ecall,
+                F12::EBREAK => Instruction::Ebreak,
+                F12::MRET => Instruction::Mret,
+                F12::SRET => Instruction::Sret,
+                F12::WFI => Instruction::Wfi,
+                _ => return None,
+            }
+        }
+        F3::CSRRW => Instruction::Csr { op: CsrOp::Rw, rd, rs1, csr },
+        F3::CSRRS => Instruction::Csr { op: CsrOp::Rs, rd, rs1, csr },
+        F3::CSRRC => Instruction::Csr { op: CsrOp::Rc, rd, rs1, csr },
+        // The rs1 field is a zero-extended 5-bit immediate
+        F3::CSRRWI => Instruction::CsrImm { op: CsrOp::Rw, rd, uimm: rs1, csr },
+        F3::CSRRSI => Instruction::CsrImm { op: CsrOp::Rs, rd, uimm: rs1, csr },
+        F3::CSRRCI => Instruction::CsrImm { op: CsrOp::Rc, rd, uimm: rs1, csr },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+// Arithmetic, sign injection, min/max, compares, conversions, moves and FCLASS
+fn decode_op_fp(raw: u32, xlen: Xlen) -> Option<Instruction> {
+    let (rd, rs1, rs2, funct3) = (rd(raw), rs1(raw), rs2(raw), funct3(raw));
+    let format = fp_format(raw)?;
+    let single = format == F32;
+    let rv64 = xlen == Xlen::Rv64;
+    let op = match ((raw & MASK::FP_F5) >> 27) as u8 {
+        F5::FADD => FpOp::Fadd,
+        F5::FSUB => FpOp::Fsub,
+        F5::FMUL => FpOp::Fmul,
+        F5::FDIV => FpOp::Fdiv,
+        F5::FSQRT if rs2 == 0 => FpOp::Fsqrt,
+        F5::FSGNJ => match funct3 {
+            F3::FSGNJ => FpOp::Fsgnj,
+            F3::FSGNJN => FpOp::Fsgnjn,
+            F3::FSGNJX => FpOp::Fsgnjx,
+            _ => return None,
+        },
+        F5::FMIN_MAX => match funct3 {
+            F3::FMIN => FpOp::Fmin,
+            F3::FMAX => FpOp::Fmax,
+            _ => return None,
+        },
+        // rs2 holds the format converted from
+        F5::FCVT_FP => match (single, rs2) {
+            (true, 1) | (false, 0) => FpOp::FcvtFmt,
+            _ => return None,
+        },
+        F5::FCMP => match funct3 {
+            F3::FEQ => FpOp::Feq,
+            F3::FLT => FpOp::Flt,
+            F3::FLE => FpOp::Fle,
+            _ => return None,
+        },
+        // rs2 tells signed (W) and unsigned (WU) apart, L and LU are RV64 only
+        F5::FCVT_W => match rs2 {
+            0 => FpOp::FcvtW,
+            1 => FpOp::FcvtWu,
+            2 if rv64 => FpOp::FcvtL,
+            3 if rv64 => FpOp::FcvtLu,
+            _ => return None,
+        },
+        F5::FCVT_FROM_W => match rs2 {
+            0 => FpOp::FcvtFromW,
+            1 => FpOp::FcvtFromWu,
+            2 if rv64 => FpOp::FcvtFromL,
+            3 if rv64 => FpOp::FcvtFromLu,
+            _ => return None,
+        },
+        // FMV.X.D and FMV.D.X are RV64 only
+        F5::FMV_X_CLASS if rs2 == 0 => match funct3 {
+            F3::FMV_X if single => FpOp::FmvXW,
+            F3::FMV_X if rv64 => FpOp::FmvXD,
+            F3::FCLASS => FpOp::Fclass,
+            _ => return None,
+        },
+        F5::FMV_W_X if rs2 == 0 && funct3 == 0 && single => FpOp::FmvWX,
+        F5::FMV_W_X if rs2 == 0 && funct3 == 0 && rv64 => FpOp::FmvDX,
+        _ => return None,
+    };
+    Some(Instruction::OpFp { op, format, rm: funct3, rd, rs1, rs2 })
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::decode::*;
+
+    fn rv32(raw: u32) -> Option<Instruction> {
+        decode(raw, Xlen::Rv32)
+    }
+
+    fn rv64(raw: u32) -> Option<Instruction> {
+        decode(raw, Xlen::Rv64)
+    }
+
+    #[test]
+    fn test_immediates() {
+        // Encodings from llvm-mc
+        assert_eq!(rv32(0xfff00513), Some(Instruction::OpImm { op: AluOp::Add, rd: 10, rs1: 0, imm: 0xFFFF_FFFF }),
+            "addi a0, zero, -1");
+        assert_eq!(rv32(0x80000537), Some(Instruction::Lui { rd: 10, imm: 0x8000_0000 }), "lui a0, 0x80000");
+        assert_eq!(rv32(0xfe5ff06f), Some(Instruction::Jal { rd: 0, offset: -28i32 as u32 }), "j -28");
+        assert_eq!(rv32(0x7fe0006f), Some(Instruction::Jal { rd: 0, offset: 2046 }), "j 2046");
+        assert_eq!(rv32(0xfeb50ee3), Some(Instruction::Branch { op: BranchOp::Beq, rs1: 10, rs2: 11, offset: -4i32 as u32 }),
+            "beq a0, a1, -4");
+        assert_eq!(rv32(0x80b51063), Some(Instruction::Branch { op: BranchOp::Bne, rs1: 10, rs2: 11, offset: -4096i32 as u32 }),
+            "bne a0, a1, -4096");
+        assert_eq!(rv32(0xfea5ae23), Some(Instruction::Store { op: StoreOp::Sw, rs1: 11, rs2: 10, offset: -4i32 as u32 }),
+            "sw a0, -4(a1)");
+        assert_eq!(rv32(0x8005c503), Some(Instruction::Load { op: LoadOp::Lbu, rd: 10, rs1: 11, offset: -2048i32 as u32 }),
+            "lbu a0, -2048(a1)");
+        assert_eq!(rv32(0x4015d513), Some(Instruction::OpImm { op: AluOp::Sra, rd: 10, rs1: 11, imm: 1 }), "srai a0, a1, 1");
+        assert_eq!(rv32(0xfff5b507), Some(Instruction::LoadFp { format: F64, rd: 10, rs1: 11, offset: 0xFFFF_FFFF }),
+            "fld fa0, -1(a1)");
+    }
+
+    #[test]
+    fn test_system() {
+        assert_eq!(rv32(0x00000073), Some(Instruction::Ecall));
+        assert_eq!(rv32(0x30200073), Some(Instruction::Mret));
+        assert_eq!(rv32(0x12b50073), Some(Instruction::SfenceVma { rs1: 10, rs2: 11 }), "sfence.vma a0, a1");
+        assert_eq!(rv32(0x30529573), Some(Instruction::Csr { op: CsrOp::Rw, rd: 10, rs1: 5, csr: 0x305 }),
+            "csrrw a0, mtvec, t0");
+        assert_eq!(rv32(0x3007f573), Some(Instruction::CsrImm { op: CsrOp::Rc, rd: 10, uimm: 15, csr: 0x300 }),
+            "csrrci a0, mstatus, 15");
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(rv32(0x02b50533), Some(Instruction::Op { op: AluOp::Mul, rd: 10, rs1: 10, rs2: 11 }), "mul a0, a0, a1");
+        assert_eq!(rv32(0x0cb5252f), Some(Instruction::Amo { op: AmoOp::Swap, doubleword: false, aq: true, rl: false, rd: 10, rs1: 10, rs2: 11 }),
+            "amoswap.w.aq a0, a1, (a0)");
+        assert_eq!(rv32(0x6985d513), Some(Instruction::Unary { op: UnaryOp::Rev8, rd: 10, rs1: 11 }), "rev8 a0, a1");
+        assert_eq!(rv32(0x0805c533), Some(Instruction::Unary { op: UnaryOp::ZextH, rd: 10, rs1: 11 }), "zext.h a0, a1");
+        assert_eq!(rv32(0x02c5f543), Some(Instruction::Fma { op: FmaOp::Fmadd, format: F64, rm: 7, rd: 10, rs1: 11,
+            rs2: 12, rs3: 0 }), "fmadd.d fa0, fa1, fa2, ft0");
+        assert_eq!(rv32(0xc0159553), Some(Instruction::OpFp { op: FpOp::FcvtWu, format: F32, rm: 1, rd: 10, rs1: 11, rs2: 1 }),
+            "fcvt.wu.s a0, fa1, rtz");
+    }
+
+    #[test]
+    fn test_rv64() {
+        assert_eq!(rv64(0xff85b503), Some(Instruction::Load { op: LoadOp::Ld, rd: 10, rs1: 11, offset: -8i32 as u32 }),
+            "ld a0, -8(a1)");
+        assert_eq!(rv64(0x0045e503), Some(Instruction::Load { op: LoadOp::Lwu, rd: 10, rs1: 11, offset: 4 }), "lwu a0, 4(a1)");
+        assert_eq!(rv64(0x00a5b423), Some(Instruction::Store { op: StoreOp::Sd, rs1: 11, rs2: 10, offset: 8 }), "sd a0, 8(a1)");
+        assert_eq!(rv64(0xfff5851b), Some(Instruction::OpImmW { op: AluOp::Add, rd: 10, rs1: 11, imm: 0xFFFF_FFFF }),
+            "addiw a0, a1, -1");
+        assert_eq!(rv64(0x03f59513), Some(Instruction::OpImm { op: AluOp::Sll, rd: 10, rs1: 11, imm: 63 }), "slli a0, a1, 63");
+        assert_eq!(rv64(0x4215d513), Some(Instruction::OpImm { op: AluOp::Sra, rd: 10, rs1: 11, imm: 33 }), "srai a0, a1, 33");
+        assert_eq!(rv64(0x41f5d51b), Some(Instruction::OpImmW { op: AluOp::Sra, rd: 10, rs1: 11, imm: 31 }), "sraiw a0, a1, 31");
+        assert_eq!(rv64(0x40c5853b), Some(Instruction::OpW { op: AluOp::Sub, rd: 10, rs1: 11, rs2: 12 }), "subw a0, a1, a2");
+        assert_eq!(rv64(0x02c5853b), Some(Instruction::OpW { op: AluOp::Mul, rd: 10, rs1: 11, rs2: 12 }), "mulw a0, a1, a2");
+        assert_eq!(rv64(0x02c5f53b), Some(Instruction::OpW { op: AluOp::Remu, rd: 10, rs1: 11, rs2: 12 }), "remuw a0, a1, a2");
+        assert_eq!(rv64(0x6b85d513), Some(Instruction::Unary { op: UnaryOp::Rev8, rd: 10, rs1: 11 }), "rev8 a0, a1");
+        assert_eq!(rv64(0x0805c53b), Some(Instruction::Unary { op: UnaryOp::ZextH, rd: 10, rs1: 11 }), "zext.h a0, a1");
+        assert_eq!(rv64(0x6005951b), Some(Instruction::UnaryW { op: UnaryOp::Clz, rd: 10, rs1: 11 }), "clzw a0, a1");
+        assert_eq!(rv64(0x08c5853b), Some(Instruction::Op { op: AluOp::AddUw, rd: 10, rs1: 11, rs2: 12 }), "add.uw a0, a1, a2");
+        assert_eq!(rv64(0x20c5c53b), Some(Instruction::Op { op: AluOp::Sh2addUw, rd: 10, rs1: 11, rs2: 12 }),
+            "sh2add.uw a0, a1, a2");
+        assert_eq!(rv64(0x0a85951b), Some(Instruction::OpImm { op: AluOp::SllUw, rd: 10, rs1: 11, imm: 40 }),
+            "slli.uw a0, a1, 40");
+        assert_eq!(rv64(0x6035d51b), Some(Instruction::OpImmW { op: AluOp::Ror, rd: 10, rs1: 11, imm: 3 }), "roriw a0, a1, 3");
+        assert_eq!(rv64(0x02b6352f), Some(Instruction::Amo { op: AmoOp::Add, doubleword: true, aq: false, rl: true, rd: 10,
+            rs1: 12, rs2: 11 }), "amoadd.d.rl a0, a1, (a2)");
+        assert_eq!(rv64(0x1005b52f), Some(Instruction::Amo { op: AmoOp::Lr, doubleword: true, aq: false, rl: false, rd: 10,
+            rs1: 11, rs2: 0 }), "lr.d a0, (a1)");
+        assert_eq!(rv64(0xc2259553), Some(Instruction::OpFp { op: FpOp::FcvtL, format: F64, rm: 1, rd: 10, rs1: 11, rs2: 2 }),
+            "fcvt.l.d a0, fa1, rtz");
+        assert_eq!(rv64(0xd035f553), Some(Instruction::OpFp { op: FpOp::FcvtFromLu, format: F32, rm: 7, rd: 10, rs1: 11,
+            rs2: 3 }), "fcvt.s.lu fa0, a1");
+        assert_eq!(rv64(0xe2058553), Some(Instruction::OpFp { op: FpOp::FmvXD, format: F64, rm: 0, rd: 10, rs1: 11, rs2: 0 }),
+            "fmv.x.d a0, fa1");
+        assert_eq!(rv64(0xf2058553), Some(Instruction::OpFp { op: FpOp::FmvDX, format: F64, rm: 0, rd: 10, rs1: 11, rs2: 0 }),
+            "fmv.d.x fa0, a1");
+        // The rest decodes the same as on RV32
+        assert_eq!(rv64(0x02b50533), rv32(0x02b50533), "mul a0, a0, a1");
+    }
+
+    #[test]
+    fn test_illegal_rv64() {
+        assert_eq!(rv32(0xff85b503), None, "ld is RV64 only");
+        assert_eq!(rv32(0xfff5851b), None, "addiw is RV64 only");
+        assert_eq!(rv32(0x40c5853b), None, "subw is RV64 only");
+        assert_eq!(rv32(0x1005b52f), None, "lr.d is RV64 only");
+        assert_eq!(rv64(0x0205951b), None, "slliw with shamt[5] set");
+        assert_eq!(rv64(0x6985d513), None, "rev8 has a different shamt on RV64");
+        assert_eq!(rv64(0x0805c533), None, "zext.h moved to OP-32");
+        assert_eq!(rv64(0x0045f503), None, "ldu doesn't exist");
+        assert_eq!(rv64(0x6045951b), None, "sext.b has no word form");
+    }
+
+    #[test]
+    fn test_illegal() {
+        assert_eq!(rv32(0x00000000), None, "All zeros");
+        assert_eq!(rv32(0xffffffff), None, "All ones");
+        assert_eq!(rv32(0x00001067), None, "jalr with funct3 = 1");
+        assert_eq!(rv32(0x0000e083), None, "lwu is RV64 only");
+        assert_eq!(rv32(0x02059513), None, "slli with shamt[5] set is RV64 only");
+        assert_eq!(rv32(0x10b525af), None, "lr.w with rs2 set");
+        assert_eq!(rv32(0x00100573), None, "ebreak with rd set");
+        assert_eq!(rv32(0x12b505f3), None, "sfence.vma with rd set");
+        assert_eq!(rv32(0x08b5c533), None, "zext.h with rs2 set");
+        assert_eq!(rv32(0xe2058553), None, "fmv.x.d is RV64 only");
+    }
+}
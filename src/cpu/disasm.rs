@@ -0,0 +1,591 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Disassembler
+ *
+ * Renders instructions the way GNU objdump does: ABI register names, a tab
+ * after the mnemonic, operands separated by commas only, decimal immediates
+ * except for shift amounts and upper immediates, and jump and branch targets
+ * as absolute addresses, with the symbol they fall in when there is one.
+ *
+ * Compressed instructions are shown as the 32-bit instruction they expand to.
+ * With pseudo-instructions on, the common aliases (li, mv, j, ret, nop, beqz,
+ * csrr, fmv.s...) are recognised, which also gives back the usual spelling of
+ * most compressed ones. Anything that doesn't decode is shown as an .insn
+ * directive.
+ */
+
+use crate::cpu::{compressed, Xlen};
+use crate::cpu::csr::{self, FCSR, FFLAGS, FRM};
+use crate::cpu::decode::*;
+use crate::cpu::opcodes::F3;
+use crate::cpu::register::{FREG_ALIASES, REG_ALIASES, REG_RA, REG_ZERO};
+use crate::cpu::softfloat::{Format, F32};
+use crate::elf::SymbolTable;
+
+const ROUNDING_MODES: [&str; 8] = ["rne", "rtz", "rdn", "rup", "rmm", "5", "6", "dyn"];
+
+pub(crate) struct Disassembler<'a> {
+    xlen: Xlen,
+    pseudo: bool,
+    symbols: &'a SymbolTable,
+}
+
+fn x(register: u8) -> &'static str {
+    REG_ALIASES[register as usize]
+}
+
+fn f(register: u8) -> &'static str {
+    FREG_ALIASES[register as usize]
+}
+
+// The immediate as the signed value it holds
+fn signed(imm: u32) -> i32 {
+    imm as i32
+}
+
+fn suffix(format: Format) -> &'static str {
+    if format == F32 { "s" } else { "d" }
+}
+
+// The rounding mode as an extra operand, left out when it's the dynamic one
+fn rounding(rm: u8) -> String {
+    if rm == F3::RM_DYN { String::new() } else { format!(",{}", ROUNDING_MODES[rm as usize]) }
+}
+
+fn csr_name(csr: u16) -> String {
+    csr::csr_name(csr).unwrap_or_else(|| format!("0x{:x}", csr))
+}
+
+// FENCE predecessor and successor sets, as a subset of "iorw"
+fn fence_set(set: u8) -> String {
+    let set: String = "iorw".chars().enumerate()
+        .filter(|(i, _)| set & (0x8 >> i) != 0)
+        .map(|(_, c)| c)
+        .collect();
+    if set.is_empty() { "0".to_string() } else { set }
+}
+
+fn branch_name(op: BranchOp) -> &'static str {
+    match op {
+        BranchOp::Beq => "beq",
+        BranchOp::Bne => "bne",
+        BranchOp::Blt => "blt",
+        BranchOp::Bge => "bge",
+        BranchOp::Bltu => "bltu",
+        BranchOp::Bgeu => "bgeu",
+    }
+}
+
+fn load_name(op: LoadOp) -> &'static str {
+    match op {
+        LoadOp::Lb => "lb",
+        LoadOp::Lh => "lh",
+        LoadOp::Lw => "lw",
+        LoadOp::Lbu => "lbu",
+        LoadOp::Lhu => "lhu",
+        LoadOp::Lwu => "lwu",
+        LoadOp::Ld => "ld",
+    }
+}
+
+fn store_name(op: StoreOp) -> &'static str {
+    match op {
+        StoreOp::Sb => "sb",
+        StoreOp::Sh => "sh",
+        StoreOp::Sw => "sw",
+        StoreOp::Sd => "sd",
+    }
+}
+
+// The register-register mnemonic, the immediate forms add an "i"
+fn alu_name(op: AluOp) -> &'static str {
+    match op {
+        AluOp::Add => "add",
+        AluOp::Sub => "sub",
+        AluOp::Sll => "sll",
+        AluOp::Slt => "slt",
+        AluOp::Sltu => "sltu",
+        AluOp::Xor => "xor",
+        AluOp::Srl => "srl",
+        AluOp::Sra => "sra",
+        AluOp::Or => "or",
+        AluOp::And => "and",
+        AluOp::Mul => "mul",
+        AluOp::Mulh => "mulh",
+        AluOp::Mulhsu => "mulhsu",
+        AluOp::Mulhu => "mulhu",
+        AluOp::Div => "div",
+        AluOp::Divu => "divu",
+        AluOp::Rem => "rem",
+        AluOp::Remu => "remu",
+        AluOp::Sh1add => "sh1add",
+        AluOp::Sh2add => "sh2add",
+        AluOp::Sh3add => "sh3add",
+        AluOp::AddUw => "add.uw",
+        AluOp::Sh1addUw => "sh1add.uw",
+        AluOp::Sh2addUw => "sh2add.uw",
+        AluOp::Sh3addUw => "sh3add.uw",
+        AluOp::SllUw => "sll.uw",
+        AluOp::Andn => "andn",
+        AluOp::Orn => "orn",
+        AluOp::Xnor => "xnor",
+        AluOp::Min => "min",
+        AluOp::Minu => "minu",
+        AluOp::Max => "max",
+        AluOp::Maxu => "maxu",
+        AluOp::Rol => "rol",
+        AluOp::Ror => "ror",
+        AluOp::Clmul => "clmul",
+        AluOp::Clmulh => "clmulh",
+        AluOp::Clmulr => "clmulr",
+        AluOp::Bclr => "bclr",
+        AluOp::Bext => "bext",
+        AluOp::Binv => "binv",
+        AluOp::Bset => "bset",
+    }
+}
+
+fn unary_name(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Clz => "clz",
+        UnaryOp::Ctz => "ctz",
+        UnaryOp::Cpop => "cpop",
+        UnaryOp::SextB => "sext.b",
+        UnaryOp::SextH => "sext.h",
+        UnaryOp::ZextH => "zext.h",
+        UnaryOp::OrcB => "orc.b",
+        UnaryOp::Rev8 => "rev8",
+    }
+}
+
+// Without the .w or .d width suffix
+fn amo_name(op: AmoOp) -> &'static str {
+    match op {
+        AmoOp::Lr => "lr",
+        AmoOp::Sc => "sc",
+        AmoOp::Swap => "amoswap",
+        AmoOp::Add => "amoadd",
+        AmoOp::Xor => "amoxor",
+        AmoOp::And => "amoand",
+        AmoOp::Or => "amoor",
+        AmoOp::Min => "amomin",
+        AmoOp::Max => "amomax",
+        AmoOp::Minu => "amominu",
+        AmoOp::Maxu => "amomaxu",
+    }
+}
+
+fn csr_op_name(op: CsrOp) -> &'static str {
+    match op {
+        CsrOp::Rw => "csrrw",
+        CsrOp::Rs => "csrrs",
+        CsrOp::Rc => "csrrc",
+    }
+}
+
+fn fma_name(op: FmaOp) -> &'static str {
+    match op {
+        FmaOp::Fmadd => "fmadd",
+        FmaOp::Fmsub => "fmsub",
+        FmaOp::Fnmsub => "fnmsub",
+        FmaOp::Fnmadd => "fnmadd",
+    }
+}
+
+// Name of the FP CSR in its pseudo-instructions (frcsr, fsrm, fsflags...)
+fn fp_csr_name(csr: u16) -> Option<&'static str> {
+    match csr {
+        FCSR => Some("csr"),
+        FRM => Some("rm"),
+        FFLAGS => Some("flags"),
+        _ => None,
+    }
+}
+
+impl<'a> Disassembler<'a> {
+    // Decodes for an `xlen` CPU. Branch and jump targets are looked up in `symbols`, which may
+    // well be empty
+    pub(crate) fn new(xlen: Xlen, pseudo: bool, symbols: &'a SymbolTable) -> Self {
+        Self { xlen, pseudo, symbols }
+    }
+
+    // Disassembles the instruction at `pc`. Only the low halfword of a compressed one is looked at.
+    pub(crate) fn disassemble(&self, raw: u32, pc: u64) -> String {
+        let text = self.disassemble_raw(raw, pc);
+        // No mnemonic has a space in it, so the first one is where the operands start.
+        // objdump puts a tab there.
+        match text.split_once(' ') {
+            Some((mnemonic, operands)) => format!("{}\t{}", mnemonic, operands),
+            None => text,
+        }
+    }
+
+    fn disassemble_raw(&self, raw: u32, pc: u64) -> String {
+        let parcel = raw as u16;
+        if compressed::is_compressed(parcel) {
+            match compressed::expand(parcel, self.xlen).and_then(|raw| decode(raw, self.xlen)) {
+                Some(instruction) => self.format(instruction, pc),
+                None => format!(".insn 2, 0x{:04x}", parcel),
+            }
+        } else {
+            match decode(raw, self.xlen) {
+                Some(instruction) => self.format(instruction, pc),
+                None => format!(".insn 4, 0x{:08x}", raw),
+            }
+        }
+    }
+
+    fn format(&self, instruction: Instruction, pc: u64) -> String {
+        if self.pseudo {
+            if let Some(text) = self.format_pseudo(instruction, pc) {
+                return text;
+            }
+        }
+        self.format_base(instruction, pc)
+    }
+
+    // An absolute address, followed by the symbol it's in if there's one. Symbols only cover the
+    // 32-bit address space.
+    fn target(&self, pc: u64, offset: u32) -> String {
+        let address = self.xlen.zext(pc.wrapping_add(offset as i32 as u64));
+        match u32::try_from(address).ok().and_then(|address| self.symbols.resolve(address)) {
+            Some((symbol, 0)) => format!("{:x} <{}>", address, symbol.name),
+            Some((symbol, offset)) => format!("{:x} <{}+0x{:x}>", address, symbol.name, offset),
+            None => format!("{:x}", address),
+        }
+    }
+
+    fn format_base(&self, instruction: Instruction, pc: u64) -> String {
+        match instruction {
+            Instruction::Lui { rd, imm } => format!("lui {},0x{:x}", x(rd), imm >> 12),
+            Instruction::Auipc { rd, imm } => format!("auipc {},0x{:x}", x(rd), imm >> 12),
+            Instruction::Jal { rd, offset } => format!("jal {},{}", x(rd), self.target(pc, offset)),
+            Instruction::Jalr { rd, rs1, offset } => format!("jalr {},{}({})", x(rd), signed(offset), x(rs1)),
+            Instruction::Branch { op, rs1, rs2, offset } => {
+                format!("{} {},{},{}", branch_name(op), x(rs1), x(rs2), self.target(pc, offset))
+            }
+            Instruction::Load { op, rd, rs1, offset } => format!("{} {},{}({})", load_name(op), x(rd), signed(offset), x(rs1)),
+            Instruction::Store { op, rs1, rs2, offset } => format!("{} {},{}({})", store_name(op), x(rs2), signed(offset), x(rs1)),
+            Instruction::OpImm { op: AluOp::SllUw, rd, rs1, imm } => format!("slli.uw {},{},0x{:x}", x(rd), x(rs1), imm),
+            Instruction::OpImm { op, rd, rs1, imm } => match op {
+                // Shift amounts and bit indexes are printed in hex
+                AluOp::Sll | AluOp::Srl | AluOp::Sra | AluOp::Ror | AluOp::Bclr | AluOp::Bext | AluOp::Binv | AluOp::Bset => {
+                    format!("{}i {},{},0x{:x}", alu_name(op), x(rd), x(rs1), imm)
+                }
+                _ => format!("{}i {},{},{}", alu_name(op), x(rd), x(rs1), signed(imm)),
+            },
+            Instruction::Op { op, rd, rs1, rs2 } => format!("{} {},{},{}", alu_name(op), x(rd), x(rs1), x(rs2)),
+            Instruction::Unary { op, rd, rs1 } => format!("{} {},{}", unary_name(op), x(rd), x(rs1)),
+            Instruction::OpImmW { op, rd, rs1, imm } => match op {
+                AluOp::Add => format!("addiw {},{},{}", x(rd), x(rs1), signed(imm)),
+                _ => format!("{}iw {},{},0x{:x}", alu_name(op), x(rd), x(rs1), imm),
+            },
+            Instruction::OpW { op, rd, rs1, rs2 } => format!("{}w {},{},{}", alu_name(op), x(rd), x(rs1), x(rs2)),
+            Instruction::UnaryW { op, rd, rs1 } => format!("{}w {},{}", unary_name(op), x(rd), x(rs1)),
+            Instruction::Fence { pred, succ } => format!("fence {},{}", fence_set(pred), fence_set(succ)),
+            Instruction::FenceI => "fence.i".to_string(),
+            Instruction::Amo { op, doubleword, aq, rl, rd, rs1, rs2 } => {
+                let width = if doubleword { ".d" } else { ".w" };
+                let ordering = match (aq, rl) {
+                    (false, false) => "",
+                    (true, false) => ".aq",
+                    (false, true) => ".rl",
+                    (true, true) => ".aqrl",
+                };
+                if op == AmoOp::Lr {
+                    format!("{}{}{} {},({})", amo_name(op), width, ordering, x(rd), x(rs1))
+                } else {
+                    format!("{}{}{} {},{},({})", amo_name(op), width, ordering, x(rd), x(rs2), x(rs1))
+                }
+            }
+            Instruction::Csr { op, rd, rs1, csr } => format!("{} {},{},{}", csr_op_name(op), x(rd), csr_name(csr), x(rs1)),
+            Instruction::CsrImm { op, rd, uimm, csr } => format!("{}i {},{},{}", csr_op_name(op), x(rd), csr_name(csr), uimm),
+            Instruction::Ecall => "ecall".to_string(),
+            Instruction::Ebreak => "ebreak".to_string(),
+            Instruction::Mret => "mret".to_string(),
+            Instruction::Sret => "sret".to_string(),
+            Instruction::Wfi => "wfi".to_string(),
+            Instruction::SfenceVma { rs1, rs2 } => format!("sfence.vma {},{}", x(rs1), x(rs2)),
+            Instruction::LoadFp { format, rd, rs1, offset } => {
+                format!("fl{} {},{}({})", if format == F32 { "w" } else { "d" }, f(rd), signed(offset), x(rs1))
+            }
+            Instruction::StoreFp { format, rs1, rs2, offset } => {
+                format!("fs{} {},{}({})", if format == F32 { "w" } else { "d" }, f(rs2), signed(offset), x(rs1))
+            }
+            Instruction::Fma { op, format, rm, rd, rs1, rs2, rs3 } => {
+                format!("{}.{} {},{},{},{}{}", fma_name(op), suffix(format), f(rd), f(rs1), f(rs2), f(rs3), rounding(rm))
+            }
+            Instruction::OpFp { op, format, rm, rd, rs1, rs2 } => self.format_op_fp(op, format, rm, rd, rs1, rs2),
+        }
+    }
+
+    fn format_op_fp(&self, op: FpOp, format: Format, rm: u8, rd: u8, rs1: u8, rs2: u8) -> String {
+        let s = suffix(format);
+        let rm = rounding(rm);
+        match op {
+            FpOp::Fadd => format!("fadd.{} {},{},{}{}", s, f(rd), f(rs1), f(rs2), rm),
+            FpOp::Fsub => format!("fsub.{} {},{},{}{}", s, f(rd), f(rs1), f(rs2), rm),
+            FpOp::Fmul => format!("fmul.{} {},{},{}{}", s, f(rd), f(rs1), f(rs2), rm),
+            FpOp::Fdiv => format!("fdiv.{} {},{},{}{}", s, f(rd), f(rs1), f(rs2), rm),
+            FpOp::Fsqrt => format!("fsqrt.{} {},{}{}", s, f(rd), f(rs1), rm),
+            FpOp::Fsgnj => format!("fsgnj.{} {},{},{}", s, f(rd), f(rs1), f(rs2)),
+            FpOp::Fsgnjn => format!("fsgnjn.{} {},{},{}", s, f(rd), f(rs1), f(rs2)),
+            FpOp::Fsgnjx => format!("fsgnjx.{} {},{},{}", s, f(rd), f(rs1), f(rs2)),
+            FpOp::Fmin => format!("fmin.{} {},{},{}", s, f(rd), f(rs1), f(rs2)),
+            FpOp::Fmax => format!("fmax.{} {},{},{}", s, f(rd), f(rs1), f(rs2)),
+            FpOp::FcvtFmt if format == F32 => format!("fcvt.s.d {},{}{}", f(rd), f(rs1), rm),
+            FpOp::FcvtFmt => format!("fcvt.d.s {},{}{}", f(rd), f(rs1), rm),
+            FpOp::Feq => format!("feq.{} {},{},{}", s, x(rd), f(rs1), f(rs2)),
+            FpOp::Flt => format!("flt.{} {},{},{}", s, x(rd), f(rs1), f(rs2)),
+            FpOp::Fle => format!("fle.{} {},{},{}", s, x(rd), f(rs1), f(rs2)),
+            FpOp::FcvtW => format!("fcvt.w.{} {},{}{}", s, x(rd), f(rs1), rm),
+            FpOp::FcvtWu => format!("fcvt.wu.{} {},{}{}", s, x(rd), f(rs1), rm),
+            FpOp::FcvtFromW => format!("fcvt.{}.w {},{}{}", s, f(rd), x(rs1), rm),
+            FpOp::FcvtFromWu => format!("fcvt.{}.wu {},{}{}", s, f(rd), x(rs1), rm),
+            FpOp::FcvtL => format!("fcvt.l.{} {},{}{}", s, x(rd), f(rs1), rm),
+            FpOp::FcvtLu => format!("fcvt.lu.{} {},{}{}", s, x(rd), f(rs1), rm),
+            FpOp::FcvtFromL => format!("fcvt.{}.l {},{}{}", s, f(rd), x(rs1), rm),
+            FpOp::FcvtFromLu => format!("fcvt.{}.lu {},{}{}", s, f(rd), x(rs1), rm),
+            FpOp::FmvXW => format!("fmv.x.w {},{}", x(rd), f(rs1)),
+            FpOp::FmvXD => format!("fmv.x.d {},{}", x(rd), f(rs1)),
+            FpOp::FmvDX => format!("fmv.d.x {},{}", f(rd), x(rs1)),
+            FpOp::Fclass => format!("fclass.{} {},{}", s, x(rd), f(rs1)),
+            FpOp::FmvWX => format!("fmv.w.x {},{}", f(rd), x(rs1)),
+        }
+    }
+
+    // The pseudo-instruction the assembler would accept for this instruction, if any
+    fn format_pseudo(&self, instruction: Instruction, pc: u64) -> Option<String> {
+        let text = match instruction {
+            Instruction::OpImm { op: AluOp::Add, rd: REG_ZERO, rs1: REG_ZERO, imm: 0 } => "nop".to_string(),
+            Instruction::OpImm { op: AluOp::Add, rd, rs1: REG_ZERO, imm } => format!("li {},{}", x(rd), signed(imm)),
+            Instruction::OpImm { op: AluOp::Add, rd, rs1, imm: 0 } => format!("mv {},{}", x(rd), x(rs1)),
+            Instruction::OpImm { op: AluOp::Xor, rd, rs1, imm: 0xFFFF_FFFF } => format!("not {},{}", x(rd), x(rs1)),
+            Instruction::OpImm { op: AluOp::Sltu, rd, rs1, imm: 1 } => format!("seqz {},{}", x(rd), x(rs1)),
+            Instruction::OpImm { op: AluOp::And, rd, rs1, imm: 0xFF } => format!("zext.b {},{}", x(rd), x(rs1)),
+            // What C.MV expands to
+            Instruction::Op { op: AluOp::Add, rd, rs1: REG_ZERO, rs2 } => format!("mv {},{}", x(rd), x(rs2)),
+            Instruction::Op { op: AluOp::Sub, rd, rs1: REG_ZERO, rs2 } => format!("neg {},{}", x(rd), x(rs2)),
+            Instruction::Op { op: AluOp::Sltu, rd, rs1: REG_ZERO, rs2 } => format!("snez {},{}", x(rd), x(rs2)),
+            Instruction::Op { op: AluOp::Slt, rd, rs1, rs2: REG_ZERO } => format!("sltz {},{}", x(rd), x(rs1)),
+            Instruction::Op { op: AluOp::Slt, rd, rs1: REG_ZERO, rs2 } => format!("sgtz {},{}", x(rd), x(rs2)),
+            Instruction::Op { op: AluOp::AddUw, rd, rs1, rs2: REG_ZERO } => format!("zext.w {},{}", x(rd), x(rs1)),
+            Instruction::OpImmW { op: AluOp::Add, rd, rs1, imm: 0 } => format!("sext.w {},{}", x(rd), x(rs1)),
+            Instruction::OpW { op: AluOp::Sub, rd, rs1: REG_ZERO, rs2 } => format!("negw {},{}", x(rd), x(rs2)),
+            Instruction::Jal { rd: REG_ZERO, offset } => format!("j {}", self.target(pc, offset)),
+            Instruction::Jal { rd: REG_RA, offset } => format!("jal {}", self.target(pc, offset)),
+            Instruction::Jalr { rd: REG_ZERO, rs1: REG_RA, offset: 0 } => "ret".to_string(),
+            Instruction::Jalr { rd: REG_ZERO, rs1, offset: 0 } => format!("jr {}", x(rs1)),
+            Instruction::Jalr { rd: REG_RA, rs1, offset: 0 } => format!("jalr {}", x(rs1)),
+            // Comparisons against zero. With x0 first, the condition reads the other way around
+            Instruction::Branch { op, rs1, rs2: REG_ZERO, offset } => {
+                let name = match op {
+                    BranchOp::Beq => "beqz",
+                    BranchOp::Bne => "bnez",
+                    BranchOp::Blt => "bltz",
+                    BranchOp::Bge => "bgez",
+                    _ => return None,
+                };
+                format!("{} {},{}", name, x(rs1), self.target(pc, offset))
+            }
+            Instruction::Branch { op, rs1: REG_ZERO, rs2, offset } => {
+                let name = match op {
+                    BranchOp::Blt => "bgtz",
+                    BranchOp::Bge => "blez",
+                    _ => return None,
+                };
+                format!("{} {},{}", name, x(rs2), self.target(pc, offset))
+            }
+            Instruction::Fence { pred: 0xF, succ: 0xF } => "fence".to_string(),
+            Instruction::Csr { op, rd, rs1, csr } => match (fp_csr_name(csr), op, rd, rs1) {
+                (Some(name), CsrOp::Rs, _, REG_ZERO) => format!("fr{} {}", name, x(rd)),
+                (Some(name), CsrOp::Rw, REG_ZERO, _) => format!("fs{} {}", name, x(rs1)),
+                (Some(name), CsrOp::Rw, _, _) => format!("fs{} {},{}", name, x(rd), x(rs1)),
+                (_, CsrOp::Rs, _, REG_ZERO) => format!("csrr {},{}", x(rd), csr_name(csr)),
+                (_, op, REG_ZERO, _) => format!("csr{} {},{}", &csr_op_name(op)[4..], csr_name(csr), x(rs1)),
+                _ => return None,
+            },
+            Instruction::CsrImm { op, rd, uimm, csr } => match (fp_csr_name(csr), op, rd) {
+                // There's no fscsri
+                (Some(name), CsrOp::Rw, REG_ZERO) if csr != FCSR => format!("fs{}i {}", name, uimm),
+                (Some(name), CsrOp::Rw, _) if csr != FCSR => format!("fs{}i {},{}", name, x(rd), uimm),
+                (_, op, REG_ZERO) => format!("csr{}i {},{}", &csr_op_name(op)[4..], csr_name(csr), uimm),
+                _ => return None,
+            },
+            Instruction::SfenceVma { rs1: REG_ZERO, rs2: REG_ZERO } => "sfence.vma".to_string(),
+            Instruction::SfenceVma { rs1, rs2: REG_ZERO } => format!("sfence.vma {}", x(rs1)),
+            // Sign injection from the register itself
+            Instruction::OpFp { op, format, rd, rs1, rs2, .. } if rs1 == rs2 => {
+                let name = match op {
+                    FpOp::Fsgnj => "fmv",
+                    FpOp::Fsgnjn => "fneg",
+                    FpOp::Fsgnjx => "fabs",
+                    _ => return None,
+                };
+                format!("{}.{} {},{}", name, suffix(format), f(rd), f(rs1))
+            }
+            _ => return None,
+        };
+        Some(text)
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::disasm::*;
+    use crate::elf::Symbol;
+
+    fn raw(instruction: u32) -> String {
+        Disassembler::new(Xlen::Rv32, false, &SymbolTable::default()).disassemble(instruction, 0x1000)
+    }
+
+    fn pseudo(instruction: u32) -> String {
+        Disassembler::new(Xlen::Rv32, true, &SymbolTable::default()).disassemble(instruction, 0x1000)
+    }
+
+    fn rv64(instruction: u32) -> String {
+        Disassembler::new(Xlen::Rv64, false, &SymbolTable::default()).disassemble(instruction, 0x1000)
+    }
+
+    fn rv64_pseudo(instruction: u32) -> String {
+        Disassembler::new(Xlen::Rv64, true, &SymbolTable::default()).disassemble(instruction, 0x1000)
+    }
+
+    #[test]
+    fn test_base() {
+        assert_eq!(raw(0x80000537), "lui\ta0,0x80000");
+        assert_eq!(raw(0x00000517), "auipc\ta0,0x0");
+        assert_eq!(raw(0xfff00513), "addi\ta0,zero,-1");
+        assert_eq!(raw(0x4015d513), "srai\ta0,a1,0x1");
+        assert_eq!(raw(0x00b50533), "add\ta0,a0,a1");
+        assert_eq!(raw(0x8005c503), "lbu\ta0,-2048(a1)");
+        assert_eq!(raw(0xfea5ae23), "sw\ta0,-4(a1)");
+        assert_eq!(raw(0x00008067), "jalr\tzero,0(ra)");
+        assert_eq!(raw(0xfe5ff06f), "jal\tzero,fe4");
+        assert_eq!(raw(0xfeb50ee3), "beq\ta0,a1,ffc");
+        assert_eq!(raw(0x0ff0000f), "fence\tiorw,iorw");
+        assert_eq!(raw(0x0220000f), "fence\tr,r");
+        assert_eq!(raw(0x12b50073), "sfence.vma\ta0,a1");
+        assert_eq!(raw(0x30529573), "csrrw\ta0,mtvec,t0");
+        assert_eq!(raw(0x3a0025f3), "csrrs\ta1,pmpcfg0,zero");
+        assert_eq!(raw(0x7c0027f3), "csrrs\ta5,0x7c0,zero");
+        assert_eq!(raw(0x3007f573), "csrrci\ta0,mstatus,15");
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(raw(0x02b50533), "mul\ta0,a0,a1");
+        assert_eq!(raw(0x1005a52f), "lr.w\ta0,(a1)");
+        assert_eq!(raw(0x0cb5252f), "amoswap.w.aq\ta0,a1,(a0)");
+        assert_eq!(raw(0x06c5a52f), "amoadd.w.aqrl\ta0,a2,(a1)");
+        assert_eq!(raw(0x6985d513), "rev8\ta0,a1");
+        assert_eq!(raw(0x6085d513), "rori\ta0,a1,0x8");
+        assert_eq!(raw(0x20c5c533), "sh2add\ta0,a1,a2");
+        assert_eq!(raw(0xff55a507), "flw\tfa0,-11(a1)");
+        assert_eq!(raw(0x00a5b427), "fsd\tfa0,8(a1)");
+        assert_eq!(raw(0x02c5f543), "fmadd.d\tfa0,fa1,fa2,ft0");
+        assert_eq!(raw(0x02c59543), "fmadd.d\tfa0,fa1,fa2,ft0,rtz");
+        assert_eq!(raw(0xc0159553), "fcvt.wu.s\ta0,fa1,rtz");
+        assert_eq!(raw(0xa2b5a553), "feq.d\ta0,fa1,fa1");
+        assert_eq!(raw(0xe0059553), "fclass.s\ta0,fa1");
+        assert_eq!(raw(0x4015f553), "fcvt.s.d\tfa0,fa1");
+    }
+
+    #[test]
+    fn test_pseudo() {
+        assert_eq!(pseudo(0x00000013), "nop");
+        assert_eq!(pseudo(0xfff00513), "li\ta0,-1");
+        assert_eq!(pseudo(0x00058513), "mv\ta0,a1");
+        assert_eq!(pseudo(0xfff5c513), "not\ta0,a1");
+        assert_eq!(pseudo(0x0015b513), "seqz\ta0,a1");
+        assert_eq!(pseudo(0x40b00533), "neg\ta0,a1");
+        assert_eq!(pseudo(0x00008067), "ret");
+        assert_eq!(pseudo(0x00058067), "jr\ta1");
+        assert_eq!(pseudo(0x000580e7), "jalr\ta1");
+        assert_eq!(pseudo(0xfe5ff06f), "j\tfe4");
+        assert_eq!(pseudo(0x008000ef), "jal\t1008");
+        assert_eq!(pseudo(0x00050463), "beqz\ta0,1008");
+        assert_eq!(pseudo(0x00a04463), "bgtz\ta0,1008");
+        assert_eq!(pseudo(0x0ff0000f), "fence");
+        assert_eq!(pseudo(0x30002573), "csrr\ta0,mstatus");
+        assert_eq!(pseudo(0x30529073), "csrw\tmtvec,t0");
+        assert_eq!(pseudo(0x30046073), "csrsi\tmstatus,8");
+        assert_eq!(pseudo(0x00302573), "frcsr\ta0");
+        assert_eq!(pseudo(0x00205073), "fsrmi\t0");
+        assert_eq!(pseudo(0x12000073), "sfence.vma");
+        assert_eq!(pseudo(0x22b58553), "fmv.d\tfa0,fa1");
+        assert_eq!(pseudo(0x20b59553), "fneg.s\tfa0,fa1");
+        // Not everything has an alias
+        assert_eq!(pseudo(0x00b50533), "add\ta0,a0,a1");
+    }
+
+    #[test]
+    fn test_compressed() {
+        // c.li, c.mv, c.jr ra and c.addi16sp
+        assert_eq!(pseudo(0x557d), "li\ta0,-1");
+        assert_eq!(pseudo(0x852e), "mv\ta0,a1");
+        assert_eq!(pseudo(0x8082), "ret");
+        assert_eq!(raw(0x7139), "addi\tsp,sp,-64");
+        // Only the low halfword counts
+        assert_eq!(pseudo(0xFFFF_8082), "ret");
+    }
+
+    #[test]
+    fn test_rv64() {
+        assert_eq!(rv64(0xfff5851b), "addiw\ta0,a1,-1");
+        assert_eq!(rv64(0x0035951b), "slliw\ta0,a1,0x3");
+        assert_eq!(rv64(0x41f5d51b), "sraiw\ta0,a1,0x1f");
+        assert_eq!(rv64(0x00c5853b), "addw\ta0,a1,a2");
+        assert_eq!(rv64(0x02c5853b), "mulw\ta0,a1,a2");
+        assert_eq!(rv64(0x02c5f53b), "remuw\ta0,a1,a2");
+        assert_eq!(rv64(0xffc5e503), "lwu\ta0,-4(a1)");
+        assert_eq!(rv64(0x02859513), "slli\ta0,a1,0x28");
+        assert_eq!(rv64(0x6005951b), "clzw\ta0,a1");
+        assert_eq!(rv64(0x08c5853b), "add.uw\ta0,a1,a2");
+        assert_eq!(rv64(0x0835951b), "slli.uw\ta0,a1,0x3");
+        assert_eq!(rv64(0x20c5c53b), "sh2add.uw\ta0,a1,a2");
+        assert_eq!(rv64(0x04c5b52f), "amoadd.d.aq\ta0,a2,(a1)");
+        assert_eq!(rv64(0x1005b52f), "lr.d\ta0,(a1)");
+        assert_eq!(rv64(0xc2259553), "fcvt.l.d\ta0,fa1,rtz");
+        assert_eq!(rv64(0xd035f553), "fcvt.s.lu\tfa0,a1");
+        assert_eq!(rv64(0xe2058553), "fmv.x.d\ta0,fa1");
+        assert_eq!(rv64(0xf2058553), "fmv.d.x\tfa0,a1");
+        // c.ld, c.sd, c.addiw and c.subw
+        assert_eq!(rv64(0x6588), "ld\ta0,8(a1)");
+        assert_eq!(rv64(0xe988), "sd\ta0,16(a1)");
+        assert_eq!(rv64(0x2505), "addiw\ta0,a0,1");
+        assert_eq!(rv64(0x9d0d), "subw\ta0,a0,a1");
+        assert_eq!(rv64_pseudo(0x0005851b), "sext.w\ta0,a1");
+        assert_eq!(rv64_pseudo(0x40b0053b), "negw\ta0,a1");
+        assert_eq!(rv64_pseudo(0x0805853b), "zext.w\ta0,a1");
+        // The same encodings mean something else on RV32, or nothing
+        assert_eq!(raw(0x2505), "jal\tra,1620");
+        assert_eq!(raw(0x00c5853b), ".insn\t4, 0x00c5853b");
+        // Targets wrap at XLEN
+        let symbols = SymbolTable::default();
+        assert_eq!(Disassembler::new(Xlen::Rv32, false, &symbols).disassemble(0xff5ff06f, 0), "jal\tzero,fffffff4");
+        assert_eq!(Disassembler::new(Xlen::Rv64, false, &symbols).disassemble(0xff5ff06f, 0), "jal\tzero,fffffffffffffff4");
+    }
+
+    #[test]
+    fn test_illegal() {
+        assert_eq!(raw(0x0000), ".insn\t2, 0x0000");
+        assert_eq!(raw(0xffffffff), ".insn\t4, 0xffffffff");
+        assert_eq!(pseudo(0x00001067), ".insn\t4, 0x00001067");
+    }
+
+    #[test]
+    fn test_symbols() {
+        let symbols = SymbolTable::new(vec![
+            Symbol { name: "main".to_string(), value: 0x1000, size: 0x10 },
+            Symbol { name: "loop".to_string(), value: 0x2000, size: 0 },
+        ]);
+        let disassembler = Disassembler::new(Xlen::Rv32, true, &symbols);
+        assert_eq!(disassembler.disassemble(0x008000ef, 0x1000), "jal\t1008 <main+0x8>");
+        assert_eq!(disassembler.disassemble(0x0000006f, 0x1000), "j\t1000 <main>");
+        assert_eq!(disassembler.disassemble(0x00050063, 0x2000), "beqz\ta0,2000 <loop>");
+        assert_eq!(disassembler.disassemble(0x0000006f, 0x2010), "j\t2010");
+    }
+}
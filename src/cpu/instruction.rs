@@ -1,288 +1,455 @@
 mod builder;
+mod float;
 mod tests;
 
-use std::num::Wrapping;
 use crate::cpu::*;
-use crate::cpu::opcodes::*;
-use crate::cpu::register::*;
+use crate::cpu::decode::{self, AluOp, AmoOp, BranchOp, CsrOp, Instruction, LoadOp, StoreOp, UnaryOp};
+use crate::cpu::csr::{Csr, Privilege, MISA, MISA_C, SATP, MSTATUS_TSR, MSTATUS_TVM, MSTATUS_TW};
+use crate::cpu::trap::Exception;
 
-#[allow(dead_code)]
-impl CPU {
+// Carry-less product of two words, CLMUL keeps the low half, CLMULH the high one and
+// CLMULR bits 62:31
+fn carryless_mul(a: u32, b: u32) -> u64 {
+    (0..32).filter(|i| b >> i & 1 != 0).fold(0, |product, i| product ^ (a as u64) << i)
+}
 
-    fn inst_lui(&mut self) {
-        let rd = ((self.instruction & MASK::RD) >> 7) as u8;
-        /* LUI is a special case, it's an immediate, not an offset
-         * The LUI instruction stores the 20-bit immediate
-         * in the 20 most significant bits of the destination register.
-         * The 12 least significant bits are set to zero.
-         * Thus we do not need to shift right at all
-         */
-        let imm = self.instruction & MASK::LUI_IMM;
-        self.registers.set_register(rd, imm);
-        self.pc += 4;
+// Same for doublewords, CLMULR keeps bits 126:63
+fn carryless_mul64(a: u64, b: u64) -> u128 {
+    (0..64).filter(|i| b >> i & 1 != 0).fold(0, |product, i| product ^ (a as u128) << i)
+}
+
+// The result of an OP instruction on rs1 and rs2, or of an OP-IMM one on rs1 and the immediate
+fn alu(op: AluOp, a: u32, b: u32) -> u32 {
+    // Shifts, rotates and the single bit instructions only use the lower 5 bits
+    let shamt = b & 0x1F;
+    match op {
+        AluOp::Add => a.wrapping_add(b),
+        AluOp::Sub => a.wrapping_sub(b),
+        AluOp::Sll => a << shamt,
+        AluOp::Slt => ((a as i32) < (b as i32)) as u32,
+        AluOp::Sltu => (a < b) as u32,
+        AluOp::Xor => a ^ b,
+        AluOp::Srl => a >> shamt,
+        // Arithmetic shift, fills in with the sign bit
+        AluOp::Sra => ((a as i32) >> shamt) as u32,
+        AluOp::Or => a | b,
+        AluOp::And => a & b,
+        AluOp::Mul => (a as i32).wrapping_mul(b as i32) as u32, // Rust does not like multiplication overflows
+        // We need to cast into larger signed int to get the upper 32 bits. We don't need to do this for MUL
+        AluOp::Mulh => ((a as i32 as i64).wrapping_mul(b as i32 as i64) as u64 >> 32) as u32,
+        // Casting rs2 to unsigned larger int before casting to signed int, guarantees that the value is not signed
+        AluOp::Mulhsu => ((a as i32 as i64).wrapping_mul(b as u64 as i64) >> 32) as u32,
+        AluOp::Mulhu => ((a as u64).wrapping_mul(b as u64) >> 32) as u32,
+        // Division by zero doesn't trap, it gives all ones. The overflowing
+        // i32::MIN / -1 gives i32::MIN, with a remainder of 0.
+        AluOp::Div if b == 0 => 0xFFFFFFFF,
+        AluOp::Div => (a as i32).wrapping_div(b as i32) as u32,
+        AluOp::Divu => a.checked_div(b).unwrap_or(0xFFFFFFFF),
+        AluOp::Rem if b == 0 => a,
+        AluOp::Rem => (a as i32).wrapping_rem(b as i32) as u32,
+        AluOp::Remu if b == 0 => a,
+        AluOp::Remu => a % b,
+        AluOp::Sh1add => (a << 1).wrapping_add(b),
+        AluOp::Sh2add => (a << 2).wrapping_add(b),
+        AluOp::Sh3add => (a << 3).wrapping_add(b),
+        AluOp::Andn => a & !b,
+        AluOp::Orn => a | !b,
+        AluOp::Xnor => !(a ^ b),
+        AluOp::Min => (a as i32).min(b as i32) as u32,
+        AluOp::Minu => a.min(b),
+        AluOp::Max => (a as i32).max(b as i32) as u32,
+        AluOp::Maxu => a.max(b),
+        AluOp::Rol => a.rotate_left(shamt),
+        AluOp::Ror => a.rotate_right(shamt),
+        AluOp::Clmul => carryless_mul(a, b) as u32,
+        AluOp::Clmulh => (carryless_mul(a, b) >> 32) as u32,
+        AluOp::Clmulr => (carryless_mul(a, b) >> 31) as u32,
+        AluOp::Bclr => a & !(1 << shamt),
+        AluOp::Bext => (a >> shamt) & 1,
+        AluOp::Binv => a ^ (1 << shamt),
+        AluOp::Bset => a | (1 << shamt),
+        // Only decoded on RV64, where they go through alu64
+        AluOp::AddUw | AluOp::Sh1addUw | AluOp::Sh2addUw | AluOp::Sh3addUw | AluOp::SllUw => {
+            unreachable!("RV64 only operation: {:?}", op)
+        }
     }
-    
-    fn inst_jal(&mut self) {
-        let rd = ((self.instruction & MASK::RD) >> 7) as u8;
-        // Immediate is split into parts, reconstruct it correctly
-        let imm_20 = (self.instruction & MASK::JAL_IMM_20) >> 11; // Bit 20
-        let imm_10_1 = (self.instruction & MASK::JAL_IMM_10_1) >> 20; // Bits 10:1
-        let imm_11 = (self.instruction & MASK::JAL_IMM_11) >> 9; // Bit 11
-        let imm_19_12 = self.instruction & MASK::JAL_IMM_19_12; // Bits 19:12
+}
 
-        // Combine and sign-extend the immediate
-        let imm = imm_20 | imm_19_12 | imm_11 | imm_10_1;
+// alu on RV64, with full width operands. The word instructions go through alu instead.
+fn alu64(op: AluOp, a: u64, b: u64) -> u64 {
+    // Shifts, rotates and the single bit instructions use the lower 6 bits
+    let shamt = (b & 0x3F) as u32;
+    // The .UW instructions zero-extend the lower word of rs1
+    let word = a as u32 as u64;
+    match op {
+        AluOp::Add => a.wrapping_add(b),
+        AluOp::Sub => a.wrapping_sub(b),
+        AluOp::Sll => a << shamt,
+        AluOp::Slt => ((a as i64) < (b as i64)) as u64,
+        AluOp::Sltu => (a < b) as u64,
+        AluOp::Xor => a ^ b,
+        AluOp::Srl => a >> shamt,
+        AluOp::Sra => ((a as i64) >> shamt) as u64,
+        AluOp::Or => a | b,
+        AluOp::And => a & b,
+        AluOp::Mul => a.wrapping_mul(b),
+        AluOp::Mulh => ((a as i64 as i128 * b as i64 as i128) >> 64) as u64,
+        AluOp::Mulhsu => ((a as i64 as i128 * b as i128) >> 64) as u64,
+        AluOp::Mulhu => ((a as u128 * b as u128) >> 64) as u64,
+        // Same rules as on RV32: all ones for a division by zero, i64::MIN / -1 overflows to i64::MIN
+        AluOp::Div if b == 0 => u64::MAX,
+        AluOp::Div => (a as i64).wrapping_div(b as i64) as u64,
+        AluOp::Divu => a.checked_div(b).unwrap_or(u64::MAX),
+        AluOp::Rem if b == 0 => a,
+        AluOp::Rem => (a as i64).wrapping_rem(b as i64) as u64,
+        AluOp::Remu if b == 0 => a,
+        AluOp::Remu => a % b,
+        AluOp::Sh1add => (a << 1).wrapping_add(b),
+        AluOp::Sh2add => (a << 2).wrapping_add(b),
+        AluOp::Sh3add => (a << 3).wrapping_add(b),
+        AluOp::AddUw => word.wrapping_add(b),
+        AluOp::Sh1addUw => (word << 1).wrapping_add(b),
+        AluOp::Sh2addUw => (word << 2).wrapping_add(b),
+        AluOp::Sh3addUw => (word << 3).wrapping_add(b),
+        AluOp::SllUw => word << shamt,
+        AluOp::Andn => a & !b,
+        AluOp::Orn => a | !b,
+        AluOp::Xnor => !(a ^ b),
+        AluOp::Min => (a as i64).min(b as i64) as u64,
+        AluOp::Minu => a.min(b),
+        AluOp::Max => (a as i64).max(b as i64) as u64,
+        AluOp::Maxu => a.max(b),
+        AluOp::Rol => a.rotate_left(shamt),
+        AluOp::Ror => a.rotate_right(shamt),
+        AluOp::Clmul => carryless_mul64(a, b) as u64,
+        AluOp::Clmulh => (carryless_mul64(a, b) >> 64) as u64,
+        AluOp::Clmulr => (carryless_mul64(a, b) >> 63) as u64,
+        AluOp::Bclr => a & !(1 << shamt),
+        AluOp::Bext => (a >> shamt) & 1,
+        AluOp::Binv => a ^ (1 << shamt),
+        AluOp::Bset => a | (1 << shamt),
+    }
+}
 
-        self.registers.set_register(rd, self.pc + 4);
+fn unary(op: UnaryOp, a: u32) -> u32 {
+    match op {
+        UnaryOp::Clz => a.leading_zeros(),
+        UnaryOp::Ctz => a.trailing_zeros(),
+        UnaryOp::Cpop => a.count_ones(),
+        UnaryOp::SextB => a as i8 as u32,
+        UnaryOp::SextH => a as i16 as u32,
+        UnaryOp::ZextH => a & 0xFFFF,
+        // Every non-zero byte becomes 0xFF
+        UnaryOp::OrcB => u32::from_le_bytes(a.to_le_bytes().map(|byte| if byte == 0 { 0 } else { 0xFF })),
+        UnaryOp::Rev8 => a.swap_bytes(),
+    }
+}
 
-        self.pc = imm;
+fn unary64(op: UnaryOp, a: u64) -> u64 {
+    match op {
+        UnaryOp::Clz => a.leading_zeros() as u64,
+        UnaryOp::Ctz => a.trailing_zeros() as u64,
+        UnaryOp::Cpop => a.count_ones() as u64,
+        UnaryOp::SextB => a as i8 as u64,
+        UnaryOp::SextH => a as i16 as u64,
+        UnaryOp::ZextH => a & 0xFFFF,
+        UnaryOp::OrcB => u64::from_le_bytes(a.to_le_bytes().map(|byte| if byte == 0 { 0 } else { 0xFF })),
+        UnaryOp::Rev8 => a.swap_bytes(),
     }
+}
 
-    fn inst_jalr(&mut self) {
-        let rd = ((self.instruction & MASK::RD) >> 7) as u8;
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8; // Bits 15:11
-        let imm = (self.instruction & MASK::JALR_IMM) >> 20;
+#[allow(dead_code)]
+impl CPU {
 
-        self.registers.set_register(rd, self.pc + 4);
-        self.pc = self.pc + imm + self.registers.get_register(rs1);
+    // An illegal instruction exception for the instruction being executed. mtval
+    // gets its bits as fetched, the 16-bit parcel for a compressed one.
+    pub(super) fn illegal(&self) -> Exception {
+        Exception::IllegalInstruction(if self.compressed { self.raw_instruction } else { self.instruction })
     }
 
-    fn inst_load(&mut self) {
-        let rd = ((self.instruction & MASK::RD) >> 7) as u8;
-        let funct3 = ((self.instruction & MASK::F3) >> 12) as u8; // Bits 14:12
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8; // Bits 15:11
-        let imm = (self.instruction & MASK::LOAD_IMM) >> 20; // Bits 31:20
+    // Address of the instruction following this one
+    fn next_pc(&self) -> u64 {
+        self.xlen.zext(self.pc.wrapping_add(if self.compressed { 2 } else { 4 }))
+    }
 
-        match funct3 {
-            F3::LW => {
-                self.registers.set_register(rd, self.memory.get_u32(self.registers.get_register(rs1) + imm));
-            }
-            F3::LH => {
-                self.registers.set_register(rd, self.memory.get_u16(self.registers.get_register(rs1) + imm + 2) as u32);
-            }
-            F3::LHU => {
-                self.registers.set_register(rd, self.memory.get_u16(self.registers.get_register(rs1) + imm) as u32); // TODO: Verify
-            }
-            F3::LB => {
-                self.registers.set_register(rd, self.memory.get_u8(self.registers.get_register(rs1) + imm + 3) as u32);
-            }
-            _ => {
-                panic!("Invalid load instruction");
-            }
+    // `base` plus a sign-extended offset or immediate, wrapping around at XLEN
+    fn offset_address(&self, base: u64, offset: u32) -> u64 {
+        self.xlen.zext(base.wrapping_add(offset as i32 as u64))
+    }
+
+    // Writes an XLEN wide result, which on RV32 is kept sign-extended like every other
+    fn set_result(&mut self, rd: u8, value: u64) {
+        self.registers.set_x(rd, self.xlen.sext(value));
+    }
+
+    // Jumps and taken branches fault on the jump itself if the target isn't aligned
+    // to IALIGN: 2 bytes with the C extension, 4 without
+    fn check_jump_target(&self, target: u64) -> Result<(), Exception> {
+        let mask = if self.csr.has_compressed() { 0x1 } else { 0x3 };
+        if target & mask != 0 {
+            return Err(Exception::InstructionAddressMisaligned(target));
         }
-        self.pc += 4;
+        Ok(())
     }
 
-    // TODO: Double check endianness
-    fn inst_store(&mut self) {
-        let funct3 = ((self.instruction & MASK::F3) >> 12) as u8;
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8;
-        let rs2 = ((self.instruction & MASK::RS2) >> 20) as u8;
-        let imm_11_5 = (self.instruction & MASK::STORE_IMM_11_5) >> 25;
-        let imm_4_0 = (self.instruction & MASK::STORE_IMM_4_0) >> 7;
-        let imm = imm_11_5 << 5 | imm_4_0;
+    // Links rd to the next instruction and jumps
+    fn jump(&mut self, rd: u8, target: u64) -> Result<(), Exception> {
+        self.check_jump_target(target)?;
+        self.set_result(rd, self.next_pc());
+        self.pc = target;
+        Ok(())
+    }
 
-        match funct3 {
-            F3::SW => {
-                self.memory.set_u32(self.registers.get_register(rs1) + imm, self.registers.get_register(rs2));
-            }
-            F3::SH => {
-                self.memory.set_u16(self.registers.get_register(rs1) + imm, self.registers.get_register(rs2) as u16);
-            }
-            F3::SB => {
-                self.memory.set_u8(self.registers.get_register(rs1) + imm, self.registers.get_register(rs2) as u8);
-            }
-            _ => {
-                panic!("Invalid store instruction");
-            }
+    fn inst_branch(&mut self, op: BranchOp, rs1: u8, rs2: u8, offset: u32) -> Result<(), Exception> {
+        // RV32 values are sign-extended, so comparing all 64 bits works for both
+        let rs1_value = self.registers.get_x(rs1);
+        let rs2_value = self.registers.get_x(rs2);
+        let condition = match op {
+            BranchOp::Beq => rs1_value == rs2_value,
+            BranchOp::Bne => rs1_value != rs2_value,
+            BranchOp::Blt => (rs1_value as i64) < (rs2_value as i64),
+            BranchOp::Bge => (rs1_value as i64) >= (rs2_value as i64),
+            BranchOp::Bltu => rs1_value < rs2_value,
+            BranchOp::Bgeu => rs1_value >= rs2_value,
+        };
+        if condition {
+            let target = self.offset_address(self.pc, offset);
+            self.check_jump_target(target)?;
+            self.pc = target;
+        } else {
+            self.pc = self.next_pc();
         }
+        Ok(())
+    }
 
-        self.pc += 4;
+    // OP and OP-IMM, `operand` being rs2 or the sign-extended immediate
+    fn inst_alu(&mut self, op: AluOp, rd: u8, rs1: u8, operand: u64) {
+        let result = match self.xlen {
+            Xlen::Rv32 => alu(op, self.registers.get_register(rs1), operand as u32) as u64,
+            Xlen::Rv64 => alu64(op, self.registers.get_x(rs1), operand),
+        };
+        self.set_result(rd, result);
     }
 
-    fn inst_branch(&mut self) {
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8;
-        let rs2 = ((self.instruction & MASK::RS2) >> 20) as u8;
-        let funct3 = ((self.instruction & MASK::F3) >> 12) as u8;
+    fn inst_load(&mut self, op: LoadOp, rd: u8, rs1: u8, offset: u32) -> Result<(), Exception> {
+        let address = self.offset_address(self.registers.get_x(rs1), offset);
+        // LB, LH and LW sign-extend the loaded value, LBU, LHU and LWU zero-extend it
+        let value = match op {
+            LoadOp::Ld => self.load_u64(address)?,
+            LoadOp::Lw => self.load_u32(address)? as i32 as u64,
+            LoadOp::Lwu => self.load_u32(address)? as u64,
+            LoadOp::Lh => self.load_u16(address)? as i16 as u64,
+            LoadOp::Lhu => self.load_u16(address)? as u64,
+            LoadOp::Lb => self.load_u8(address)? as i8 as u64,
+            LoadOp::Lbu => self.load_u8(address)? as u64,
+        };
+        self.registers.set_x(rd, value);
+        Ok(())
+    }
 
-        let imm_12 = ((self.instruction & MASK::BRANCH_IMM_12) >> 31) << 12;
-        let imm_11 = ((self.instruction & MASK::BRANCH_IMM_11) >> 7) << 11;
-        let imm_10_5 = ((self.instruction & MASK::BRANCH_IMM_10_5) >> 25) << 5;
-        let imm_4_1 = ((self.instruction & MASK::BRANCH_IMM_4_1) >> 8) << 1;
-        
-        let imm = imm_12 | imm_11 | imm_10_5 | imm_4_1;
+    fn inst_store(&mut self, op: StoreOp, rs1: u8, rs2: u8, offset: u32) -> Result<(), Exception> {
+        let address = self.offset_address(self.registers.get_x(rs1), offset);
+        let value = self.registers.get_x(rs2);
+        match op {
+            StoreOp::Sd => self.store_u64(address, value),
+            StoreOp::Sw => self.store_u32(address, value as u32),
+            StoreOp::Sh => self.store_u16(address, value as u16),
+            StoreOp::Sb => self.store_u8(address, value as u8),
+        }
+    }
 
-        let condition:bool;
+    // `operand` is rs1 or the immediate. CSRRW(I) always writes, the set/clear
+    // variants only write when rs1/uimm isn't zero.
+    fn inst_csr(&mut self, op: CsrOp, rd: u8, address: u16, operand: u64, writes: bool) -> Result<(), Exception> {
+        let illegal = self.illegal();
+        if !self.csr.is_accessible(address) || (writes && Csr::is_read_only(address)) {
+            return Err(illegal);
+        }
 
-        match funct3 {
-            F3::BEQ => {
-                condition = self.registers.get_register(rs1) == self.registers.get_register(rs2);
-            }
-            F3::BNE => {
-                condition = self.registers.get_register(rs1) != self.registers.get_register(rs2);
-            }
-            F3::BLT => {
-                condition = self.registers.get_register(rs1) < self.registers.get_register(rs2);
-            }
-            F3::BGE => {
-                condition = self.registers.get_register(rs1) >= self.registers.get_register(rs2);
+        let old = self.csr.read(address).ok_or(illegal)?;
+        if writes {
+            let mut new = match op {
+                CsrOp::Rw => operand,
+                CsrOp::Rs => old | operand,
+                CsrOp::Rc => old & !operand,
+            };
+            // Turning C off when the next instruction isn't word aligned would leave
+            // it unreachable, the write is dropped instead
+            if address == MISA && new & MISA_C == 0 && self.next_pc() & 0x3 != 0 {
+                new = old;
             }
-            F3::BLTU => {
-                condition = self.registers.get_register(rs1) < self.registers.get_register(rs2);
-            }
-            F3::BGEU => {
-                condition = self.registers.get_register(rs1) >= self.registers.get_register(rs2);
-            }
-            _ => {
-                panic!("Invalid branch instruction");
+            self.csr.write(address, new).ok_or(illegal)?;
+            // Translations cached for the old address space are gone
+            if address == SATP {
+                self.mmu.flush(None, None);
             }
         }
-        if condition {
-            self.registers.set_register(REG_RA, self.pc + 4);
-            self.pc = self.pc + imm;
+        self.set_result(rd, old);
+        Ok(())
+    }
+
+    // LR, SC and the AMOs. There's a single hart executing one instruction at a
+    // time, so every access is already ordered and aq/rl have nothing left to do.
+    fn inst_amo(&mut self, op: AmoOp, doubleword: bool, rd: u8, rs1: u8, rs2: u8) -> Result<(), Exception> {
+        let address = self.xlen.zext(self.registers.get_x(rs1));
+        let operand = self.registers.get_x(rs2);
+        let size = if doubleword { 8 } else { 4 };
+        // Atomics are never split, misaligned ones fault
+        if !address.is_multiple_of(size as u64) {
+            return Err(if op == AmoOp::Lr {
+                Exception::LoadAddressMisaligned(address)
+            } else {
+                Exception::StoreAddressMisaligned(address)
+            });
+        }
+        // Word AMOs only look at the lower 32 bits, min and max compare them as words
+        let signed = |value: u64| if doubleword { value as i64 } else { value as i32 as i64 };
+        let unsigned = |value: u64| if doubleword { value } else { value as u32 as u64 };
+        let value = match op {
+            AmoOp::Lr => self.load_reserved(address, size)?,
+            AmoOp::Sc => !self.store_conditional(address, size, operand)? as u64,
+            AmoOp::Swap => self.amo(address, size, |_| operand)?,
+            AmoOp::Add => self.amo(address, size, |old| old.wrapping_add(operand))?,
+            AmoOp::Xor => self.amo(address, size, |old| old ^ operand)?,
+            AmoOp::And => self.amo(address, size, |old| old & operand)?,
+            AmoOp::Or => self.amo(address, size, |old| old | operand)?,
+            AmoOp::Min => self.amo(address, size, |old| if signed(old) <= signed(operand) { old } else { operand })?,
+            AmoOp::Max => self.amo(address, size, |old| if signed(old) >= signed(operand) { old } else { operand })?,
+            AmoOp::Minu => self.amo(address, size, |old| if unsigned(old) <= unsigned(operand) { old } else { operand })?,
+            AmoOp::Maxu => self.amo(address, size, |old| if unsigned(old) >= unsigned(operand) { old } else { operand })?,
+        };
+        // The loaded word is sign-extended, like LW
+        if doubleword {
+            self.registers.set_x(rd, value);
         } else {
-            self.pc += 4;
+            self.registers.set_register(rd, value as u32);
         }
+        Ok(())
     }
 
-    fn inst_alui(&mut self) {
-        let rd = ((self.instruction & MASK::RD) >> 7) as u8;
-        let funct3 = ((self.instruction & MASK::F3) >> 12) as u8;
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8;
-        let imm = (self.instruction & MASK::ALUI_IMM) >> 20;
+    // SFENCE.VMA: flushes the cached translations of the address in rs1, in the
+    // address space in rs2. x0 stands for every address / address space.
+    fn inst_sfence_vma(&mut self, rs1: u8, rs2: u8) -> Result<(), Exception> {
+        let privilege = self.csr.privilege();
+        // mstatus.TVM traps it in S-mode, like satp accesses
+        if privilege == Privilege::User || (privilege == Privilege::Supervisor && self.csr.status(MSTATUS_TVM)) {
+            return Err(self.illegal());
+        }
+        // Only Sv32 is supported, which has 32-bit virtual addresses
+        let address = (rs1 != 0).then(|| self.registers.get_register(rs1));
+        let asid = (rs2 != 0).then(|| self.registers.get_register(rs2));
+        self.mmu.flush(address, asid);
+        Ok(())
+    }
 
-        let rs1_value = self.registers.get_register(rs1);
-        let result:u32;
-        match funct3 {
-            F3::ADDI => result = imm + rs1_value,
-            F3::SLTI => {
-                let mut imm_extended = imm as i32;
-                // We need to sign extend the immediate
-                if (imm & 0x800) != 0 {  // MSB is not set
-                    imm_extended = imm_extended | (0xFFFF_F000u32 as i32);
+    // ECALL, EBREAK, MRET, SRET and WFI
+    fn inst_priv(&mut self, instruction: Instruction) -> Result<(), Exception> {
+        let illegal = self.illegal();
+        let privilege = self.csr.privilege();
+        match instruction {
+            Instruction::Ecall => return Err(match privilege {
+                Privilege::User => Exception::EnvironmentCallFromUMode,
+                Privilege::Supervisor => Exception::EnvironmentCallFromSMode,
+                Privilege::Machine => Exception::EnvironmentCallFromMMode,
+            }),
+            Instruction::Ebreak => return Err(Exception::Breakpoint(self.pc)),
+            Instruction::Mret => {
+                if privilege != Privilege::Machine {
+                    return Err(illegal);
                 }
-                result = ((rs1_value as i32) < imm_extended) as u32;
+                self.pc = self.csr.leave_trap();
+                return Ok(());
             }
-            F3::SLTIU => {
-                let mut imm_extended = imm;
-                // We need to sign extend the immediate
-                if (imm & 0x800) != 0 {  // MSB is not set
-                    imm_extended = imm_extended | 0xFFFF_F000;
+            Instruction::Sret => {
+                // mstatus.TSR lets M-mode trap SRET from S-mode
+                if privilege == Privilege::User || (privilege == Privilege::Supervisor && self.csr.status(MSTATUS_TSR)) {
+                    return Err(illegal);
                 }
-                result = (rs1_value < imm_extended) as u32;
+                self.pc = self.csr.leave_supervisor_trap();
+                return Ok(());
             }
-            F3::XORI => result = rs1_value ^ imm,
-            F3::ORI => result = rs1_value | imm,
-            F3::ANDI => result = rs1_value & imm,
-            F3::SLLI => result = rs1_value << imm,
-            F3::SRLI_SRAI => {
-                let slai_bit = ((self.instruction >> 30) as u8) & 0x1;
-                if slai_bit == 0 { // SLRI
-                    result = rs1_value >> imm;
-                }
-                else { // SLAI
-                    // Here, we need to rotate, instead of shifting and zero filling
-                    result = rs1_value.rotate_right(imm);
+            // Stall until an interrupt is pending. If none is enabled nothing
+            // could ever wake us up, so it's a NOP. Below M-mode mstatus.TW makes it illegal.
+            Instruction::Wfi => {
+                if privilege == Privilege::User || (privilege == Privilege::Supervisor && self.csr.status(MSTATUS_TW)) {
+                    return Err(illegal);
                 }
+                self.waiting = self.csr.has_enabled_interrupt();
             }
-            _ => {
-                panic!("Invalid alui instruction - funct3");
-            }
+            _ => unreachable!("not a privileged instruction: {:?}", instruction),
         }
-        self.registers.set_register(rd, result);
-        self.pc += 4;
+        self.pc = self.next_pc();
+        Ok(())
     }
 
-    fn inst_alu(&mut self) {
-        let rd = (self.instruction & MASK::RD) >> 7;
-        let funct3 = ((self.instruction & MASK::F3) >> 12) as u8;
-        let funct7 = ((self.instruction & MASK::F7) >> 25) as u8;
-        let funct73:u16 = ((funct7 as u16) << 3) | funct3 as u16;
-        let rs1 = ((self.instruction & MASK::RS1) >> 15) as u8;
-        let rs2 = ((self.instruction & MASK::RS2) >> 20) as u8;
-
-        let rs1_value = self.registers.get_register(rs1);
-        let rs2_value = self.registers.get_register(rs2);
-        let result:u32;
-
-        match funct73 {
-            F73_ADD => result = rs1_value + rs2_value,
-            F73_SUB => result = (Wrapping(rs1_value) - Wrapping(rs2_value)).0,
-            F73_SLL => result = rs1_value << (rs2_value & 0x1F),
-            F73_SLT => result = ( (rs1_value as i32) < (rs2_value as i32) ) as u32,
-            F73_SLTU => result = ( rs1_value < rs2_value ) as u32,
-            F73_XOR => result = rs1_value ^ rs2_value,
-            F73_SRL => result = rs1_value >> (rs2_value & 0x1F),
-            F73_SRA => result = rs1_value.rotate_right(rs2_value & 0x1F), // Only lower 5 bits of rs2 are used
-            F73_OR => result = rs1_value | rs2_value,
-            F73_AND => result = rs1_value & rs2_value,
-            F73_MUL => result = (rs1_value as i32).wrapping_mul(rs2_value as i32) as u32, // Rust does not like multiplication overflows
-            F73_MULH => { // We need to cast into larger signed int to get the upper 32 bits. We don't need to do this for MUL
-                let result64 = (rs1_value as i32 as i64).wrapping_mul(rs2_value as i32 as i64);
-                result = (result64 as u64 >> 32) as u32;
-            },
-            F73_MULHSU => { // Casting rs2 to unsigned larger int before casting to signed int, guarantees that the value is not signed
-                let result64 = (rs1_value as i32 as i64).wrapping_mul(rs2_value as u64 as i64);
-                result = (result64 >> 32) as u32;
-            },
-            F73_MULHU => {
-                let result64 = (rs1_value as u64).wrapping_mul(rs2_value as u64);
-                result = (result64 >> 32) as u32;
-            },
-            F73_DIV => {
-                if rs2_value == 0 {
-                    result = 0xFFFFFFFF;
-                } else {
-                    result = (rs1_value as i32 / rs2_value as i32) as u32;
-                }
-            },
-            F73_DIVU => {
-                if rs2_value == 0 {
-                    result = 0xFFFFFFFF;
-                } else {
-                    result = rs1_value / rs2_value;
-                }
-            },
-            F73_REM => {
-                if rs2_value == 0 {
-                    result = rs1_value;
-                } else if (rs1_value == (i32::MIN as u32)) && ((rs2_value as i32) == -1) {
-                    result = 0;
-                } else {
-                    result = (rs1_value as i32 % rs2_value as i32) as u32;
-                }
-            },
-            F73_REMU => {
-                if rs2_value == 0 {
-                    result = rs1_value;
-                } else {
-                    result = rs1_value % rs2_value;
-                }
-            },
-            _ => {
-                panic!("Invalid alu instruction - funct3");
+    // Executes a decoded instruction. Jumps, branches and returns from traps set
+    // the pc themselves, everything else moves on to the next instruction.
+    pub(crate) fn execute(&mut self, instruction: Instruction) -> Result<(), Exception> {
+        match instruction {
+            /* LUI is a special case, it's an immediate, not an offset
+             * The LUI instruction stores the 20-bit immediate
+             * in the 20 most significant bits of the destination register.
+             * The 12 least significant bits are set to zero.
+             */
+            Instruction::Lui { rd, imm } => self.registers.set_register(rd, imm),
+            // Same immediate as LUI, but added to the address of this instruction
+            Instruction::Auipc { rd, imm } => self.set_result(rd, self.offset_address(self.pc, imm)),
+            // The offset is from this instruction
+            Instruction::Jal { rd, offset } => return self.jump(rd, self.offset_address(self.pc, offset)),
+            // The target is relative to rs1, not the PC, with the lowest bit cleared
+            Instruction::Jalr { rd, rs1, offset } => {
+                return self.jump(rd, self.offset_address(self.registers.get_x(rs1), offset) & !0x1);
+            }
+            Instruction::Branch { op, rs1, rs2, offset } => return self.inst_branch(op, rs1, rs2, offset),
+            Instruction::Load { op, rd, rs1, offset } => self.inst_load(op, rd, rs1, offset)?,
+            Instruction::Store { op, rs1, rs2, offset } => self.inst_store(op, rs1, rs2, offset)?,
+            Instruction::OpImm { op, rd, rs1, imm } => self.inst_alu(op, rd, rs1, imm as i32 as u64),
+            Instruction::Op { op, rd, rs1, rs2 } => self.inst_alu(op, rd, rs1, self.registers.get_x(rs2)),
+            Instruction::Unary { op, rd, rs1 } => {
+                let result = match self.xlen {
+                    Xlen::Rv32 => unary(op, self.registers.get_register(rs1)) as u64,
+                    Xlen::Rv64 => unary64(op, self.registers.get_x(rs1)),
+                };
+                self.set_result(rd, result);
             }
+            // The word instructions sign-extend their 32-bit result, which set_register does
+            Instruction::OpImmW { op, rd, rs1, imm } => {
+                self.registers.set_register(rd, alu(op, self.registers.get_register(rs1), imm));
+            }
+            Instruction::OpW { op, rd, rs1, rs2 } => {
+                let result = alu(op, self.registers.get_register(rs1), self.registers.get_register(rs2));
+                self.registers.set_register(rd, result);
+            }
+            Instruction::UnaryW { op, rd, rs1 } => self.registers.set_register(rd, unary(op, self.registers.get_register(rs1))),
+            // There is a single hart and no caches, so every memory access is already ordered
+            Instruction::Fence { .. } => {}
+            // Instructions are decoded straight from memory on every fetch, so there's nothing to flush
+            Instruction::FenceI => {}
+            Instruction::Amo { op, doubleword, rd, rs1, rs2, .. } => self.inst_amo(op, doubleword, rd, rs1, rs2)?,
+            Instruction::Csr { op, rd, rs1, csr } => {
+                let writes = op == CsrOp::Rw || rs1 != 0;
+                self.inst_csr(op, rd, csr, self.xlen.zext(self.registers.get_x(rs1)), writes)?;
+            }
+            Instruction::CsrImm { op, rd, uimm, csr } => {
+                let writes = op == CsrOp::Rw || uimm != 0;
+                self.inst_csr(op, rd, csr, uimm as u64, writes)?;
+            }
+            Instruction::Ecall | Instruction::Ebreak | Instruction::Mret | Instruction::Sret | Instruction::Wfi => {
+                return self.inst_priv(instruction);
+            }
+            Instruction::SfenceVma { rs1, rs2 } => self.inst_sfence_vma(rs1, rs2)?,
+            Instruction::LoadFp { format, rd, rs1, offset } => self.inst_load_fp(format, rd, rs1, offset)?,
+            Instruction::StoreFp { format, rs1, rs2, offset } => self.inst_store_fp(format, rs1, rs2, offset)?,
+            Instruction::Fma { op, format, rm, rd, rs1, rs2, rs3 } => self.inst_fma(op, format, rm, rd, [rs1, rs2, rs3])?,
+            Instruction::OpFp { op, format, rm, rd, rs1, rs2 } => self.inst_op_fp(op, format, rm, rd, rs1, rs2)?,
         }
-
-        self.registers.set_register(rd as u8, result);
-        self.pc += 4;
+        self.pc = self.next_pc();
+        Ok(())
     }
 
-    pub(crate) fn exec_inst(&mut self) -> bool {
-        match self.opcode {
-            OP::LUI => self.inst_lui(),
-            OP::JAL => self.inst_jal(),
-            OP::JALR => self.inst_jalr(),
-            OP::BRANCH => self.inst_branch(),
-            OP::LOAD => self.inst_load(),
-            OP::STORE => self.inst_store(),
-            OP::ALUI => self.inst_alui(),
-            OP::ALU => self.inst_alu(),
-            //OP::FENCE => self.inst_fence(),
-            OP::E_C => return true,
-            0x0 => return true,
-            _ => panic!("Invalid opcode: 0b{:0>8b}", self.opcode),
-        }
-        false
+    // Decodes and executes the fetched instruction
+    pub(crate) fn exec_inst(&mut self) -> Result<(), Exception> {
+        let instruction = decode::decode(self.instruction, self.xlen).ok_or_else(|| self.illegal())?;
+        self.execute(instruction)
     }
-}
\ No newline at end of file
+}
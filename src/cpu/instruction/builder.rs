@@ -14,22 +14,29 @@ impl InstructionBuilder {
         | OP::LUI as u32
     }
     
-    pub fn jal(&self, address: u32, rd: u8) -> u32 {
-        let imm_encoded = ((address & 0x80000) >> 20)        // Bit 20
-            | ((address & 0xFF000) >> 12)                   // Bits 19:12
-            | ((address & 0x800) << 9)                      // Bit 11
-            | ((address & 0x7FE) << 20);                    // Bits 10:1
+    pub fn auipc(&self, imm: u32, rd: u8) -> u32 {
+        imm << 12
+        | ((rd as u32) << 7)
+        | OP::AUIPC as u32
+    }
+
+    pub fn fence(&self, funct3: u8) -> u32 {
+        (funct3 as u32) << 12
+        | OP::FENCE as u32
+    }
+
+    pub fn jal(&self, offset: u32, rd: u8) -> u32 {
+        let imm_encoded = ((offset >> 20) & 0x1) << 31     // Bit 20
+            | ((offset >> 1) & 0x3FF) << 21                 // Bits 10:1
+            | ((offset >> 11) & 0x1) << 20                  // Bit 11
+            | ((offset >> 12) & 0xFF) << 12;                // Bits 19:12
         imm_encoded
         | ((rd as u32) << 7)
         | OP::JAL as u32
     }
 
     pub fn jalr(&self, offset: u32, rs1: u8, rd: u8) -> u32 {
-        let imm_encoded = ((offset & 0x80000) >> 20)     // Bit 20
-            | ((offset & 0xFF000) >> 12)   // Bits 19:12
-            | ((offset & 0x800) << 9)      // Bit 11
-            | ((offset & 0x7FE) << 20);    // Bits 10:1
-        imm_encoded // We don't offset imm here, as it already is offset
+        (offset & 0xFFF) << 20
         | (rs1 as u32) << 15
         | (rd as u32) << 7
         | OP::JALR as u32
@@ -86,4 +93,71 @@ impl InstructionBuilder {
         | (rd as u32) << 7
         | OP::ALU as u32
     }
-}
\ No newline at end of file
+
+    pub fn alui_w(&self, imm: u32, funct3: u8, rs1: u8, rd: u8) -> u32 {
+        self.alui(imm, funct3, rs1, rd) & !MASK::OP
+        | OP::ALUI_W as u32
+    }
+
+    pub fn alu_w(&self, funct7: u8, funct3: u8, rs2: u8, rs1: u8, rd: u8) -> u32 {
+        self.alu(funct7, funct3, rs2, rs1, rd) & !MASK::OP
+        | OP::ALU_W as u32
+    }
+
+    pub fn amo(&self, funct5: u8, aq: bool, rl: bool, rs2: u8, rs1: u8, rd: u8) -> u32 {
+        (funct5 as u32) << 27
+        | (aq as u32) << 26
+        | (rl as u32) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | (F3::AMO_W as u32) << 12
+        | (rd as u32) << 7
+        | OP::AMO as u32
+    }
+
+    pub fn load_fp(&self, offset: u32, funct3: u8, rs1: u8, rd: u8) -> u32 {
+        (offset & 0xFFF) << 20
+        | (rs1 as u32) << 15
+        | (funct3 as u32) << 12
+        | (rd as u32) << 7
+        | OP::LOAD_FP as u32
+    }
+
+    pub fn store_fp(&self, offset: u32, funct3: u8, rs2: u8, rs1: u8) -> u32 {
+        ((offset >> 5) & 0x7F) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | (funct3 as u32) << 12
+        | (offset & 0x1F) << 7
+        | OP::STORE_FP as u32
+    }
+
+    // FMADD, FMSUB, FNMSUB and FNMADD, told apart by their opcode. Rounds to nearest, ties to even.
+    pub fn fmadd(&self, opcode: u8, fmt: u8, rs3: u8, rs2: u8, rs1: u8, rd: u8) -> u32 {
+        (rs3 as u32) << 27
+        | (fmt as u32) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | (rd as u32) << 7
+        | opcode as u32
+    }
+
+    // `funct3` is the rounding mode for the instructions that round
+    pub fn op_fp(&self, funct5: u8, fmt: u8, funct3: u8, rs2: u8, rs1: u8, rd: u8) -> u32 {
+        (funct5 as u32) << 27
+        | (fmt as u32) << 25
+        | (rs2 as u32) << 20
+        | (rs1 as u32) << 15
+        | (funct3 as u32) << 12
+        | (rd as u32) << 7
+        | OP::OP_FP as u32
+    }
+
+    pub fn csr(&self, csr: u16, funct3: u8, rs1: u8, rd: u8) -> u32 {
+        (csr as u32) << 20
+        | (rs1 as u32) << 15
+        | (funct3 as u32) << 12
+        | (rd as u32) << 7
+        | OP::E_C as u32
+    }
+}
@@ -0,0 +1,157 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* The F and D extensions
+ *
+ * The arithmetic itself is in softfloat, here the operands are read and
+ * unboxed, and the exception flags raised are accrued in fflags. Everything
+ * is illegal while mstatus.FS is Off, and anything that writes an FP register
+ * or fflags sets it to Dirty.
+ */
+
+use crate::cpu::CPU;
+use crate::cpu::decode::{FmaOp, FpOp};
+use crate::cpu::opcodes::F3;
+use crate::cpu::softfloat::{self, Format, RoundingMode, SoftFloat, F32, F64};
+use crate::cpu::trap::Exception;
+
+// Where the result of an OP-FP instruction goes. 32-bit integers are sign-extended
+// on RV64, Long is for the RV64 only instructions with a 64-bit result.
+enum Destination {
+    Float(u64),
+    Integer(u32),
+    Long(u64),
+}
+
+impl CPU {
+    fn check_fp_enabled(&self) -> Result<(), Exception> {
+        if !self.csr.fp_enabled() {
+            return Err(self.illegal());
+        }
+        Ok(())
+    }
+
+    // Reads a register as `format`, single precision values have to be properly NaN-boxed
+    fn read_fp(&self, format: Format, register: u8) -> u64 {
+        if format == F32 {
+            self.fregisters.get_f32(register) as u64
+        } else {
+            self.fregisters.get_f64(register)
+        }
+    }
+
+    fn write_fp(&mut self, format: Format, register: u8, value: u64) {
+        if format == F32 {
+            self.fregisters.set_f32(register, value as u32);
+        } else {
+            self.fregisters.set_f64(register, value);
+        }
+        self.csr.set_fp_dirty();
+    }
+
+    // The rounding mode in funct3, or frm for DYN. Reserved modes, in either, are illegal.
+    fn fp_rounding(&self, rm: u8) -> Result<SoftFloat, Exception> {
+        let rm = if rm == F3::RM_DYN { self.csr.frm() } else { rm as u32 };
+        RoundingMode::from_bits(rm)
+            .map(SoftFloat::new)
+            .ok_or_else(|| self.illegal())
+    }
+
+    fn accrue_fp_flags(&mut self, fp: &SoftFloat) {
+        if fp.flags != 0 {
+            self.csr.accrue_fp_flags(fp.flags);
+        }
+    }
+
+    pub(super) fn inst_load_fp(&mut self, format: Format, rd: u8, rs1: u8, offset: u32) -> Result<(), Exception> {
+        self.check_fp_enabled()?;
+        let address = self.offset_address(self.registers.get_x(rs1), offset);
+        let value = if format == F32 { self.load_u32(address)? as u64 } else { self.load_u64(address)? };
+        self.write_fp(format, rd, value);
+        Ok(())
+    }
+
+    // FSW stores the low 32 bits of the register as they are, boxed or not
+    pub(super) fn inst_store_fp(&mut self, format: Format, rs1: u8, rs2: u8, offset: u32) -> Result<(), Exception> {
+        self.check_fp_enabled()?;
+        let address = self.offset_address(self.registers.get_x(rs1), offset);
+        let value = self.fregisters.get_f64(rs2);
+        if format == F32 {
+            self.store_u32(address, value as u32)
+        } else {
+            self.store_u64(address, value)
+        }
+    }
+
+    // FMADD, FMSUB, FNMSUB and FNMADD: rs1 * rs2 + rs3, with the product and/or
+    // rs3 negated, and a single rounding
+    pub(super) fn inst_fma(&mut self, op: FmaOp, format: Format, rm: u8, rd: u8, sources: [u8; 3]) -> Result<(), Exception> {
+        self.check_fp_enabled()?;
+        let mut fp = self.fp_rounding(rm)?;
+
+        let sign = format.sign_bit();
+        let [a, b, c] = sources.map(|register| self.read_fp(format, register));
+        // Negating the product is negating one of its factors
+        let (a, c) = match op {
+            FmaOp::Fmadd => (a, c),
+            FmaOp::Fmsub => (a, c ^ sign),
+            FmaOp::Fnmsub => (a ^ sign, c),
+            FmaOp::Fnmadd => (a ^ sign, c ^ sign),
+        };
+        let result = fp.fma(format, a, b, c);
+        self.write_fp(format, rd, result);
+        self.accrue_fp_flags(&fp);
+        Ok(())
+    }
+
+    // Arithmetic, sign injection, min/max, compares, conversions, moves and FCLASS
+    pub(super) fn inst_op_fp(&mut self, op: FpOp, format: Format, rm: u8, rd: u8, rs1: u8, rs2: u8) -> Result<(), Exception> {
+        self.check_fp_enabled()?;
+        let mut fp = if op.rounds() { self.fp_rounding(rm)? } else { SoftFloat::new(RoundingMode::NearestEven) };
+        let a = self.read_fp(format, rs1);
+        let b = self.read_fp(format, rs2);
+        let sign = format.sign_bit();
+
+        let result = match op {
+            FpOp::Fadd => Destination::Float(fp.add(format, a, b)),
+            FpOp::Fsub => Destination::Float(fp.sub(format, a, b)),
+            FpOp::Fmul => Destination::Float(fp.mul(format, a, b)),
+            FpOp::Fdiv => Destination::Float(fp.div(format, a, b)),
+            FpOp::Fsqrt => Destination::Float(fp.sqrt(format, a)),
+            FpOp::Fsgnj => Destination::Float((a & !sign) | (b & sign)),
+            FpOp::Fsgnjn => Destination::Float((a & !sign) | (!b & sign)),
+            FpOp::Fsgnjx => Destination::Float((a & !sign) | ((a ^ b) & sign)),
+            FpOp::Fmin => Destination::Float(fp.min_max(format, a, b, false)),
+            FpOp::Fmax => Destination::Float(fp.min_max(format, a, b, true)),
+            FpOp::FcvtFmt if format == F32 => Destination::Float(fp.convert(F64, F32, self.read_fp(F64, rs1))),
+            FpOp::FcvtFmt => Destination::Float(fp.convert(F32, F64, self.read_fp(F32, rs1))),
+            FpOp::Feq => Destination::Integer(fp.eq(format, a, b) as u32),
+            FpOp::Flt => Destination::Integer(fp.lt(format, a, b) as u32),
+            FpOp::Fle => Destination::Integer(fp.le(format, a, b) as u32),
+            FpOp::FcvtW => Destination::Integer(fp.float_to_int(format, a, true, 32) as u32),
+            FpOp::FcvtWu => Destination::Integer(fp.float_to_int(format, a, false, 32) as u32),
+            FpOp::FcvtL => Destination::Long(fp.float_to_int(format, a, true, 64)),
+            FpOp::FcvtLu => Destination::Long(fp.float_to_int(format, a, false, 64)),
+            // The W conversions only look at the lower 32 bits of rs1
+            FpOp::FcvtFromW => {
+                Destination::Float(fp.int_to_float(format, self.registers.get_register(rs1) as i32 as u64, true))
+            }
+            FpOp::FcvtFromWu => Destination::Float(fp.int_to_float(format, self.registers.get_register(rs1) as u64, false)),
+            FpOp::FcvtFromL => Destination::Float(fp.int_to_float(format, self.registers.get_x(rs1), true)),
+            FpOp::FcvtFromLu => Destination::Float(fp.int_to_float(format, self.registers.get_x(rs1), false)),
+            // The raw bits, boxed or not
+            FpOp::FmvXW => Destination::Integer(self.fregisters.get_f64(rs1) as u32),
+            FpOp::Fclass => Destination::Integer(softfloat::classify(format, a)),
+            FpOp::FmvWX => Destination::Float(self.registers.get_register(rs1) as u64),
+            FpOp::FmvXD => Destination::Long(self.fregisters.get_f64(rs1)),
+            FpOp::FmvDX => Destination::Float(self.registers.get_x(rs1)),
+        };
+
+        match result {
+            Destination::Float(value) => self.write_fp(format, rd, value),
+            Destination::Integer(value) => self.registers.set_register(rd, value),
+            Destination::Long(value) => self.registers.set_x(rd, value),
+        }
+        self.accrue_fp_flags(&fp);
+        Ok(())
+    }
+}
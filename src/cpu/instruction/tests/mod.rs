@@ -1,3 +1,5 @@
+#![allow(clippy::module_inception)]
+
 mod test_lui;
 mod test_jal;
 mod test_jalr;
@@ -8,4 +10,12 @@ mod test_alui;
 mod test_alu_base;
 mod test_alu_mul;
 mod test_alu_div;
-mod test_alu_rem;
\ No newline at end of file
+mod test_alu_rem;
+mod test_csr;
+mod test_priv;
+mod test_auipc;
+mod test_fence;
+mod test_amo;
+mod test_float;
+mod test_bitmanip;
+mod test_rv64;
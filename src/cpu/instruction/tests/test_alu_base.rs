@@ -11,14 +11,13 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_ADD, F3::ADD_SUB, REG_S1, REG_S0, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
-        let expected:u32 = 0xCC33CC3d;
+        let expected:u32 = 0xCC33CC3D;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
                 "Stored value was not correct!\
                 \nExpected: 0x{:0>8x},\
@@ -33,11 +32,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_SUB, F3::ADD_SUB, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xCC33CC29;
@@ -55,11 +53,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 8);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::SLL, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x33CC3300;
@@ -77,11 +74,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x419);
         cpu.registers.set_register(REG_S0, 0x420);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::SLT, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 1;
@@ -99,11 +95,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x421);
         cpu.registers.set_register(REG_S0, 0x420);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::SLTU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0;
@@ -121,11 +116,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 0xF00FF00F);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::XOR, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x3C3C3C3C;
@@ -143,11 +137,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 0x8);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_SRL, F3::SRL_SLA, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x00CC33CC;
@@ -165,14 +158,13 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 0x8);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_SRA, F3::SRL_SLA, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
-        let expected:u32 = 0x33CC33CC;
+        let expected:u32 = 0xFFCC33CC;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
                 "Stored value was not correct!\
                 \nExpected: 0x{:0>8x},\
@@ -187,11 +179,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 0x330000CC);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::OR, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFF33CCFF;
@@ -209,11 +200,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(0, F3::AND, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x10;
@@ -11,11 +11,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 1;
@@ -33,11 +32,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x14);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 1;
@@ -55,11 +53,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFF);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFF0;
@@ -77,11 +74,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFF);
         cpu.registers.set_register(REG_S0, 0x1);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFFF;
@@ -99,11 +95,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFC);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFE);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x2;
@@ -121,11 +116,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x840);
         cpu.registers.set_register(REG_S0, 0x1F4);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x4;
@@ -143,11 +137,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x0);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0;
@@ -165,11 +158,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x0);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFFF;
@@ -187,11 +179,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x14);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIVU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x1;
@@ -209,11 +200,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x0);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIVU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -231,11 +221,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x0);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIVU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFFF;
@@ -12,11 +12,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MUL, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x100;
@@ -35,11 +34,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFF);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFE);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MUL, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x2;
@@ -58,11 +56,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFE);
         cpu.registers.set_register(REG_S0, 0x2);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MUL, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = -4i32 as u32;
@@ -81,11 +78,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10000000);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MUL, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -103,11 +99,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULH, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -126,11 +121,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFF);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFE);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULH, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -149,11 +143,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFE);
         cpu.registers.set_register(REG_S0, 0x2);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULH, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFFF;
@@ -172,11 +165,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x1000000);
         cpu.registers.set_register(REG_S0, 0x100);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULH, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x1;
@@ -195,11 +187,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFFFFFF);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFF);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULH, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -217,11 +208,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHSU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -239,11 +229,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xC4653600);
         cpu.registers.set_register(REG_S0, 0x3B9ACA00);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHSU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xF21F494C;
@@ -261,11 +250,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xC4653600);
         cpu.registers.set_register(REG_S0, 0xC4653600);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHSU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xD245ECB3;
@@ -283,11 +271,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x3B9ACA00);
         cpu.registers.set_register(REG_S0, 0xC4653600);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHSU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x2DBA134C;
@@ -305,11 +292,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -327,11 +313,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x1A2B7F0D);
         cpu.registers.set_register(REG_S0, 0x10000000);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x01A2B7F0;
@@ -349,11 +334,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10000000);
         cpu.registers.set_register(REG_S0, 0x1A2B7F0D);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x01A2B7F0;
@@ -371,11 +355,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xEE6B2800);
         cpu.registers.set_register(REG_S0, 0xEE6B2800);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xDE0B6B3A;
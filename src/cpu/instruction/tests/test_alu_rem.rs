@@ -11,11 +11,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -33,11 +32,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x0);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -55,11 +53,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x0);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x10;
@@ -77,11 +74,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFF0000);
         cpu.registers.set_register(REG_S0, 0xA);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFFA;
@@ -99,11 +95,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFD);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x1;
@@ -121,11 +116,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xFFFF0000);
         cpu.registers.set_register(REG_S0, 0xFFFFFFF5);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0xFFFFFFF7;
@@ -143,11 +137,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xF0000000);
         cpu.registers.set_register(REG_S0, 0xFFFFFFFF);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REM, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -165,11 +158,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REMU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -187,11 +179,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x0);
         cpu.registers.set_register(REG_S0, 0x10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REMU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x0;
@@ -209,11 +200,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0x10);
         cpu.registers.set_register(REG_S0, 0x0);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REMU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x10;
@@ -231,11 +221,10 @@ mod test_alu {
         cpu.registers.set_register(REG_S1, 0xF0000000);
         cpu.registers.set_register(REG_S0, 0x15);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALU;
         cpu.instruction = InstructionBuilder.alu(F7_M_EXTENSION, F3::REMU, REG_S0, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_alu();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         let expected:u32 = 0x9;
@@ -8,7 +8,6 @@ mod test_alui {
     fn prep_alui_inst(cpu: &mut CPU, funct3: u8, rs1: u32, rd: u8, imm: u32) {
         cpu.registers.set_register(REG_S1, rs1);
         cpu.pc = 0x10;
-        cpu.opcode = OP::ALUI;
         cpu.instruction = InstructionBuilder.alui(imm, funct3, REG_S1, rd);
     }
 
@@ -16,7 +15,7 @@ mod test_alui {
     fn test_addi() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::ADDI, 0x420, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x840;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -29,7 +28,7 @@ mod test_alui {
     fn test_slti_yes() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTI, 0x419, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 1;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -42,7 +41,7 @@ mod test_alui {
     fn test_slti_no_eq() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTI, 0x420, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -55,7 +54,7 @@ mod test_alui {
     fn test_slti_no_gt() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTI, 0x421, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -68,7 +67,7 @@ mod test_alui {
     fn test_sltiu_yes() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTIU, 0x419, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 1;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -81,7 +80,7 @@ mod test_alui {
     fn test_sltiu_no_eq() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTIU, 0x420, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -94,7 +93,7 @@ mod test_alui {
     fn test_sltiu_no_gt() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLTIU, 0x421, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -107,7 +106,7 @@ mod test_alui {
     fn test_xori() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::XORI, 0x400, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x20;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -120,7 +119,7 @@ mod test_alui {
     fn test_ori() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::ORI, 0x400, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x420;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -133,7 +132,7 @@ mod test_alui {
     fn test_andi() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::ANDI, 0x400, REG_S0, 0x420);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x400;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -146,7 +145,7 @@ mod test_alui {
     fn test_slli() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLLI, 0x400, REG_S0, 0x1);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x800;
         assert_eq!(cpu.registers.get_register(REG_S0), 0x800,
             "\nexpected: 0x{:0>8x},\n\
@@ -159,7 +158,7 @@ mod test_alui {
     fn test_slli_overflow() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SLLI, 0x80_00_00_00, REG_S0, 0x1);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x0;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
            "\nexpected: 0x{:0>8x},\n\
@@ -172,7 +171,7 @@ mod test_alui {
     fn test_srli() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SRLI_SRAI, 0x401, REG_S0, 0x1);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x200;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
             "\nexpected: 0x{:0>8x},\n\
@@ -185,7 +184,7 @@ mod test_alui {
     fn test_srli_underflow() {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SRLI_SRAI, 0x1, REG_S0, 0x1);
-        cpu.inst_alui();
+        cpu.exec_inst().unwrap();
         let expected = 0x0;
         assert_eq!(cpu.registers.get_register(REG_S0), 0x0,
                "\nSRLI should NOT underflow to: 0x{:0>8x},\n\
@@ -199,25 +198,49 @@ mod test_alui {
         let mut cpu = CPU::new();
         prep_alui_inst(&mut cpu, F3::SRLI_SRAI, 0x400, REG_S0, 0x1);
         // Set the SRAI bit (bit 30)
-        cpu.instruction = cpu.instruction | (0x1 << 30);
-        cpu.inst_alui();
+        cpu.instruction |= 0x1 << 30;
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.registers.get_register(REG_S0), 0x200);
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 
     #[test]
-    fn test_srai_overflow() {
+    fn test_srai_negative() {
         let mut cpu = CPU::new();
-        prep_alui_inst(&mut cpu, F3::SRLI_SRAI, 0x0000_03b1, REG_S0, 0x4);
+        prep_alui_inst(&mut cpu, F3::SRLI_SRAI, 0x8000_03B1, REG_S0, 0x4);
         // Set the SRAI bit (bit 30)
-        cpu.instruction = cpu.instruction | (0x1 << 30);
-        cpu.inst_alui();
+        cpu.instruction |= 0x1 << 30;
+        cpu.exec_inst().unwrap();
 
-        let expected = 0x1000_003b;
+        let expected = 0xF800_003B;
         assert_eq!(cpu.registers.get_register(REG_S0), expected,
-            "\nSRAI should overflow to: 0x{:0>8x},\n\
-            but instead returned:    0x{:0>8x}",
+            "\nSRAI should sign extend to: 0x{:0>8x},\n\
+            but instead returned:       0x{:0>8x}",
             expected, cpu.registers.get_register(REG_S0));
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_addi_negative() {
+        let mut cpu = CPU::new();
+        prep_alui_inst(&mut cpu, F3::ADDI, 0x420, REG_S0, 0xFFF);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x41F);
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_andi_sign_extended() {
+        let mut cpu = CPU::new();
+        prep_alui_inst(&mut cpu, F3::ANDI, 0xCC33CC33, REG_S0, 0xF0F);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0xCC33CC03);
+    }
+
+    #[test]
+    fn test_slli_invalid_funct7() {
+        let mut cpu = CPU::new();
+        prep_alui_inst(&mut cpu, F3::SLLI, 0x1, REG_S0, 0x401);
+        assert!(cpu.exec_inst().is_err(), "SLLI with imm[11:5] != 0 should be illegal");
+    }
+}
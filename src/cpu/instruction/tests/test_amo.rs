@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod test_amo {
+    use crate::cpu::CPU;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    const ADDRESS: u32 = 0x100;
+
+    // Runs the AMO `funct5` with `old` in memory and `operand` in rs2, returns what
+    // ended up in rd and in memory
+    fn run_amo(funct5: u8, old: u32, operand: u32) -> (u32, u32) {
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(ADDRESS, old).unwrap();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        cpu.registers.set_register(REG_S2, operand);
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.amo(funct5, false, false, REG_S2, REG_S1, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+        (cpu.registers.get_register(REG_S0), cpu.memory.get_u32(ADDRESS).unwrap())
+    }
+
+    #[test]
+    fn test_amo_arithmetic() {
+        assert_eq!(run_amo(F5::AMOSWAP, 0x1234, 0x5678), (0x1234, 0x5678));
+        assert_eq!(run_amo(F5::AMOADD, 0xFFFF_FFFF, 2), (0xFFFF_FFFF, 1));
+        assert_eq!(run_amo(F5::AMOXOR, 0xFF00, 0x0FF0), (0xFF00, 0xF0F0));
+        assert_eq!(run_amo(F5::AMOAND, 0xFF00, 0x0FF0), (0xFF00, 0x0F00));
+        assert_eq!(run_amo(F5::AMOOR, 0xFF00, 0x0FF0), (0xFF00, 0xFFF0));
+    }
+
+    #[test]
+    fn test_amo_min_max() {
+        assert_eq!(run_amo(F5::AMOMIN, 0xFFFF_FFFF, 1), (0xFFFF_FFFF, 0xFFFF_FFFF));
+        assert_eq!(run_amo(F5::AMOMAX, 0xFFFF_FFFF, 1), (0xFFFF_FFFF, 1));
+        assert_eq!(run_amo(F5::AMOMINU, 0xFFFF_FFFF, 1), (0xFFFF_FFFF, 1));
+        assert_eq!(run_amo(F5::AMOMAXU, 0xFFFF_FFFF, 1), (0xFFFF_FFFF, 0xFFFF_FFFF));
+    }
+
+    #[test]
+    fn test_aq_rl() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        cpu.registers.set_register(REG_S2, 7);
+        cpu.instruction = InstructionBuilder.amo(F5::AMOADD, true, true, REG_S2, REG_S1, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.memory.get_u32(ADDRESS), Ok(7));
+    }
+
+    fn prep_lr(cpu: &mut CPU) {
+        cpu.instruction = InstructionBuilder.amo(F5::LR, false, false, REG_ZERO, REG_S1, REG_S0);
+    }
+
+    fn prep_sc(cpu: &mut CPU) {
+        cpu.instruction = InstructionBuilder.amo(F5::SC, false, false, REG_S2, REG_S1, REG_S0);
+    }
+
+    #[test]
+    fn test_lr_sc() {
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(ADDRESS, 0x42).unwrap();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        cpu.registers.set_register(REG_S2, 0x43);
+        prep_lr(&mut cpu);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x42);
+        prep_sc(&mut cpu);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0, "SC should succeed");
+        assert_eq!(cpu.memory.get_u32(ADDRESS), Ok(0x43));
+
+        // The reservation was used up
+        cpu.registers.set_register(REG_S2, 0x44);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 1, "SC should fail");
+        assert_eq!(cpu.memory.get_u32(ADDRESS), Ok(0x43));
+    }
+
+    #[test]
+    fn test_sc_after_store() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        prep_lr(&mut cpu);
+        cpu.exec_inst().unwrap();
+        // Stores elsewhere leave the reservation alone
+        cpu.store_u32(ADDRESS as u64 + 4, 1).unwrap();
+        cpu.store_u8(ADDRESS as u64 + 3, 1).unwrap();
+        assert_eq!(cpu.reservation, None, "Overlapping stores break the reservation");
+
+        prep_lr(&mut cpu);
+        cpu.exec_inst().unwrap();
+        cpu.store_u32(ADDRESS as u64 - 4, 1).unwrap();
+        assert_eq!(cpu.reservation, Some((ADDRESS, 4)));
+        prep_sc(&mut cpu);
+        cpu.registers.set_register(REG_S1, ADDRESS + 8);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 1, "SC to another address should fail");
+        assert_eq!(cpu.reservation, None);
+    }
+
+    #[test]
+    fn test_misaligned() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, ADDRESS + 2);
+        prep_lr(&mut cpu);
+        assert_eq!(cpu.exec_inst(), Err(Exception::LoadAddressMisaligned(ADDRESS as u64 + 2)));
+        prep_sc(&mut cpu);
+        assert_eq!(cpu.exec_inst(), Err(Exception::StoreAddressMisaligned(ADDRESS as u64 + 2)));
+        cpu.instruction = InstructionBuilder.amo(F5::AMOADD, false, false, REG_S2, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::StoreAddressMisaligned(ADDRESS as u64 + 2)));
+    }
+
+    #[test]
+    fn test_illegal() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        // LR with rs2 set
+        cpu.instruction = InstructionBuilder.amo(F5::LR, false, false, REG_S2, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        cpu.instruction = InstructionBuilder.amo(0x1F, false, false, REG_S2, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        // Doubleword AMOs are RV64 only
+        cpu.instruction = InstructionBuilder.amo(F5::AMOADD, false, false, REG_S2, REG_S1, REG_S0) | 0x1 << 12;
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod test_auipc {
+    use crate::cpu::CPU;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::register::REG_S0;
+
+    #[test]
+    fn test_auipc() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.auipc(0x420, REG_S0);
+
+        // Execute AUIPC
+        cpu.exec_inst().unwrap();
+
+        // Verify results
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x420010,
+                   "\nexpected: 0x{:0>8x},\n\
+        but got:  0x{:0>8x}",
+                   0x420010, cpu.registers.get_register(REG_S0));
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_auipc_negative() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x1010;
+        // 0xFFFFF << 12 is -4096
+        cpu.instruction = InstructionBuilder.auipc(0xFFFFF, REG_S0);
+
+        cpu.exec_inst().unwrap();
+
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x10);
+        assert_eq!(cpu.pc, 0x1014, "PC was not updated correctly!");
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod test_bitmanip {
+    use crate::cpu::CPU;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    // Runs a register-register instruction on rs1 and rs2, returns rd
+    fn alu(funct7: u8, funct3: u8, rs1: u32, rs2: u32) -> u32 {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, rs1);
+        cpu.registers.set_register(REG_S2, rs2);
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.alu(funct7, funct3, REG_S2, REG_S1, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+        cpu.registers.get_register(REG_S0)
+    }
+
+    // Runs an immediate instruction whose upper 7 immediate bits are a funct7
+    fn alui(funct7: u8, funct3: u8, rs1: u32, shamt: u32) -> u32 {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, rs1);
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.alui((funct7 as u32) << 5 | shamt, funct3, REG_S1, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+        cpu.registers.get_register(REG_S0)
+    }
+
+    #[test]
+    fn test_zba() {
+        assert_eq!(alu(F7_SHADD, F3::SH1ADD, 0x10, 0x3), 0x23);
+        assert_eq!(alu(F7_SHADD, F3::SH2ADD, 0x10, 0x3), 0x43);
+        assert_eq!(alu(F7_SHADD, F3::SH3ADD, 0x10, 0x3), 0x83);
+        // The shifted out bits are lost, the sum wraps
+        assert_eq!(alu(F7_SHADD, F3::SH1ADD, 0x8000_0001, 0xFFFF_FFFF), 0x0000_0001);
+    }
+
+    #[test]
+    fn test_logic_with_negate() {
+        assert_eq!(alu(F7_NEGATED, F3::ANDN, 0xFF00_FF00, 0x0F0F_0F0F), 0xF000_F000);
+        assert_eq!(alu(F7_NEGATED, F3::ORN, 0xFF00_FF00, 0x0F0F_0F0F), 0xFFF0_FFF0);
+        assert_eq!(alu(F7_NEGATED, F3::XNOR, 0xFF00_FF00, 0x0F0F_0F0F), 0x0FF0_0FF0);
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::MIN, 0xFFFF_FFFF, 1), 0xFFFF_FFFF);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::MINU, 0xFFFF_FFFF, 1), 1);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::MAX, 0xFFFF_FFFF, 1), 1);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::MAXU, 0xFFFF_FFFF, 1), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn test_rotate() {
+        assert_eq!(alu(F7_ROTATE, F3::ROL, 0x8000_0001, 4), 0x0000_0018);
+        assert_eq!(alu(F7_ROTATE, F3::ROR, 0x8000_0001, 4), 0x1800_0000);
+        // Only the lower 5 bits of rs2 count
+        assert_eq!(alu(F7_ROTATE, F3::ROR, 0x8000_0001, 0x24), 0x1800_0000);
+        assert_eq!(alui(F7_ROTATE, F3::SRLI_SRAI, 0x1234_5678, 8), 0x7812_3456);
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0x0010_0000, SHAMT_CLZ), 11);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0, SHAMT_CLZ), 32);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0x0010_0000, SHAMT_CTZ), 20);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0, SHAMT_CTZ), 32);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0xF0F0_0001, SHAMT_CPOP), 9);
+    }
+
+    #[test]
+    fn test_extend() {
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0x1234_5680, SHAMT_SEXT_B), 0xFFFF_FF80);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0x1234_567F, SHAMT_SEXT_B), 0x0000_007F);
+        assert_eq!(alui(F7_ROTATE, F3::SLLI, 0x1234_8000, SHAMT_SEXT_H), 0xFFFF_8000);
+
+        // ZEXT.H is encoded with rs2 = x0
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, 0x1234_8000);
+        cpu.instruction = InstructionBuilder.alu(F7_ZEXT_H, F3::ZEXT_H, REG_ZERO, REG_S1, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x0000_8000);
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(alui(F7_BSET, F3::SRLI_SRAI, 0x0010_8000, SHAMT_ORC_B), 0x00FF_FF00);
+        assert_eq!(alui(F7_BINV, F3::SRLI_SRAI, 0x1234_5678, SHAMT_REV8), 0x7856_3412);
+    }
+
+    #[test]
+    fn test_carryless_multiply() {
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMUL, 0x3, 0x3), 0x5);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMUL, 0x8000_0001, 0x3), 0x8000_0003);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMULH, 0x8000_0001, 0x3), 0x1);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMULH, 0x8000_0000, 0x8000_0000), 0x4000_0000);
+        // Bits 62:31 of the product
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMULR, 0x8000_0000, 0x8000_0000), 0x8000_0000);
+        assert_eq!(alu(F7_MINMAX_CLMUL, F3::CLMULR, 0x8000_0001, 0x3), 0x3);
+    }
+
+    #[test]
+    fn test_single_bit() {
+        assert_eq!(alu(F7_BSET, F3::BSET, 0x0000_0001, 31), 0x8000_0001);
+        assert_eq!(alu(F7_BCLR, F3::BCLR, 0x8000_0001, 31), 0x0000_0001);
+        assert_eq!(alu(F7_BINV, F3::BINV, 0x8000_0001, 0), 0x8000_0000);
+        assert_eq!(alu(F7_BCLR, F3::BEXT, 0x8000_0001, 31), 1);
+        assert_eq!(alu(F7_BCLR, F3::BEXT, 0x8000_0001, 30), 0);
+        // The index wraps at 32
+        assert_eq!(alu(F7_BSET, F3::BSET, 0, 33), 0x2);
+        assert_eq!(alui(F7_BSET, F3::SLLI, 0x0000_0001, 4), 0x0000_0011);
+        assert_eq!(alui(F7_BCLR, F3::SLLI, 0x0000_0011, 4), 0x0000_0001);
+        assert_eq!(alui(F7_BINV, F3::SLLI, 0x0000_0011, 0), 0x0000_0010);
+        assert_eq!(alui(F7_BCLR, F3::SRLI_SRAI, 0x0000_0010, 4), 1);
+    }
+
+    #[test]
+    fn test_illegal() {
+        let mut cpu = CPU::new();
+        // ZEXT.H with a non-zero rs2 is a reserved encoding
+        cpu.instruction = InstructionBuilder.alu(F7_ZEXT_H, F3::ZEXT_H, REG_S2, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        // Shift amounts of the unary group that don't name an instruction
+        cpu.instruction = InstructionBuilder.alui((F7_ROTATE as u32) << 5 | 3, F3::SLLI, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        cpu.instruction = InstructionBuilder.alui((F7_BSET as u32) << 5 | 8, F3::SRLI_SRAI, REG_S1, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+}
@@ -10,7 +10,6 @@ mod test_branch {
         cpu.registers.set_register(REG_S1, rs1);
         cpu.registers.set_register(REG_S2, rs2);
         cpu.pc = 0x10;
-        cpu.opcode = OP::BRANCH;
         cpu.instruction = InstructionBuilder.branch(offset, funct3, REG_S2, REG_S1);
     }
 
@@ -18,16 +17,16 @@ mod test_branch {
     fn test_beq_yes() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BEQ, 0x420, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
-        assert_eq!(cpu.registers.get_register(REG_RA), 0x14);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0, "Branches should not link!");
     }
 
     #[test]
     fn test_beq_no() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BEQ, 0x420, 0x421);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 
@@ -35,16 +34,16 @@ mod test_branch {
     fn test_bne_yes() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BNE, 0x420, 0x421);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
-        assert_eq!(cpu.registers.get_register(REG_RA), 0x14);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0, "Branches should not link!");
     }
 
     #[test]
     fn test_bne_no() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BNE, 0x420, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 
@@ -52,16 +51,16 @@ mod test_branch {
     fn test_blt_yes() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BLT, 0x41F, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
-        assert_eq!(cpu.registers.get_register(REG_RA), 0x14);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0, "Branches should not link!");
     }
 
     #[test]
     fn test_blt_no() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BLT, 0x420, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 
@@ -69,25 +68,82 @@ mod test_branch {
     fn test_bge_yes_gt() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BGE, 0x422, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
-        assert_eq!(cpu.registers.get_register(REG_RA), 0x14);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0, "Branches should not link!");
     }
 
     #[test]
     fn test_bge_yes_eq() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BGE, 0x420, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
-        assert_eq!(cpu.registers.get_register(REG_RA), 0x14);
+        assert_eq!(cpu.registers.get_register(REG_RA), 0, "Branches should not link!");
     }
 
     #[test]
     fn test_bge_no() {
         let mut cpu = CPU::new();
         prep_branch_inst(&mut cpu, F3::BGE, 0x419, 0x420);
-        cpu.inst_branch();
+        cpu.exec_inst().unwrap();
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_blt_signed() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BLT, -1i32 as u32, 0x420);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_bge_signed() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BGE, -1i32 as u32, 0x420);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_bltu_yes() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BLTU, 0x420, -1i32 as u32);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_bltu_no() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BLTU, -1i32 as u32, 0x420);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_bgeu_yes() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BGEU, -1i32 as u32, 0x420);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x118, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_bgeu_no() {
+        let mut cpu = CPU::new();
+        prep_branch_inst(&mut cpu, F3::BGEU, 0x420, -1i32 as u32);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_branch_backwards() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x100;
+        cpu.instruction = InstructionBuilder.branch(-0x20i32 as u32, F3::BEQ, REG_ZERO, REG_ZERO);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0xE0, "PC was not updated correctly!");
+    }
+}
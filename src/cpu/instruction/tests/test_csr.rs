@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod test_csr {
+    use crate::cpu::CPU;
+    use crate::cpu::csr::*;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    fn prep_csr_inst(cpu: &mut CPU, csr: u16, funct3: u8, rs1: u8) {
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.csr(csr, funct3, rs1, REG_S0);
+    }
+
+    #[test]
+    fn test_csrrw() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSCRATCH, 0x1234).unwrap();
+        cpu.registers.set_register(REG_S1, 0xCAFE);
+        prep_csr_inst(&mut cpu, MSCRATCH, F3::CSRRW, REG_S1);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x1234);
+        assert_eq!(cpu.csr.read(MSCRATCH), Some(0xCAFE));
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_misa_c() {
+        let mut cpu = CPU::new();
+        // The next instruction at 0x12 wouldn't be reachable with C off
+        prep_csr_inst(&mut cpu, MISA, F3::CSRRCI, 0x4);
+        cpu.pc = 0x0E;
+        cpu.exec_inst().unwrap();
+        assert!(cpu.csr.has_compressed(), "Turning C off should have been suppressed");
+        assert_eq!(cpu.pc, 0x12);
+
+        cpu.pc = 0x10;
+        cpu.exec_inst().unwrap();
+        assert!(!cpu.csr.has_compressed());
+    }
+
+    #[test]
+    fn test_csrrs() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSCRATCH, 0xF0).unwrap();
+        cpu.registers.set_register(REG_S1, 0x0F);
+        prep_csr_inst(&mut cpu, MSCRATCH, F3::CSRRS, REG_S1);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0xF0);
+        assert_eq!(cpu.csr.read(MSCRATCH), Some(0xFF));
+    }
+
+    #[test]
+    fn test_csrrc() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSCRATCH, 0xFF).unwrap();
+        cpu.registers.set_register(REG_S1, 0x0F);
+        prep_csr_inst(&mut cpu, MSCRATCH, F3::CSRRC, REG_S1);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0xFF);
+        assert_eq!(cpu.csr.read(MSCRATCH), Some(0xF0));
+    }
+
+    #[test]
+    fn test_csrrwi() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSCRATCH, 0x1234).unwrap();
+        prep_csr_inst(&mut cpu, MSCRATCH, F3::CSRRWI, 0x1F);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x1234);
+        assert_eq!(cpu.csr.read(MSCRATCH), Some(0x1F));
+    }
+
+    #[test]
+    fn test_csrrsi() {
+        let mut cpu = CPU::new();
+        prep_csr_inst(&mut cpu, MSTATUS, F3::CSRRSI, 0x8);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), FS_INITIAL);
+        assert_eq!(cpu.csr.read(MSTATUS), Some(FS_INITIAL | MSTATUS_MIE));
+    }
+
+    #[test]
+    fn test_csrrci() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSCRATCH, 0xFF).unwrap();
+        prep_csr_inst(&mut cpu, MSCRATCH, F3::CSRRCI, 0x3);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0xFF);
+        assert_eq!(cpu.csr.read(MSCRATCH), Some(0xFC));
+    }
+
+    #[test]
+    fn test_read_only_csr() {
+        let mut cpu = CPU::new();
+        // csrr (csrrs with rs1 = x0) doesn't write, so it's fine on read only CSRs
+        prep_csr_inst(&mut cpu, MHARTID, F3::CSRRS, REG_ZERO);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0);
+
+        prep_csr_inst(&mut cpu, MHARTID, F3::CSRRW, REG_S1);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        assert_eq!(cpu.pc, 0x10, "PC should not move on an illegal instruction!");
+    }
+
+    #[test]
+    fn test_unknown_csr() {
+        let mut cpu = CPU::new();
+        prep_csr_inst(&mut cpu, 0x7C0, F3::CSRRS, REG_ZERO);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+
+    #[test]
+    fn test_exec_csr() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, 0x80);
+        prep_csr_inst(&mut cpu, MTVEC, F3::CSRRW, REG_S1);
+        assert_eq!(cpu.exec_inst(), Ok(()));
+        assert_eq!(cpu.csr.read(MTVEC), Some(0x80));
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod test_fence {
+    use crate::cpu::{CPU, StopReason};
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    #[test]
+    fn test_fence() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.fence(F3::FENCE) | (0xFF << 20); // fence iorw, iorw
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_fence_i() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.fence(F3::FENCE_I);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_fence_invalid() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.fence(0x2);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+
+    // Code that patches the instruction after it, then runs FENCE.I and executes the new instruction
+    #[test]
+    fn test_self_modifying_code() {
+        let mut cpu = CPU::new();
+        let patched = InstructionBuilder.alui(0x2A, F3::ADDI, REG_ZERO, REG_S0);
+        let program = [
+            InstructionBuilder.lui(patched >> 12, REG_T0),
+            InstructionBuilder.alui(patched & 0xFFF, F3::ADDI, REG_T0, REG_T0),
+            InstructionBuilder.store(0x20, F3::SW, REG_T0, REG_ZERO),
+            InstructionBuilder.fence(F3::FENCE_I),
+            InstructionBuilder.alui(1, F3::ADDI, REG_ZERO, REG_S0), // Overwritten before it runs
+        ];
+        for (i, inst) in program.iter().enumerate() {
+            cpu.memory.set_u32(0x10 + 4 * i as u32, *inst).unwrap();
+        }
+        assert_eq!(cpu.run(0x10, Some(program.len() as u64)), StopReason::InstructionLimit);
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x2A);
+    }
+}
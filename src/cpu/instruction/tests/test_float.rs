@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod test_float {
+    use crate::cpu::CPU;
+    use crate::cpu::csr::*;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::softfloat::*;
+    use crate::cpu::trap::Exception;
+
+    const ADDRESS: u32 = 0x100;
+    const RNE: u8 = 0;
+    const RTZ: u8 = 1;
+    const F0: u8 = 0;
+    const F1: u8 = 1;
+    const F2: u8 = 2;
+    const F3_: u8 = 3;
+
+    fn single(value: f32) -> u32 {
+        value.to_bits()
+    }
+
+    fn double(value: f64) -> u64 {
+        value.to_bits()
+    }
+
+    // Runs `instruction` at 0x10, its opcode decoded as fetch would
+    fn exec(cpu: &mut CPU, instruction: u32) -> Result<(), Exception> {
+        cpu.pc = 0x10;
+        cpu.instruction = instruction;
+        cpu.exec_inst()
+    }
+
+    fn op_s(cpu: &mut CPU, funct5: u8, funct3: u8, a: f32, b: f32) -> Result<(), Exception> {
+        cpu.fregisters.set_f32(F1, single(a));
+        cpu.fregisters.set_f32(F2, single(b));
+        exec(cpu, InstructionBuilder.op_fp(funct5, FMT_S, funct3, F2, F1, F0))
+    }
+
+    #[test]
+    fn test_flw_fsw() {
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(ADDRESS, single(1.5)).unwrap();
+        cpu.registers.set_register(REG_S1, ADDRESS - 4);
+        exec(&mut cpu, InstructionBuilder.load_fp(4, F3::FLW, REG_S1, F1)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F1), 0xFFFF_FFFF_0000_0000 | single(1.5) as u64, "FLW should NaN-box");
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+
+        // Only the low word is stored, boxed or not
+        cpu.fregisters.set_f64(F2, 0x1234_5678_9ABC_DEF0);
+        exec(&mut cpu, InstructionBuilder.store_fp(8, F3::FSW, F2, REG_S1)).unwrap();
+        assert_eq!(cpu.memory.get_u32(ADDRESS + 4), Ok(0x9ABC_DEF0));
+    }
+
+    #[test]
+    fn test_fld_fsd() {
+        let mut cpu = CPU::new();
+        cpu.registers.set_register(REG_S1, ADDRESS);
+        cpu.fregisters.set_f64(F2, double(-2.25));
+        exec(&mut cpu, InstructionBuilder.store_fp(0, F3::FSD, F2, REG_S1)).unwrap();
+        assert_eq!(cpu.memory.get_u32(ADDRESS), Ok(double(-2.25) as u32));
+        assert_eq!(cpu.memory.get_u32(ADDRESS + 4), Ok((double(-2.25) >> 32) as u32));
+        exec(&mut cpu, InstructionBuilder.load_fp(0, F3::FLD, REG_S1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), double(-2.25));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut cpu = CPU::new();
+        op_s(&mut cpu, F5::FADD, RNE, 1.5, 2.25).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(3.75));
+        op_s(&mut cpu, F5::FSUB, RNE, 1.5, 2.25).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(-0.75));
+        op_s(&mut cpu, F5::FMUL, RNE, 1.5, 2.25).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(3.375));
+        op_s(&mut cpu, F5::FDIV, RNE, 3.0, 2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.5));
+        assert_eq!(cpu.csr.read(FFLAGS), Some(0));
+
+        cpu.fregisters.set_f64(F1, double(2.0));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FSQRT, FMT_D, RNE, 0, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), double(2f64.sqrt()));
+        assert_eq!(cpu.csr.read(FFLAGS), Some(FLAG_NX as u64));
+    }
+
+    #[test]
+    fn test_flags_and_fs() {
+        let mut cpu = CPU::new();
+        assert_eq!(cpu.csr.read(MSTATUS).unwrap() & MSTATUS_FS, FS_INITIAL);
+        op_s(&mut cpu, F5::FDIV, RNE, 1.0, 0.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(f32::INFINITY));
+        assert_eq!(cpu.csr.read(MSTATUS).unwrap() & (MSTATUS_FS | MSTATUS_SD), FS_DIRTY | MSTATUS_SD);
+        op_s(&mut cpu, F5::FSUB, RNE, f32::INFINITY, f32::INFINITY).unwrap();
+        assert_eq!(cpu.csr.read(FFLAGS), Some((FLAG_DZ | FLAG_NV) as u64), "Flags accrue");
+        assert_eq!(cpu.csr.read(FCSR), Some((FLAG_DZ | FLAG_NV) as u64));
+    }
+
+    #[test]
+    fn test_rounding_mode() {
+        let mut cpu = CPU::new();
+        // 1 + 3/4 of an ulp rounds up to nearest, and down toward zero
+        cpu.fregisters.set_f64(F1, double(1.0 + f64::from(f32::EPSILON) * 0.75));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FP, FMT_S, RNE, 1, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.0) + 1);
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FP, FMT_S, RTZ, 1, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.0));
+
+        // DYN takes it from frm
+        cpu.csr.write(FRM, RoundingMode::Up as u64).unwrap();
+        cpu.fregisters.set_f64(F1, double(1.0 + f64::from(f32::EPSILON) * 0.25));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FP, FMT_S, F3::RM_DYN, 1, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.0) + 1);
+
+        let instruction = InstructionBuilder.op_fp(F5::FADD, FMT_S, 5, F2, F1, F0);
+        assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)), "rm 5 is reserved");
+        cpu.csr.write(FRM, 5).unwrap();
+        let instruction = InstructionBuilder.op_fp(F5::FADD, FMT_S, F3::RM_DYN, F2, F1, F0);
+        assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)), "DYN with a reserved frm");
+        // Instructions that don't round don't care
+        op_s(&mut cpu, F5::FSGNJ, F3::FSGNJ, 1.0, 2.0).unwrap();
+    }
+
+    #[test]
+    fn test_nan_boxing() {
+        let mut cpu = CPU::new();
+        // A double isn't a valid single, it reads as the canonical NaN
+        cpu.fregisters.set_f64(F1, double(1.0));
+        cpu.fregisters.set_f32(F2, single(1.0));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FADD, FMT_S, RNE, F2, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), 0xFFFF_FFFF_7FC0_0000);
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FMV_X_CLASS, FMT_S, F3::FCLASS, 0, F1, REG_S0)).unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 1 << 9);
+        // FMV.X.W moves the low bits as they are
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FMV_X_CLASS, FMT_S, F3::FMV_X, 0, F1, REG_S0)).unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0);
+    }
+
+    #[test]
+    fn test_fused() {
+        let mut cpu = CPU::new();
+        cpu.fregisters.set_f64(F1, double(2.0));
+        cpu.fregisters.set_f64(F2, double(3.0));
+        cpu.fregisters.set_f64(F3_, double(1.0));
+        let cases = [(OP::MADD, 7.0), (OP::MSUB, 5.0), (OP::NMSUB, -5.0), (OP::NMADD, -7.0)];
+        for (opcode, expected) in cases {
+            exec(&mut cpu, InstructionBuilder.fmadd(opcode, FMT_D, F3_, F2, F1, F0)).unwrap();
+            assert_eq!(cpu.fregisters.get_f64(F0), double(expected), "opcode 0x{:02x}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_sign_injection() {
+        let mut cpu = CPU::new();
+        op_s(&mut cpu, F5::FSGNJ, F3::FSGNJ, 1.0, -2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(-1.0));
+        op_s(&mut cpu, F5::FSGNJ, F3::FSGNJN, 1.0, -2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.0));
+        op_s(&mut cpu, F5::FSGNJ, F3::FSGNJX, -1.0, -2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(1.0));
+        op_s(&mut cpu, F5::FMIN_MAX, F3::FMAX, -1.0, -2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(-1.0));
+        op_s(&mut cpu, F5::FMIN_MAX, F3::FMIN, f32::NAN, -2.0).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(-2.0));
+        assert_eq!(cpu.csr.read(FFLAGS), Some(0));
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut cpu = CPU::new();
+        cpu.fregisters.set_f32(F1, single(1.0));
+        cpu.fregisters.set_f32(F2, single(2.0));
+        let compare = |cpu: &mut CPU, funct3| {
+            exec(cpu, InstructionBuilder.op_fp(F5::FCMP, FMT_S, funct3, F2, F1, REG_S0)).unwrap();
+            cpu.registers.get_register(REG_S0)
+        };
+        assert_eq!(compare(&mut cpu, F3::FLT), 1);
+        assert_eq!(compare(&mut cpu, F3::FLE), 1);
+        assert_eq!(compare(&mut cpu, F3::FEQ), 0);
+        cpu.fregisters.set_f32(F2, single(f32::NAN));
+        assert_eq!(compare(&mut cpu, F3::FEQ), 0);
+        assert_eq!(cpu.csr.read(FFLAGS), Some(0), "FEQ is quiet");
+        assert_eq!(compare(&mut cpu, F3::FLT), 0);
+        assert_eq!(cpu.csr.read(FFLAGS), Some(FLAG_NV as u64));
+    }
+
+    #[test]
+    fn test_convert() {
+        let mut cpu = CPU::new();
+        cpu.fregisters.set_f64(F1, double(-3.75));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_W, FMT_D, RTZ, 0, F1, REG_S0)).unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), -3i32 as u32);
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_W, FMT_D, RTZ, 1, F1, REG_S0)).unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0);
+        assert_eq!(cpu.csr.read(FFLAGS), Some((FLAG_NX | FLAG_NV) as u64));
+
+        cpu.registers.set_register(REG_S1, -7i32 as u32);
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FROM_W, FMT_S, RNE, 0, REG_S1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f32(F0), single(-7.0));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FROM_W, FMT_D, RNE, 1, REG_S1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), double(4294967289.0));
+
+        cpu.fregisters.set_f32(F1, single(0.1));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FCVT_FP, FMT_D, RNE, 0, F1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), double(0.1f32 as f64));
+
+        cpu.registers.set_register(REG_S1, single(-0.5));
+        exec(&mut cpu, InstructionBuilder.op_fp(F5::FMV_W_X, FMT_S, 0, 0, REG_S1, F0)).unwrap();
+        assert_eq!(cpu.fregisters.get_f64(F0), 0xFFFF_FFFF_0000_0000 | single(-0.5) as u64);
+    }
+
+    #[test]
+    fn test_fs_off() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSTATUS, 0).unwrap();
+        let instruction = InstructionBuilder.op_fp(F5::FADD, FMT_S, RNE, F2, F1, F0);
+        assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)));
+        let instruction = InstructionBuilder.load_fp(0, F3::FLW, REG_ZERO, F0);
+        assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)));
+        let instruction = InstructionBuilder.csr(FCSR, F3::CSRRS, REG_ZERO, REG_S0);
+        assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)));
+    }
+
+    #[test]
+    fn test_compressed_fs_off() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSTATUS, 0).unwrap();
+        cpu.csr.write(MTVEC, 0x200).unwrap();
+        // c.fld fa0, 0(a1)
+        cpu.memory.set_u16(0x10, 0x2188).unwrap();
+        cpu.pc = 0x10;
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc, 0x200);
+        assert_eq!(cpu.csr.read(MTVAL), Some(0x2188), "mtval gets the 16-bit parcel, not its expansion");
+    }
+
+    #[test]
+    fn test_illegal() {
+        let mut cpu = CPU::new();
+        let illegal = [
+            // The H and Q formats
+            InstructionBuilder.op_fp(F5::FADD, 0x2, RNE, F2, F1, F0),
+            InstructionBuilder.fmadd(OP::MADD, 0x3, F3_, F2, F1, F0),
+            // FMV.X.D and FCVT.L.S are RV64 only
+            InstructionBuilder.op_fp(F5::FMV_X_CLASS, FMT_D, F3::FMV_X, 0, F1, REG_S0),
+            InstructionBuilder.op_fp(F5::FCVT_W, FMT_S, RNE, 2, F1, REG_S0),
+            InstructionBuilder.op_fp(F5::FSQRT, FMT_S, RNE, 1, F1, F0),
+            // FCVT.S.S
+            InstructionBuilder.op_fp(F5::FCVT_FP, FMT_S, RNE, 0, F1, F0),
+            InstructionBuilder.op_fp(F5::FSGNJ, FMT_S, 3, F2, F1, F0),
+            // FLH is Zfh
+            InstructionBuilder.load_fp(0, 0x1, REG_ZERO, F0),
+        ];
+        for instruction in illegal {
+            assert_eq!(exec(&mut cpu, instruction), Err(Exception::IllegalInstruction(instruction)), "0x{:08x}", instruction);
+        }
+    }
+}
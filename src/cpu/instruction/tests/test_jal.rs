@@ -2,7 +2,6 @@
 mod test_jal {
     use crate::cpu::CPU;
     use crate::cpu::instruction::builder::InstructionBuilder;
-    use crate::cpu::opcodes::OP;
     use crate::cpu::register::REG_S0;
 
     #[test]
@@ -12,13 +11,39 @@ mod test_jal {
         // Set PC and prepare instruction (rd = REG_S0, imm = 8)
         cpu.pc = 0x10;
         cpu.instruction = InstructionBuilder.jal(8, REG_S0);
-        cpu.opcode = OP::JAL;
 
         // Execute JAL
-        cpu.inst_jal();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         assert_eq!(cpu.registers.get_register(REG_S0), 0x14); // Return address
-        assert_eq!(cpu.get_pc(), 0x8);             // New PC (0x10 + 8)
+        assert_eq!(cpu.get_pc(), 0x18);            // New PC (0x10 + 8)
+    }
+
+    #[test]
+    fn test_jal_backwards() {
+        let mut cpu = CPU::new();
+
+        // imm = -0x10, the immediate is sign extended
+        cpu.pc = 0x1000;
+        cpu.instruction = InstructionBuilder.jal(-0x10i32 as u32, REG_S0);
+
+        cpu.exec_inst().unwrap();
+
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x1004);
+        assert_eq!(cpu.get_pc(), 0xFF0);
+    }
+
+    #[test]
+    fn test_jal_far() {
+        let mut cpu = CPU::new();
+
+        // Uses every immediate bit but the sign and bit 1
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.jal(0xFFFFC, REG_S0);
+
+        cpu.exec_inst().unwrap();
+
+        assert_eq!(cpu.get_pc(), 0x10000C);
     }
 }
\ No newline at end of file
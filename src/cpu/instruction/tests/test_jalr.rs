@@ -1,8 +1,10 @@
 #[cfg(test)]
 mod test_jalr {
     use crate::cpu::CPU;
+    use crate::cpu::csr::MISA;
     use crate::cpu::instruction::builder::InstructionBuilder;
     use crate::cpu::register::{REG_S0, REG_S1};
+    use crate::cpu::trap::Exception;
 
     #[test]
     fn test_jalr() {
@@ -14,10 +16,52 @@ mod test_jalr {
         cpu.instruction = InstructionBuilder.jalr(8,REG_S1, REG_S0);
 
         // Execute JALR
-        cpu.inst_jalr();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         assert_eq!(cpu.registers.get_register(REG_S0), 0x14); // Return address
-        assert_eq!(cpu.get_pc(), 0x28);             // New PC (0x10 + 8)
+        assert_eq!(cpu.get_pc(), 0x18);             // New PC (s1 + 8)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_jalr_misaligned() {
+        let mut cpu = CPU::new();
+        // Without C, targets have to be word aligned
+        cpu.csr.write(MISA, 0).unwrap();
+        cpu.pc = 0x10;
+        cpu.registers.set_register(REG_S1, 0x12);
+        cpu.instruction = InstructionBuilder.jalr(8, REG_S1, REG_S0);
+
+        // Target isn't word aligned, nothing should be written back
+        assert_eq!(cpu.exec_inst(), Err(Exception::InstructionAddressMisaligned(0x1A)));
+        assert_eq!(cpu.registers.get_register(REG_S0), 0);
+        assert_eq!(cpu.get_pc(), 0x10);
+    }
+
+    #[test]
+    fn test_jalr_halfword_aligned() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.registers.set_register(REG_S1, 0x12);
+        cpu.instruction = InstructionBuilder.jalr(8, REG_S1, REG_S0);
+
+        // With C, halfword aligned targets are fine
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x14);
+        assert_eq!(cpu.get_pc(), 0x1A);
+    }
+
+    #[test]
+    fn test_jalr_negative_offset() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        // The lowest bit of the target is always cleared
+        cpu.registers.set_register(REG_S1, 0x105);
+        cpu.instruction = InstructionBuilder.jalr(-4i32 as u32, REG_S1, REG_S0);
+
+        cpu.exec_inst().unwrap();
+
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x14);
+        assert_eq!(cpu.get_pc(), 0x100);
+    }
+}
@@ -2,7 +2,6 @@
 mod test_lui {
     use crate::cpu::CPU;
     use crate::cpu::instruction::builder::InstructionBuilder;
-    use crate::cpu::opcodes::OP;
     use crate::cpu::register::REG_S0;
 
     #[test]
@@ -10,10 +9,9 @@ mod test_lui {
         let mut cpu = CPU::new();
         cpu.pc = 0x10;
         cpu.instruction = InstructionBuilder.lui(0x420, REG_S0);
-        cpu.opcode = OP::LUI;
 
         // Execute LUI
-        cpu.inst_lui();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         assert_eq!(cpu.registers.get_register(REG_S0), 0x420000,
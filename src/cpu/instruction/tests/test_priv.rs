@@ -0,0 +1,230 @@
+#[cfg(test)]
+mod test_priv {
+    use crate::cpu::{CPU, StopReason};
+    use crate::cpu::csr::*;
+    use crate::cpu::pmp::{PMPADDR0, PMPCFG0};
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    fn prep_priv_inst(cpu: &mut CPU, funct12: u16) {
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.csr(funct12, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO);
+    }
+
+    #[test]
+    fn test_ecall() {
+        let mut cpu = CPU::new();
+        prep_priv_inst(&mut cpu, F12::ECALL);
+        assert_eq!(cpu.exec_inst(), Err(Exception::EnvironmentCallFromMMode));
+        assert_eq!(cpu.pc, 0x10, "PC should not move on ECALL!");
+    }
+
+    #[test]
+    fn test_ebreak() {
+        let mut cpu = CPU::new();
+        prep_priv_inst(&mut cpu, F12::EBREAK);
+        assert_eq!(cpu.exec_inst(), Err(Exception::Breakpoint(0x10)));
+    }
+
+    #[test]
+    fn test_wfi() {
+        let mut cpu = CPU::new();
+        prep_priv_inst(&mut cpu, F12::WFI);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+    }
+
+    #[test]
+    fn test_mret() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MEPC, 0x200).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MPIE | MSTATUS_MPP).unwrap();
+        prep_priv_inst(&mut cpu, F12::MRET);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x200, "PC was not restored from mepc!");
+        assert_eq!(cpu.csr.privilege(), Privilege::Machine);
+        assert_eq!(cpu.csr.read(MSTATUS), Some(MSTATUS_MIE | MSTATUS_MPIE));
+    }
+
+    // Drops to `privilege` through mret, landing at 0x10
+    fn enter_mode(cpu: &mut CPU, privilege: Privilege) {
+        cpu.csr.write(MEPC, 0x10).unwrap();
+        cpu.csr.write(MSTATUS, (privilege as u64) << 11).unwrap();
+        prep_priv_inst(cpu, F12::MRET);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.csr.privilege(), privilege);
+    }
+
+    #[test]
+    fn test_ecall_cause() {
+        let mut cpu = CPU::new();
+        enter_mode(&mut cpu, Privilege::Supervisor);
+        prep_priv_inst(&mut cpu, F12::ECALL);
+        assert_eq!(cpu.exec_inst(), Err(Exception::EnvironmentCallFromSMode));
+
+        let mut cpu = CPU::new();
+        enter_mode(&mut cpu, Privilege::User);
+        prep_priv_inst(&mut cpu, F12::ECALL);
+        assert_eq!(cpu.exec_inst(), Err(Exception::EnvironmentCallFromUMode));
+    }
+
+    #[test]
+    fn test_sret() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(SEPC, 0x300).unwrap();
+        enter_mode(&mut cpu, Privilege::Supervisor);
+        cpu.csr.write(SSTATUS, MSTATUS_SPIE).unwrap();
+        prep_priv_inst(&mut cpu, F12::SRET);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x300, "PC was not restored from sepc!");
+        assert_eq!(cpu.csr.privilege(), Privilege::User);
+        assert_eq!(cpu.csr.read(SSTATUS), Some(MSTATUS_SIE | MSTATUS_SPIE));
+
+        // Not from U-mode
+        prep_priv_inst(&mut cpu, F12::SRET);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+
+    #[test]
+    fn test_trapped_in_supervisor() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MSTATUS, MSTATUS_TSR | MSTATUS_TW | 1 << 11).unwrap();
+        cpu.csr.write(MEPC, 0x10).unwrap();
+        prep_priv_inst(&mut cpu, F12::MRET);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.csr.privilege(), Privilege::Supervisor);
+
+        for funct12 in [F12::MRET, F12::SRET, F12::WFI] {
+            prep_priv_inst(&mut cpu, funct12);
+            assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)), "funct12 0x{:03x}", funct12);
+        }
+    }
+
+    #[test]
+    fn test_user_restrictions() {
+        let mut cpu = CPU::new();
+        enter_mode(&mut cpu, Privilege::User);
+        for funct12 in [F12::MRET, F12::SRET, F12::WFI] {
+            prep_priv_inst(&mut cpu, funct12);
+            assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)), "funct12 0x{:03x}", funct12);
+        }
+        cpu.instruction = InstructionBuilder.csr(SSCRATCH, F3::CSRRS, REG_ZERO, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+        cpu.instruction = InstructionBuilder.csr(MSTATUS, F3::CSRRS, REG_ZERO, REG_S0);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+
+    #[test]
+    fn test_nonzero_fields() {
+        let mut cpu = CPU::new();
+        prep_priv_inst(&mut cpu, F12::ECALL);
+        cpu.instruction |= (REG_S0 as u32) << 7;
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)));
+    }
+
+    #[test]
+    fn test_take_exception() {
+        let mut cpu = CPU::new();
+        cpu.csr.write(MTVEC, 0x100).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MIE).unwrap();
+        cpu.pc = 0x10;
+        cpu.take_exception(Exception::IllegalInstruction(0xFFFF_FFFF));
+        assert_eq!(cpu.pc, 0x100);
+        assert_eq!(cpu.csr.read(MEPC), Some(0x10));
+        assert_eq!(cpu.csr.read(MCAUSE), Some(2));
+        assert_eq!(cpu.csr.read(MTVAL), Some(0xFFFF_FFFF));
+        assert_eq!(cpu.csr.read(MSTATUS), Some(MSTATUS_MPIE | MSTATUS_MPP));
+    }
+
+    // ecall into a handler that bumps mepc past the ecall and returns with mret
+    #[test]
+    fn test_ecall_round_trip() {
+        let mut cpu = CPU::new();
+        let program = [
+            InstructionBuilder.alui(0x100, F3::ADDI, REG_ZERO, REG_T0),
+            InstructionBuilder.csr(MTVEC, F3::CSRRW, REG_T0, REG_ZERO),
+            InstructionBuilder.csr(F12::ECALL, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO),
+            InstructionBuilder.alui(1, F3::ADDI, REG_ZERO, REG_S1),
+        ];
+        for (i, inst) in program.iter().enumerate() {
+            cpu.memory.set_u32(0x10 + 4 * i as u32, *inst).unwrap();
+        }
+        let handler = [
+            InstructionBuilder.alui(0x2A, F3::ADDI, REG_ZERO, REG_S0),
+            InstructionBuilder.csr(MEPC, F3::CSRRS, REG_ZERO, REG_T1),
+            InstructionBuilder.alui(4, F3::ADDI, REG_T1, REG_T1),
+            InstructionBuilder.csr(MEPC, F3::CSRRW, REG_T1, REG_ZERO),
+            InstructionBuilder.csr(F12::MRET, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO),
+        ];
+        for (i, inst) in handler.iter().enumerate() {
+            cpu.memory.set_u32(0x100 + 4 * i as u32, *inst).unwrap();
+        }
+
+        // Four instructions in the program, five in the handler
+        assert_eq!(cpu.run(0x10, Some(9)), StopReason::InstructionLimit);
+        assert_eq!(cpu.pc, 0x20);
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x2A, "Handler did not run!");
+        assert_eq!(cpu.registers.get_register(REG_S1), 1, "Execution did not resume after ecall!");
+        assert_eq!(cpu.csr.read(MCAUSE), Some(11));
+    }
+
+    // M-mode drops to U-mode, whose ecall is delegated to an S-mode handler that sret's back
+    #[test]
+    fn test_delegated_ecall_round_trip() {
+        let mut cpu = CPU::new();
+        // U-mode can't touch memory without a PMP entry
+        cpu.csr.write(PMPADDR0, 0xFFFF_FFFF).unwrap();
+        cpu.csr.write(PMPCFG0, 0x1F).unwrap();
+        let program = [
+            InstructionBuilder.alui(0x100, F3::ADDI, REG_ZERO, REG_T0),
+            InstructionBuilder.csr(STVEC, F3::CSRRW, REG_T0, REG_ZERO),
+            InstructionBuilder.alui(1 << 8, F3::ADDI, REG_ZERO, REG_T0),
+            InstructionBuilder.csr(MEDELEG, F3::CSRRW, REG_T0, REG_ZERO),
+            InstructionBuilder.alui(0x30, F3::ADDI, REG_ZERO, REG_T0),
+            InstructionBuilder.csr(MEPC, F3::CSRRW, REG_T0, REG_ZERO),
+            InstructionBuilder.csr(F12::MRET, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO),
+            0,
+            // U-mode, at 0x30
+            InstructionBuilder.csr(F12::ECALL, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO),
+            InstructionBuilder.alui(1, F3::ADDI, REG_ZERO, REG_S1),
+        ];
+        for (i, inst) in program.iter().enumerate() {
+            cpu.memory.set_u32(0x10 + 4 * i as u32, *inst).unwrap();
+        }
+        let handler = [
+            InstructionBuilder.alui(0x2A, F3::ADDI, REG_ZERO, REG_S0),
+            InstructionBuilder.csr(SEPC, F3::CSRRS, REG_ZERO, REG_T1),
+            InstructionBuilder.alui(4, F3::ADDI, REG_T1, REG_T1),
+            InstructionBuilder.csr(SEPC, F3::CSRRW, REG_T1, REG_ZERO),
+            InstructionBuilder.csr(F12::SRET, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO),
+        ];
+        for (i, inst) in handler.iter().enumerate() {
+            cpu.memory.set_u32(0x100 + 4 * i as u32, *inst).unwrap();
+        }
+
+        // Seven instructions to get to U-mode, two there and five in the handler
+        assert_eq!(cpu.run(0x10, Some(14)), StopReason::InstructionLimit);
+        assert_eq!(cpu.pc, 0x38);
+        assert_eq!(cpu.csr.privilege(), Privilege::User);
+        assert_eq!(cpu.registers.get_register(REG_S0), 0x2A, "Handler did not run!");
+        assert_eq!(cpu.registers.get_register(REG_S1), 1, "Execution did not resume after ecall!");
+        assert_eq!(cpu.csr.read(SCAUSE), Some(8));
+        assert_eq!(cpu.csr.read(MCAUSE), Some(0), "The trap should not have gone through M-mode");
+    }
+
+    #[test]
+    fn test_sfence_vma() {
+        let mut cpu = CPU::new();
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.csr((F7_SFENCE_VMA as u16) << 5 | REG_S1 as u16, F3::ECALL_EBREAK, REG_S0, REG_ZERO);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+
+        enter_mode(&mut cpu, Privilege::Supervisor);
+        cpu.csr.write(MSTATUS, MSTATUS_TVM).unwrap();
+        cpu.instruction = InstructionBuilder.csr((F7_SFENCE_VMA as u16) << 5, F3::ECALL_EBREAK, REG_ZERO, REG_ZERO);
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(cpu.instruction)), "mstatus.TVM traps it");
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod test_rv64 {
+    use crate::cpu::{Xlen, CPU};
+    use crate::cpu::csr::*;
+    use crate::cpu::instruction::builder::InstructionBuilder;
+    use crate::cpu::opcodes::*;
+    use crate::cpu::register::*;
+    use crate::cpu::trap::Exception;
+
+    fn rv64() -> CPU {
+        let mut cpu = CPU::with_xlen(Xlen::Rv64);
+        cpu.pc = 0x10;
+        cpu
+    }
+
+    // Runs `instruction` with `a` in s1 and `b` in s2, returns s0
+    fn run(cpu: &mut CPU, instruction: u32, a: u64, b: u64) -> u64 {
+        cpu.registers.set_x(REG_S1, a);
+        cpu.registers.set_x(REG_S2, b);
+        cpu.instruction = instruction;
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
+        cpu.pc = 0x10;
+        cpu.registers.get_x(REG_S0)
+    }
+
+    #[test]
+    fn test_misa_mstatus() {
+        let cpu = rv64();
+        assert_eq!(cpu.csr.read(MISA).unwrap() >> 62, 2, "MXL says 64 bits");
+        let mstatus = cpu.csr.read(MSTATUS).unwrap();
+        assert_eq!((mstatus & MSTATUS_UXL) >> 32, 2);
+        assert_eq!((mstatus & MSTATUS_SXL) >> 34, 2);
+        assert_eq!(CPU::new().csr.read(MISA).unwrap() >> 30, 1, "MXL says 32 bits");
+    }
+
+    #[test]
+    fn test_full_width() {
+        let mut cpu = rv64();
+        let add = InstructionBuilder.alu(F7_ADD, F3::ADD_SUB, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, add, 0xFFFF_FFFF, 1), 0x1_0000_0000, "The carry goes into bit 32");
+        let slli = InstructionBuilder.alui(40, F3::SLLI, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, slli, 1, 0), 1 << 40, "Shift amounts are 6 bits");
+        let sra = InstructionBuilder.alu(F7_SRA, F3::SRL_SLA, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, sra, 1 << 63, 63), u64::MAX);
+        let sltu = InstructionBuilder.alu(0, F3::SLTU, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, sltu, 0xFFFF_FFFF, 0x1_0000_0000), 1);
+        let mulhu = InstructionBuilder.alu(F7_M_EXTENSION, F3::MULHU, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, mulhu, u64::MAX, u64::MAX), u64::MAX - 1);
+        let div = InstructionBuilder.alu(F7_M_EXTENSION, F3::DIV, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, div, 1 << 63, u64::MAX), 1 << 63, "Overflow gives the dividend");
+    }
+
+    #[test]
+    fn test_word_ops() {
+        let mut cpu = rv64();
+        let addiw = InstructionBuilder.alui_w(1, F3::ADDI, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, addiw, 0x7FFF_FFFF, 0), 0xFFFF_FFFF_8000_0000, "The result is sign-extended");
+        assert_eq!(run(&mut cpu, addiw, 0x1234_5678_0000_0000, 0), 1, "The upper half is ignored");
+        let subw = InstructionBuilder.alu_w(F7_SUB, F3::ADD_SUB, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, subw, 0, 1), u64::MAX);
+        let sllw = InstructionBuilder.alu_w(0, F3::SLL, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, sllw, 1, 63), 0xFFFF_FFFF_8000_0000, "Shift amounts are 5 bits");
+        let srliw = InstructionBuilder.alui_w(4, F3::SRLI_SRAI, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, srliw, 0xFFFF_FFFF_8000_0000, 0), 0x0800_0000);
+        let mulw = InstructionBuilder.alu_w(F7_M_EXTENSION_W, F3::MULW, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, mulw, 0x1_0000, 0x8000), 0xFFFF_FFFF_8000_0000);
+        let divw = InstructionBuilder.alu_w(F7_M_EXTENSION_W, F3::DIVW, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, divw, 0xFFFF_FFFF_8000_0000, u64::MAX), 0xFFFF_FFFF_8000_0000);
+        let divuw = InstructionBuilder.alu_w(F7_M_EXTENSION_W, F3::DIVUW, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, divuw, 0xFFFF_FFFF_FFFF_FFFE, 1), 0xFFFF_FFFF_FFFF_FFFE);
+        let remuw = InstructionBuilder.alu_w(F7_M_EXTENSION_W, F3::REMUW, REG_S2, REG_S1, REG_S0);
+        assert_eq!(run(&mut cpu, remuw, 7, 0), 7, "Remainder by zero gives the dividend");
+    }
+
+    #[test]
+    fn test_word_ops_rv32() {
+        let mut cpu = CPU::new();
+        let addiw = InstructionBuilder.alui_w(1, F3::ADDI, REG_S1, REG_S0);
+        cpu.instruction = addiw;
+        assert_eq!(cpu.exec_inst(), Err(Exception::IllegalInstruction(addiw)));
+    }
+
+    #[test]
+    fn test_load_store() {
+        let mut cpu = rv64();
+        cpu.memory.set_u32(0x50, 0xCC33_CC33).unwrap();
+        cpu.memory.set_u32(0x54, 0x8765_4321).unwrap();
+        cpu.instruction = InstructionBuilder.load(0x50, F3::LD, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0x8765_4321_CC33_CC33);
+        cpu.instruction = InstructionBuilder.load(0x50, F3::LWU, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0xCC33_CC33, "LWU zero-extends");
+        cpu.instruction = InstructionBuilder.load(0x50, F3::LW, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0xFFFF_FFFF_CC33_CC33, "LW sign-extends");
+
+        cpu.registers.set_x(REG_S1, 0x60);
+        cpu.registers.set_x(REG_S2, 0x1122_3344_5566_7788);
+        cpu.instruction = InstructionBuilder.store(8, F3::SD, REG_S2, REG_S1);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.memory.get_u32(0x68), Ok(0x5566_7788));
+        assert_eq!(cpu.memory.get_u32(0x6C), Ok(0x1122_3344));
+    }
+
+    #[test]
+    fn test_pc_relative() {
+        let mut cpu = rv64();
+        // auipc s0, 0x80000: the offset is sign-extended to 64 bits
+        cpu.instruction = InstructionBuilder.auipc(0x80000, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0xFFFF_FFFF_8000_0010);
+        cpu.instruction = InstructionBuilder.lui(0x80000, REG_S0);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0xFFFF_FFFF_8000_0000);
+
+        // Jumps land anywhere in the 64-bit space
+        cpu.registers.set_x(REG_S1, 0x1_0000_0000);
+        cpu.pc = 0x10;
+        cpu.instruction = InstructionBuilder.jalr(8, REG_S1, REG_RA);
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.pc, 0x1_0000_0008);
+        assert_eq!(cpu.registers.get_x(REG_RA), 0x14);
+    }
+
+    #[test]
+    fn test_amo_d() {
+        let mut cpu = rv64();
+        cpu.memory.set_u32(0x100, 0xFFFF_FFFF).unwrap();
+        cpu.memory.set_u32(0x104, 0x0000_0001).unwrap();
+        cpu.registers.set_x(REG_S1, 0x100);
+        cpu.registers.set_x(REG_S2, 1);
+        // amoadd.d s0, s2, (s1)
+        cpu.instruction = InstructionBuilder.amo(F5::AMOADD, false, false, REG_S2, REG_S1, REG_S0) | 1 << 12;
+        cpu.exec_inst().unwrap();
+        assert_eq!(cpu.registers.get_x(REG_S0), 0x1_FFFF_FFFF);
+        assert_eq!(cpu.memory.get_u32(0x100), Ok(0));
+        assert_eq!(cpu.memory.get_u32(0x104), Ok(2));
+    }
+}
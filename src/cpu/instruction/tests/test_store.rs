@@ -11,25 +11,23 @@ mod test_store {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::STORE;
 
         // WORD
 
         cpu.instruction = InstructionBuilder.store(0x550, F3::SW, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_store();
+        cpu.exec_inst().unwrap();
 
         // Verify results
         // word at 0x55A is 0b11001100_11001100_00110011_00110011
-        assert_eq!(cpu.memory.get_u32(0x55A), 0xCC33CC33
+        assert_eq!(cpu.memory.get_u32(0x55A).unwrap(), 0xCC33CC33
             , "Stored value was not correct!\
             \nExpected: 0xCC33CC33,\
             \nGot:      0b{:0>8x}",
-            cpu.memory.get_u32(0x55A));
+            cpu.memory.get_u32(0x55A).unwrap());
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
 
-        cpu.opcode = OP::STORE;
         cpu.instruction = InstructionBuilder.store(0x554, F3::SH, REG_S1, REG_S0);
 
     }
@@ -40,21 +38,20 @@ mod test_store {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::STORE;
 
         // WORD
 
         cpu.instruction = InstructionBuilder.store(0x554, F3::SH, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_store();
+        cpu.exec_inst().unwrap();
 
         // Verify results (half word at 0x55E is 0b11001100_11001100)
-        assert_eq!(cpu.memory.get_u16(0x55E), 0xCC33
+        assert_eq!(cpu.memory.get_u16(0x55E).unwrap(), 0xCC33
                    , "Stored value was not correct!\
             \nExpected: 0x3333,\
             \nGot:      0x{:0>4x}",
-                   cpu.memory.get_u16(0x55E));
+                   cpu.memory.get_u16(0x55E).unwrap());
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 
@@ -64,21 +61,20 @@ mod test_store {
         cpu.registers.set_register(REG_S1, 0xCC33CC33);
         cpu.registers.set_register(REG_S0, 10);
         cpu.pc = 0x10;
-        cpu.opcode = OP::STORE;
 
         // WORD
 
         cpu.instruction = InstructionBuilder.store(0x558, F3::SB, REG_S1, REG_S0);
 
         // Execute load
-        cpu.inst_store();
+        cpu.exec_inst().unwrap();
 
         // Verify results (byte at 0x562 is 0b11001100)
-        assert_eq!(cpu.memory.get_u8(0x562), 0x33
+        assert_eq!(cpu.memory.get_u8(0x562).unwrap(), 0x33
                    , "Stored value was not correct!\
             \nExpected: 0x33,\
             \nGot:      0b{:0>2x}",
-                   cpu.memory.get_u8(0x562));
+                   cpu.memory.get_u8(0x562).unwrap());
         assert_eq!(cpu.pc, 0x14, "PC was not updated correctly!");
     }
 }
\ No newline at end of file
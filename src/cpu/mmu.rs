@@ -0,0 +1,797 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Sv32 address translation
+ *
+ * Every fetch, load and store the CPU makes goes through here. In M-mode, or
+ * while satp is in bare mode, virtual addresses are physical addresses.
+ * Otherwise the two level page table satp points at is walked:
+ *
+ *   31       22 21       12 11          0
+ *   |  VPN[1]  |  VPN[0]  |   offset    |    virtual address
+ *
+ *   31                 10 9   8 7 6 5 4 3 2 1 0
+ *   |       PPN         | RSW |D|A|G|U|X|W|R|V|  page table entry
+ *
+ * A leaf found at the first level maps a 4 MiB superpage. The A and D bits
+ * are set by the walk itself rather than faulting, as on QEMU. Physical
+ * addresses are 34 bits wide, anything past the 32 bit bus is an access fault.
+ *
+ * Walks are cached in two direct mapped TLBs, one for fetches and one for
+ * loads and stores. Entries keep the leaf's permissions, which are checked
+ * again on every hit since privilege, SUM and MXR can change without a
+ * flush. SFENCE.VMA flushes them, selectively by address and/or ASID, and
+ * so does any write to satp.
+ *
+ * The physical addresses the accesses end up at, page table entries
+ * included, are then checked against the PMP.
+ */
+
+use crate::cpu::CPU;
+use crate::cpu::csr::{Privilege, SATP, SATP_MODE_SV32, MSTATUS_MPRV, MSTATUS_SUM, MSTATUS_MXR};
+use crate::cpu::trap::Exception;
+
+pub(crate) const PAGE_SIZE: u32 = 4096;
+const PAGE_SHIFT: u32 = 12;
+const LEVELS: u32 = 2;
+const PTE_SIZE: u32 = 4;
+const SATP_PPN_MASK: u32 = 0x003F_FFFF;
+const SATP_ASID_SHIFT: u32 = 22;
+const SATP_ASID_MASK: u32 = 0x1FF;
+
+// Entries per TLB, a power of two
+pub(crate) const TLB_ENTRIES: usize = 64;
+
+// Page table entry fields
+pub(crate) const PTE_V: u32 = 1 << 0;
+pub(crate) const PTE_R: u32 = 1 << 1;
+pub(crate) const PTE_W: u32 = 1 << 2;
+pub(crate) const PTE_X: u32 = 1 << 3;
+pub(crate) const PTE_U: u32 = 1 << 4;
+pub(crate) const PTE_G: u32 = 1 << 5;
+pub(crate) const PTE_A: u32 = 1 << 6;
+pub(crate) const PTE_D: u32 = 1 << 7;
+const PTE_PPN_SHIFT: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Access {
+    Fetch,
+    Load,
+    Store,
+}
+
+impl Access {
+    fn page_fault(self, address: u64) -> Exception {
+        match self {
+            Access::Fetch => Exception::InstructionPageFault(address),
+            Access::Load => Exception::LoadPageFault(address),
+            Access::Store => Exception::StorePageFault(address),
+        }
+    }
+
+    fn access_fault(self, address: u64) -> Exception {
+        match self {
+            Access::Fetch => Exception::InstructionAccessFault(address),
+            Access::Load => Exception::LoadAccessFault(address),
+            Access::Store => Exception::StoreAccessFault(address),
+        }
+    }
+}
+
+// A cached translation
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TlbEntry {
+    vpn: u32, // Virtual page number of the 4 KiB page that was walked
+    asid: u32,
+    level: u32, // 1 for superpages
+    pte: u32, // The leaf, as written back by the walk
+    global: bool, // Mapped in every address space
+}
+
+impl TlbEntry {
+    // Mask of the virtual page number bits the entry covers
+    fn vpn_mask(&self) -> u32 {
+        !((1 << (10 * self.level)) - 1)
+    }
+
+    fn matches(&self, vpn: u32, asid: u32) -> bool {
+        (self.vpn ^ vpn) & self.vpn_mask() == 0 && (self.global || self.asid == asid)
+    }
+
+    // Physical address of `address` in the page, which may not fit on the bus
+    fn physical(&self, address: u32) -> u64 {
+        let offset_mask = (1u32 << (PAGE_SHIFT + 10 * self.level)) - 1;
+        ((self.pte >> PTE_PPN_SHIFT) as u64) << PAGE_SHIFT | (address & offset_mask) as u64
+    }
+}
+
+pub(crate) struct Tlb {
+    entries: [Option<TlbEntry>; TLB_ENTRIES],
+    hits: u64,
+    misses: u64,
+}
+
+impl Tlb {
+    fn new() -> Self {
+        Self {
+            entries: [None; TLB_ENTRIES],
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    // Superpages are cached under the 4 KiB page that was walked, like any other page
+    fn index(vpn: u32) -> usize {
+        vpn as usize % TLB_ENTRIES
+    }
+
+    // The entry for `vpn`, without counting a hit or a miss
+    fn get(&self, vpn: u32, asid: u32) -> Option<TlbEntry> {
+        self.entries[Self::index(vpn)].filter(|entry| entry.matches(vpn, asid))
+    }
+
+    // Stores to pages cached as clean miss, the walk has to mark them dirty
+    fn lookup(&mut self, vpn: u32, asid: u32, access: Access) -> Option<TlbEntry> {
+        match self.get(vpn, asid).filter(|entry| access != Access::Store || entry.pte & PTE_D != 0) {
+            Some(entry) => {
+                self.hits += 1;
+                Some(entry)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, entry: TlbEntry) {
+        self.entries[Self::index(entry.vpn)] = Some(entry);
+    }
+
+    // SFENCE.VMA: drops the entries mapping `address` if given, in address space
+    // `asid` if given. Global entries stay unless every address space is flushed.
+    fn flush(&mut self, address: Option<u32>, asid: Option<u32>) {
+        for slot in &mut self.entries {
+            let Some(entry) = slot else { continue };
+            let address_matches = address.is_none_or(|address| (entry.vpn ^ (address >> PAGE_SHIFT)) & entry.vpn_mask() == 0);
+            let asid_matches = asid.is_none_or(|asid| !entry.global && entry.asid == asid);
+            if address_matches && asid_matches {
+                *slot = None;
+            }
+        }
+    }
+}
+
+// Translation caches, split between fetches and data accesses
+pub(crate) struct Mmu {
+    pub(crate) fetch: Tlb,
+    pub(crate) data: Tlb,
+}
+
+impl Mmu {
+    pub(crate) fn new() -> Self {
+        Self {
+            fetch: Tlb::new(),
+            data: Tlb::new(),
+        }
+    }
+
+    fn tlb(&mut self, access: Access) -> &mut Tlb {
+        match access {
+            Access::Fetch => &mut self.fetch,
+            Access::Load | Access::Store => &mut self.data,
+        }
+    }
+
+    pub(crate) fn flush(&mut self, address: Option<u32>, asid: Option<u32>) {
+        self.fetch.flush(address, asid);
+        self.data.flush(address, asid);
+    }
+}
+
+// Whether an access of `size` bytes at `address` spills over into the next page
+fn crosses_page(address: u64, size: u32) -> bool {
+    (address % PAGE_SIZE as u64) + size as u64 > PAGE_SIZE as u64
+}
+
+impl CPU {
+    // Mode the access is checked against: with mstatus.MPRV set, loads and
+    // stores from M-mode are made as if from the mode in MPP
+    fn effective_privilege(&self, access: Access) -> Privilege {
+        let privilege = self.csr.privilege();
+        if access != Access::Fetch && privilege == Privilege::Machine && self.csr.status(MSTATUS_MPRV) {
+            return self.csr.mpp();
+        }
+        privilege
+    }
+
+    // Whether the access goes through the page table
+    fn is_paged(&self, access: Access) -> bool {
+        let satp = self.csr.read(SATP).unwrap_or(0);
+        satp & SATP_MODE_SV32 != 0 && self.effective_privilege(access) != Privilege::Machine
+    }
+
+    // Whether the leaf `pte` allows the access from the current effective mode
+    fn is_allowed(&self, pte: u32, access: Access) -> bool {
+        let allowed = match access {
+            Access::Fetch => pte & PTE_X != 0,
+            // With MXR, executable pages are readable too
+            Access::Load => pte & PTE_R != 0 || (self.csr.status(MSTATUS_MXR) && pte & PTE_X != 0),
+            Access::Store => pte & PTE_W != 0,
+        };
+        let user_ok = match self.effective_privilege(access) {
+            Privilege::User => pte & PTE_U != 0,
+            // S-mode never executes user pages, and only touches their data with SUM
+            Privilege::Supervisor => pte & PTE_U == 0 || (access != Access::Fetch && self.csr.status(MSTATUS_SUM)),
+            Privilege::Machine => true,
+        };
+        allowed && user_ok
+    }
+
+    // The address `offset` bytes past `address`, wrapping around at XLEN like every address computation
+    fn next_address(&self, address: u64, offset: u32) -> u64 {
+        self.xlen.zext(address.wrapping_add(offset as u64))
+    }
+
+    // Physical address `address` maps to, or the fault the access raises. Bare
+    // RV64 addresses past the 32-bit bus are access faults.
+    pub(crate) fn translate(&mut self, address: u64, access: Access) -> Result<u32, Exception> {
+        if !self.is_paged(access) {
+            return u32::try_from(address).map_err(|_| access.access_fault(address));
+        }
+        // Sv32 is RV32 only, virtual addresses are 32 bits
+        let virt = address as u32;
+        let satp = self.csr.read(SATP).unwrap_or(0) as u32;
+        let asid = (satp >> SATP_ASID_SHIFT) & SATP_ASID_MASK;
+        let vpn = virt >> PAGE_SHIFT;
+        let entry = match self.mmu.tlb(access).lookup(vpn, asid, access) {
+            Some(entry) => {
+                if !self.is_allowed(entry.pte, access) {
+                    return Err(access.page_fault(address));
+                }
+                entry
+            }
+            None => {
+                let entry = self.walk(virt, asid, access, false)?;
+                self.mmu.tlb(access).insert(entry);
+                entry
+            }
+        };
+        u32::try_from(entry.physical(virt)).map_err(|_| access.access_fault(address))
+    }
+
+    // Walks the page table for `address`, checking the leaf's permissions and
+    // marking it accessed (and dirty on stores). A `peek` leaves the page table
+    // as it is, and reads it without side effects.
+    fn walk(&mut self, address: u32, asid: u32, access: Access, peek: bool) -> Result<TlbEntry, Exception> {
+        let page_fault = access.page_fault(address as u64);
+        let access_fault = access.access_fault(address as u64);
+
+        let satp = self.csr.read(SATP).unwrap_or(0) as u32;
+        let mut table = ((satp & SATP_PPN_MASK) as u64) << PAGE_SHIFT;
+        let mut level = LEVELS - 1;
+        let mut global = false;
+        let (pte, pte_address) = loop {
+            let vpn = (address >> (PAGE_SHIFT + 10 * level)) & 0x3FF;
+            let pte_address = u32::try_from(table + (vpn * PTE_SIZE) as u64).map_err(|_| access_fault)?;
+            // The walk itself reads memory as S-mode
+            if !self.csr.pmp().check(pte_address, PTE_SIZE, Access::Load, Privilege::Supervisor) {
+                return Err(access_fault);
+            }
+            let pte = if peek {
+                self.memory.peek(pte_address, PTE_SIZE).ok_or(access_fault)?
+            } else {
+                self.memory.get_u32(pte_address).map_err(|_| access_fault)?
+            };
+            // Writable pages must be readable
+            if pte & PTE_V == 0 || (pte & PTE_R == 0 && pte & PTE_W != 0) {
+                return Err(page_fault);
+            }
+            // A global pointer makes everything below it global
+            global |= pte & PTE_G != 0;
+            if pte & (PTE_R | PTE_X) != 0 {
+                break (pte, pte_address);
+            }
+            // Pointer to the next level, there is none past the last one
+            if level == 0 {
+                return Err(page_fault);
+            }
+            level -= 1;
+            table = ((pte >> PTE_PPN_SHIFT) as u64) << PAGE_SHIFT;
+        };
+
+        if !self.is_allowed(pte, access) {
+            return Err(page_fault);
+        }
+        // Superpages must be aligned to their size
+        let superpage_mask = (1 << (10 * level)) - 1;
+        if (pte >> PTE_PPN_SHIFT) & superpage_mask != 0 {
+            return Err(page_fault);
+        }
+
+        // Mark the page accessed, and dirty on stores
+        let mut updated = pte | PTE_A;
+        if access == Access::Store {
+            updated |= PTE_D;
+        }
+        if updated != pte && !peek {
+            if !self.csr.pmp().check(pte_address, PTE_SIZE, Access::Store, Privilege::Supervisor) {
+                return Err(access_fault);
+            }
+            self.memory.set_u32(pte_address, updated).map_err(|_| access_fault)?;
+        }
+        Ok(TlbEntry { vpn: address >> PAGE_SHIFT, asid, level, pte: updated, global })
+    }
+
+    // Translates an access of `size` bytes at `address` and checks the physical
+    // address against the PMP
+    fn physical(&mut self, address: u64, size: u32, access: Access) -> Result<u32, Exception> {
+        let physical = self.translate(address, access)?;
+        if !self.csr.pmp().check(physical, size, access, self.effective_privilege(access)) {
+            return Err(access.access_fault(address));
+        }
+        Ok(physical)
+    }
+
+    // Physical address of an access for a debugger, which mustn't count in the
+    // TLB statistics, fill the TLB or mark pages. None if the access would fault.
+    pub(crate) fn peek_physical(&mut self, address: u64, size: u32, access: Access) -> Option<u32> {
+        let physical = if self.is_paged(access) {
+            let virt = address as u32;
+            let satp = self.csr.read(SATP).unwrap_or(0) as u32;
+            let asid = (satp >> SATP_ASID_SHIFT) & SATP_ASID_MASK;
+            let entry = match self.mmu.tlb(access).get(virt >> PAGE_SHIFT, asid) {
+                Some(entry) if self.is_allowed(entry.pte, access) => entry,
+                _ => self.walk(virt, asid, access, true).ok()?,
+            };
+            u32::try_from(entry.physical(virt)).ok()?
+        } else {
+            u32::try_from(address).ok()?
+        };
+        self.csr.pmp().check(physical, size, access, self.effective_privilege(access)).then_some(physical)
+    }
+
+    // Loads `size` bytes at virtual address `address`. Misaligned accesses that
+    // spill over into the next page are split into bytes, each page being
+    // translated on its own.
+    fn load(&mut self, address: u64, size: u32) -> Result<u32, Exception> {
+        if crosses_page(address, size) && self.is_paged(Access::Load) {
+            let mut value = 0;
+            for i in 0..size {
+                value |= self.load(self.next_address(address, i), 1)? << (8 * i);
+            }
+            return Ok(value);
+        }
+        let physical = self.physical(address, size, Access::Load)?;
+        let value = match size {
+            1 => self.memory.get_u8(physical).map(u32::from),
+            2 => self.memory.get_u16(physical).map(u32::from),
+            _ => self.memory.get_u32(physical),
+        };
+        // Faults report the virtual address
+        value.map_err(|_| Exception::LoadAccessFault(address))
+    }
+
+    fn store(&mut self, address: u64, size: u32, value: u32) -> Result<(), Exception> {
+        if crosses_page(address, size) && self.is_paged(Access::Store) {
+            // Translate every byte first, so a fault doesn't leave the store half done
+            for i in 0..size {
+                self.physical(self.next_address(address, i), 1, Access::Store)?;
+            }
+            for i in 0..size {
+                self.store(self.next_address(address, i), 1, value >> (8 * i))?;
+            }
+            return Ok(());
+        }
+        let physical = self.physical(address, size, Access::Store)?;
+        self.break_reservation(physical, size);
+        let result = match size {
+            1 => self.memory.set_u8(physical, value as u8),
+            2 => self.memory.set_u16(physical, value as u16),
+            _ => self.memory.set_u32(physical, value),
+        };
+        result.map_err(|_| Exception::StoreAccessFault(address))
+    }
+
+    pub(crate) fn load_u8(&mut self, address: u64) -> Result<u8, Exception> {
+        self.trace_load(address);
+        self.load(address, 1).map(|value| value as u8)
+    }
+
+    pub(crate) fn load_u16(&mut self, address: u64) -> Result<u16, Exception> {
+        self.trace_load(address);
+        self.load(address, 2).map(|value| value as u16)
+    }
+
+    pub(crate) fn load_u32(&mut self, address: u64) -> Result<u32, Exception> {
+        self.trace_load(address);
+        self.load(address, 4)
+    }
+
+    pub(crate) fn store_u8(&mut self, address: u64, value: u8) -> Result<(), Exception> {
+        self.trace_store(address, value as u64, 1);
+        self.store(address, 1, value as u32)
+    }
+
+    pub(crate) fn store_u16(&mut self, address: u64, value: u16) -> Result<(), Exception> {
+        self.trace_store(address, value as u64, 2);
+        self.store(address, 2, value as u32)
+    }
+
+    pub(crate) fn store_u32(&mut self, address: u64, value: u32) -> Result<(), Exception> {
+        self.trace_store(address, value as u64, 4);
+        self.store(address, 4, value)
+    }
+
+    // Doublewords, for LD and SD on RV64 and FLD and FSD, are accessed as two words
+    pub(crate) fn load_u64(&mut self, address: u64) -> Result<u64, Exception> {
+        self.trace_load(address);
+        let low = self.load(address, 4)?;
+        let high = self.load(self.next_address(address, 4), 4)?;
+        Ok((high as u64) << 32 | low as u64)
+    }
+
+    pub(crate) fn store_u64(&mut self, address: u64, value: u64) -> Result<(), Exception> {
+        self.trace_store(address, value, 8);
+        // Both words are checked first, so a fault doesn't leave the store half done
+        if crosses_page(address, 8) && self.is_paged(Access::Store) {
+            for i in 0..8 {
+                self.physical(self.next_address(address, i), 1, Access::Store)?;
+            }
+        } else {
+            self.physical(address, 8, Access::Store)?;
+        }
+        self.store(address, 4, value as u32)?;
+        self.store(self.next_address(address, 4), 4, (value >> 32) as u32)
+    }
+
+    // Stores overlapping the reserved word or doubleword make the next SC fail
+    fn break_reservation(&mut self, physical: u32, size: u32) {
+        if let Some((reserved, reserved_size)) = self.reservation {
+            if (physical as u64) < reserved as u64 + reserved_size as u64 && physical as u64 + size as u64 > reserved as u64 {
+                self.reservation = None;
+            }
+        }
+    }
+
+    // Reads the word or doubleword at a physical address, the latter as two words
+    fn get_physical(&mut self, physical: u32, size: u32) -> Result<u64, Exception> {
+        let low = self.memory.get_u32(physical)? as u64;
+        if size == 4 {
+            return Ok(low);
+        }
+        let high = self.memory.get_u32(physical.wrapping_add(4))? as u64;
+        Ok(high << 32 | low)
+    }
+
+    fn set_physical(&mut self, physical: u32, size: u32, value: u64) -> Result<(), Exception> {
+        self.memory.set_u32(physical, value as u32)?;
+        if size == 8 {
+            self.memory.set_u32(physical.wrapping_add(4), (value >> 32) as u32)?;
+        }
+        Ok(())
+    }
+
+    // LR.W and LR.D: loads the `size` bytes at `address` and reserves them
+    pub(crate) fn load_reserved(&mut self, address: u64, size: u32) -> Result<u64, Exception> {
+        let physical = self.physical(address, size, Access::Load)?;
+        let value = self.get_physical(physical, size).map_err(|_| Exception::LoadAccessFault(address))?;
+        self.reservation = Some((physical, size));
+        self.trace_load(address);
+        Ok(value)
+    }
+
+    // SC.W and SC.D: stores `value` at `address` if it's still reserved, returns
+    // whether it did. The reservation is gone either way.
+    pub(crate) fn store_conditional(&mut self, address: u64, size: u32, value: u64) -> Result<bool, Exception> {
+        let physical = self.physical(address, size, Access::Store)?;
+        if self.reservation.take() != Some((physical, size)) {
+            return Ok(false);
+        }
+        self.set_physical(physical, size, value).map_err(|_| Exception::StoreAccessFault(address))?;
+        self.trace_store(address, value, size);
+        Ok(true)
+    }
+
+    // Atomically replaces the `size` bytes at `address` with `op` applied to them,
+    // returns the old value. The access needs both read and write permissions,
+    // and faults as a store.
+    pub(crate) fn amo(&mut self, address: u64, size: u32, op: impl FnOnce(u64) -> u64) -> Result<u64, Exception> {
+        let physical = self.physical(address, size, Access::Store)?;
+        if !self.csr.pmp().check(physical, size, Access::Load, self.effective_privilege(Access::Load)) {
+            return Err(Exception::StoreAccessFault(address));
+        }
+        let old = self.get_physical(physical, size).map_err(|_| Exception::StoreAccessFault(address))?;
+        self.break_reservation(physical, size);
+        let new = op(old);
+        self.set_physical(physical, size, new).map_err(|_| Exception::StoreAccessFault(address))?;
+        self.trace_load(address);
+        self.trace_store(address, new, size);
+        Ok(old)
+    }
+
+    // Fetches the instruction word at virtual address `address`
+    pub(crate) fn fetch_u32(&mut self, address: u64) -> Result<u32, Exception> {
+        let physical = self.physical(address, 4, Access::Fetch)?;
+        self.memory.fetch_u32(physical).map_err(|_| Exception::InstructionAccessFault(address))
+    }
+
+    // Fetches the 16-bit instruction parcel at virtual address `address`
+    pub(crate) fn fetch_u16(&mut self, address: u64) -> Result<u16, Exception> {
+        let physical = self.physical(address, 2, Access::Fetch)?;
+        self.memory.fetch_u16(physical).map_err(|_| Exception::InstructionAccessFault(address))
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::CPU;
+    use crate::cpu::csr::*;
+    use crate::cpu::mmu::*;
+    use crate::cpu::pmp::{PMPADDR0, PMPCFG0};
+
+    const ROOT: u32 = 0x1000;
+    const LEAVES: u32 = 0x2000;
+
+    fn pte(physical: u32, flags: u32) -> u32 {
+        (physical >> 12) << 10 | flags | PTE_V
+    }
+
+    // CPU whose PMP lets every mode at the whole address space
+    fn open_cpu() -> CPU {
+        let mut cpu = CPU::new();
+        cpu.csr.write(PMPADDR0, 0xFFFF_FFFF).unwrap();
+        cpu.csr.write(PMPCFG0, 0x1F).unwrap();
+        cpu
+    }
+
+    // S-mode CPU with Sv32 on, the root page table at ROOT and a second level table at
+    // LEAVES covering virtual addresses 0x4000_0000 to 0x403F_FFFF
+    fn paged_cpu() -> CPU {
+        let mut cpu = open_cpu();
+        cpu.memory.set_u32(ROOT + (0x4000_0000 >> 22) * 4, pte(LEAVES, 0)).unwrap();
+        cpu.csr.write(SATP, SATP_MODE_SV32 | (ROOT >> 12) as u64).unwrap();
+        cpu.csr.write(MSTATUS, 1 << 11).unwrap();
+        cpu.csr.leave_trap();
+        assert_eq!(cpu.csr.privilege(), Privilege::Supervisor);
+        cpu
+    }
+
+    fn map(cpu: &mut CPU, virt: u32, physical: u32, flags: u32) {
+        let index = (virt >> 12) & 0x3FF;
+        cpu.memory.set_u32(LEAVES + index * 4, pte(physical, flags)).unwrap();
+    }
+
+    #[test]
+    fn test_bare() {
+        let mut cpu = CPU::new();
+        assert_eq!(cpu.translate(0x1234, Access::Load), Ok(0x1234));
+        cpu.csr.write(SATP, SATP_MODE_SV32 | (ROOT >> 12) as u64).unwrap();
+        assert_eq!(cpu.translate(0x1234, Access::Load), Ok(0x1234), "M-mode isn't translated");
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_5000, 0x8000, PTE_R | PTE_W);
+        cpu.memory.set_u32(0x8010, 0xDEAD_BEEF).unwrap();
+        assert_eq!(cpu.load_u32(0x4000_5010), Ok(0xDEAD_BEEF));
+        cpu.store_u16(0x4000_5020, 0x1234).unwrap();
+        assert_eq!(cpu.memory.get_u16(0x8020), Ok(0x1234));
+
+        let leaf = cpu.memory.get_u32(LEAVES + 5 * 4).unwrap();
+        assert_eq!(leaf & (PTE_A | PTE_D), PTE_A | PTE_D, "The walk sets A and D");
+        assert_eq!(cpu.load_u32(0x4000_6000), Err(Exception::LoadPageFault(0x4000_6000)));
+        assert_eq!(cpu.fetch_u32(0x4000_5000), Err(Exception::InstructionPageFault(0x4000_5000)));
+    }
+
+    #[test]
+    fn test_permissions() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R);
+        map(&mut cpu, 0x4000_2000, 0x8000, PTE_X);
+        map(&mut cpu, 0x4000_3000, 0x8000, PTE_W);
+        assert_eq!(cpu.store_u8(0x4000_1000, 0), Err(Exception::StorePageFault(0x4000_1000)));
+        assert_eq!(cpu.translate(0x4000_2000, Access::Fetch), Ok(0x8000));
+        assert_eq!(cpu.load_u8(0x4000_2000), Err(Exception::LoadPageFault(0x4000_2000)));
+        cpu.csr.write(MSTATUS, MSTATUS_MXR).unwrap();
+        assert_eq!(cpu.translate(0x4000_2000, Access::Load), Ok(0x8000), "MXR makes executable pages readable");
+        assert_eq!(cpu.translate(0x4000_3000, Access::Store), Err(Exception::StorePageFault(0x4000_3000)),
+            "Writable pages must be readable");
+    }
+
+    #[test]
+    fn test_user_pages() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_X | PTE_U);
+        map(&mut cpu, 0x4000_2000, 0x9000, PTE_R | PTE_X);
+        assert_eq!(cpu.translate(0x4000_1000, Access::Load), Err(Exception::LoadPageFault(0x4000_1000)));
+        cpu.csr.write(SSTATUS, MSTATUS_SUM).unwrap();
+        assert_eq!(cpu.translate(0x4000_1000, Access::Load), Ok(0x8000), "SUM lets S-mode at user data");
+        assert_eq!(cpu.translate(0x4000_1000, Access::Fetch), Err(Exception::InstructionPageFault(0x4000_1000)));
+
+        // Down to U-mode
+        cpu.csr.leave_supervisor_trap();
+        assert_eq!(cpu.csr.privilege(), Privilege::User);
+        assert_eq!(cpu.translate(0x4000_1000, Access::Fetch), Ok(0x8000));
+        assert_eq!(cpu.translate(0x4000_2000, Access::Load), Err(Exception::LoadPageFault(0x4000_2000)));
+    }
+
+    #[test]
+    fn test_superpage() {
+        let mut cpu = paged_cpu();
+        cpu.memory.set_u32(ROOT + (0x8040_0000 >> 22) * 4, pte(0x0040_0000, PTE_R)).unwrap();
+        assert_eq!(cpu.translate(0x8041_2345, Access::Load), Ok(0x0041_2345));
+        // Misaligned superpage
+        cpu.memory.set_u32(ROOT + (0x8040_0000 >> 22) * 4, pte(0x0040_1000, PTE_R)).unwrap();
+        cpu.mmu.flush(None, None);
+        assert_eq!(cpu.translate(0x8041_2345, Access::Load), Err(Exception::LoadPageFault(0x8041_2345)));
+        // Past the bus
+        cpu.memory.set_u32(ROOT + (0x8040_0000 >> 22) * 4, (1 << 30) | PTE_R | PTE_V).unwrap();
+        cpu.mmu.flush(None, None);
+        assert_eq!(cpu.translate(0x8040_0000, Access::Load), Err(Exception::LoadAccessFault(0x8040_0000)));
+    }
+
+    #[test]
+    fn test_mprv() {
+        let mut cpu = open_cpu();
+        cpu.memory.set_u32(ROOT + (0x4000_0000 >> 22) * 4, pte(LEAVES, 0)).unwrap();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_W | PTE_X);
+        cpu.csr.write(SATP, SATP_MODE_SV32 | (ROOT >> 12) as u64).unwrap();
+        cpu.csr.write(MSTATUS, MSTATUS_MPRV | 1 << 11).unwrap();
+        assert_eq!(cpu.translate(0x4000_1000, Access::Load), Ok(0x8000));
+        assert_eq!(cpu.translate(0x4000_1000, Access::Fetch), Ok(0x4000_1000), "MPRV doesn't apply to fetches");
+    }
+
+    #[test]
+    fn test_crossing_pages() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x9000, PTE_R | PTE_W);
+        map(&mut cpu, 0x4000_2000, 0x8000, PTE_R | PTE_W);
+        cpu.store_u32(0x4000_1FFE, 0x1122_3344).unwrap();
+        assert_eq!(cpu.memory.get_u16(0x9FFE), Ok(0x3344));
+        assert_eq!(cpu.memory.get_u16(0x8000), Ok(0x1122));
+        assert_eq!(cpu.load_u32(0x4000_1FFE), Ok(0x1122_3344));
+        assert_eq!(cpu.store_u32(0x4000_2FFE, 0), Err(Exception::StorePageFault(0x4000_3000)));
+        assert_eq!(cpu.memory.get_u16(0x8FFE), Ok(0), "Nothing should be stored on a fault");
+    }
+
+    #[test]
+    fn test_doubleword() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x9000, PTE_R | PTE_W);
+        map(&mut cpu, 0x4000_2000, 0x8000, PTE_R | PTE_W);
+        cpu.store_u64(0x4000_1FFC, 0x1122_3344_5566_7788).unwrap();
+        assert_eq!(cpu.memory.get_u32(0x9FFC), Ok(0x5566_7788));
+        assert_eq!(cpu.memory.get_u32(0x8000), Ok(0x1122_3344));
+        assert_eq!(cpu.load_u64(0x4000_1FFC), Ok(0x1122_3344_5566_7788));
+        assert_eq!(cpu.store_u64(0x4000_2FFC, 0), Err(Exception::StorePageFault(0x4000_3000)));
+        assert_eq!(cpu.memory.get_u32(0x8FFC), Ok(0), "The low word must not be stored on a fault");
+    }
+
+    #[test]
+    fn test_tlb() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_W | PTE_X);
+        cpu.load_u32(0x4000_1000).unwrap();
+        cpu.load_u32(0x4000_1004).unwrap();
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (1, 1));
+        cpu.store_u32(0x4000_1008, 0).unwrap();
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (1, 2), "Stores to clean pages walk again");
+        assert_eq!(cpu.mmu.fetch.misses(), 0, "Fetches have their own TLB");
+        cpu.fetch_u32(0x4000_1000).unwrap();
+        assert_eq!(cpu.mmu.fetch.misses(), 1);
+
+        // Stale until flushed
+        map(&mut cpu, 0x4000_1000, 0x9000, PTE_R | PTE_W);
+        assert_eq!(cpu.translate(0x4000_1000, Access::Load), Ok(0x8000));
+        cpu.mmu.flush(Some(0x4000_1234), None);
+        assert_eq!(cpu.translate(0x4000_1000, Access::Load), Ok(0x9000));
+        assert_eq!(cpu.translate(0x4000_1000, Access::Fetch), Err(Exception::InstructionPageFault(0x4000_1000)));
+    }
+
+    #[test]
+    fn test_peek_physical() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_X);
+        assert_eq!(cpu.peek_physical(0x4000_1004, 4, Access::Load), Some(0x8004));
+        assert_eq!(cpu.peek_physical(0x4000_1004, 4, Access::Store), None);
+        assert_eq!(cpu.memory.get_u32(LEAVES + 4).unwrap() & PTE_A, 0, "Peeking doesn't mark the page");
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (0, 0));
+        cpu.load_u32(0x4000_1000).unwrap();
+        assert_eq!(cpu.peek_physical(0x4000_1004, 4, Access::Load), Some(0x8004));
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (0, 1));
+    }
+
+    #[test]
+    fn test_tlb_permissions() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_W | PTE_U);
+        cpu.csr.write(SSTATUS, MSTATUS_SUM).unwrap();
+        cpu.load_u8(0x4000_1000).unwrap();
+        cpu.csr.write(SSTATUS, 0).unwrap();
+        assert_eq!(cpu.load_u8(0x4000_1000), Err(Exception::LoadPageFault(0x4000_1000)),
+            "Permissions are checked on hits too");
+        assert_eq!(cpu.mmu.data.hits(), 1);
+
+        // The first store to a page cached as clean goes back to the page table
+        cpu.csr.write(SSTATUS, MSTATUS_SUM).unwrap();
+        cpu.store_u8(0x4000_1000, 1).unwrap();
+        assert_eq!(cpu.memory.get_u32(LEAVES + 4).unwrap() & PTE_D, PTE_D);
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (1, 2), "The store counts as a miss");
+        cpu.store_u8(0x4000_1000, 2).unwrap();
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (2, 2));
+    }
+
+    #[test]
+    fn test_flush_asid() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R);
+        map(&mut cpu, 0x4000_2000, 0x9000, PTE_R | PTE_G);
+        cpu.csr.write(SATP, SATP_MODE_SV32 | 5 << 22 | (ROOT >> 12) as u64).unwrap();
+        cpu.load_u8(0x4000_1000).unwrap();
+        cpu.load_u8(0x4000_2000).unwrap();
+
+        // Another address space only sees the global page
+        cpu.csr.write(SATP, SATP_MODE_SV32 | 6 << 22 | (ROOT >> 12) as u64).unwrap();
+        cpu.load_u8(0x4000_1000).unwrap();
+        cpu.load_u8(0x4000_2000).unwrap();
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (1, 3));
+
+        // Global entries survive flushing a single address space
+        cpu.mmu.flush(None, Some(6));
+        cpu.load_u8(0x4000_1000).unwrap();
+        cpu.load_u8(0x4000_2000).unwrap();
+        assert_eq!((cpu.mmu.data.hits(), cpu.mmu.data.misses()), (2, 4));
+        cpu.mmu.flush(None, None);
+        cpu.load_u8(0x4000_2000).unwrap();
+        assert_eq!(cpu.mmu.data.misses(), 5);
+    }
+
+    #[test]
+    fn test_flush_superpage() {
+        let mut cpu = paged_cpu();
+        cpu.memory.set_u32(ROOT + (0x8040_0000 >> 22) * 4, pte(0x0040_0000, PTE_R)).unwrap();
+        cpu.translate(0x8041_2345, Access::Load).unwrap();
+        cpu.mmu.flush(Some(0x8070_0000), None);
+        cpu.translate(0x8041_2345, Access::Load).unwrap();
+        assert_eq!(cpu.mmu.data.misses(), 2, "Any address in a superpage flushes it");
+    }
+
+    #[test]
+    fn test_pmp() {
+        let mut cpu = paged_cpu();
+        map(&mut cpu, 0x4000_1000, 0x8000, PTE_R | PTE_W);
+        // Read only 4 KiB at 0x8000 ahead of the catch all
+        cpu.csr.write(PMPADDR0, 0x8000 >> 2 | 0x1FF).unwrap();
+        cpu.csr.write(PMPADDR0 + 1, 0xFFFF_FFFF).unwrap();
+        cpu.csr.write(PMPCFG0, 0x1F19).unwrap();
+        assert_eq!(cpu.load_u32(0x4000_1000), Ok(0));
+        assert_eq!(cpu.store_u32(0x4000_1000, 0), Err(Exception::StoreAccessFault(0x4000_1000)),
+            "Faults report the virtual address");
+
+        // Page tables are out of reach too
+        cpu.csr.write(PMPADDR0, (LEAVES >> 2 | 0x1FF) as u64).unwrap();
+        cpu.csr.write(PMPCFG0, 0x1F18).unwrap();
+        cpu.mmu.flush(None, None);
+        assert_eq!(cpu.load_u32(0x4000_1000), Err(Exception::LoadAccessFault(0x4000_1000)));
+
+        // M-mode isn't affected, unless the entry is locked
+        cpu.csr.write(SATP, 0).unwrap();
+        cpu.csr.write(MSTATUS, 3 << 11).unwrap();
+        cpu.csr.leave_trap();
+        assert_eq!(cpu.load_u32(LEAVES as u64), Ok(cpu.memory.get_u32(LEAVES).unwrap()));
+        cpu.csr.write(PMPCFG0, 0x1F98).unwrap();
+        assert_eq!(cpu.load_u32(LEAVES as u64), Err(Exception::LoadAccessFault(LEAVES as u64)));
+        assert_eq!(cpu.fetch_u32(0x10), Ok(0));
+    }
+}
@@ -10,6 +10,7 @@
 
 // Silly rust compiler
 #![allow(dead_code)]
+#![allow(clippy::identity_op)]
 
 // Masks
 #[allow(non_snake_case)]
@@ -23,6 +24,8 @@ pub mod MASK {
         pub const F7 : u8 = 25;
         pub const RS1: u8 = 15;
         pub const RS2: u8 = 20;
+        pub const FMT: u8 = 25;
+        pub const RS3: u8 = 27;
     }
 
     pub const OP : u32 = 0x7F << OFF::OP;
@@ -31,21 +34,29 @@ pub mod MASK {
     pub const F7 : u32 = 0x7F << OFF::F7;
     pub const RS1: u32 = 0x1F << OFF::RS1;
     pub const RS2: u32 = 0x1F << OFF::RS2;
+    pub const FMT: u32 = 0x3  << OFF::FMT;
+    pub const RS3: u32 = 0x1F << OFF::RS3;
 
     pub const LUI_IMM         : u32 = 0xFF_FF_F0_00;
+    pub const AUIPC_IMM       : u32 = 0xFF_FF_F0_00;
     pub const JAL_IMM_20      : u32 = 0x1     << 31;
     pub const JAL_IMM_10_1    : u32 = 0x03_FF << 21;
     pub const JAL_IMM_11      : u32 = 0x1     << 20;
-    pub const JAL_IMM_19_12   : u32 = 0x1     << 12;
+    pub const JAL_IMM_19_12   : u32 = 0xFF    << 12;
     pub const JALR_IMM        : u32 = 0x0F_FF << 20;
     pub const LOAD_IMM        : u32 = 0x0F_FF << 20;
     pub const STORE_IMM_11_5  : u32 = 0x7F    << 25;
     pub const STORE_IMM_4_0   : u32 = 0x1F    << 7;
     pub const BRANCH_IMM_12   : u32 = 0x1     << 31;
     pub const BRANCH_IMM_11   : u32 = 0x1     << 7;
-    pub const BRANCH_IMM_10_5 : u32 = 0x7F    << 25;
+    pub const BRANCH_IMM_10_5 : u32 = 0x3F    << 25;
     pub const BRANCH_IMM_4_1  : u32 = 0xF     << 8;
     pub const ALUI_IMM        : u32 = 0x0F_FF << 20;
+    pub const CSR             : u32 = 0x0F_FF << 20;
+    pub const AMO_F5          : u32 = 0x1F    << 27;
+    pub const AMO_AQ          : u32 = 0x1     << 26;
+    pub const AMO_RL          : u32 = 0x1     << 25;
+    pub const FP_F5           : u32 = 0x1F    << 27;
 
 
 
@@ -59,12 +70,22 @@ pub(crate) mod OP {
     pub const JAL    : u8 = 0x6F; // JAL
     pub const JALR   : u8 = 0x67; // JALR
     pub const BRANCH : u8 = 0x63; // BEQ, BNE, BLT, BGE, BLTU, BGEU
-    pub const LOAD   : u8 = 0x03; // LB, LH, LW, LBU, LHU
-    pub const STORE  : u8 = 0x02; // SB, SH, SW
+    pub const LOAD   : u8 = 0x03; // LB, LH, LW, LBU, LHU, LWU, LD
+    pub const STORE  : u8 = 0x23; // SB, SH, SW, SD
     pub const ALUI   : u8 = 0x13; // ADDI, SLTI, SLTIU, XORI, ORI, ANDI, SLLI, SRLI, SRAI
     pub const ALU    : u8 = 0x33; // ADD, SUB, SLL, SLT, SLTU, XOR, SRL, SRA, OR, AND
+    pub const ALUI_W : u8 = 0x1B; // ADDIW, SLLIW, SRLIW, SRAIW, RV64 only
+    pub const ALU_W  : u8 = 0x3B; // ADDW, SUBW, SLLW, SRLW, SRAW, RV64 only
     pub const FENCE  : u8 = 0x0F; // FENCE, FENCE.I
     pub const E_C    : u8 = 0x73; // ECALL, EBREAK, CSRRW, CSRRS, CSRRC, CSRRWI, CSRRSI, CSRRCI
+    pub const AMO    : u8 = 0x2F; // LR, SC, AMOSWAP, AMOADD, AMOXOR, AMOAND, AMOOR, AMOMIN, AMOMAX, AMOMINU, AMOMAXU; .W and .D
+    pub const LOAD_FP  : u8 = 0x07; // FLW, FLD
+    pub const STORE_FP : u8 = 0x27; // FSW, FSD
+    pub const MADD     : u8 = 0x43; // FMADD.S, FMADD.D
+    pub const MSUB     : u8 = 0x47; // FMSUB.S, FMSUB.D
+    pub const NMSUB    : u8 = 0x4B; // FNMSUB.S, FNMSUB.D
+    pub const NMADD    : u8 = 0x4F; // FNMADD.S, FNMADD.D
+    pub const OP_FP    : u8 = 0x53; // Every other F and D instruction
 }
 
 // Function 3 Codes
@@ -82,10 +103,13 @@ pub(crate) mod F3 {
     pub const LW       : u8 = 0x02;
     pub const LBU      : u8 = 0x04;
     pub const LHU      : u8 = 0x05;
+    pub const LWU      : u8 = 0x06; // RV64 only
+    pub const LD       : u8 = 0x03; // RV64 only
     
     pub const SB       : u8 = 0x00;
     pub const SH       : u8 = 0x01;
     pub const SW       : u8 = 0x02;
+    pub const SD       : u8 = 0x03; // RV64 only
     
     pub const ADDI     : u8 = 0x00;
     pub const SLTI     : u8 = 0x02;
@@ -131,6 +155,28 @@ pub(crate) mod F3 {
     pub const SRL_SLA  : u8 = 0x05; // check F7C
     pub const OR       : u8 = 0x06;
     pub const AND      : u8 = 0x07;
+
+    // Zba, Zbb, Zbc and Zbs, funct7 tells them apart from the base instructions
+    pub const SH1ADD   : u8 = 0x02;
+    pub const SH2ADD   : u8 = 0x04;
+    pub const SH3ADD   : u8 = 0x06;
+    pub const XNOR     : u8 = 0x04;
+    pub const ORN      : u8 = 0x06;
+    pub const ANDN     : u8 = 0x07;
+    pub const CLMUL    : u8 = 0x01;
+    pub const CLMULR   : u8 = 0x02;
+    pub const CLMULH   : u8 = 0x03;
+    pub const MIN      : u8 = 0x04;
+    pub const MINU     : u8 = 0x05;
+    pub const MAX      : u8 = 0x06;
+    pub const MAXU     : u8 = 0x07;
+    pub const ZEXT_H   : u8 = 0x04; // check rs2 is zero
+    pub const ROL      : u8 = 0x01;
+    pub const ROR      : u8 = 0x05;
+    pub const BCLR     : u8 = 0x01;
+    pub const BEXT     : u8 = 0x05;
+    pub const BINV     : u8 = 0x01;
+    pub const BSET     : u8 = 0x01;
     
     pub const FENCE    : u8 = 0x00;
     pub const FENCE_I  : u8 = 0x01;
@@ -143,26 +189,116 @@ pub(crate) mod F3 {
     pub const CSRRWI : u8 = 0x05;
     pub const CSRRSI : u8 = 0x06;
     pub const CSRRCI : u8 = 0x07;
+
+    // Doubleword atomics are RV64 only
+    pub const AMO_W  : u8 = 0x02;
+    pub const AMO_D  : u8 = 0x03;
+
+    pub const FLW    : u8 = 0x02;
+    pub const FLD    : u8 = 0x03;
+    pub const FSW    : u8 = 0x02;
+    pub const FSD    : u8 = 0x03;
+
+    // Rounding instructions have the rounding mode in funct3, 7 means the one in frm
+    pub const RM_DYN : u8 = 0x07;
+
+    // The others use it to tell apart instructions sharing a funct5
+    pub const FSGNJ  : u8 = 0x00;
+    pub const FSGNJN : u8 = 0x01;
+    pub const FSGNJX : u8 = 0x02;
+    pub const FMIN   : u8 = 0x00;
+    pub const FMAX   : u8 = 0x01;
+    pub const FLE    : u8 = 0x00;
+    pub const FLT    : u8 = 0x01;
+    pub const FEQ    : u8 = 0x02;
+    pub const FMV_X  : u8 = 0x00;
+    pub const FCLASS : u8 = 0x01;
 }
 
-// Function 7 codes
+// Function 5 codes, bits 31:27 of the A extension and OP-FP instructions. Bits 26 and 25
+// are aq and rl for the former, the format for the latter.
 #[allow(non_snake_case)]
-pub(crate) mod F7 {
-    pub const SRLI: u8 = 0x00;
-    pub const F7_SRAI: u8 = 0x08;
+pub(crate) mod F5 {
+    pub const AMOADD  : u8 = 0x00;
+    pub const AMOSWAP : u8 = 0x01;
+    pub const LR      : u8 = 0x02;
+    pub const SC      : u8 = 0x03;
+    pub const AMOXOR  : u8 = 0x04;
+    pub const AMOOR   : u8 = 0x08;
+    pub const AMOAND  : u8 = 0x0C;
+    pub const AMOMIN  : u8 = 0x10;
+    pub const AMOMAX  : u8 = 0x14;
+    pub const AMOMINU : u8 = 0x18;
+    pub const AMOMAXU : u8 = 0x1C;
 
-    pub const ADD: u8 = 0x00;
-    pub const SUB: u8 = 0x20;
+    pub const FADD      : u8 = 0x00;
+    pub const FSUB      : u8 = 0x01;
+    pub const FMUL      : u8 = 0x02;
+    pub const FDIV      : u8 = 0x03;
+    pub const FSGNJ     : u8 = 0x04; // FSGNJ, FSGNJN, FSGNJX
+    pub const FMIN_MAX  : u8 = 0x05;
+    pub const FCVT_FP   : u8 = 0x08; // FCVT.S.D, FCVT.D.S
+    pub const FSQRT     : u8 = 0x0B;
+    pub const FCMP      : u8 = 0x14; // FLE, FLT, FEQ
+    pub const FCVT_W    : u8 = 0x18; // FCVT.W(U), FCVT.L(U), from FP to integer
+    pub const FCVT_FROM_W : u8 = 0x1A; // FCVT.S.W(U), FCVT.D.W(U), FCVT.S.L(U), FCVT.D.L(U)
+    pub const FMV_X_CLASS : u8 = 0x1C; // FMV.X.W, FMV.X.D, FCLASS
+    pub const FMV_W_X   : u8 = 0x1E; // FMV.W.X, FMV.D.X
+}
 
-    // These codes are used for every M extension instruction
-    pub const M_EXTENSION: u8 = 0x33;
-    // W instructions are valid for RV64. We're only targeting RV32 for now
-    // pub const M_EXTENSION_W: u8 = 0x3B;
+// FP formats, in bits 26:25 of OP-FP and the fused multiply-adds. H and Q aren't supported.
+pub(crate) const FMT_S: u8 = 0x0;
+pub(crate) const FMT_D: u8 = 0x1;
 
-    pub const SRL: u8 = 0x00;
-    pub const SRA: u8 = 0x20;
+// Function 12 codes, used to tell the privileged instructions under ECALL_EBREAK apart
+#[allow(non_snake_case)]
+pub(crate) mod F12 {
+    pub const ECALL  : u16 = 0x000;
+    pub const EBREAK : u16 = 0x001;
+    pub const SRET   : u16 = 0x102;
+    pub const WFI    : u16 = 0x105;
+    pub const MRET   : u16 = 0x302;
 }
 
+// SFENCE.VMA only has funct7 fixed, rs2 and rs1 hold the ASID and address to flush
+pub(crate) const F7_SFENCE_VMA: u8 = 0x09;
+
+// Function 7 codes
+pub(crate) const F7_SRLI: u8 = 0x00;
+pub(crate) const F7_SRAI: u8 = 0x20;
+
+pub(crate) const F7_ADD: u8 = 0x00;
+pub(crate) const F7_SUB: u8 = 0x20;
+
+// These codes are used for every M extension instruction
+pub(crate) const F7_M_EXTENSION: u8 = 0x01;
+// W instructions are RV64 only, under the ALU_W opcode
+pub(crate) const F7_M_EXTENSION_W: u8 = 0x01;
+
+pub(crate) const F7_SRL: u8 = 0x00;
+pub(crate) const F7_SRA: u8 = 0x20;
+
+// Bit manipulation. The immediate forms use the same funct7 as their register counterparts
+pub(crate) const F7_SHADD: u8 = 0x10;
+pub(crate) const F7_NEGATED: u8 = 0x20; // ANDN, ORN, XNOR
+pub(crate) const F7_MINMAX_CLMUL: u8 = 0x05;
+pub(crate) const F7_ZEXT_H: u8 = 0x04;
+pub(crate) const F7_ADD_UW: u8 = 0x04; // ADD.UW, and SLLI.UW with shamt[5] in its lowest bit
+pub(crate) const F7_ROTATE: u8 = 0x30; // ROL, ROR, RORI, and the CLZ/CTZ/CPOP/SEXT group
+pub(crate) const F7_BSET: u8 = 0x14; // Also ORC.B
+pub(crate) const F7_BCLR: u8 = 0x24; // Also BEXT(I)
+pub(crate) const F7_BINV: u8 = 0x34; // Also REV8
+
+// The unary Zbb instructions are immediate shifts with a fixed shamt
+pub(crate) const SHAMT_CLZ: u32 = 0x00;
+pub(crate) const SHAMT_CTZ: u32 = 0x01;
+pub(crate) const SHAMT_CPOP: u32 = 0x02;
+pub(crate) const SHAMT_SEXT_B: u32 = 0x04;
+pub(crate) const SHAMT_SEXT_H: u32 = 0x05;
+pub(crate) const SHAMT_ORC_B: u32 = 0x07;
+pub(crate) const SHAMT_REV8: u32 = 0x18;
+pub(crate) const SHAMT_REV8_64: u32 = 0x38;
+
 pub(crate) const F73_ADD: u16 = ((F7_ADD as u16) << 3) | (F3::ADD_SUB as u16);
 pub(crate) const F73_SUB: u16 = ((F7_SUB as u16) << 3) | (F3::ADD_SUB as u16);
 pub(crate) const F73_SLL: u16 = ((0x0u16) << 3) | (F3::SLL as u16);
@@ -177,15 +313,36 @@ pub(crate) const F73_MUL: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::MUL as u16
 pub(crate) const F73_MULH: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::MULH as u16);
 pub(crate) const F73_MULHSU: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::MULHSU as u16);
 pub(crate) const F73_MULHU: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::MULHU as u16);
-// W instructions are valid for RV64. We're only targeting RV32
-// pub(crate) const F73_MULW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::MULW as u16);
+pub(crate) const F73_MULW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::MULW as u16);
 
 pub(crate) const F73_DIV: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::DIV as u16);
 pub(crate) const F73_DIVU: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::DIVU as u16);
-// W instructions are valid for RV64. We're only targeting RV32
-//pub(crate) const F73_DIVW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::DIVW as u16);
+pub(crate) const F73_DIVW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::DIVW as u16);
+pub(crate) const F73_DIVUW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::DIVUW as u16);
 
 pub(crate) const F73_REM: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::REM as u16);
 pub(crate) const F73_REMU: u16 = ((F7_M_EXTENSION as u16) << 3) | (F3::REMU as u16);
-// W instructions are valid for RV64. We're only targeting RV32
-//pub(crate) const F73_REMW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::REMW as u16);
+pub(crate) const F73_REMW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::REMW as u16);
+pub(crate) const F73_REMUW: u16 = ((F7_M_EXTENSION_W as u16) << 3) | (F3::REMUW as u16);
+
+pub(crate) const F73_SH1ADD: u16 = ((F7_SHADD as u16) << 3) | (F3::SH1ADD as u16);
+pub(crate) const F73_SH2ADD: u16 = ((F7_SHADD as u16) << 3) | (F3::SH2ADD as u16);
+pub(crate) const F73_SH3ADD: u16 = ((F7_SHADD as u16) << 3) | (F3::SH3ADD as u16);
+pub(crate) const F73_ANDN: u16 = ((F7_NEGATED as u16) << 3) | (F3::ANDN as u16);
+pub(crate) const F73_ORN: u16 = ((F7_NEGATED as u16) << 3) | (F3::ORN as u16);
+pub(crate) const F73_XNOR: u16 = ((F7_NEGATED as u16) << 3) | (F3::XNOR as u16);
+pub(crate) const F73_CLMUL: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::CLMUL as u16);
+pub(crate) const F73_CLMULR: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::CLMULR as u16);
+pub(crate) const F73_CLMULH: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::CLMULH as u16);
+pub(crate) const F73_MIN: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::MIN as u16);
+pub(crate) const F73_MINU: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::MINU as u16);
+pub(crate) const F73_MAX: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::MAX as u16);
+pub(crate) const F73_MAXU: u16 = ((F7_MINMAX_CLMUL as u16) << 3) | (F3::MAXU as u16);
+pub(crate) const F73_ZEXT_H: u16 = ((F7_ZEXT_H as u16) << 3) | (F3::ZEXT_H as u16);
+pub(crate) const F73_ADD_UW: u16 = ((F7_ADD_UW as u16) << 3) | (F3::ADD_SUB as u16);
+pub(crate) const F73_ROL: u16 = ((F7_ROTATE as u16) << 3) | (F3::ROL as u16);
+pub(crate) const F73_ROR: u16 = ((F7_ROTATE as u16) << 3) | (F3::ROR as u16);
+pub(crate) const F73_BCLR: u16 = ((F7_BCLR as u16) << 3) | (F3::BCLR as u16);
+pub(crate) const F73_BEXT: u16 = ((F7_BCLR as u16) << 3) | (F3::BEXT as u16);
+pub(crate) const F73_BINV: u16 = ((F7_BINV as u16) << 3) | (F3::BINV as u16);
+pub(crate) const F73_BSET: u16 = ((F7_BSET as u16) << 3) | (F3::BSET as u16);
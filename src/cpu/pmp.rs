@@ -0,0 +1,261 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Physical memory protection (PMP)
+ *
+ * 16 entries, each a configuration byte packed four to a pmpcfg CSR and an
+ * address in its own pmpaddr CSR, holding bits 33:2 of a physical address.
+ * An entry's A field picks how it matches:
+ *
+ *   OFF    nothing
+ *   TOR    [pmpaddr(i-1), pmpaddr(i)), from 0 for the first entry
+ *   NA4    the 4 bytes at pmpaddr
+ *   NAPOT  a naturally aligned power of two, sized by the trailing ones of pmpaddr
+ *
+ * The lowest numbered entry matching an access decides, and the access must
+ * fall entirely inside it. S and U-mode accesses no entry matches fail.
+ * M-mode only obeys locked entries, which also can't be changed until reset.
+ */
+
+use crate::cpu::csr::Privilege;
+use crate::cpu::mmu::Access;
+
+pub(crate) const PMP_ENTRIES: usize = 16;
+
+pub(crate) const PMPCFG0: u16 = 0x3A0;
+pub(crate) const PMPCFG3: u16 = 0x3A3;
+pub(crate) const PMPADDR0: u16 = 0x3B0;
+pub(crate) const PMPADDR15: u16 = 0x3BF;
+
+// pmpcfg fields
+pub(crate) const PMP_R: u8 = 1 << 0;
+pub(crate) const PMP_W: u8 = 1 << 1;
+pub(crate) const PMP_X: u8 = 1 << 2;
+pub(crate) const PMP_A: u8 = 0x3 << 3;
+pub(crate) const PMP_L: u8 = 1 << 7;
+const PMP_A_SHIFT: u8 = 3;
+// Bits 6:5 are reserved
+const PMP_CFG_MASK: u8 = PMP_R | PMP_W | PMP_X | PMP_A | PMP_L;
+
+// Values of the A field, 0 being OFF
+pub(crate) const PMP_TOR: u8 = 1;
+pub(crate) const PMP_NA4: u8 = 2;
+pub(crate) const PMP_NAPOT: u8 = 3;
+
+pub(crate) struct Pmp {
+    cfg: [u8; PMP_ENTRIES],
+    addr: [u32; PMP_ENTRIES],
+}
+
+impl Pmp {
+    pub(crate) fn new() -> Self {
+        Self {
+            cfg: [0; PMP_ENTRIES],
+            addr: [0; PMP_ENTRIES],
+        }
+    }
+
+    fn mode(&self, entry: usize) -> u8 {
+        (self.cfg[entry] & PMP_A) >> PMP_A_SHIFT
+    }
+
+    fn is_locked(&self, entry: usize) -> bool {
+        self.cfg[entry] & PMP_L != 0
+    }
+
+    // pmpaddr is also locked when the next entry is a locked TOR, since it's that entry's base
+    fn is_addr_locked(&self, entry: usize) -> bool {
+        self.is_locked(entry)
+            || (entry + 1 < PMP_ENTRIES && self.is_locked(entry + 1) && self.mode(entry + 1) == PMP_TOR)
+    }
+
+    pub(crate) fn read(&self, address: u16) -> Option<u32> {
+        match address {
+            PMPCFG0..=PMPCFG3 => {
+                let first = (address - PMPCFG0) as usize * 4;
+                Some(u32::from_le_bytes(self.cfg[first..first + 4].try_into().unwrap()))
+            }
+            PMPADDR0..=PMPADDR15 => Some(self.addr[(address - PMPADDR0) as usize]),
+            _ => None,
+        }
+    }
+
+    // Writes to locked entries are ignored
+    pub(crate) fn write(&mut self, address: u16, value: u32) -> Option<()> {
+        match address {
+            PMPCFG0..=PMPCFG3 => {
+                let first = (address - PMPCFG0) as usize * 4;
+                for (i, byte) in value.to_le_bytes().into_iter().enumerate() {
+                    let entry = first + i;
+                    if self.is_locked(entry) {
+                        continue;
+                    }
+                    let mut cfg = byte & PMP_CFG_MASK;
+                    // Write without read is reserved, the write permission is dropped
+                    if cfg & PMP_R == 0 {
+                        cfg &= !PMP_W;
+                    }
+                    self.cfg[entry] = cfg;
+                }
+            }
+            PMPADDR0..=PMPADDR15 => {
+                let entry = (address - PMPADDR0) as usize;
+                if !self.is_addr_locked(entry) {
+                    self.addr[entry] = value;
+                }
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // Physical address range [start, end) the entry covers, None if it's off.
+    // Addresses are 34 bits wide.
+    fn range(&self, entry: usize) -> Option<(u64, u64)> {
+        let addr = self.addr[entry] as u64;
+        match self.mode(entry) {
+            PMP_TOR => {
+                let start = if entry == 0 { 0 } else { (self.addr[entry - 1] as u64) << 2 };
+                Some((start, addr << 2))
+            }
+            PMP_NA4 => Some((addr << 2, (addr << 2) + 4)),
+            PMP_NAPOT => {
+                let ones = self.addr[entry].trailing_ones();
+                let start = (addr & !((1u64 << ones) - 1)) << 2;
+                Some((start, start + (8u64 << ones)))
+            }
+            _ => None,
+        }
+    }
+
+    // Whether an access of `size` bytes at physical address `address`, made from `privilege`, is allowed
+    pub(crate) fn check(&self, address: u32, size: u32, access: Access, privilege: Privilege) -> bool {
+        let (first, last) = (address as u64, address as u64 + size as u64);
+        for entry in 0..PMP_ENTRIES {
+            let Some((start, end)) = self.range(entry) else { continue };
+            if last <= start || first >= end {
+                continue;
+            }
+            // Matching only some of the bytes fails the access
+            if first < start || last > end {
+                return false;
+            }
+            if privilege == Privilege::Machine && !self.is_locked(entry) {
+                return true;
+            }
+            let permission = match access {
+                Access::Fetch => PMP_X,
+                Access::Load => PMP_R,
+                Access::Store => PMP_W,
+            };
+            return self.cfg[entry] & permission != 0;
+        }
+        privilege == Privilege::Machine
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::csr::Privilege;
+    use crate::cpu::mmu::Access;
+    use crate::cpu::pmp::*;
+
+    fn cfg(mode: u8, permissions: u8) -> u8 {
+        mode << PMP_A_SHIFT | permissions
+    }
+
+    fn set_cfg(pmp: &mut Pmp, entry: usize, value: u8) {
+        let address = PMPCFG0 + (entry / 4) as u16;
+        let shift = (entry % 4) * 8;
+        let old = pmp.read(address).unwrap() & !(0xFF << shift);
+        pmp.write(address, old | (value as u32) << shift).unwrap();
+    }
+
+    #[test]
+    fn test_no_entries() {
+        let pmp = Pmp::new();
+        assert!(pmp.check(0x1000, 4, Access::Load, Privilege::Machine));
+        assert!(!pmp.check(0x1000, 4, Access::Load, Privilege::Supervisor), "S/U need a matching entry");
+        assert!(!pmp.check(0x1000, 4, Access::Fetch, Privilege::User));
+    }
+
+    #[test]
+    fn test_napot() {
+        let mut pmp = Pmp::new();
+        // 4 KiB at 0x8000
+        pmp.write(PMPADDR0, (0x8000 >> 2) | 0x1FF).unwrap();
+        set_cfg(&mut pmp, 0, cfg(PMP_NAPOT, PMP_R | PMP_X));
+        assert!(pmp.check(0x8000, 4, Access::Load, Privilege::User));
+        assert!(pmp.check(0x8FFC, 4, Access::Fetch, Privilege::User));
+        assert!(!pmp.check(0x8000, 4, Access::Store, Privilege::User));
+        assert!(!pmp.check(0x9000, 4, Access::Load, Privilege::User));
+        assert!(!pmp.check(0x8FFE, 4, Access::Load, Privilege::User), "Partial matches fail");
+
+        // Everything
+        pmp.write(PMPADDR0, 0xFFFF_FFFF).unwrap();
+        assert!(pmp.check(0xFFFF_FFFC, 4, Access::Load, Privilege::User));
+    }
+
+    #[test]
+    fn test_tor_na4() {
+        let mut pmp = Pmp::new();
+        pmp.write(PMPADDR0, 0x1000 >> 2).unwrap();
+        pmp.write(PMPADDR0 + 1, 0x2000 >> 2).unwrap();
+        pmp.write(PMPADDR0 + 2, 0x3000 >> 2).unwrap();
+        set_cfg(&mut pmp, 1, cfg(PMP_TOR, PMP_R | PMP_W));
+        set_cfg(&mut pmp, 2, cfg(PMP_NA4, PMP_X));
+        assert!(!pmp.check(0x0FFC, 4, Access::Load, Privilege::Supervisor));
+        assert!(pmp.check(0x1000, 4, Access::Store, Privilege::Supervisor));
+        assert!(pmp.check(0x1FFF, 1, Access::Load, Privilege::Supervisor));
+        assert!(!pmp.check(0x2000, 1, Access::Load, Privilege::Supervisor));
+        assert!(pmp.check(0x3000, 4, Access::Fetch, Privilege::Supervisor));
+        assert!(!pmp.check(0x3004, 4, Access::Fetch, Privilege::Supervisor));
+
+        // Entry 0 as TOR starts from address 0
+        set_cfg(&mut pmp, 0, cfg(PMP_TOR, PMP_R));
+        assert!(pmp.check(0x0FFC, 4, Access::Load, Privilege::Supervisor));
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut pmp = Pmp::new();
+        pmp.write(PMPADDR0, 0x1000 >> 2).unwrap();
+        set_cfg(&mut pmp, 0, cfg(PMP_NA4, 0));
+        pmp.write(PMPADDR0 + 1, 0xFFFF_FFFF).unwrap();
+        set_cfg(&mut pmp, 1, cfg(PMP_NAPOT, PMP_R | PMP_W | PMP_X));
+        assert!(!pmp.check(0x1000, 4, Access::Load, Privilege::User), "The lowest entry wins");
+        assert!(pmp.check(0x1004, 4, Access::Load, Privilege::User));
+        assert!(pmp.check(0x1000, 4, Access::Load, Privilege::Machine), "M-mode ignores unlocked entries");
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut pmp = Pmp::new();
+        pmp.write(PMPADDR0, 0x1000 >> 2).unwrap();
+        pmp.write(PMPADDR0 + 1, 0x2000 >> 2).unwrap();
+        set_cfg(&mut pmp, 1, cfg(PMP_TOR, PMP_R) | PMP_L);
+        assert!(!pmp.check(0x1000, 4, Access::Store, Privilege::Machine), "Locked entries apply to M-mode");
+        assert!(pmp.check(0x1000, 4, Access::Load, Privilege::Machine));
+
+        set_cfg(&mut pmp, 1, 0);
+        assert_eq!(pmp.read(PMPCFG0), Some(((cfg(PMP_TOR, PMP_R) | PMP_L) as u32) << 8));
+        pmp.write(PMPADDR0 + 1, 0).unwrap();
+        pmp.write(PMPADDR0, 0).unwrap();
+        assert_eq!(pmp.read(PMPADDR0 + 1), Some(0x2000 >> 2));
+        assert_eq!(pmp.read(PMPADDR0), Some(0x1000 >> 2), "A locked TOR locks its base too");
+        // Other entries are still writable
+        pmp.write(PMPADDR0 + 2, 0x1234).unwrap();
+        assert_eq!(pmp.read(PMPADDR0 + 2), Some(0x1234));
+    }
+
+    #[test]
+    fn test_warl() {
+        let mut pmp = Pmp::new();
+        pmp.write(PMPCFG0, 0x7F).unwrap();
+        assert_eq!(pmp.read(PMPCFG0), Some(0x1F));
+        set_cfg(&mut pmp, 0, cfg(PMP_NAPOT, PMP_W));
+        assert_eq!(pmp.read(PMPCFG0), Some(cfg(PMP_NAPOT, 0) as u32), "W without R is reserved");
+        assert_eq!(pmp.read(PMPCFG3 + 1), None);
+    }
+}
@@ -14,8 +14,18 @@ pub const REG_S0:u8 = 8;
 pub const REG_S1:u8 = 9;
 pub const REG_S2:u8 = 18;
 
+// ABI names of the registers, indexed by register number
+pub(crate) const REG_ALIASES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+// The integer registers, 64 bits wide for RV64. RV32 values are kept sign-extended
+// to the full width, so that both widths compare and branch the same way.
 pub(crate) struct Register {
-    pub(crate) registers: [u32; 32],
+    pub(crate) registers: [u64; 32],
 }
 
 impl Register {
@@ -25,17 +35,72 @@ impl Register {
         }
     }
 
+    // Writes a 32-bit value, sign-extended. That's an RV32 register, and what
+    // the RV64 W instructions leave in theirs.
     pub fn set_register(&mut self, register: u8, value: u32) {
+        self.set_x(register, value as i32 as u64);
+    }
+
+    // The lower 32 bits, all there is on RV32
+    pub fn get_register(&self, register: u8) -> u32 {
+        self.get_x(register) as u32
+    }
+
+    // Writes all 64 bits. On RV32 the value has to be sign-extended already.
+    pub fn set_x(&mut self, register: u8, value: u64) {
         if register == 0 {
             return;
         }
         self.registers[register as usize] = value;
     }
 
-    pub fn get_register(&self, register: u8) -> u32 {
+    pub fn get_x(&self, register: u8) -> u64 {
         if register == 0 {
             return 0;
         }
         self.registers[register as usize]
     }
-}
\ No newline at end of file
+}
+
+// ABI names of the FP registers, indexed by register number
+pub(crate) const FREG_ALIASES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+    "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+    "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+// Single precision values are NaN-boxed: stored with the upper 32 bits all ones
+const NAN_BOX: u64 = 0xFFFF_FFFF << 32;
+const CANONICAL_NAN_F32: u32 = 0x7FC0_0000;
+
+// The FP registers, 64 bits wide for D. Unlike x0, f0 is a register like any other.
+pub(crate) struct FloatRegister {
+    pub(crate) registers: [u64; 32],
+}
+
+impl FloatRegister {
+    pub(crate) fn new() -> FloatRegister {
+        FloatRegister {
+            registers: [0; 32],
+        }
+    }
+
+    pub fn set_f64(&mut self, register: u8, value: u64) {
+        self.registers[register as usize] = value;
+    }
+
+    pub fn get_f64(&self, register: u8) -> u64 {
+        self.registers[register as usize]
+    }
+
+    pub fn set_f32(&mut self, register: u8, value: u32) {
+        self.registers[register as usize] = NAN_BOX | value as u64;
+    }
+
+    // A value that isn't properly NaN-boxed, like a double, reads as the canonical NaN
+    pub fn get_f32(&self, register: u8) -> u32 {
+        let value = self.registers[register as usize];
+        if value & NAN_BOX == NAN_BOX { value as u32 } else { CANONICAL_NAN_F32 }
+    }
+}
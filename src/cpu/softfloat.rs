@@ -0,0 +1,713 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* IEEE 754 binary32 and binary64 arithmetic, in software
+ *
+ * The host FPU only rounds to nearest and doesn't report exception flags, so
+ * every operation F and D need is done here on the raw bits, the RISC-V way:
+ *
+ *   - results are rounded in any of the five rounding modes
+ *   - tininess is detected after rounding
+ *   - NaN results are always the canonical NaN, payloads aren't propagated
+ *
+ * Both formats are held in a u64, a binary32 in the low 32 bits. Finite values
+ * are unpacked into sig * 2^exp, with an integer significand wide enough for
+ * the operation to be exact, and rounded once at the end.
+ */
+
+use std::cmp::Ordering;
+
+// fflags bits
+pub(crate) const FLAG_NX: u32 = 1 << 0; // Inexact
+pub(crate) const FLAG_UF: u32 = 1 << 1; // Underflow
+pub(crate) const FLAG_OF: u32 = 1 << 2; // Overflow
+pub(crate) const FLAG_DZ: u32 = 1 << 3; // Divide by zero
+pub(crate) const FLAG_NV: u32 = 1 << 4; // Invalid operation
+
+// Rounding modes, encoded as in frm and the rm field of instructions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RoundingMode {
+    NearestEven = 0,
+    TowardZero = 1,
+    Down = 2,
+    Up = 3,
+    NearestMaxMagnitude = 4,
+}
+
+impl RoundingMode {
+    // 5 and 6 are reserved, 7 means "use frm" and isn't a mode of its own
+    pub(crate) fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            0 => Some(RoundingMode::NearestEven),
+            1 => Some(RoundingMode::TowardZero),
+            2 => Some(RoundingMode::Down),
+            3 => Some(RoundingMode::Up),
+            4 => Some(RoundingMode::NearestMaxMagnitude),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Format {
+    exp_bits: u32,
+    frac_bits: u32,
+}
+
+pub(crate) const F32: Format = Format { exp_bits: 8, frac_bits: 23 };
+pub(crate) const F64: Format = Format { exp_bits: 11, frac_bits: 52 };
+
+impl Format {
+    const fn bias(self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    // Exponent of the smallest normal number
+    const fn min_exp(self) -> i32 {
+        1 - self.bias()
+    }
+
+    const fn max_biased(self) -> u64 {
+        (1 << self.exp_bits) - 1
+    }
+
+    const fn frac_mask(self) -> u64 {
+        (1 << self.frac_bits) - 1
+    }
+
+    pub(crate) const fn sign_bit(self) -> u64 {
+        1 << (self.exp_bits + self.frac_bits)
+    }
+
+    const fn quiet_bit(self) -> u64 {
+        1 << (self.frac_bits - 1)
+    }
+
+    pub(crate) const fn canonical_nan(self) -> u64 {
+        self.max_biased() << self.frac_bits | self.quiet_bit()
+    }
+
+    const fn signed(self, sign: bool, bits: u64) -> u64 {
+        if sign { bits | self.sign_bit() } else { bits }
+    }
+
+    const fn zero(self, sign: bool) -> u64 {
+        self.signed(sign, 0)
+    }
+
+    const fn infinity(self, sign: bool) -> u64 {
+        self.signed(sign, self.max_biased() << self.frac_bits)
+    }
+
+    const fn max_finite(self, sign: bool) -> u64 {
+        self.signed(sign, (self.max_biased() - 1) << self.frac_bits | self.frac_mask())
+    }
+
+    pub(crate) fn is_nan(self, a: u64) -> bool {
+        a & !self.sign_bit() > self.infinity(false)
+    }
+
+    fn is_signaling(self, a: u64) -> bool {
+        self.is_nan(a) && a & self.quiet_bit() == 0
+    }
+
+    fn unpack(self, a: u64) -> (bool, Value) {
+        let sign = a & self.sign_bit() != 0;
+        let biased = (a >> self.frac_bits) & self.max_biased();
+        let frac = a & self.frac_mask();
+        let value = if biased == self.max_biased() {
+            if frac == 0 { Value::Infinity } else { Value::NaN }
+        } else if biased == 0 {
+            if frac == 0 { Value::Zero } else { Value::Finite(frac as u128, self.min_exp() - self.frac_bits as i32) }
+        } else {
+            let sig = frac | 1 << self.frac_bits;
+            Value::Finite(sig as u128, biased as i32 - self.bias() - self.frac_bits as i32)
+        };
+        (sign, value)
+    }
+}
+
+// An unpacked operand, a finite one being sig * 2^exp
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+    Zero,
+    Finite(u128, i32),
+    Infinity,
+    NaN,
+}
+
+// Position of the highest set bit
+fn msb(sig: u128) -> i32 {
+    127 - sig.leading_zeros() as i32
+}
+
+// Floor of the square root
+fn isqrt(mut n: u128) -> u128 {
+    let mut root = 0;
+    let mut bit = 1 << 126;
+    while bit > n {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if n >= root + bit {
+            n -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+// Exact sum of two signed values, as sign, sig * 2^exp and sticky, sticky meaning
+// the true value lies strictly between sig and sig + 1. None if it's exactly zero.
+// The significands must fit in 110 bits.
+fn add_parts(a: (bool, u128, i32), b: (bool, u128, i32)) -> Option<(bool, u128, i32, bool)> {
+    let top = |(_, sig, exp): (bool, u128, i32)| msb(sig) + exp;
+    let (mut big, small) = if top(b) > top(a) { (b, a) } else { (a, b) };
+    // Line the larger one up at bit 125, leaving room for a carry
+    let up = 125 - msb(big.1);
+    big.1 <<= up;
+    big.2 -= up;
+
+    let shift = small.2 - big.2;
+    let (small_sig, sticky) = if shift >= 0 {
+        (small.1 << shift, false)
+    } else if shift > -128 {
+        let shift = -shift as u32;
+        (small.1 >> shift, small.1 & ((1 << shift) - 1) != 0)
+    } else {
+        (0, true)
+    };
+
+    if big.0 == small.0 {
+        return Some((big.0, big.1 + small_sig, big.2, sticky));
+    }
+    // Whatever got shifted out is taken away too, from one lower
+    if sticky {
+        return Some((big.0, big.1 - small_sig - 1, big.2, true));
+    }
+    match big.1.cmp(&small_sig) {
+        Ordering::Equal => None,
+        Ordering::Greater => Some((big.0, big.1 - small_sig, big.2, false)),
+        Ordering::Less => Some((small.0, small_sig - big.1, big.2, false)),
+    }
+}
+
+// Operations round in `rounding` and accumulate exception flags in `flags`
+pub(crate) struct SoftFloat {
+    pub(crate) rounding: RoundingMode,
+    pub(crate) flags: u32,
+}
+
+impl SoftFloat {
+    pub(crate) fn new(rounding: RoundingMode) -> Self {
+        Self { rounding, flags: 0 }
+    }
+
+    // Any NaN operand makes the result the canonical NaN, signaling ones are also invalid
+    fn nan(&mut self, format: Format, operands: &[u64]) -> u64 {
+        if operands.iter().any(|&a| format.is_signaling(a)) {
+            self.flags |= FLAG_NV;
+        }
+        format.canonical_nan()
+    }
+
+    fn invalid(&mut self, format: Format) -> u64 {
+        self.flags |= FLAG_NV;
+        format.canonical_nan()
+    }
+
+    // An exact zero result of a sum of values with different signs is +0, or -0 rounding down
+    fn zero_sum(&self, format: Format) -> u64 {
+        format.zero(self.rounding == RoundingMode::Down)
+    }
+
+    // Drops the lowest `shift` bits of sig, rounding the rest. Returns the
+    // rounded value and whether it's inexact.
+    fn round_shift(&self, sign: bool, sig: u128, shift: u32, sticky: bool) -> (u128, bool) {
+        let (kept, half, rest) = match shift {
+            0 => (sig, false, sticky),
+            1..=127 => (sig >> shift, (sig >> (shift - 1)) & 1 != 0, sticky || sig & ((1 << (shift - 1)) - 1) != 0),
+            _ => (0, false, sticky || sig != 0),
+        };
+        let inexact = half || rest;
+        let increment = match self.rounding {
+            RoundingMode::NearestEven => half && (rest || kept & 1 != 0),
+            RoundingMode::NearestMaxMagnitude => half,
+            RoundingMode::TowardZero => false,
+            RoundingMode::Down => sign && inexact,
+            RoundingMode::Up => !sign && inexact,
+        };
+        (kept + increment as u128, inexact)
+    }
+
+    // Rounds sig * 2^exp (plus a bit more if sticky) to the format. With sticky
+    // set, sig must have at least two bits more than the format's precision.
+    fn round_pack(&mut self, format: Format, sign: bool, sig: u128, exp: i32, sticky: bool) -> u64 {
+        if sig == 0 {
+            return format.zero(sign);
+        }
+        let precision = format.frac_bits as i32;
+        let top = msb(sig) + exp;
+        // Weight of the last bit kept, which can't go below that of the subnormals
+        let lsb = (top - precision).max(format.min_exp() - precision);
+        let shift = lsb - exp;
+        let (mut kept, inexact) = if shift > 0 {
+            self.round_shift(sign, sig, shift as u32, sticky)
+        } else {
+            (sig << -shift, sticky)
+        };
+        let mut lsb = lsb;
+        // Rounding up carried into a new bit
+        if kept >> (precision + 1) != 0 {
+            kept >>= 1;
+            lsb += 1;
+        }
+
+        if inexact {
+            self.flags |= FLAG_NX;
+            // Tiny if it would still be below the normal range rounded with an unbounded
+            // exponent. Only values just below it can round up into it.
+            let tiny = top < format.min_exp() && {
+                let shift = top - precision - exp;
+                let rounded = if shift > 0 { self.round_shift(sign, sig, shift as u32, sticky).0 } else { sig };
+                top < format.min_exp() - 1 || rounded >> (precision + 1) == 0
+            };
+            if tiny {
+                self.flags |= FLAG_UF;
+            }
+        }
+
+        let biased = if kept >> precision != 0 { (lsb + precision + format.bias()) as u64 } else { 0 };
+        if biased >= format.max_biased() {
+            self.flags |= FLAG_OF | FLAG_NX;
+            let infinite = match self.rounding {
+                RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+                RoundingMode::TowardZero => false,
+                RoundingMode::Down => sign,
+                RoundingMode::Up => !sign,
+            };
+            return if infinite { format.infinity(sign) } else { format.max_finite(sign) };
+        }
+        format.signed(sign, biased << format.frac_bits | (kept as u64 & format.frac_mask()))
+    }
+
+    pub(crate) fn add(&mut self, format: Format, a: u64, b: u64) -> u64 {
+        let ((sign_a, value_a), (sign_b, value_b)) = (format.unpack(a), format.unpack(b));
+        match (value_a, value_b) {
+            (Value::NaN, _) | (_, Value::NaN) => self.nan(format, &[a, b]),
+            (Value::Infinity, Value::Infinity) if sign_a != sign_b => self.invalid(format),
+            (Value::Infinity, _) => a,
+            (_, Value::Infinity) => b,
+            (Value::Zero, Value::Zero) if sign_a != sign_b => self.zero_sum(format),
+            (_, Value::Zero) => a,
+            (Value::Zero, _) => b,
+            (Value::Finite(sig_a, exp_a), Value::Finite(sig_b, exp_b)) => {
+                match add_parts((sign_a, sig_a, exp_a), (sign_b, sig_b, exp_b)) {
+                    Some((sign, sig, exp, sticky)) => self.round_pack(format, sign, sig, exp, sticky),
+                    None => self.zero_sum(format),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn sub(&mut self, format: Format, a: u64, b: u64) -> u64 {
+        self.add(format, a, b ^ format.sign_bit())
+    }
+
+    pub(crate) fn mul(&mut self, format: Format, a: u64, b: u64) -> u64 {
+        let ((sign_a, value_a), (sign_b, value_b)) = (format.unpack(a), format.unpack(b));
+        let sign = sign_a != sign_b;
+        match (value_a, value_b) {
+            (Value::NaN, _) | (_, Value::NaN) => self.nan(format, &[a, b]),
+            (Value::Infinity, Value::Zero) | (Value::Zero, Value::Infinity) => self.invalid(format),
+            (Value::Infinity, _) | (_, Value::Infinity) => format.infinity(sign),
+            (Value::Zero, _) | (_, Value::Zero) => format.zero(sign),
+            (Value::Finite(sig_a, exp_a), Value::Finite(sig_b, exp_b)) => {
+                self.round_pack(format, sign, sig_a * sig_b, exp_a + exp_b, false)
+            }
+        }
+    }
+
+    pub(crate) fn div(&mut self, format: Format, a: u64, b: u64) -> u64 {
+        let ((sign_a, value_a), (sign_b, value_b)) = (format.unpack(a), format.unpack(b));
+        let sign = sign_a != sign_b;
+        match (value_a, value_b) {
+            (Value::NaN, _) | (_, Value::NaN) => self.nan(format, &[a, b]),
+            (Value::Infinity, Value::Infinity) | (Value::Zero, Value::Zero) => self.invalid(format),
+            (Value::Infinity, _) => format.infinity(sign),
+            (_, Value::Infinity) | (Value::Zero, _) => format.zero(sign),
+            (_, Value::Zero) => {
+                self.flags |= FLAG_DZ;
+                format.infinity(sign)
+            }
+            (Value::Finite(sig_a, exp_a), Value::Finite(sig_b, exp_b)) => {
+                // With both significands at bit 63, the quotient has 64 or 65 bits
+                let (shift_a, shift_b) = (63 - msb(sig_a), 63 - msb(sig_b));
+                let (sig_a, sig_b) = (sig_a << shift_a << 64, sig_b << shift_b);
+                let exp = (exp_a - shift_a - 64) - (exp_b - shift_b);
+                self.round_pack(format, sign, sig_a / sig_b, exp, sig_a % sig_b != 0)
+            }
+        }
+    }
+
+    pub(crate) fn sqrt(&mut self, format: Format, a: u64) -> u64 {
+        match format.unpack(a) {
+            (_, Value::NaN) => self.nan(format, &[a]),
+            // Square root of -0 is -0
+            (_, Value::Zero) => a,
+            (true, _) => self.invalid(format),
+            (false, Value::Infinity) => a,
+            (false, Value::Finite(sig, exp)) => {
+                // Widen to 125 or 126 bits, whichever makes the exponent even
+                let mut shift = 125 - msb(sig);
+                if (exp - shift) % 2 != 0 {
+                    shift += 1;
+                }
+                let wide = sig << shift;
+                let root = isqrt(wide);
+                self.round_pack(format, false, root, (exp - shift) / 2, root * root != wide)
+            }
+        }
+    }
+
+    // a * b + c with a single rounding. The other three fused forms negate a and/or c first.
+    pub(crate) fn fma(&mut self, format: Format, a: u64, b: u64, c: u64) -> u64 {
+        let ((sign_a, value_a), (sign_b, value_b), (sign_c, value_c)) = (format.unpack(a), format.unpack(b), format.unpack(c));
+        let sign = sign_a != sign_b;
+        match (value_a, value_b) {
+            // Invalid even if c is a quiet NaN
+            (Value::Infinity, Value::Zero) | (Value::Zero, Value::Infinity) => return self.invalid(format),
+            (Value::NaN, _) | (_, Value::NaN) => return self.nan(format, &[a, b, c]),
+            _ => {}
+        }
+        match (value_a, value_b, value_c) {
+            (_, _, Value::NaN) => self.nan(format, &[c]),
+            (Value::Infinity, _, Value::Infinity) | (_, Value::Infinity, Value::Infinity) if sign != sign_c => self.invalid(format),
+            (Value::Infinity, _, _) | (_, Value::Infinity, _) => format.infinity(sign),
+            (_, _, Value::Infinity) => c,
+            (Value::Zero, _, Value::Zero) | (_, Value::Zero, Value::Zero) if sign != sign_c => self.zero_sum(format),
+            (Value::Zero, _, _) | (_, Value::Zero, _) => c,
+            (Value::Finite(sig_a, exp_a), Value::Finite(sig_b, exp_b), Value::Zero) => {
+                self.round_pack(format, sign, sig_a * sig_b, exp_a + exp_b, false)
+            }
+            (Value::Finite(sig_a, exp_a), Value::Finite(sig_b, exp_b), Value::Finite(sig_c, exp_c)) => {
+                match add_parts((sign, sig_a * sig_b, exp_a + exp_b), (sign_c, sig_c, exp_c)) {
+                    Some((sign, sig, exp, sticky)) => self.round_pack(format, sign, sig, exp, sticky),
+                    None => self.zero_sum(format),
+                }
+            }
+            _ => unreachable!("NaN operands are handled above"),
+        }
+    }
+
+    // None if either is a NaN. Signaling comparisons are invalid on any NaN, quiet ones only on signaling NaNs.
+    fn compare(&mut self, format: Format, a: u64, b: u64, signaling: bool) -> Option<Ordering> {
+        if format.is_nan(a) || format.is_nan(b) {
+            if signaling || format.is_signaling(a) || format.is_signaling(b) {
+                self.flags |= FLAG_NV;
+            }
+            return None;
+        }
+        // The bits of the magnitude order like integers, and both zeros are equal
+        let key = |x: u64| {
+            let magnitude = (x & !format.sign_bit()) as i64;
+            if x & format.sign_bit() != 0 { -magnitude } else { magnitude }
+        };
+        Some(key(a).cmp(&key(b)))
+    }
+
+    pub(crate) fn eq(&mut self, format: Format, a: u64, b: u64) -> bool {
+        self.compare(format, a, b, false) == Some(Ordering::Equal)
+    }
+
+    pub(crate) fn lt(&mut self, format: Format, a: u64, b: u64) -> bool {
+        self.compare(format, a, b, true) == Some(Ordering::Less)
+    }
+
+    pub(crate) fn le(&mut self, format: Format, a: u64, b: u64) -> bool {
+        matches!(self.compare(format, a, b, true), Some(Ordering::Less | Ordering::Equal))
+    }
+
+    // FMIN/FMAX: a NaN operand gives the other one, -0 is smaller than +0
+    pub(crate) fn min_max(&mut self, format: Format, a: u64, b: u64, max: bool) -> u64 {
+        if format.is_signaling(a) || format.is_signaling(b) {
+            self.flags |= FLAG_NV;
+        }
+        match (format.is_nan(a), format.is_nan(b)) {
+            (true, true) => return format.canonical_nan(),
+            (true, false) => return b,
+            (false, true) => return a,
+            _ => {}
+        }
+        let a_first = match self.compare(format, a, b, false) {
+            Some(Ordering::Equal) => (a & format.sign_bit() != 0) != max,
+            ordering => (ordering == Some(Ordering::Less)) != max,
+        };
+        if a_first { a } else { b }
+    }
+
+    // FCVT between formats, exact when widening
+    pub(crate) fn convert(&mut self, from: Format, to: Format, a: u64) -> u64 {
+        match from.unpack(a) {
+            (_, Value::NaN) => {
+                self.nan(from, &[a]);
+                to.canonical_nan()
+            }
+            (sign, Value::Zero) => to.zero(sign),
+            (sign, Value::Infinity) => to.infinity(sign),
+            (sign, Value::Finite(sig, exp)) => self.round_pack(to, sign, sig, exp, false),
+        }
+    }
+
+    // FCVT.W, FCVT.WU, FCVT.L and FCVT.LU, to a bits wide integer. Out of range values and NaNs are invalid, and saturate.
+    pub(crate) fn float_to_int(&mut self, format: Format, a: u64, signed: bool, bits: u32) -> u64 {
+        let (sign, value) = format.unpack(a);
+        let mask = u64::MAX >> (64 - bits);
+        let (max, min) = if signed { (mask >> 1, !(mask >> 1) & mask) } else { (mask, 0) };
+        let (magnitude, inexact) = match value {
+            // NaNs convert to the largest integer, whatever their sign
+            Value::NaN => {
+                self.flags |= FLAG_NV;
+                return max;
+            }
+            Value::Infinity => (u128::MAX, false),
+            Value::Zero => (0, false),
+            // Anything with more than 2^64 is out of range anyway
+            Value::Finite(_, exp) if exp > 64 => (u128::MAX, false),
+            Value::Finite(sig, exp) if exp >= 0 => (sig << exp, false),
+            Value::Finite(sig, exp) => self.round_shift(sign, sig, -exp as u32, false),
+        };
+        let limit = if sign { if signed { 1 << (bits - 1) } else { 0 } } else { max as u128 };
+        if magnitude > limit {
+            self.flags |= FLAG_NV;
+            return if sign { min } else { max };
+        }
+        if inexact {
+            self.flags |= FLAG_NX;
+        }
+        if sign { (magnitude as u64).wrapping_neg() & mask } else { magnitude as u64 }
+    }
+
+    // FCVT.S.W, FCVT.S.WU, FCVT.S.L, FCVT.S.LU and their D counterparts; signed words come sign-extended
+    pub(crate) fn int_to_float(&mut self, format: Format, value: u64, signed: bool) -> u64 {
+        let sign = signed && (value as i64) < 0;
+        let magnitude = if sign { (value as i64).unsigned_abs() } else { value };
+        self.round_pack(format, sign, magnitude as u128, 0, false)
+    }
+}
+
+// FCLASS: a mask with the one bit set that describes the value
+pub(crate) fn classify(format: Format, a: u64) -> u32 {
+    let (sign, value) = format.unpack(a);
+    let subnormal = (a >> format.frac_bits) & format.max_biased() == 0;
+    let bit = match value {
+        Value::Infinity => if sign { 0 } else { 7 },
+        Value::Finite(..) if subnormal => if sign { 2 } else { 5 },
+        Value::Finite(..) => if sign { 1 } else { 6 },
+        Value::Zero => if sign { 3 } else { 4 },
+        Value::NaN if format.is_signaling(a) => 8,
+        Value::NaN => 9,
+    };
+    1 << bit
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::softfloat::*;
+
+    const ONE: u64 = 0x3F80_0000;
+    const TWO: u64 = 0x4000_0000;
+    const MAX_F32: u64 = 0x7F7F_FFFF;
+    const MIN_SUBNORMAL: u64 = 0x0000_0001;
+    const MIN_NORMAL: u64 = 0x0080_0000;
+    const INF: u64 = 0x7F80_0000;
+    const SNAN: u64 = 0x7F80_0001;
+    const QNAN: u64 = 0x7FC0_1234;
+
+    fn f32_bits(value: f32) -> u64 {
+        value.to_bits() as u64
+    }
+
+    fn f64_bits(value: f64) -> u64 {
+        value.to_bits()
+    }
+
+    // Result and flags of an operation in the given rounding mode
+    fn run(rounding: RoundingMode, op: impl FnOnce(&mut SoftFloat) -> u64) -> (u64, u32) {
+        let mut fp = SoftFloat::new(rounding);
+        let result = op(&mut fp);
+        (result, fp.flags)
+    }
+
+    fn nearest(op: impl FnOnce(&mut SoftFloat) -> u64) -> (u64, u32) {
+        run(RoundingMode::NearestEven, op)
+    }
+
+    #[test]
+    fn test_add() {
+        assert_eq!(nearest(|fp| fp.add(F32, f32_bits(2.5), f32_bits(1.0))), (f32_bits(3.5), 0));
+        assert_eq!(nearest(|fp| fp.add(F64, f64_bits(0.1), f64_bits(0.2))), (f64_bits(0.1 + 0.2), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.sub(F32, f32_bits(1.0), f32_bits(1.0))), (0, 0));
+        assert_eq!(run(RoundingMode::Down, |fp| fp.sub(F32, f32_bits(1.0), f32_bits(1.0))), (f32_bits(-0.0), 0));
+        assert_eq!(nearest(|fp| fp.add(F32, INF, f32_bits(-1.0))), (INF, 0));
+        assert_eq!(nearest(|fp| fp.sub(F32, INF, INF)), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.add(F32, f32_bits(-0.0), f32_bits(-0.0))), (f32_bits(-0.0), 0));
+        // A tiny addend only shows in the last bit when rounding away from it
+        assert_eq!(nearest(|fp| fp.add(F32, ONE, MIN_SUBNORMAL)), (ONE, FLAG_NX));
+        assert_eq!(run(RoundingMode::Up, |fp| fp.add(F32, ONE, MIN_SUBNORMAL)), (ONE + 1, FLAG_NX));
+        assert_eq!(run(RoundingMode::TowardZero, |fp| fp.sub(F32, ONE, MIN_SUBNORMAL)), (0x3F7F_FFFF, FLAG_NX));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // As a double, 1 + 2^-24 is exact, and a tie between 1 and 1 + 2^-23 in single
+        let tie = f64_bits(1.0 + f64::from(f32::EPSILON) / 2.0);
+        let cases = [
+            (RoundingMode::NearestEven, ONE, f32_bits(-1.0)),
+            (RoundingMode::NearestMaxMagnitude, ONE + 1, f32_bits(-1.0) + 1),
+            (RoundingMode::TowardZero, ONE, f32_bits(-1.0)),
+            (RoundingMode::Down, ONE, f32_bits(-1.0) + 1),
+            (RoundingMode::Up, ONE + 1, f32_bits(-1.0)),
+        ];
+        for (rounding, positive, negative) in cases {
+            assert_eq!(run(rounding, |fp| fp.convert(F64, F32, tie)), (positive, FLAG_NX), "{:?}", rounding);
+            assert_eq!(run(rounding, |fp| fp.convert(F64, F32, tie | F64.sign_bit())), (negative, FLAG_NX), "{:?}", rounding);
+        }
+    }
+
+    #[test]
+    fn test_overflow_underflow() {
+        assert_eq!(nearest(|fp| fp.mul(F32, MAX_F32, TWO)), (INF, FLAG_OF | FLAG_NX));
+        assert_eq!(run(RoundingMode::TowardZero, |fp| fp.mul(F32, MAX_F32, TWO)), (MAX_F32, FLAG_OF | FLAG_NX));
+        assert_eq!(run(RoundingMode::Down, |fp| fp.mul(F32, MAX_F32, TWO)), (MAX_F32, FLAG_OF | FLAG_NX));
+        // Exact subnormal results don't underflow
+        assert_eq!(nearest(|fp| fp.div(F32, MIN_NORMAL, TWO)), (MIN_NORMAL >> 1, 0));
+        assert_eq!(nearest(|fp| fp.div(F32, MIN_SUBNORMAL, TWO)), (0, FLAG_UF | FLAG_NX));
+        assert_eq!(run(RoundingMode::Up, |fp| fp.div(F32, MIN_SUBNORMAL, TWO)), (MIN_SUBNORMAL, FLAG_UF | FLAG_NX));
+        // Rounds up to the smallest normal, which isn't tiny after rounding
+        let below = f64_bits(f64::from(f32::MIN_POSITIVE) * (1.0 - f64::EPSILON * 64.0));
+        assert_eq!(nearest(|fp| fp.convert(F64, F32, below)), (MIN_NORMAL, FLAG_NX));
+        assert_eq!(run(RoundingMode::TowardZero, |fp| fp.convert(F64, F32, below)), (MIN_NORMAL - 1, FLAG_UF | FLAG_NX));
+    }
+
+    #[test]
+    fn test_div_sqrt() {
+        assert_eq!(nearest(|fp| fp.div(F32, ONE, f32_bits(3.0))), (f32_bits(1.0 / 3.0), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.div(F64, f64_bits(-1.0), 0)), (f64_bits(f64::NEG_INFINITY), FLAG_DZ));
+        assert_eq!(nearest(|fp| fp.div(F32, 0, 0)), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.sqrt(F64, f64_bits(2.0))), (f64_bits(2f64.sqrt()), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.sqrt(F32, f32_bits(6.25))), (f32_bits(2.5), 0));
+        assert_eq!(nearest(|fp| fp.sqrt(F32, f32_bits(-0.0))), (f32_bits(-0.0), 0));
+        assert_eq!(nearest(|fp| fp.sqrt(F32, f32_bits(-1.0))), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.sqrt(F32, MIN_SUBNORMAL)), (f32_bits(f32::from_bits(1).sqrt()), FLAG_NX));
+    }
+
+    #[test]
+    fn test_fma() {
+        // Exactly 2^-54, where rounding the product first would give 0
+        assert_eq!(nearest(|fp| fp.fma(F64, f64_bits(0.1), f64_bits(10.0), f64_bits(-1.0))), (f64_bits(2f64.powi(-54)), 0));
+        let (a, b, c) = (0.1f64, 0.3f64, -0.7f64);
+        assert_eq!(nearest(|fp| fp.fma(F64, f64_bits(a), f64_bits(b), f64_bits(c))), (f64_bits(a.mul_add(b, c)), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.fma(F32, INF, 0, QNAN)), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.fma(F32, ONE, ONE, f32_bits(-1.0))), (0, 0));
+        assert_eq!(run(RoundingMode::Down, |fp| fp.fma(F32, ONE, ONE, f32_bits(-1.0))), (f32_bits(-0.0), 0));
+        assert_eq!(nearest(|fp| fp.fma(F32, INF, ONE, INF | F32.sign_bit())), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.fma(F32, 0, ONE, f32_bits(-2.0))), (f32_bits(-2.0), 0));
+    }
+
+    #[test]
+    fn test_nan() {
+        assert_eq!(nearest(|fp| fp.add(F32, QNAN, ONE)), (F32.canonical_nan(), 0));
+        assert_eq!(nearest(|fp| fp.mul(F32, ONE, SNAN)), (F32.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.convert(F32, F64, SNAN)), (F64.canonical_nan(), FLAG_NV));
+        assert_eq!(nearest(|fp| fp.convert(F64, F32, F64.canonical_nan())), (F32.canonical_nan(), 0));
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut fp = SoftFloat::new(RoundingMode::NearestEven);
+        assert!(fp.eq(F32, 0, f32_bits(-0.0)));
+        assert!(fp.lt(F32, f32_bits(-2.0), f32_bits(-1.0)));
+        assert!(fp.le(F32, ONE, ONE));
+        assert!(!fp.lt(F32, ONE, ONE));
+        assert_eq!(fp.flags, 0);
+        assert!(!fp.eq(F32, QNAN, QNAN));
+        assert_eq!(fp.flags, 0, "Quiet comparisons only trip on signaling NaNs");
+        assert!(!fp.le(F32, QNAN, ONE));
+        assert_eq!(fp.flags, FLAG_NV);
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(nearest(|fp| fp.min_max(F32, ONE, TWO, false)), (ONE, 0));
+        assert_eq!(nearest(|fp| fp.min_max(F32, ONE, TWO, true)), (TWO, 0));
+        assert_eq!(nearest(|fp| fp.min_max(F32, 0, f32_bits(-0.0), false)), (f32_bits(-0.0), 0));
+        assert_eq!(nearest(|fp| fp.min_max(F32, f32_bits(-0.0), 0, true)), (0, 0));
+        assert_eq!(nearest(|fp| fp.min_max(F32, QNAN, ONE, false)), (ONE, 0));
+        assert_eq!(nearest(|fp| fp.min_max(F32, SNAN, ONE, true)), (ONE, FLAG_NV));
+        assert_eq!(nearest(|fp| fp.min_max(F32, QNAN, SNAN, true)), (F32.canonical_nan(), FLAG_NV));
+    }
+
+    #[test]
+    fn test_to_int() {
+        let to_int = |rounding, format, a, signed| {
+            let mut fp = SoftFloat::new(rounding);
+            (fp.float_to_int(format, a, signed, 32) as u32, fp.flags)
+        };
+        assert_eq!(to_int(RoundingMode::NearestEven, F32, f32_bits(2.5), true), (2, FLAG_NX));
+        assert_eq!(to_int(RoundingMode::NearestMaxMagnitude, F32, f32_bits(-2.5), true), (-3i32 as u32, FLAG_NX));
+        assert_eq!(to_int(RoundingMode::Down, F64, f64_bits(-0.5), true), (-1i32 as u32, FLAG_NX));
+        assert_eq!(to_int(RoundingMode::TowardZero, F64, f64_bits(-0.5), false), (0, FLAG_NX));
+        assert_eq!(to_int(RoundingMode::NearestEven, F64, f64_bits(-1.0), false), (0, FLAG_NV));
+        assert_eq!(to_int(RoundingMode::NearestEven, F64, f64_bits(-2147483648.0), true), (i32::MIN as u32, 0));
+        assert_eq!(to_int(RoundingMode::NearestEven, F64, f64_bits(2147483648.0), true), (i32::MAX as u32, FLAG_NV));
+        assert_eq!(to_int(RoundingMode::NearestEven, F64, f64_bits(4294967295.0), false), (u32::MAX, 0));
+        assert_eq!(to_int(RoundingMode::NearestEven, F32, INF | F32.sign_bit(), false), (0, FLAG_NV));
+        assert_eq!(to_int(RoundingMode::NearestEven, F32, QNAN | F32.sign_bit(), true), (i32::MAX as u32, FLAG_NV));
+        assert_eq!(to_int(RoundingMode::NearestEven, F64, f64_bits(1e300), true), (i32::MAX as u32, FLAG_NV));
+    }
+
+    #[test]
+    fn test_to_long() {
+        let to_long = |format, a, signed| {
+            let mut fp = SoftFloat::new(RoundingMode::NearestEven);
+            (fp.float_to_int(format, a, signed, 64), fp.flags)
+        };
+        assert_eq!(to_long(F64, f64_bits(-4294967296.0), true), (-4294967296i64 as u64, 0));
+        assert_eq!(to_long(F64, f64_bits(-2.0), false), (0, FLAG_NV));
+        assert_eq!(to_long(F64, f64_bits(9223372036854775808.0), true), (i64::MAX as u64, FLAG_NV));
+        assert_eq!(to_long(F64, f64_bits(-9223372036854775808.0), true), (i64::MIN as u64, 0));
+        assert_eq!(to_long(F64, f64_bits(18446744073709549568.0), false), (18446744073709549568, 0));
+        assert_eq!(to_long(F32, INF, false), (u64::MAX, FLAG_NV));
+        assert_eq!(to_long(F32, QNAN, true), (i64::MAX as u64, FLAG_NV));
+    }
+
+    #[test]
+    fn test_from_int() {
+        assert_eq!(nearest(|fp| fp.int_to_float(F32, -1i64 as u64, true)), (f32_bits(-1.0), 0));
+        assert_eq!(nearest(|fp| fp.int_to_float(F32, u32::MAX as u64, false)), (f32_bits(4294967296.0), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.int_to_float(F64, i32::MIN as u64, true)), (f64_bits(-2147483648.0), 0));
+        assert_eq!(nearest(|fp| fp.int_to_float(F64, 0, true)), (0, 0));
+        assert_eq!(run(RoundingMode::TowardZero, |fp| fp.int_to_float(F32, 0x0FFF_FFFF, false)), (f32_bits(268435440.0), FLAG_NX));
+        assert_eq!(nearest(|fp| fp.int_to_float(F64, i64::MIN as u64, true)), (f64_bits(-9223372036854775808.0), 0));
+        assert_eq!(nearest(|fp| fp.int_to_float(F64, u64::MAX, false)), (f64_bits(18446744073709551616.0), FLAG_NX));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(F32, INF | F32.sign_bit()), 1 << 0);
+        assert_eq!(classify(F32, f32_bits(-1.0)), 1 << 1);
+        assert_eq!(classify(F32, MIN_SUBNORMAL | F32.sign_bit()), 1 << 2);
+        assert_eq!(classify(F32, F32.sign_bit()), 1 << 3);
+        assert_eq!(classify(F32, 0), 1 << 4);
+        assert_eq!(classify(F32, MIN_SUBNORMAL), 1 << 5);
+        assert_eq!(classify(F64, f64_bits(1.0)), 1 << 6);
+        assert_eq!(classify(F64, f64_bits(f64::INFINITY)), 1 << 7);
+        assert_eq!(classify(F32, SNAN), 1 << 8);
+        assert_eq!(classify(F32, QNAN), 1 << 9);
+    }
+}
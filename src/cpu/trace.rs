@@ -0,0 +1,289 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Execution trace
+ *
+ * Logs the instructions that commit the way `spike --log-commits` does, so a
+ * run can be diffed line by line against the reference simulator. Each one
+ * gets a line with the privilege mode it ran in, its address and bits, the
+ * registers it wrote and the memory it accessed:
+ *
+ *   core   0: 3 0x80000004 (0x00a00513) x10 0x0000000a
+ *
+ * Addresses, x registers and CSRs are XLEN bits wide, printed with 16 digits on RV64.
+ *
+ * It's a commit log only. Spike's -l adds disassembly and trap lines, in its
+ * own disassembler's syntax, which aren't reproduced: an instruction that traps
+ * simply doesn't commit, and gets no line.
+ */
+
+use std::io::Write;
+
+use crate::cpu::csr::{self, Privilege, FFLAGS, MSTATUS};
+use crate::cpu::decode::{self, FpOp, Instruction};
+use crate::cpu::{Exception, CPU};
+
+// Spike numbers its harts, we only have the one
+const CORE: &str = "core   0:";
+
+pub(crate) struct Trace {
+    output: Box<dyn Write>,
+    range: Option<(u32, u32)>, // Only instructions with their pc in [start, end) are logged
+    // What the instruction being executed did, besides writing rd
+    loads: Vec<u64>,
+    stores: Vec<(u64, u64, u32)>, // Address, value and size
+    // State before the instruction, to tell what it changed
+    privilege: Privilege,
+    mstatus: u64,
+    fflags: u64,
+}
+
+impl Trace {
+    fn new(output: Box<dyn Write>, range: Option<(u32, u32)>) -> Self {
+        Trace {
+            output,
+            range,
+            loads: Vec::new(),
+            stores: Vec::new(),
+            privilege: Privilege::Machine,
+            mstatus: 0,
+            fflags: 0,
+        }
+    }
+
+    fn in_range(&self, pc: u64) -> bool {
+        self.range.is_none_or(|(start, end)| (start as u64..end as u64).contains(&pc))
+    }
+}
+
+// The register an instruction writes its result to
+enum Destination {
+    X(u8),
+    F(u8),
+}
+
+fn destination(instruction: &Instruction) -> Option<Destination> {
+    match *instruction {
+        Instruction::Lui { rd, .. } | Instruction::Auipc { rd, .. } | Instruction::Jal { rd, .. }
+        | Instruction::Jalr { rd, .. } | Instruction::Load { rd, .. } | Instruction::OpImm { rd, .. }
+        | Instruction::Op { rd, .. } | Instruction::Unary { rd, .. } | Instruction::OpImmW { rd, .. }
+        | Instruction::OpW { rd, .. } | Instruction::UnaryW { rd, .. } | Instruction::Amo { rd, .. }
+        | Instruction::Csr { rd, .. } | Instruction::CsrImm { rd, .. } => Some(Destination::X(rd)),
+        Instruction::LoadFp { rd, .. } | Instruction::Fma { rd, .. } => Some(Destination::F(rd)),
+        // Comparisons, classification and conversions to integers go to the integer registers
+        Instruction::OpFp { op, rd, .. } => Some(match op {
+            FpOp::Feq | FpOp::Flt | FpOp::Fle | FpOp::FcvtW | FpOp::FcvtWu | FpOp::FcvtL | FpOp::FcvtLu
+            | FpOp::FmvXW | FpOp::FmvXD | FpOp::Fclass => Destination::X(rd),
+            _ => Destination::F(rd),
+        }),
+        _ => None,
+    }
+}
+
+impl CPU {
+    // Logs every instruction executed from now on to `output`, or only those
+    // with their pc in [start, end) if there's a range
+    pub(crate) fn start_trace(&mut self, output: Box<dyn Write>, range: Option<(u32, u32)>) {
+        self.trace = Some(Trace::new(output, range));
+    }
+
+    fn trace_write(&mut self, text: &str) {
+        if let Some(trace) = &mut self.trace {
+            if let Err(e) = trace.output.write_all(text.as_bytes()) {
+                eprintln!("warning: stopped tracing: {}", e);
+                self.trace = None;
+            }
+        }
+    }
+
+    // Called before the instruction at pc executes
+    pub(super) fn trace_begin(&mut self) {
+        if self.trace.is_none() {
+            return;
+        }
+        let privilege = self.csr.privilege();
+        let mstatus = self.csr.read(MSTATUS).unwrap_or(0);
+        let fflags = self.csr.read(FFLAGS).unwrap_or(0);
+        if let Some(trace) = &mut self.trace {
+            trace.loads.clear();
+            trace.stores.clear();
+            (trace.privilege, trace.mstatus, trace.fflags) = (privilege, mstatus, fflags);
+        }
+    }
+
+    pub(super) fn trace_load(&mut self, address: u64) {
+        if let Some(trace) = &mut self.trace {
+            trace.loads.push(address);
+        }
+    }
+
+    pub(super) fn trace_store(&mut self, address: u64, value: u64, size: u32) {
+        if let Some(trace) = &mut self.trace {
+            trace.stores.push((address, value, size));
+        }
+    }
+
+    // Called once the instruction at `pc` has executed, or trapped
+    pub(super) fn trace_step(&mut self, pc: u64, result: &Result<(), Exception>) {
+        if result.is_ok() && self.trace.as_ref().is_some_and(|trace| trace.in_range(pc)) {
+            let line = self.commit_line(pc);
+            self.trace_write(&line);
+        }
+    }
+
+    // The commit line of the instruction just executed
+    fn commit_line(&self, pc: u64) -> String {
+        let Some(trace) = &self.trace else {
+            return String::new();
+        };
+        let xlen = self.xlen.bits() as usize / 4;
+        let width = if self.compressed { 4 } else { 8 };
+        let mut line = format!("{} {} 0x{:0xlen$x} (0x{:0width$x})", CORE, trace.privilege as u8, pc, self.raw_instruction);
+
+        // Spike keys the writes by register number and kind, and lists them in that order
+        let mut writes = Vec::new();
+        let instruction = decode::decode(self.instruction, self.xlen);
+        match instruction.as_ref().and_then(destination) {
+            Some(Destination::X(rd)) if rd != 0 => {
+                let value = self.xlen.zext(self.registers.get_x(rd));
+                writes.push(((rd as u32) << 4, format!("x{:<2} 0x{:0xlen$x}", rd, value)));
+            }
+            Some(Destination::F(rd)) => {
+                writes.push(((rd as u32) << 4 | 1, format!("f{:<2} 0x{:016x}", rd, self.fregisters.get_f64(rd))));
+            }
+            _ => {}
+        }
+        let mut csrs = Vec::new();
+        match instruction {
+            Some(Instruction::Csr { op, rs1: operand, csr, .. } | Instruction::CsrImm { op, uimm: operand, csr, .. })
+                if op == decode::CsrOp::Rw || operand != 0 => csrs.push(csr),
+            _ => {}
+        }
+        // Written on the side: FS turning dirty, MRET/SRET, FP exception flags
+        for (csr, before) in [(MSTATUS, trace.mstatus), (FFLAGS, trace.fflags)] {
+            if !csrs.contains(&csr) && self.csr.read(csr).unwrap_or(0) != before {
+                csrs.push(csr);
+            }
+        }
+        for csr in csrs {
+            let name = csr::csr_name(csr).unwrap_or_else(|| "unknown".to_string());
+            let value = self.csr.read(csr).unwrap_or(0);
+            writes.push(((csr as u32) << 4 | 4, format!("c{}_{} 0x{:0xlen$x}", csr, name, value)));
+        }
+        writes.sort_by_key(|(key, _)| *key);
+
+        for (_, write) in writes {
+            line += " ";
+            line += &write;
+        }
+        for address in &trace.loads {
+            line += &format!(" mem 0x{:0xlen$x}", address);
+        }
+        for (address, value, size) in &trace.stores {
+            line += &format!(" mem 0x{:0xlen$x} 0x{:0width$x}", address, value, width = 2 * *size as usize);
+        }
+        line + "\n"
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::cpu::*;
+    use crate::cpu::bus::SharedOutput;
+
+    fn lines(output: &SharedOutput) -> Vec<String> {
+        output.text().lines().map(str::to_string).collect()
+    }
+
+    // Runs `program` from 0x100 for `count` instructions, traced
+    fn trace(program: &[u32], count: u64, range: Option<(u32, u32)>) -> Vec<String> {
+        let mut cpu = CPU::new();
+        for (i, instruction) in program.iter().enumerate() {
+            cpu.memory.set_u32(0x100 + 4 * i as u32, *instruction).unwrap();
+        }
+        let output = SharedOutput::default();
+        cpu.start_trace(Box::new(output.clone()), range);
+        cpu.run(0x100, Some(count));
+        lines(&output)
+    }
+
+    #[test]
+    fn test_registers() {
+        // li a0, 10; auipc t0, 0; addi zero, a0, 1
+        assert_eq!(trace(&[0x00A00513, 0x00000297, 0x00150013], 3, None), [
+            "core   0: 3 0x00000100 (0x00a00513) x10 0x0000000a",
+            "core   0: 3 0x00000104 (0x00000297) x5  0x00000104",
+            "core   0: 3 0x00000108 (0x00150013)",
+        ]);
+    }
+
+    #[test]
+    fn test_memory() {
+        // li a0, 0x204; sb a0, 0x1ff(zero); lw a1, 0x1fc(zero); amoadd.w a2, a0, (a0)
+        let lines = trace(&[0x20400513, 0x1EA00FA3, 0x1FC02583, 0x00A5262F], 4, None);
+        assert_eq!(lines[1], "core   0: 3 0x00000104 (0x1ea00fa3) mem 0x000001ff 0x04");
+        assert_eq!(lines[2], "core   0: 3 0x00000108 (0x1fc02583) x11 0x04000000 mem 0x000001fc");
+        assert_eq!(lines[3], "core   0: 3 0x0000010c (0x00a5262f) x12 0x00000000 mem 0x00000204 mem 0x00000204 0x00000204");
+    }
+
+    #[test]
+    fn test_rv64() {
+        // li a0, -1; sd a0, 0x200(zero): 16 digits for the pc, registers and addresses
+        let mut cpu = CPU::with_xlen(Xlen::Rv64);
+        cpu.memory.set_u32(0x100, 0xFFF00513).unwrap();
+        cpu.memory.set_u32(0x104, 0x20A03023).unwrap();
+        let output = SharedOutput::default();
+        cpu.start_trace(Box::new(output.clone()), None);
+        cpu.run(0x100, Some(2));
+        assert_eq!(lines(&output), [
+            "core   0: 3 0x0000000000000100 (0xfff00513) x10 0xffffffffffffffff",
+            "core   0: 3 0x0000000000000104 (0x20a03023) mem 0x0000000000000200 0xffffffffffffffff",
+        ]);
+    }
+
+    #[test]
+    fn test_csr() {
+        // csrrw a0, mscratch, a0 with a0 = 0; csrr a1, mscratch; csrwi fflags, 1
+        let lines = trace(&[0x34051573, 0x340025F3, 0x0010D073], 3, None);
+        assert_eq!(lines[0], "core   0: 3 0x00000100 (0x34051573) x10 0x00000000 c832_mscratch 0x00000000");
+        assert_eq!(lines[1], "core   0: 3 0x00000104 (0x340025f3) x11 0x00000000");
+        assert_eq!(lines[2], "core   0: 3 0x00000108 (0x0010d073) c1_fflags 0x00000001 c768_mstatus 0x80006000");
+    }
+
+    #[test]
+    fn test_exception() {
+        // ecall, into a handler at 0x200: it doesn't commit, so only the handler's nop is logged
+        let mut cpu = CPU::new();
+        cpu.memory.set_u32(0x100, 0x00000073).unwrap();
+        cpu.memory.set_u32(0x200, 0x00000013).unwrap();
+        cpu.csr.write(csr::MTVEC, 0x200).unwrap();
+        let output = SharedOutput::default();
+        cpu.start_trace(Box::new(output.clone()), None);
+        cpu.run(0x100, Some(2));
+        assert_eq!(lines(&output), ["core   0: 3 0x00000200 (0x00000013)"]);
+    }
+
+    #[test]
+    fn test_compressed() {
+        // c.li a0, 1; c.nop
+        let lines = trace(&[0x00014505], 2, None);
+        assert_eq!(lines[0], "core   0: 3 0x00000100 (0x4505) x10 0x00000001");
+        assert_eq!(lines[1], "core   0: 3 0x00000102 (0x0001)");
+    }
+
+    #[test]
+    fn test_repeat() {
+        // nop; j .; every execution of the jump commits
+        let lines = trace(&[0x00000013, 0x0000006F, 0x00000013], 4, None);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1..].iter().all(|line| line == "core   0: 3 0x00000104 (0x0000006f)"));
+    }
+
+    #[test]
+    fn test_range() {
+        // Three nops, only the middle one is logged
+        let lines = trace(&[0x00000013, 0x00000013, 0x00000013], 3, Some((0x104, 0x108)));
+        assert_eq!(lines, ["core   0: 3 0x00000104 (0x00000013)"]);
+    }
+}
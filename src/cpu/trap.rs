@@ -0,0 +1,144 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Synchronous exceptions and interrupts, as defined by the privileged spec.
+ *
+ * Exception variants carry the value that ends up in mtval/stval: the faulting
+ * address for misaligned/access/page faults, the instruction bits for illegal
+ * instructions and the PC for breakpoints.
+ */
+
+use std::fmt;
+use crate::cpu::CPU;
+use crate::cpu::csr::{MIP_MEIP, MIP_MSIP, MIP_MTIP, MIP_SEIP, MIP_SSIP, MIP_STIP};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(dead_code)]
+pub(crate) enum Exception {
+    InstructionAddressMisaligned(u64),
+    InstructionAccessFault(u64),
+    IllegalInstruction(u32),
+    Breakpoint(u64),
+    LoadAddressMisaligned(u64),
+    LoadAccessFault(u64),
+    StoreAddressMisaligned(u64),
+    StoreAccessFault(u64),
+    EnvironmentCallFromUMode,
+    EnvironmentCallFromSMode,
+    EnvironmentCallFromMMode,
+    InstructionPageFault(u64),
+    LoadPageFault(u64),
+    StorePageFault(u64),
+}
+
+#[allow(dead_code)]
+impl Exception {
+    // Exception code, as written to mcause
+    pub(crate) fn cause(&self) -> u32 {
+        match self {
+            Exception::InstructionAddressMisaligned(_) => 0,
+            Exception::InstructionAccessFault(_) => 1,
+            Exception::IllegalInstruction(_) => 2,
+            Exception::Breakpoint(_) => 3,
+            Exception::LoadAddressMisaligned(_) => 4,
+            Exception::LoadAccessFault(_) => 5,
+            Exception::StoreAddressMisaligned(_) => 6,
+            Exception::StoreAccessFault(_) => 7,
+            Exception::EnvironmentCallFromUMode => 8,
+            Exception::EnvironmentCallFromSMode => 9,
+            Exception::EnvironmentCallFromMMode => 11,
+            Exception::InstructionPageFault(_) => 12,
+            Exception::LoadPageFault(_) => 13,
+            Exception::StorePageFault(_) => 15,
+        }
+    }
+
+    // Trap value, as written to mtval
+    pub(crate) fn tval(&self) -> u64 {
+        match *self {
+            Exception::InstructionAddressMisaligned(value)
+            | Exception::InstructionAccessFault(value)
+            | Exception::Breakpoint(value)
+            | Exception::LoadAddressMisaligned(value)
+            | Exception::LoadAccessFault(value)
+            | Exception::StoreAddressMisaligned(value)
+            | Exception::StoreAccessFault(value)
+            | Exception::InstructionPageFault(value)
+            | Exception::LoadPageFault(value)
+            | Exception::StorePageFault(value) => value,
+            Exception::IllegalInstruction(instruction) => instruction as u64,
+            Exception::EnvironmentCallFromUMode
+            | Exception::EnvironmentCallFromSMode
+            | Exception::EnvironmentCallFromMMode => 0,
+        }
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exception::InstructionAddressMisaligned(addr) => write!(f, "instruction address misaligned (0x{:08x})", addr),
+            Exception::InstructionAccessFault(addr) => write!(f, "instruction access fault (0x{:08x})", addr),
+            Exception::IllegalInstruction(inst) => write!(f, "illegal instruction (0x{:08x})", inst),
+            Exception::Breakpoint(pc) => write!(f, "breakpoint (0x{:08x})", pc),
+            Exception::LoadAddressMisaligned(addr) => write!(f, "load address misaligned (0x{:08x})", addr),
+            Exception::LoadAccessFault(addr) => write!(f, "load access fault (0x{:08x})", addr),
+            Exception::StoreAddressMisaligned(addr) => write!(f, "store address misaligned (0x{:08x})", addr),
+            Exception::StoreAccessFault(addr) => write!(f, "store access fault (0x{:08x})", addr),
+            Exception::EnvironmentCallFromUMode => write!(f, "environment call from U-mode"),
+            Exception::EnvironmentCallFromSMode => write!(f, "environment call from S-mode"),
+            Exception::EnvironmentCallFromMMode => write!(f, "environment call from M-mode"),
+            Exception::InstructionPageFault(addr) => write!(f, "instruction page fault (0x{:08x})", addr),
+            Exception::LoadPageFault(addr) => write!(f, "load page fault (0x{:08x})", addr),
+            Exception::StorePageFault(addr) => write!(f, "store page fault (0x{:08x})", addr),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Interrupt {
+    SupervisorSoftware,
+    SupervisorTimer,
+    SupervisorExternal,
+    MachineSoftware,
+    MachineTimer,
+    MachineExternal,
+}
+
+impl Interrupt {
+    // Interrupt code, as written to mcause/scause (without the interrupt bit)
+    pub(crate) fn cause(&self) -> u32 {
+        match self {
+            Interrupt::SupervisorSoftware => 1,
+            Interrupt::SupervisorTimer => 5,
+            Interrupt::SupervisorExternal => 9,
+            Interrupt::MachineSoftware => 3,
+            Interrupt::MachineTimer => 7,
+            Interrupt::MachineExternal => 11,
+        }
+    }
+
+    // Bit in mip/mie
+    pub(crate) fn mask(&self) -> u64 {
+        match self {
+            Interrupt::SupervisorSoftware => MIP_SSIP,
+            Interrupt::SupervisorTimer => MIP_STIP,
+            Interrupt::SupervisorExternal => MIP_SEIP,
+            Interrupt::MachineSoftware => MIP_MSIP,
+            Interrupt::MachineTimer => MIP_MTIP,
+            Interrupt::MachineExternal => MIP_MEIP,
+        }
+    }
+}
+
+impl CPU {
+    // Takes a synchronous exception: saves the trap state and jumps to the handler of
+    // the mode it's taken in, M-mode unless medeleg delegates it to S-mode
+    pub(crate) fn take_exception(&mut self, exception: Exception) {
+        self.pc = self.csr.enter_trap(self.pc, exception.cause(), exception.tval(), false);
+    }
+
+    // Takes an interrupt before the instruction at pc, which is where mret comes back to
+    pub(crate) fn take_interrupt(&mut self, interrupt: Interrupt) {
+        self.pc = self.csr.enter_trap(self.pc, interrupt.cause(), 0, true);
+    }
+}
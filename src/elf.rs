@@ -0,0 +1,575 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* ELF32 and ELF64 executable loader
+ *
+ * Only the parts of the format we need to get a program running are parsed:
+ * the file header, the program headers (for PT_LOAD segments) and the
+ * symbol table, if the file has one.
+ * Everything is little endian, as that is the only byte order we support.
+ * The class tells RV32 and RV64 executables apart. Physical addresses are 32 bits
+ * wide either way, so ELF64 addresses and sizes have to fit in 32 bits.
+ */
+
+use std::fmt;
+
+use crate::cpu::Xlen;
+
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+
+const ET_EXEC: u16 = 2;
+const EM_RISCV: u16 = 0xF3;
+
+const PT_LOAD: u32 = 1;
+const PF_RWX: u32 = 0x7;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHN_ABS: u16 = 0xFFF1;
+
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+const SHDR_SIZE: usize = 40;
+const SYM_SIZE: usize = 16;
+
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+// Offsets of the fields we read, which move around in ELF64 as addresses and sizes
+// (the words) grow to 8 bytes
+struct Layout {
+    word: usize,
+    e_entry: usize,
+    e_phoff: usize,
+    e_shoff: usize,
+    e_phentsize: usize, // Followed by e_phnum, then e_shentsize and e_shnum
+    phdr_size: usize,
+    p_offset: usize, // Followed by p_vaddr, then p_filesz and p_memsz after p_paddr
+    shdr_size: usize,
+    sh_offset: usize, // Followed by sh_size and sh_link
+    sym_size: usize,
+    st_info: usize, // Followed by st_other and st_shndx
+    st_value: usize, // Followed by st_size
+}
+
+const ELF32: Layout = Layout {
+    word: 4, e_entry: 24, e_phoff: 28, e_shoff: 32, e_phentsize: 42, phdr_size: PHDR_SIZE, p_offset: 4,
+    shdr_size: SHDR_SIZE, sh_offset: 16, sym_size: SYM_SIZE, st_info: 12, st_value: 4,
+};
+
+const ELF64: Layout = Layout {
+    word: 8, e_entry: 24, e_phoff: 32, e_shoff: 40, e_phentsize: 54, phdr_size: 56, p_offset: 8,
+    shdr_size: 64, sh_offset: 24, sym_size: 24, st_info: 4, st_value: 8,
+};
+
+impl Layout {
+    // A word sized field. Anything that doesn't fit in 32 bits is out of reach.
+    fn read_word(&self, data: &[u8], offset: usize) -> Result<u32, ElfError> {
+        if self.word == 4 {
+            return read_u32(data, offset);
+        }
+        let low = read_u32(data, offset)? as u64;
+        let value = low | (read_u32(data, offset + 4)? as u64) << 32;
+        u32::try_from(value).map_err(|_| ElfError::TooLarge(value))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ElfError {
+    Truncated,
+    BadMagic,
+    BadClass(u8),
+    NotLittleEndian,
+    NotExecutable(u16),
+    WrongMachine(u16),
+    BadSegment(u32),
+    OutOfMemory(u32),
+    TooLarge(u64),
+    WrongXlen { elf: Xlen, cpu: Xlen },
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElfError::Truncated => write!(f, "file is truncated"),
+            ElfError::BadMagic => write!(f, "not an ELF file"),
+            ElfError::BadClass(class) => write!(f, "not a 32 or 64-bit ELF file (EI_CLASS = {})", class),
+            ElfError::NotLittleEndian => write!(f, "not a little endian ELF file"),
+            ElfError::NotExecutable(t) => write!(f, "not an executable (e_type = {})", t),
+            ElfError::WrongMachine(m) => write!(f, "not a RISC-V executable (e_machine = 0x{:x})", m),
+            ElfError::BadSegment(vaddr) => write!(f, "segment at 0x{:08x} is malformed", vaddr),
+            ElfError::OutOfMemory(vaddr) => write!(f, "segment at 0x{:08x} does not fit in memory", vaddr),
+            ElfError::TooLarge(value) => write!(f, "0x{:x} is past the 32-bit address space", value),
+            ElfError::WrongXlen { elf, cpu } => write!(f, "built for RV{}, the CPU is RV{}", elf.bits(), cpu.bits()),
+        }
+    }
+}
+
+/// A PT_LOAD segment. `data` holds the `p_filesz` bytes present in the file,
+/// `mem_size` may be larger, in which case the rest is zero-filled (.bss).
+pub(crate) struct Segment {
+    pub(crate) vaddr: u32,
+    pub(crate) mem_size: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Symbol {
+    pub(crate) name: String,
+    pub(crate) value: u32,
+    pub(crate) size: u32,
+}
+
+/// Symbols sorted by address, so we can look them up both ways.
+#[derive(Default)]
+#[allow(dead_code)]
+pub(crate) struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+#[allow(dead_code)]
+impl SymbolTable {
+    pub(crate) fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|s| s.value);
+        Self { symbols }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub(crate) fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    // Finds the address of a symbol by name
+    pub(crate) fn lookup(&self, name: &str) -> Option<u32> {
+        self.symbols.iter().find(|s| s.name == name).map(|s| s.value)
+    }
+
+    // Finds the symbol containing an address, returns it together with the offset into it.
+    // Symbols without a size only match their exact address.
+    pub(crate) fn resolve(&self, address: u32) -> Option<(&Symbol, u32)> {
+        let end = self.symbols.partition_point(|s| s.value <= address);
+        self.symbols[..end].iter().rev()
+            .find(|s| s.value == address || address - s.value < s.size)
+            .map(|s| (s, address - s.value))
+    }
+}
+
+pub(crate) struct Elf {
+    pub(crate) xlen: Xlen, // RV32 for ELF32, RV64 for ELF64
+    pub(crate) entry: u32,
+    pub(crate) segments: Vec<Segment>,
+    pub(crate) symbols: SymbolTable,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ElfError> {
+    let bytes = data.get(offset..offset + 2).ok_or(ElfError::Truncated)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ElfError> {
+    let bytes = data.get(offset..offset + 4).ok_or(ElfError::Truncated)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_slice(data: &[u8], offset: u32, size: u32) -> Result<&[u8], ElfError> {
+    let start = offset as usize;
+    let end = start.checked_add(size as usize).ok_or(ElfError::Truncated)?;
+    data.get(start..end).ok_or(ElfError::Truncated)
+}
+
+fn read_cstr(data: &[u8], offset: usize) -> Result<String, ElfError> {
+    let bytes = data.get(offset..).ok_or(ElfError::Truncated)?;
+    let len = bytes.iter().position(|&b| b == 0).ok_or(ElfError::Truncated)?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, values: &[u32]) {
+    for value in values {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+impl Elf {
+    pub(crate) fn is_elf(data: &[u8]) -> bool {
+        data.starts_with(&ELF_MAGIC)
+    }
+
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        if data.len() < EHDR_SIZE {
+            return Err(ElfError::Truncated);
+        }
+        if !Self::is_elf(data) {
+            return Err(ElfError::BadMagic);
+        }
+        let (xlen, layout) = match data[4] {
+            ELFCLASS32 => (Xlen::Rv32, &ELF32),
+            ELFCLASS64 => (Xlen::Rv64, &ELF64),
+            class => return Err(ElfError::BadClass(class)),
+        };
+        if data[5] != ELFDATA2LSB || data[6] != EV_CURRENT {
+            return Err(ElfError::NotLittleEndian);
+        }
+
+        let e_type = read_u16(data, 16)?;
+        if e_type != ET_EXEC {
+            return Err(ElfError::NotExecutable(e_type));
+        }
+        let e_machine = read_u16(data, 18)?;
+        if e_machine != EM_RISCV {
+            return Err(ElfError::WrongMachine(e_machine));
+        }
+
+        let entry = layout.read_word(data, layout.e_entry)?;
+        let segments = Self::parse_segments(data, layout)?;
+        let symbols = Self::parse_symbols(data, layout)?;
+
+        Ok(Self { xlen, entry, segments, symbols })
+    }
+
+    fn parse_segments(data: &[u8], layout: &Layout) -> Result<Vec<Segment>, ElfError> {
+        let e_phoff = layout.read_word(data, layout.e_phoff)? as usize;
+        let e_phentsize = read_u16(data, layout.e_phentsize)? as usize;
+        let e_phnum = read_u16(data, layout.e_phentsize + 2)? as usize;
+        if e_phnum > 0 && e_phentsize < layout.phdr_size {
+            return Err(ElfError::Truncated);
+        }
+
+        let mut segments = Vec::new();
+        for i in 0..e_phnum {
+            let phdr = e_phoff + i * e_phentsize;
+            if read_u32(data, phdr)? != PT_LOAD {
+                continue;
+            }
+            let field = |index: usize| layout.read_word(data, phdr + layout.p_offset + index * layout.word);
+            let p_offset = field(0)?;
+            let p_vaddr = field(1)?;
+            let p_filesz = field(3)?;
+            let p_memsz = field(4)?;
+            if p_filesz > p_memsz || p_vaddr.checked_add(p_memsz).is_none() {
+                return Err(ElfError::BadSegment(p_vaddr));
+            }
+            segments.push(Segment {
+                vaddr: p_vaddr,
+                mem_size: p_memsz,
+                data: read_slice(data, p_offset, p_filesz)?.to_vec(),
+            });
+        }
+        Ok(segments)
+    }
+
+    // Writes the executable out: one PT_LOAD segment per segment, and the symbols in a
+    // .symtab section so they survive the round trip. Symbols don't belong to any section,
+    // they're written as absolute ones. Always as ELF32, which is what the assembler makes.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let symbols = self.symbols.symbols();
+        let shstrtab = b"\0.symtab\0.strtab\0.shstrtab\0";
+        let mut strtab = vec![0u8];
+        let mut names = Vec::new();
+        for symbol in symbols {
+            names.push(strtab.len() as u32);
+            strtab.extend_from_slice(symbol.name.as_bytes());
+            strtab.push(0);
+        }
+
+        let phoff = EHDR_SIZE;
+        let mut offset = phoff + self.segments.len() * PHDR_SIZE;
+        let mut data_offsets = Vec::new();
+        for segment in &self.segments {
+            offset = align4(offset);
+            data_offsets.push(offset);
+            offset += segment.data.len();
+        }
+        let symtab_off = align4(offset);
+        let symtab_size = (symbols.len() + 1) * SYM_SIZE;
+        let strtab_off = symtab_off + symtab_size;
+        let shstrtab_off = strtab_off + strtab.len();
+        let shoff = align4(shstrtab_off + shstrtab.len());
+
+        let mut out = Vec::with_capacity(shoff + 4 * SHDR_SIZE);
+        out.extend_from_slice(&ELF_MAGIC);
+        out.extend_from_slice(&[ELFCLASS32, ELFDATA2LSB, EV_CURRENT]);
+        out.resize(16, 0);
+        put_u16(&mut out, ET_EXEC);
+        put_u16(&mut out, EM_RISCV);
+        put_u32(&mut out, &[EV_CURRENT as u32, self.entry, phoff as u32, shoff as u32, 0]);
+        for value in [EHDR_SIZE, PHDR_SIZE, self.segments.len(), SHDR_SIZE, 4, 3] {
+            put_u16(&mut out, value as u16);
+        }
+
+        for (segment, &offset) in self.segments.iter().zip(&data_offsets) {
+            put_u32(&mut out, &[PT_LOAD, offset as u32, segment.vaddr, segment.vaddr, segment.data.len() as u32,
+                segment.mem_size, PF_RWX, 1]);
+        }
+        for (segment, &offset) in self.segments.iter().zip(&data_offsets) {
+            out.resize(offset, 0);
+            out.extend_from_slice(&segment.data);
+        }
+
+        // The first entry is the null symbol
+        out.resize(symtab_off + SYM_SIZE, 0);
+        for (symbol, &name) in symbols.iter().zip(&names) {
+            put_u32(&mut out, &[name, symbol.value, symbol.size]);
+            out.extend_from_slice(&[STT_NOTYPE, 0]);
+            put_u16(&mut out, SHN_ABS);
+        }
+        out.extend_from_slice(&strtab);
+        out.extend_from_slice(shstrtab);
+        out.resize(shoff, 0);
+
+        // Sections: null, .symtab, .strtab and .shstrtab. Every symbol is local, so
+        // the first global one (sh_info) would come after the last.
+        out.resize(shoff + SHDR_SIZE, 0);
+        put_u32(&mut out, &[1, SHT_SYMTAB, 0, 0, symtab_off as u32, symtab_size as u32, 2, symbols.len() as u32 + 1,
+            4, SYM_SIZE as u32]);
+        put_u32(&mut out, &[9, SHT_STRTAB, 0, 0, strtab_off as u32, strtab.len() as u32, 0, 0, 1, 0]);
+        put_u32(&mut out, &[17, SHT_STRTAB, 0, 0, shstrtab_off as u32, shstrtab.len() as u32, 0, 0, 1, 0]);
+        out
+    }
+
+    // The segments as a flat binary, starting at the lowest one. Gaps and .bss are zero-filled.
+    pub(crate) fn to_flat(&self) -> Vec<u8> {
+        let Some(base) = self.segments.iter().map(|s| s.vaddr).min() else {
+            return Vec::new();
+        };
+        let mut image = Vec::new();
+        for segment in &self.segments {
+            let start = (segment.vaddr - base) as usize;
+            let end = start + segment.mem_size as usize;
+            if image.len() < end {
+                image.resize(end, 0);
+            }
+            image[start..start + segment.data.len()].copy_from_slice(&segment.data);
+        }
+        image
+    }
+
+    // Reads every FUNC, OBJECT and NOTYPE symbol out of the .symtab section
+    fn parse_symbols(data: &[u8], layout: &Layout) -> Result<SymbolTable, ElfError> {
+        let e_shoff = layout.read_word(data, layout.e_shoff)? as usize;
+        let e_shentsize = read_u16(data, layout.e_phentsize + 4)? as usize;
+        let e_shnum = read_u16(data, layout.e_phentsize + 6)? as usize;
+        if e_shoff == 0 || e_shnum == 0 {
+            return Ok(SymbolTable::default());
+        }
+        if e_shentsize < layout.shdr_size {
+            return Err(ElfError::Truncated);
+        }
+
+        let mut symbols = Vec::new();
+        for i in 0..e_shnum {
+            let shdr = e_shoff + i * e_shentsize;
+            if read_u32(data, shdr + 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let sh_offset = layout.read_word(data, shdr + layout.sh_offset)?;
+            let sh_size = layout.read_word(data, shdr + layout.sh_offset + layout.word)?;
+            let sh_link = read_u32(data, shdr + layout.sh_offset + 2 * layout.word)? as usize;
+
+            // sh_link points at the string table holding the symbol names
+            let strtab_hdr = e_shoff + sh_link * e_shentsize;
+            let strtab_offset = layout.read_word(data, strtab_hdr + layout.sh_offset)?;
+            let strtab_size = layout.read_word(data, strtab_hdr + layout.sh_offset + layout.word)?;
+            let strtab = read_slice(data, strtab_offset, strtab_size)?;
+
+            let symtab = read_slice(data, sh_offset, sh_size)?;
+            // The first entry is always the null symbol
+            for sym in symtab.chunks_exact(layout.sym_size).skip(1) {
+                let st_name = read_u32(sym, 0)? as usize;
+                let st_info = sym[layout.st_info];
+                let st_shndx = read_u16(sym, layout.st_info + 2)?;
+                if st_name == 0 || st_shndx == 0 {
+                    continue;
+                }
+                if !matches!(st_info & 0xF, STT_NOTYPE | STT_OBJECT | STT_FUNC) {
+                    continue;
+                }
+                // An ELF64 symbol past 4 GiB can't be anywhere in memory, it's left out
+                let (Ok(value), Ok(size)) = (layout.read_word(sym, layout.st_value),
+                    layout.read_word(sym, layout.st_value + layout.word)) else {
+                    continue;
+                };
+                symbols.push(Symbol { name: read_cstr(strtab, st_name)?, value, size });
+            }
+        }
+        Ok(SymbolTable::new(symbols))
+    }
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::elf::*;
+
+    // Builds a minimal executable with one PT_LOAD segment and a symbol table
+    // holding `_start` and `data`, as ELF32 or ELF64 after `layout`.
+    fn build_elf_as(layout: &Layout, machine: u16) -> Vec<u8> {
+        let code: [u8; 8] = [0x13, 0x04, 0x10, 0x00, 0x93, 0x04, 0x24, 0x00];
+        let strtab = b"\0_start\0data\0";
+        let elf64 = layout.word == 8;
+        let (phdr_size, shdr_size, sym_size) = (layout.phdr_size, layout.shdr_size, layout.sym_size);
+        let phoff = if elf64 { 64 } else { EHDR_SIZE };
+        let code_off = phoff + phdr_size;
+        let strtab_off = code_off + code.len();
+        let symtab_off = (strtab_off + strtab.len() + 7) & !7;
+        let shoff = symtab_off + 3 * sym_size;
+
+        let mut elf = vec![0u8; shoff + 3 * shdr_size];
+        elf[0..4].copy_from_slice(&ELF_MAGIC);
+        elf[4] = if elf64 { ELFCLASS64 } else { ELFCLASS32 };
+        elf[5] = ELFDATA2LSB;
+        elf[6] = EV_CURRENT;
+        let put16 = |elf: &mut Vec<u8>, off: usize, v: u16| elf[off..off + 2].copy_from_slice(&v.to_le_bytes());
+        let put32 = |elf: &mut Vec<u8>, off: usize, v: u32| elf[off..off + 4].copy_from_slice(&v.to_le_bytes());
+        let put_word = |elf: &mut Vec<u8>, off: usize, v: u64| {
+            elf[off..off + layout.word].copy_from_slice(&v.to_le_bytes()[..layout.word])
+        };
+        put16(&mut elf, 16, ET_EXEC);
+        put16(&mut elf, 18, machine);
+        put_word(&mut elf, layout.e_entry, 0x1000);
+        put_word(&mut elf, layout.e_phoff, phoff as u64);
+        put_word(&mut elf, layout.e_shoff, shoff as u64);
+        put16(&mut elf, layout.e_phentsize, phdr_size as u16);
+        put16(&mut elf, layout.e_phentsize + 2, 1);
+        put16(&mut elf, layout.e_phentsize + 4, shdr_size as u16);
+        put16(&mut elf, layout.e_phentsize + 6, 3);
+
+        // PT_LOAD: 8 bytes of code followed by 8 bytes of .bss
+        let field = |index: usize| phoff + layout.p_offset + index * layout.word;
+        put32(&mut elf, phoff, PT_LOAD);
+        put_word(&mut elf, field(0), code_off as u64);
+        put_word(&mut elf, field(1), 0x1000);
+        put_word(&mut elf, field(3), code.len() as u64);
+        put_word(&mut elf, field(4), 16);
+        elf[code_off..code_off + code.len()].copy_from_slice(&code);
+        elf[strtab_off..strtab_off + strtab.len()].copy_from_slice(strtab);
+
+        // Symbols: null, _start (FUNC), data (OBJECT)
+        for (n, (name, value, size, kind)) in [(1, 0x1000, 8, STT_FUNC), (8, 0x1008, 4, STT_OBJECT)].into_iter().enumerate() {
+            let sym = symtab_off + (n + 1) * sym_size;
+            put32(&mut elf, sym, name);
+            put_word(&mut elf, sym + layout.st_value, value);
+            put_word(&mut elf, sym + layout.st_value + layout.word, size);
+            elf[sym + layout.st_info] = kind;
+            put16(&mut elf, sym + layout.st_info + 2, 1);
+        }
+
+        // Sections: null, .symtab, .strtab
+        let sh = shoff + shdr_size;
+        put32(&mut elf, sh + 4, SHT_SYMTAB);
+        put_word(&mut elf, sh + layout.sh_offset, symtab_off as u64);
+        put_word(&mut elf, sh + layout.sh_offset + layout.word, (3 * sym_size) as u64);
+        put32(&mut elf, sh + layout.sh_offset + 2 * layout.word, 2);
+        let sh = sh + shdr_size;
+        put32(&mut elf, sh + 4, 3);
+        put_word(&mut elf, sh + layout.sh_offset, strtab_off as u64);
+        put_word(&mut elf, sh + layout.sh_offset + layout.word, strtab.len() as u64);
+        elf
+    }
+
+    fn build_elf(machine: u16) -> Vec<u8> {
+        build_elf_as(&ELF32, machine)
+    }
+
+    #[test]
+    fn test_parse() {
+        let elf = Elf::parse(&build_elf(EM_RISCV)).unwrap();
+        assert_eq!(elf.entry, 0x1000);
+        assert_eq!(elf.segments.len(), 1);
+        assert_eq!(elf.segments[0].vaddr, 0x1000);
+        assert_eq!(elf.segments[0].mem_size, 16);
+        assert_eq!(elf.segments[0].data.len(), 8);
+        assert_eq!(elf.symbols.symbols().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_elf64() {
+        let elf = Elf::parse(&build_elf_as(&ELF64, EM_RISCV)).unwrap();
+        assert_eq!(elf.xlen, Xlen::Rv64);
+        assert_eq!(elf.entry, 0x1000);
+        assert_eq!(elf.segments.len(), 1);
+        assert_eq!(elf.segments[0].vaddr, 0x1000);
+        assert_eq!(elf.segments[0].mem_size, 16);
+        assert_eq!(elf.segments[0].data.len(), 8);
+        assert_eq!(elf.symbols.lookup("data"), Some(0x1008));
+        assert_eq!(Elf::parse(&build_elf(EM_RISCV)).unwrap().xlen, Xlen::Rv32);
+    }
+
+    #[test]
+    fn test_elf64_too_large() {
+        let mut data = build_elf_as(&ELF64, EM_RISCV);
+        data[ELF64.e_entry + 4] = 1;
+        assert_eq!(Elf::parse(&data).err(), Some(ElfError::TooLarge(0x1_0000_1000)));
+    }
+
+    #[test]
+    fn test_bad_class() {
+        let mut data = build_elf(EM_RISCV);
+        data[4] = 3;
+        assert_eq!(Elf::parse(&data).err(), Some(ElfError::BadClass(3)));
+    }
+
+    #[test]
+    fn test_symbols() {
+        let elf = Elf::parse(&build_elf(EM_RISCV)).unwrap();
+        assert_eq!(elf.symbols.lookup("_start"), Some(0x1000));
+        assert_eq!(elf.symbols.lookup("data"), Some(0x1008));
+        assert_eq!(elf.symbols.lookup("missing"), None);
+
+        let (symbol, offset) = elf.symbols.resolve(0x1004).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("_start", 4));
+        let (symbol, offset) = elf.symbols.resolve(0x1008).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("data", 0));
+        assert!(elf.symbols.resolve(0x100C).is_none());
+        assert!(elf.symbols.resolve(0x0FFF).is_none());
+    }
+
+    #[test]
+    fn test_write() {
+        let elf = Elf::parse(&build_elf(EM_RISCV)).unwrap();
+        let written = Elf::parse(&elf.to_bytes()).unwrap();
+        assert_eq!(written.entry, 0x1000);
+        assert_eq!(written.segments.len(), 1);
+        assert_eq!(written.segments[0].vaddr, 0x1000);
+        assert_eq!(written.segments[0].mem_size, 16);
+        assert_eq!(written.segments[0].data, elf.segments[0].data);
+        assert_eq!(written.symbols.symbols(), elf.symbols.symbols());
+    }
+
+    #[test]
+    fn test_flat() {
+        let mut elf = Elf::parse(&build_elf(EM_RISCV)).unwrap();
+        elf.segments.push(Segment { vaddr: 0x1020, mem_size: 2, data: vec![0xAA, 0xBB] });
+        let image = elf.to_flat();
+        assert_eq!(image.len(), 0x22);
+        assert_eq!(image[..8], elf.segments[0].data[..]);
+        assert!(image[8..0x20].iter().all(|&b| b == 0), "The .bss and the gap are zero-filled");
+        assert_eq!(image[0x20..], [0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn test_wrong_machine() {
+        assert_eq!(Elf::parse(&build_elf(0x3E)).err(), Some(ElfError::WrongMachine(0x3E)));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let elf = build_elf(EM_RISCV);
+        assert_eq!(Elf::parse(&elf[..40]).err(), Some(ElfError::Truncated));
+        assert_eq!(Elf::parse(&[0u8; 64]).err(), Some(ElfError::BadMagic));
+    }
+}
@@ -0,0 +1,797 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* GDB remote serial protocol stub
+ *
+ * Lets GDB drive the VM over TCP with `target remote :PORT`. The program only
+ * runs when GDB says so: a single instruction at a time, or until it hits a
+ * breakpoint or a watchpoint, faults, exits or GDB sends a Ctrl-C.
+ *
+ * Registers are numbered the way GDB numbers them on RISC-V: x0-x31, pc,
+ * f0-f31, then the CSRs at 65 + their address. The target XML GDB asks for
+ * describes all of them. Memory is read and written at virtual addresses,
+ * translated the way the program's loads are, without touching the TLB or the
+ * page table. RAM and the HTIF registers read as they are, but the other
+ * devices can't be accessed without side effects and give an error. Breakpoints, software or hardware, are
+ * checked against the pc rather than patched into memory, and watchpoints
+ * against the access the next instruction is about to make, which stops the
+ * program right before it, as GDB expects on RISC-V.
+ */
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::cpu::{csr_name, Exception, MemoryAccess, Xlen, CPU, FREG_ALIASES, REG_ALIASES};
+
+// Signals, as GDB numbers them
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGBUS: u8 = 10;
+const SIGSEGV: u8 = 11;
+
+// What GDB sends to stop a running program
+const INTERRUPT: u8 = 0x03;
+// Instructions executed between two checks for an interrupt
+const POLL_INTERVAL: u64 = 4096;
+
+// Register numbers
+const REG_PC: usize = 32;
+const REG_F0: usize = 33;
+const REG_CSR0: usize = 65;
+const CSR_COUNT: usize = 4096;
+
+// The FP CSRs go with the FP registers, the other ones get a feature of their own
+const FP_CSRS: [u16; 3] = [0x001, 0x002, 0x003];
+
+const ERROR: &str = "E01";
+const MEMORY_ERROR: &str = "E14";
+
+// How a session ended
+#[derive(Debug, PartialEq)]
+pub(crate) enum Session {
+    Exited(u32), // Exit code the program passed to the HTIF
+    Detached,
+    Killed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WatchKind { Write, Read, Access }
+
+#[derive(Debug, PartialEq)]
+struct Watchpoint {
+    kind: WatchKind,
+    address: u64,
+    length: u32,
+}
+
+impl Watchpoint {
+    // Stops on `access` if it overlaps the watched bytes, and goes the watched way.
+    // The address reported is the first watched byte the access touches.
+    fn check(&self, access: &MemoryAccess) -> Option<Stop> {
+        let kind = match self.kind {
+            WatchKind::Write => access.write,
+            WatchKind::Read => access.read,
+            WatchKind::Access => access.read || access.write,
+        };
+        let overlaps = access.address < self.address.saturating_add(self.length as u64)
+            && self.address < access.address.saturating_add(access.size as u64);
+        (kind && overlaps).then_some(Stop::Watchpoint(self.kind, access.address.max(self.address)))
+    }
+}
+
+// Why the program stopped
+#[derive(Debug, PartialEq)]
+enum Stop {
+    Signal(u8),
+    Breakpoint { hardware: bool },
+    Watchpoint(WatchKind, u64),
+    Exited(u32),
+}
+
+impl Stop {
+    // The stop reply packet
+    fn reply(&self) -> String {
+        match self {
+            Stop::Signal(signal) => format!("S{:02x}", signal),
+            Stop::Breakpoint { hardware: false } => format!("T{:02x}swbreak:;", SIGTRAP),
+            Stop::Breakpoint { hardware: true } => format!("T{:02x}hwbreak:;", SIGTRAP),
+            Stop::Watchpoint(kind, address) => {
+                let name = match kind {
+                    WatchKind::Write => "watch",
+                    WatchKind::Read => "rwatch",
+                    WatchKind::Access => "awatch",
+                };
+                format!("T{:02x}{}:{:x};", SIGTRAP, name, address)
+            }
+            Stop::Exited(code) => format!("W{:02x}", code & 0xFF),
+        }
+    }
+}
+
+// The signal GDB is told about when an exception has no handler to go to
+fn signal(exception: Exception) -> u8 {
+    match exception {
+        Exception::IllegalInstruction(_) => SIGILL,
+        Exception::InstructionAddressMisaligned(_)
+        | Exception::LoadAddressMisaligned(_)
+        | Exception::StoreAddressMisaligned(_) => SIGBUS,
+        Exception::InstructionAccessFault(_)
+        | Exception::LoadAccessFault(_)
+        | Exception::StoreAccessFault(_)
+        | Exception::InstructionPageFault(_)
+        | Exception::LoadPageFault(_)
+        | Exception::StorePageFault(_) => SIGSEGV,
+        Exception::Breakpoint(_)
+        | Exception::EnvironmentCallFromUMode
+        | Exception::EnvironmentCallFromSMode
+        | Exception::EnvironmentCallFromMMode => SIGTRAP,
+    }
+}
+
+// What to do once a packet has been handled
+#[derive(Debug, PartialEq)]
+enum Action {
+    Reply(String),
+    Resume { step: bool },
+    Detach,
+    Kill,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn number(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
+}
+
+// The register description GDB reads before anything else. The integer
+// registers, pc and the CSRs are XLEN bits wide.
+fn target_xml(xlen: Xlen) -> String {
+    let bits = xlen.bits();
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\"?>\n",
+        "<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n",
+        "<target version=\"1.0\">\n",
+    ));
+    xml += &format!("<architecture>riscv:rv{}</architecture>\n", bits);
+    xml += "<feature name=\"org.gnu.gdb.riscv.cpu\">\n";
+    for (n, name) in REG_ALIASES.iter().enumerate() {
+        let kind = match *name {
+            "ra" => "code_ptr",
+            "sp" | "gp" | "tp" => "data_ptr",
+            _ => "int",
+        };
+        xml += &format!("<reg name=\"{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{}\"/>\n", name, bits, kind, n);
+    }
+    xml += &format!("<reg name=\"pc\" bitsize=\"{}\" type=\"code_ptr\" regnum=\"{}\"/>\n", bits, REG_PC);
+    xml += "</feature>\n<feature name=\"org.gnu.gdb.riscv.fpu\">\n";
+    for (n, name) in FREG_ALIASES.iter().enumerate() {
+        xml += &format!("<reg name=\"{}\" bitsize=\"64\" type=\"ieee_double\" regnum=\"{}\"/>\n", name, REG_F0 + n);
+    }
+    let csr = |csr: u16| {
+        format!("<reg name=\"{}\" bitsize=\"{}\" type=\"int\" regnum=\"{}\"/>\n",
+            csr_name(csr).unwrap_or_default(), bits, REG_CSR0 + csr as usize)
+    };
+    for address in FP_CSRS {
+        xml += &csr(address);
+    }
+    xml += "</feature>\n<feature name=\"org.gnu.gdb.riscv.csr\">\n";
+    for address in 0..CSR_COUNT as u16 {
+        if csr_name(address).is_some() && !FP_CSRS.contains(&address) {
+            xml += &csr(address);
+        }
+    }
+    xml += "</feature>\n</target>\n";
+    xml
+}
+
+// The debugger's side of the VM, independent of how packets get here
+struct Stub<'a> {
+    cpu: &'a mut CPU,
+    breakpoints: Vec<(u64, bool)>, // Address, and whether it's a hardware one
+    watchpoints: Vec<Watchpoint>,
+    last_stop: String,
+    target_xml: String,
+}
+
+impl<'a> Stub<'a> {
+    fn new(cpu: &'a mut CPU) -> Self {
+        let target_xml = target_xml(cpu.xlen());
+        Stub {
+            cpu,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            last_stop: Stop::Signal(SIGTRAP).reply(),
+            target_xml,
+        }
+    }
+
+    // Bytes in an integer register, pc or a CSR
+    fn width(&self) -> usize {
+        self.cpu.xlen().bits() as usize / 8
+    }
+
+    fn read_register(&self, n: usize) -> Option<Vec<u8>> {
+        let width = self.width();
+        let bytes = match n {
+            0..REG_PC => self.cpu.registers.get_x(n as u8).to_le_bytes()[..width].to_vec(),
+            REG_PC => self.cpu.get_pc().to_le_bytes()[..width].to_vec(),
+            REG_F0..REG_CSR0 => self.cpu.fregisters.get_f64((n - REG_F0) as u8).to_le_bytes().to_vec(),
+            _ if n - REG_CSR0 < CSR_COUNT => self.cpu.csr.read((n - REG_CSR0) as u16)?.to_le_bytes()[..width].to_vec(),
+            _ => return None,
+        };
+        Some(bytes)
+    }
+
+    // Writes to x0 and to read only CSRs are ignored, like they are in the program
+    fn write_register(&mut self, n: usize, bytes: &[u8]) -> Option<()> {
+        let size = if (REG_F0..REG_CSR0).contains(&n) { 8 } else { self.width() };
+        if bytes.len() != size {
+            return None;
+        }
+        let mut value = [0; 8];
+        value[..size].copy_from_slice(bytes);
+        let value = u64::from_le_bytes(value);
+        match n {
+            0..REG_PC => self.cpu.registers.set_x(n as u8, self.cpu.xlen().sext(value)),
+            REG_PC => self.cpu.set_pc(value),
+            REG_F0..REG_CSR0 => self.cpu.fregisters.set_f64((n - REG_F0) as u8, value),
+            _ if n - REG_CSR0 < CSR_COUNT => self.cpu.csr.write((n - REG_CSR0) as u16, value)?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // g: the general purpose registers and pc, the others are read one by one
+    fn read_registers(&self) -> String {
+        (0..=REG_PC).filter_map(|n| self.read_register(n)).map(|bytes| to_hex(&bytes)).collect()
+    }
+
+    fn write_registers(&mut self, data: &str) -> Option<()> {
+        let bytes = from_hex(data)?;
+        for (n, value) in bytes.chunks_exact(self.width()).take(REG_PC + 1).enumerate() {
+            self.write_register(n, value)?;
+        }
+        Some(())
+    }
+
+    fn read_memory(&mut self, args: &str) -> Option<String> {
+        let (address, length) = args.split_once(',')?;
+        let (address, length) = (number(address)?, number(length)?);
+        let bytes = (0..length)
+            .map(|i| self.cpu.peek_u8(self.cpu.xlen().zext(address.wrapping_add(i))))
+            .collect::<Option<Vec<u8>>>()?;
+        Some(to_hex(&bytes))
+    }
+
+    fn write_memory(&mut self, args: &str) -> Option<()> {
+        let (range, data) = args.split_once(':')?;
+        let (address, length) = range.split_once(',')?;
+        let (address, length) = (number(address)?, number(length)?);
+        let bytes = from_hex(data)?;
+        if bytes.len() != length as usize {
+            return None;
+        }
+        for (i, byte) in bytes.into_iter().enumerate() {
+            self.cpu.poke_u8(self.cpu.xlen().zext(address.wrapping_add(i as u64)), byte)?;
+        }
+        Some(())
+    }
+
+    // Z and z: inserts or removes a breakpoint or a watchpoint. None if the type isn't supported.
+    fn set_point(&mut self, insert: bool, args: &str) -> Option<String> {
+        let mut fields = args.split(';').next()?.split(',');
+        let (Some(kind), Some(address), Some(length)) = (fields.next(), fields.next(), fields.next()) else {
+            return Some(ERROR.to_string());
+        };
+        let (Some(address), Some(length)) = (number(address), number(length)) else {
+            return Some(ERROR.to_string());
+        };
+        match kind {
+            "0" | "1" => {
+                let breakpoint = (address, kind == "1");
+                self.breakpoints.retain(|point| *point != breakpoint);
+                if insert {
+                    self.breakpoints.push(breakpoint);
+                }
+            }
+            "2" | "3" | "4" => {
+                let kind = match kind {
+                    "2" => WatchKind::Write,
+                    "3" => WatchKind::Read,
+                    _ => WatchKind::Access,
+                };
+                let watchpoint = Watchpoint { kind, address, length: length as u32 };
+                self.watchpoints.retain(|point| *point != watchpoint);
+                if insert {
+                    self.watchpoints.push(watchpoint);
+                }
+            }
+            _ => return None,
+        }
+        Some("OK".to_string())
+    }
+
+    // qXfer:features:read, the target XML in chunks of at most `length` bytes
+    fn read_features(&self, args: &str) -> Option<String> {
+        let (annex, range) = args.split_once(':')?;
+        let (offset, length) = range.split_once(',')?;
+        let (offset, length) = (number(offset)? as usize, number(length)? as usize);
+        if annex != "target.xml" {
+            return None;
+        }
+        let xml = self.target_xml.as_bytes();
+        let start = offset.min(xml.len());
+        let end = offset.saturating_add(length).min(xml.len());
+        let more = if end < xml.len() { "m" } else { "l" };
+        Some(format!("{}{}", more, String::from_utf8_lossy(&xml[start..end])))
+    }
+
+    // c, s, C and S, which may give the address to resume from
+    fn resume_at(&mut self, packet: &str) -> Action {
+        let args = &packet[1..];
+        let address = if packet.starts_with(['C', 'S']) { args.split_once(';').map(|(_, address)| address) } else { Some(args) };
+        if let Some(address) = address.and_then(number) {
+            self.cpu.set_pc(address);
+        }
+        Action::Resume { step: packet.starts_with(['s', 'S']) }
+    }
+
+    fn command(&mut self, packet: &str) -> Action {
+        let ok = |done: Option<()>| done.map_or(ERROR.to_string(), |_| "OK".to_string());
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => self.last_stop.clone(),
+            Some(b'g') => self.read_registers(),
+            Some(b'G') => ok(self.write_registers(&packet[1..])),
+            Some(b'p') => number(&packet[1..])
+                .and_then(|n| self.read_register(n as usize))
+                .map_or(ERROR.to_string(), |bytes| to_hex(&bytes)),
+            Some(b'P') => ok(packet[1..].split_once('=').and_then(|(n, value)| {
+                self.write_register(number(n)? as usize, &from_hex(value)?)
+            })),
+            Some(b'm') => self.read_memory(&packet[1..]).unwrap_or(MEMORY_ERROR.to_string()),
+            Some(b'M') => self.write_memory(&packet[1..]).map_or(MEMORY_ERROR.to_string(), |_| "OK".to_string()),
+            Some(b'c' | b'C' | b's' | b'S') => return self.resume_at(packet),
+            Some(b'Z' | b'z') => self.set_point(packet.starts_with('Z'), &packet[1..]).unwrap_or_default(),
+            // There's a single thread, whatever GDB picks
+            Some(b'H' | b'T') => "OK".to_string(),
+            Some(b'D') => return Action::Detach,
+            Some(b'k') => return Action::Kill,
+            _ if packet.starts_with("qSupported") => {
+                "PacketSize=4000;qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+;vContSupported+".to_string()
+            }
+            _ if packet.starts_with("qXfer:features:read:") => {
+                self.read_features(&packet["qXfer:features:read:".len()..]).unwrap_or(ERROR.to_string())
+            }
+            _ if packet == "vCont?" => "vCont;c;C;s;S".to_string(),
+            // Only the first action counts, they would all apply to the same thread
+            _ if packet.starts_with("vCont;") => {
+                return match packet["vCont;".len()..].bytes().next() {
+                    Some(b'c' | b'C') => Action::Resume { step: false },
+                    Some(b's' | b'S') => Action::Resume { step: true },
+                    _ => Action::Reply(ERROR.to_string()),
+                };
+            }
+            _ if packet == "qAttached" => "1".to_string(),
+            _ if packet == "qC" => "QC1".to_string(),
+            _ if packet == "qfThreadInfo" => "m1".to_string(),
+            _ if packet == "qsThreadInfo" => "l".to_string(),
+            // Anything else isn't supported, which is what an empty reply says
+            _ => String::new(),
+        };
+        Action::Reply(reply)
+    }
+
+    // Where the next instruction would stop, if it would
+    fn check_points(&mut self) -> Option<Stop> {
+        let pc = self.cpu.get_pc();
+        if let Some(&(_, hardware)) = self.breakpoints.iter().find(|(address, _)| *address == pc) {
+            return Some(Stop::Breakpoint { hardware });
+        }
+        if self.watchpoints.is_empty() {
+            return None;
+        }
+        let access = self.cpu.next_access()?;
+        self.watchpoints.iter().find_map(|watchpoint| watchpoint.check(&access))
+    }
+
+    // Runs a single instruction or until something stops the program. The first
+    // instruction never stops on a breakpoint or a watchpoint, it's the one GDB
+    // resumes from. `interrupted` tells whether GDB wants it stopped.
+    fn resume(&mut self, step: bool, interrupted: &mut dyn FnMut() -> bool) -> Stop {
+        let mut executed: u64 = 0;
+        let stop = loop {
+            if executed > 0 {
+                if let Some(stop) = self.check_points() {
+                    break stop;
+                }
+                if executed.is_multiple_of(POLL_INTERVAL) && interrupted() {
+                    break Stop::Signal(SIGINT);
+                }
+            }
+            executed += 1;
+            if let Err(fault) = self.cpu.step() {
+                break Stop::Signal(signal(fault.exception));
+            }
+            if let Some(code) = self.cpu.memory.exit_code() {
+                break Stop::Exited(code);
+            }
+            if step {
+                break Stop::Signal(SIGTRAP);
+            }
+        };
+        self.last_stop = stop.reply();
+        stop
+    }
+}
+
+// Packets over TCP
+struct Connection {
+    stream: TcpStream,
+    no_ack: bool, // GDB asked for QStartNoAckMode
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+impl Connection {
+    // The bytes up to `end`, which is dropped. None if GDB hung up.
+    fn read_until(&mut self, end: u8) -> io::Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        let mut byte = [0];
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == end {
+                return Ok(Some(data));
+            }
+            data.push(byte[0]);
+        }
+    }
+
+    // The next packet, acknowledged. Acks, and interrupts sent while the program
+    // is already stopped, are skipped. None if GDB hung up.
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        loop {
+            if self.read_until(b'$')?.is_none() {
+                return Ok(None);
+            }
+            let Some(data) = self.read_until(b'#')? else {
+                return Ok(None);
+            };
+            let mut sum = [0; 2];
+            self.stream.read_exact(&mut sum)?;
+            let valid = std::str::from_utf8(&sum).ok().and_then(|sum| u8::from_str_radix(sum, 16).ok()) == Some(checksum(&data));
+            if !self.no_ack {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let mut packet = vec![b'$'];
+        for &byte in data.as_bytes() {
+            if matches!(byte, b'#' | b'$' | b'}' | b'*') {
+                packet.extend([b'}', byte ^ 0x20]);
+            } else {
+                packet.push(byte);
+            }
+        }
+        let sum = checksum(&packet[1..]);
+        packet.extend(format!("#{:02x}", sum).bytes());
+        self.stream.write_all(&packet)
+    }
+
+    // Whether GDB sent an interrupt, without waiting for one. GDB hanging up counts as one.
+    fn interrupted(&mut self) -> bool {
+        let mut byte = [0];
+        let _ = self.stream.set_nonblocking(true);
+        let read = self.stream.read(&mut byte);
+        let _ = self.stream.set_nonblocking(false);
+        match read {
+            Ok(0) => true,
+            Ok(_) => byte[0] == INTERRUPT,
+            Err(_) => false,
+        }
+    }
+}
+
+// Serves the first GDB to connect to `listener` until it detaches, kills the
+// program or the program exits
+fn session(cpu: &mut CPU, listener: TcpListener) -> io::Result<Session> {
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    let mut connection = Connection { stream, no_ack: false };
+    let mut stub = Stub::new(cpu);
+    loop {
+        // Hanging up without detaching leaves the program running, like detaching does
+        let Some(packet) = connection.receive()? else {
+            return Ok(Session::Detached);
+        };
+        if packet == "QStartNoAckMode" {
+            connection.send("OK")?;
+            connection.no_ack = true;
+            continue;
+        }
+        match stub.command(&packet) {
+            Action::Reply(reply) => connection.send(&reply)?,
+            Action::Resume { step } => {
+                let stop = stub.resume(step, &mut || connection.interrupted());
+                connection.send(&stop.reply())?;
+                if let Stop::Exited(code) = stop {
+                    return Ok(Session::Exited(code));
+                }
+            }
+            Action::Detach => {
+                connection.send("OK")?;
+                return Ok(Session::Detached);
+            }
+            Action::Kill => return Ok(Session::Killed),
+        }
+    }
+}
+
+// Waits for GDB on localhost:`port` and hands it `cpu`, from its current pc
+pub(crate) fn serve(cpu: &mut CPU, port: u16) -> io::Result<Session> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Waiting for GDB on localhost:{}", listener.local_addr()?.port());
+    session(cpu, listener)
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::gdb::*;
+    use crate::cpu::{Uart, UART_BASE, UART_IRQ};
+    use std::sync::mpsc;
+    use std::thread;
+
+    // li a0, 1; sw a0, 0x100(zero); lw a1, 0x100(zero); ebreak
+    const PROGRAM: [u32; 4] = [0x00100513, 0x10A02023, 0x10002583, 0x00100073];
+
+    // A CPU with `program` at 0, about to run its first instruction
+    fn cpu_with(program: &[u32]) -> CPU {
+        let mut cpu = CPU::new();
+        for (i, instruction) in program.iter().enumerate() {
+            cpu.memory.set_u32(4 * i as u32, *instruction).unwrap();
+        }
+        cpu.set_pc(0);
+        cpu
+    }
+
+    fn reply(stub: &mut Stub, packet: &str) -> String {
+        match stub.command(packet) {
+            Action::Reply(reply) => reply,
+            action => panic!("{} got {:?} rather than a reply", packet, action),
+        }
+    }
+
+    // Sends `packet`, which should resume the program, and returns the stop reply
+    fn resume(stub: &mut Stub, packet: &str) -> String {
+        match stub.command(packet) {
+            Action::Resume { step } => stub.resume(step, &mut || false).reply(),
+            action => panic!("{} got {:?} rather than resuming", packet, action),
+        }
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut cpu = cpu_with(&PROGRAM);
+        cpu.registers.set_register(10, 0x12345678);
+        cpu.fregisters.set_f64(0, 0x3FF0_0000_0000_0000);
+        let mut stub = Stub::new(&mut cpu);
+
+        let registers = reply(&mut stub, "g");
+        assert_eq!(registers.len(), 33 * 8);
+        assert_eq!(&registers[80..88], "78563412", "a0 is little endian");
+        assert_eq!(reply(&mut stub, &format!("G{}", registers)), "OK");
+
+        assert_eq!(reply(&mut stub, "pa"), "78563412");
+        assert_eq!(reply(&mut stub, "P20=08000000"), "OK", "pc is register 32");
+        assert_eq!(reply(&mut stub, "p20"), "08000000");
+        assert_eq!(reply(&mut stub, "P0=01000000"), "OK");
+        assert_eq!(reply(&mut stub, "p0"), "00000000", "x0 stays 0");
+        assert_eq!(reply(&mut stub, "p21"), "000000000000f03f", "f0 is register 33");
+        assert_eq!(reply(&mut stub, "P381=efbeadde"), "OK", "mscratch is register 65 + 0x340");
+        assert_eq!(reply(&mut stub, "p381"), "efbeadde");
+        assert_eq!(reply(&mut stub, "P42=ff000000"), "OK", "fflags is register 66");
+        assert_eq!(reply(&mut stub, "p42"), "1f000000");
+        assert_eq!(reply(&mut stub, "p801"), ERROR, "0x7C0 isn't a CSR we have");
+        assert_eq!(reply(&mut stub, "P1=0100"), ERROR, "Registers are written whole");
+        drop(stub);
+        assert_eq!(cpu.get_pc(), 8);
+        assert_eq!(cpu.csr.read(0x340), Some(0xDEADBEEF));
+    }
+
+    #[test]
+    fn test_registers_rv64() {
+        let mut cpu = CPU::with_xlen(Xlen::Rv64);
+        cpu.registers.set_x(10, 0x1122_3344_5566_7788);
+        cpu.set_pc(0x1_0000_0000);
+        let mut stub = Stub::new(&mut cpu);
+        assert!(stub.target_xml.contains("<architecture>riscv:rv64</architecture>"));
+        assert!(stub.target_xml.contains("<reg name=\"pc\" bitsize=\"64\" type=\"code_ptr\" regnum=\"32\"/>"));
+        assert!(stub.target_xml.contains("<reg name=\"mscratch\" bitsize=\"64\" type=\"int\" regnum=\"897\"/>"));
+
+        let registers = reply(&mut stub, "g");
+        assert_eq!(registers.len(), 33 * 16, "Registers are 8 bytes each");
+        assert_eq!(&registers[160..176], "8877665544332211");
+        assert_eq!(&registers[512..528], "0000000001000000");
+        assert_eq!(reply(&mut stub, &format!("G{}", registers)), "OK");
+        assert_eq!(reply(&mut stub, "P381=efbeadde00000080"), "OK");
+        assert_eq!(reply(&mut stub, "P381=efbeadde"), ERROR, "Registers are written whole");
+        drop(stub);
+        assert_eq!(cpu.registers.get_x(10), 0x1122_3344_5566_7788);
+        assert_eq!(cpu.csr.read(0x340), Some(0x8000_0000_DEAD_BEEF));
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let mut stub = Stub::new(&mut cpu);
+        assert_eq!(reply(&mut stub, "m0,4"), "13051000");
+        assert_eq!(reply(&mut stub, "M100,2:abcd"), "OK");
+        assert_eq!(reply(&mut stub, "m100,3"), "abcd00");
+        assert_eq!(reply(&mut stub, "m7fff0000,4"), MEMORY_ERROR, "Nothing is mapped there");
+        assert_eq!(reply(&mut stub, "M100,2:ab"), MEMORY_ERROR);
+        drop(stub);
+        assert_eq!(cpu.memory.get_u16(0x100), Ok(0xCDAB));
+    }
+
+    #[test]
+    fn test_device_memory() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let (input, receiver) = mpsc::channel();
+        cpu.attach_uart(UART_BASE, Uart::with_input(Box::new(io::sink()), receiver), UART_IRQ).unwrap();
+        input.send(b'x').unwrap();
+        // Reading RBR while the FIFO is empty pulls the input in
+        cpu.memory.get_u8(UART_BASE).unwrap();
+        let mut stub = Stub::new(&mut cpu);
+        assert_eq!(reply(&mut stub, &format!("m{:x},1", UART_BASE)), MEMORY_ERROR, "Reading RBR would pop the FIFO");
+        assert_eq!(reply(&mut stub, &format!("M{:x},1:41", UART_BASE)), MEMORY_ERROR);
+        assert_eq!(reply(&mut stub, "m0,2"), "1305", "RAM is still readable");
+        drop(stub);
+        assert_eq!(cpu.memory.get_u8(UART_BASE), Ok(b'x'));
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let mut stub = Stub::new(&mut cpu);
+        assert!(reply(&mut stub, "qSupported:multiprocess+;swbreak+;hwbreak+").contains("qXfer:features:read+"));
+        assert_eq!(reply(&mut stub, "?"), "S05");
+        assert_eq!(reply(&mut stub, "vCont?"), "vCont;c;C;s;S");
+        assert_eq!(reply(&mut stub, "Hg0"), "OK");
+        assert_eq!(reply(&mut stub, "qfThreadInfo"), "m1");
+        assert_eq!(reply(&mut stub, "qSomethingElse"), "", "Unsupported packets get an empty reply");
+
+        // GDB reads the XML in chunks
+        let mut xml = String::new();
+        loop {
+            let chunk = reply(&mut stub, &format!("qXfer:features:read:target.xml:{:x},100", xml.len()));
+            xml += &chunk[1..];
+            if chunk.starts_with('l') {
+                break;
+            }
+            assert!(chunk.starts_with('m'));
+        }
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.ends_with("</target>\n"));
+        assert!(xml.contains("<architecture>riscv:rv32</architecture>"));
+        assert!(xml.contains("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"32\"/>"));
+        assert!(xml.contains("<reg name=\"ft0\" bitsize=\"64\" type=\"ieee_double\" regnum=\"33\"/>"));
+        assert!(xml.contains("<reg name=\"fflags\" bitsize=\"32\" type=\"int\" regnum=\"66\"/>"));
+        assert!(xml.contains("<reg name=\"mscratch\" bitsize=\"32\" type=\"int\" regnum=\"897\"/>"));
+        assert_eq!(reply(&mut stub, "qXfer:features:read:other.xml:0,100"), ERROR);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let mut stub = Stub::new(&mut cpu);
+        assert_eq!(resume(&mut stub, "s"), "S05");
+        assert_eq!(stub.cpu.get_pc(), 4);
+        assert_eq!(resume(&mut stub, "vCont;s:1"), "S05");
+        assert_eq!(stub.cpu.get_pc(), 8);
+        assert_eq!(resume(&mut stub, "s0"), "S05", "Stepping from address 0");
+        assert_eq!(stub.cpu.get_pc(), 4);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let mut stub = Stub::new(&mut cpu);
+        assert_eq!(reply(&mut stub, "Z0,8,4"), "OK");
+        assert_eq!(resume(&mut stub, "c"), "T05swbreak:;");
+        assert_eq!(stub.cpu.get_pc(), 8);
+        assert_eq!(reply(&mut stub, "?"), "T05swbreak:;");
+        // Continuing goes past the breakpoint it stopped on, to the ebreak
+        assert_eq!(resume(&mut stub, "vCont;c"), "S05");
+        assert_eq!(stub.cpu.get_pc(), 0xC);
+
+        assert_eq!(reply(&mut stub, "z0,8,4"), "OK");
+        assert_eq!(reply(&mut stub, "Z1,4,4"), "OK");
+        assert_eq!(resume(&mut stub, "c0"), "T05hwbreak:;");
+        assert_eq!(stub.cpu.get_pc(), 4);
+        assert_eq!(reply(&mut stub, "Z5,4,4"), "", "Unsupported type");
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut cpu = cpu_with(&PROGRAM);
+        let mut stub = Stub::new(&mut cpu);
+        // The program stops right before the access
+        assert_eq!(reply(&mut stub, "Z2,100,4"), "OK");
+        assert_eq!(resume(&mut stub, "c"), "T05watch:100;");
+        assert_eq!(stub.cpu.get_pc(), 4);
+        assert_eq!(stub.cpu.memory.get_u32(0x100), Ok(0));
+        // The load doesn't write
+        assert_eq!(resume(&mut stub, "c"), "S05");
+        assert_eq!(reply(&mut stub, "z2,100,4"), "OK");
+
+        assert_eq!(reply(&mut stub, "Z3,102,1"), "OK");
+        assert_eq!(resume(&mut stub, "c0"), "T05rwatch:102;");
+        assert_eq!(stub.cpu.get_pc(), 8);
+        assert_eq!(reply(&mut stub, "z3,102,1"), "OK");
+
+        assert_eq!(reply(&mut stub, "Z4,fe,4"), "OK");
+        assert_eq!(resume(&mut stub, "c0"), "T05awatch:100;");
+        assert_eq!(stub.cpu.get_pc(), 4);
+    }
+
+    #[test]
+    fn test_exit() {
+        // li a0, 7; sw a0, 0x100(zero); sw zero, 0x104(zero), with tohost at 0x100
+        let mut cpu = cpu_with(&[0x00700513, 0x10A02023, 0x10002223]);
+        cpu.attach_htif(0x100, None);
+        let mut stub = Stub::new(&mut cpu);
+        assert_eq!(resume(&mut stub, "c"), "W03");
+    }
+
+    #[test]
+    fn test_session() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let gdb = thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let mut exchange = |packet: &str, reply_length: usize| {
+                stream.write_all(packet.as_bytes()).unwrap();
+                let mut reply = vec![0; reply_length];
+                stream.read_exact(&mut reply).unwrap();
+                String::from_utf8(reply).unwrap()
+            };
+            let frame = |data: &str| format!("${}#{:02x}", data, checksum(data.as_bytes()));
+            let replies = [
+                exchange(&frame("m0,4"), 13),
+                exchange("$m0,4#00", 1),
+                exchange(&format!("+{}", frame("QStartNoAckMode")), 7),
+                exchange(&frame("c"), 7),
+            ];
+            stream.write_all(frame("k").as_bytes()).unwrap();
+            replies
+        });
+
+        let mut cpu = cpu_with(&PROGRAM);
+        assert_eq!(session(&mut cpu, listener).unwrap(), Session::Killed);
+        assert_eq!(gdb.join().unwrap(), [
+            "+$13051000#8a",
+            "-",
+            "+$OK#9a",
+            "$S05#b8", // No more acks
+        ]);
+    }
+}
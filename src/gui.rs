@@ -1,55 +1,46 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
 use eframe::egui;
 use eframe::egui::{Color32, Stroke};
 use crate::cpu::CPU;
+use crate::cpu::REG_ALIASES;
 
-pub(crate) fn gui(cpu: CPU) -> eframe::Result {
+pub(crate) fn gui(cpu: CPU, console: Console) -> eframe::Result {
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "VM Control Panel",
         options,
-        Box::new(|_cc| Ok(Box::new(VmApp::new(cpu)) as Box<dyn eframe::App>)),
+        Box::new(|_cc| Ok(Box::new(VmApp::new(cpu, console)) as Box<dyn eframe::App>)),
     )
 }
 
-const REG_ALIASES: [&str; 32] = [
-    "zero",
-    "ra",
-    "sp",
-    "gp",
-    "tp",
-    "t0",
-    "t1",
-    "t2",
-    "s0",
-    "s1",
-    "s2",
-    "a0",
-    "a1",
-    "a2",
-    "a3",
-    "a4",
-    "a5",
-    "a6",
-    "a7",
-    "s3",
-    "s4",
-    "s5",
-    "s6",
-    "s7",
-    "s8",
-    "s9",
-    "s10",
-    "s11",
-    "t3",
-    "t4",
-    "t5",
-    "t6"
-];
+// Everything the UART has transmitted, for the console tab.
+// Clones share the same buffer, so one can be handed to the UART.
+#[derive(Clone, Default)]
+pub(crate) struct Console(Arc<Mutex<Vec<u8>>>);
+
+impl Console {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for Console {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 struct VmApp {
     register_aliases: bool,
     active_tab: Tab,
     cpu: CPU,
+    console: Console,
 }
 
 // Define an enum to represent the tabs
@@ -57,13 +48,14 @@ struct VmApp {
 enum Tab {
     Registers,
     Memory,
+    Console,
 }
 
 
 impl VmApp {
 
-    pub fn new(cpu: CPU) -> Self{
-        VmApp { register_aliases: true, active_tab: Tab::Registers, cpu }
+    pub fn new(cpu: CPU, console: Console) -> Self{
+        VmApp { register_aliases: true, active_tab: Tab::Registers, cpu, console }
     }
 
     // TODO: Draw grid cell lines.
@@ -91,16 +83,17 @@ impl VmApp {
             ui.label("Decimal");
             col_rects.push(ui.cursor().left());
             ui.end_row();
-            for i in 0..32 {
+            for (i, alias) in REG_ALIASES.iter().enumerate() {
                 let row_start = ui.cursor();
                 if self.register_aliases {
-                    ui.label(format!(" {} ", REG_ALIASES[i]));
+                    ui.label(format!(" {} ", alias));
                 } else {
                     ui.label(format!(" x{} ", i));
                 }
-                let value = format!(" 0x{:0>4} ", self.cpu.registers.get_register(i as u8));
-                let ascii = format!(" {} ", self.cpu.registers.get_register(i as u8) as u8 as char);
-                let dec = format!(" {} ", self.cpu.registers.get_register(i as u8) as u8);
+                let register = self.cpu.xlen().zext(self.cpu.registers.get_x(i as u8));
+                let value = format!(" 0x{:0>4} ", register);
+                let ascii = format!(" {} ", register as u8 as char);
+                let dec = format!(" {} ", register as u8);
                 ui.label(value);
                 ui.label(ascii);
                 ui.label(dec);
@@ -122,10 +115,6 @@ impl VmApp {
             );*/
 
             // Draw vertical lines for columns
-            let column_positions = [
-                rect.left(),
-                rect.right(),
-            ];
             for &x in &col_rects {
                 painter.line_segment(
                     [
@@ -181,6 +170,19 @@ impl VmApp {
                 }
             });
     }
+
+    // TODO: Send keyboard input to the UART once execution can be resumed from here
+    fn show_console(&mut self, ui: &mut egui::Ui) {
+        let mut text = self.console.text();
+        egui::ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut text)
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(f32::INFINITY)
+                    .interactive(false));
+            });
+    }
 }
 
 impl eframe::App for VmApp {
@@ -198,6 +200,9 @@ impl eframe::App for VmApp {
                     if ui.button("Memory").clicked() {
                         self.active_tab = Tab::Memory;
                     }
+                    if ui.button("Console").clicked() {
+                        self.active_tab = Tab::Console;
+                    }
                 });
             });
 
@@ -210,6 +215,10 @@ impl eframe::App for VmApp {
                     ui.heading("Memory");
                     self.show_memory(ui);
                 }
+                Tab::Console => {
+                    ui.heading("Console");
+                    self.show_console(ui);
+                }
             }
         });
     }
@@ -0,0 +1,158 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Runner for the ISA tests
+ *
+ * The tests in isa-tests/ are our own, written in the format of the riscv-tests
+ * suite and named like its binaries, so upstream builds can be run the same way.
+ * Every rv32u{i,m,a,c,f,d,zba,zbb,zbc,zbs}-p-* and rv64u{i,m}-p-* binary is a bare metal ELF
+ * linked at 0x80000000, ELF64 for the RV64 ones, which get an RV64 CPU. It runs its test cases in order and exits through the HTIF: with code 0 if all of them passed,
+ * or with the number of the failing case.
+ */
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::cpu::{CPU, StopReason};
+use crate::elf::Elf;
+
+pub(crate) const RAM_BASE: u32 = 0x8000_0000;
+const MEMSIZE: usize = 1024 * 1024;
+const PAGE_OFFSET_BITS: usize = 12;
+// The tests are short, anything running for this long is stuck
+const MAX_INSTRUCTIONS: u64 = 1_000_000;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Pass,
+    Fail(u32), // Number of the failing test case
+    Timeout,
+    Error(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "PASS"),
+            Outcome::Fail(test) => write!(f, "FAIL (test {})", test),
+            Outcome::Timeout => write!(f, "FAIL (no result after {} instructions)", MAX_INSTRUCTIONS),
+            Outcome::Error(e) => write!(f, "FAIL ({})", e),
+        }
+    }
+}
+
+// Only the test binaries themselves, not the .dump files that come with upstream builds
+fn is_test(name: &str) -> bool {
+    const SUITES: [&str; 12] = ["rv32ui-p-", "rv32um-p-", "rv32ua-p-", "rv32uc-p-", "rv32uf-p-", "rv32ud-p-",
+        "rv32uzba-p-", "rv32uzbb-p-", "rv32uzbc-p-", "rv32uzbs-p-", "rv64ui-p-", "rv64um-p-"];
+    SUITES.iter().any(|suite| name.starts_with(suite)) && !name.contains('.')
+}
+
+// Loads a test into a fresh CPU, as wide as the ELF class says
+fn load(image: &[u8]) -> Result<CPU, String> {
+    let elf = Elf::parse(image).map_err(|e| e.to_string())?;
+    if elf.symbols.lookup("tohost").is_none() {
+        return Err("no tohost symbol".to_string());
+    }
+    let mut cpu = CPU::with_memory(elf.xlen, RAM_BASE, MEMSIZE, PAGE_OFFSET_BITS).map_err(|e| e.to_string())?;
+    cpu.load_elf(elf).map_err(|e| e.to_string())?;
+    Ok(cpu)
+}
+
+fn run(cpu: &mut CPU) -> Outcome {
+    match cpu.run(cpu.get_pc(), Some(MAX_INSTRUCTIONS)) {
+        StopReason::Exit(0) => Outcome::Pass,
+        StopReason::Exit(test) => Outcome::Fail(test),
+        StopReason::InstructionLimit => Outcome::Timeout,
+        StopReason::Fault(fault) => Outcome::Error(format!("{} at pc 0x{:08x}", fault.exception, fault.pc)),
+    }
+}
+
+pub(crate) fn run_test(image: &[u8]) -> Outcome {
+    match load(image) {
+        Ok(mut cpu) => run(&mut cpu),
+        Err(e) => Outcome::Error(e),
+    }
+}
+
+// Runs every test binary in `dir`, sorted by name
+pub(crate) fn run_suite(dir: &Path) -> io::Result<Vec<(String, Outcome)>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if is_test(&name) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    names.into_iter()
+        .map(|name| {
+            let image = fs::read(dir.join(&name))?;
+            let outcome = run_test(&image);
+            Ok((name, outcome))
+        })
+        .collect()
+}
+
+///// TESTS /////
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use crate::isa_tests::*;
+    use crate::cpu::Xlen;
+    use std::path::PathBuf;
+
+    fn isa_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("isa-tests").join("isa")
+    }
+
+    #[test]
+    fn test_is_test() {
+        assert!(is_test("rv32ui-p-add"));
+        assert!(is_test("rv32um-p-mulh"));
+        assert!(is_test("rv32ua-p-lrsc"));
+        assert!(is_test("rv32uc-p-rvc"));
+        assert!(is_test("rv32ud-p-fadd"));
+        assert!(is_test("rv32uzbb-p-clz"));
+        assert!(!is_test("rv32ui-p-add.dump"));
+        assert!(!is_test("rv32ui-v-add"));
+        assert!(is_test("rv64ui-p-addw"));
+        assert!(is_test("rv64um-p-divuw"));
+        assert!(!is_test("rv64ua-p-amoadd_d"));
+    }
+
+    #[test]
+    fn test_suite() {
+        let results = run_suite(&isa_dir()).unwrap();
+        assert!(results.len() >= 47, "Only found {} tests", results.len());
+        let failed: Vec<String> = results.iter()
+            .filter(|(_, outcome)| *outcome != Outcome::Pass)
+            .map(|(name, outcome)| format!("{} {}", name, outcome))
+            .collect();
+        assert!(failed.is_empty(), "\n{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn test_failing_case() {
+        let image = fs::read(isa_dir().join("rv32ui-p-lw")).unwrap();
+        let mut cpu = load(&image).unwrap();
+        // Test 3 loads tdat2, make it read something else
+        let tdat2 = cpu.symbols.lookup("tdat2").unwrap();
+        cpu.memory.set_u32(tdat2, 0).unwrap();
+        assert_eq!(run(&mut cpu), Outcome::Fail(3));
+    }
+
+    #[test]
+    fn test_rv64() {
+        let image = fs::read(isa_dir().join("rv64ui-p-ld")).unwrap();
+        let cpu = load(&image).unwrap();
+        assert_eq!(cpu.xlen(), Xlen::Rv64);
+        assert_eq!(run_test(&image), Outcome::Pass);
+    }
+
+    #[test]
+    fn test_not_elf() {
+        assert!(matches!(run_test(&[0; 64]), Outcome::Error(_)));
+    }
+}
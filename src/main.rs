@@ -1,22 +1,239 @@
-use std::io::Read;
+#![allow(clippy::upper_case_acronyms)]
+
+use std::io::{self, BufWriter, Read, Write};
 use std::env;
+use std::path::Path;
+use std::process::ExitCode;
 
+mod asm;
+mod cli;
 mod cpu;
+mod elf;
+mod gdb;
 mod gui;
+mod isa_tests;
+mod terminal;
+
+use crate::cli::{AssembleArgs, Command, USAGE};
+use crate::cpu::{CPU, Clint, Disassembler, Exception, Fault, Plic, StopReason, Uart, Xlen, UART_IRQ};
+use crate::elf::Elf;
+use crate::gdb::Session;
 
 // TODO: Check endianness
-fn read_image(filename: &str) -> Vec<u8> {
-    let mut file = std::fs::File::open(filename).unwrap();
+fn read_image(filename: &str) -> std::io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(filename)?;
     let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).unwrap();
-    buffer
+    file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+// Assembly sources are recognised by their extension
+fn is_assembly(filename: &str) -> bool {
+    filename.ends_with(".s") || filename.ends_with(".S")
+}
+
+// Assembles `filename` for `base`, the error is ready to print
+fn assemble_file(filename: &str, base: u32) -> Result<Elf, String> {
+    let source = std::fs::read_to_string(filename).map_err(|e| format!("could not read {}: {}", filename, e))?;
+    asm::assemble(&source, base).map_err(|e| format!("{}: {}", filename, e))
+}
+
+// Assembles a source into an ELF executable or a flat binary
+fn run_assembler(args: &AssembleArgs) -> ExitCode {
+    let elf = match assemble_file(&args.source, args.load_addr) {
+        Ok(elf) => elf,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let output = if args.elf { elf.to_bytes() } else { elf.to_flat() };
+    if let Err(e) = std::fs::write(&args.output, output) {
+        eprintln!("error: could not write {}: {}", args.output, e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// Runs the ISA test binaries in `dir`, one line per test
+fn run_isa_tests(dir: &str) -> ExitCode {
+    let results = match isa_tests::run_suite(Path::new(dir)) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("error: could not read {}: {}", dir, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut failed = 0;
+    for (name, outcome) in &results {
+        if *outcome != isa_tests::Outcome::Pass {
+            failed += 1;
+        }
+        println!("{:<24} {}", name, outcome);
+    }
+    println!("\n{} passed, {} failed", results.len() - failed, failed);
+    if failed == 0 && !results.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let image = read_image(&*args[1]); // TODO make sure 1 isn't out of bounds. Can be fixed by using a flag parsing system :)
-    let mut cpu = cpu::CPU::new();
-    cpu.load_image(0x4, &image);
-    cpu.run(0x4);
-    gui::gui(cpu).expect("GUI failed to initialize"); // TODO add --no-gui flag
+fn main() -> ExitCode {
+    let args = match Command::parse(env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::IsaTests(dir)) => return run_isa_tests(&dir),
+        Ok(Command::Assemble(args)) => return run_assembler(&args),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Sources are assembled at --load-addr, and then loaded like any ELF executable
+    let image = if is_assembly(&args.image) {
+        assemble_file(&args.image, args.load_addr).map(|elf| elf.to_bytes())
+    } else {
+        read_image(&args.image).map_err(|e| format!("could not read {}: {}", args.image, e))
+    };
+    let image = match image {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // ELF executables carry their own load addresses and entry point, flat binaries go at --load-addr
+    let elf = if Elf::is_elf(&image) {
+        match Elf::parse(&image) {
+            Ok(elf) => Some(elf),
+            Err(e) => {
+                eprintln!("error: could not load {}: {}", args.image, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+    // The ELF class picks the register width, unless --xlen does
+    let xlen = args.xlen.or(elf.as_ref().map(|elf| elf.xlen)).unwrap_or(Xlen::Rv32);
+
+    let mut cpu = match CPU::with_memory(xlen, args.ram_base, args.memsize_mb * 1024 * 1024, args.page_offset_bits) {
+        Ok(cpu) => cpu,
+        Err(e) => {
+            eprintln!("error: could not map the RAM at 0x{:08x}: {}", args.ram_base, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(elf) = elf {
+        if let Err(e) = cpu.load_elf(elf) {
+            eprintln!("error: could not load {}: {}", args.image, e);
+            return ExitCode::FAILURE;
+        }
+    } else {
+        if cpu.load_image(args.load_addr, &image).is_err() {
+            eprintln!("error: image does not fit in memory at 0x{:08x}", args.load_addr);
+            return ExitCode::FAILURE;
+        }
+        cpu.set_pc(args.load_addr as u64);
+    }
+
+    if let Some(path) = &args.trace {
+        let output: Box<dyn Write> = if path == "-" {
+            Box::new(io::stderr())
+        } else {
+            match std::fs::File::create(path) {
+                Ok(file) => Box::new(BufWriter::new(file)),
+                Err(e) => {
+                    eprintln!("error: could not create {}: {}", path, e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        };
+        cpu.start_trace(output, args.trace_range);
+    }
+
+    if let Some(tohost) = args.tohost {
+        cpu.attach_htif(tohost, args.fromhost);
+    }
+
+    // Headless, the UART talks to the terminal. Otherwise its output ends up in the console tab
+    let console = gui::Console::default();
+    let uart = if args.no_gui {
+        Uart::with_input(Box::new(io::stdout()), terminal::stdin_reader())
+    } else {
+        Uart::new(Box::new(console.clone()))
+    };
+    if let Err(e) = cpu.attach_plic(args.plic, Plic::new()) {
+        eprintln!("error: could not map the PLIC at 0x{:08x}: {}", args.plic, e);
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = cpu.attach_uart(args.uart, uart, UART_IRQ) {
+        eprintln!("error: could not map the UART at 0x{:08x}: {}", args.uart, e);
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = cpu.attach_clint(args.clint, Clint::new(args.mtime)) {
+        eprintln!("error: could not map the CLINT at 0x{:08x}: {}", args.clint, e);
+        return ExitCode::FAILURE;
+    }
+
+    let mut entry = args.entry.map_or(cpu.get_pc(), |entry| entry as u64);
+    // GDB gets the program first. If it detaches, the program runs on from where it was left
+    let mut exited = None;
+    if let Some(port) = args.gdb {
+        cpu.set_pc(entry);
+        match gdb::serve(&mut cpu, port) {
+            Ok(Session::Exited(code)) => exited = Some(code),
+            Ok(Session::Detached) => entry = cpu.get_pc(),
+            Ok(Session::Killed) => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: GDB connection on port {} failed: {}", port, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    let raw_mode = if args.no_gui && exited.is_none() { terminal::RawMode::enable() } else { None };
+    let stop = match exited {
+        Some(code) => StopReason::Exit(code),
+        None => cpu.run(entry, args.max_instructions),
+    };
+    drop(raw_mode);
+    match stop {
+        StopReason::Exit(0) => {}
+        StopReason::Exit(code) => {
+            eprintln!("Program exited with code {}", code);
+            status = ExitCode::from(code.min(255) as u8);
+        }
+        StopReason::InstructionLimit => eprintln!("Stopped after {} instructions", cpu.instret),
+        // A breakpoint without a handler is how bare programs say they're done
+        StopReason::Fault(Fault { exception: Exception::Breakpoint(pc), .. }) => {
+            eprintln!("Stopped at breakpoint at pc 0x{:08x}", pc);
+        }
+        StopReason::Fault(Fault { exception, pc, instruction: None }) => {
+            eprintln!("error: {} at pc 0x{:08x}", exception, pc);
+            status = ExitCode::FAILURE;
+        }
+        StopReason::Fault(Fault { exception, pc, instruction: Some(instruction) }) => {
+            let text = Disassembler::new(cpu.xlen(), true, &cpu.symbols).disassemble(instruction, pc);
+            eprintln!("error: {} at pc 0x{:08x} (instruction 0x{:08x}: {})", exception, pc, instruction, text);
+            status = ExitCode::FAILURE;
+        }
+    }
+    if args.dump_regs {
+        cpu.dump_registers();
+    }
+    if args.tlb_stats {
+        cpu.dump_tlb_stats();
+    }
+    if !args.no_gui {
+        gui::gui(cpu, console).expect("GUI failed to initialize");
+    }
+    status
 }
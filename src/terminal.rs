@@ -0,0 +1,112 @@
+// RISC-V Tiny VM - Ivi Ballou / Amechania
+
+/* Host terminal, as seen by the UART
+ *
+ * When running headless the terminal is switched to raw mode, so every key
+ * press reaches the program as is, Ctrl-C included. Like QEMU, output
+ * processing is left on so a bare "\n" still starts a new line, and Ctrl-A x
+ * gets out of the VM (Ctrl-A Ctrl-A sends a literal Ctrl-A).
+ */
+
+use std::io::{self, Read, IsTerminal};
+use std::process;
+#[cfg(unix)]
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+const CTRL_A: u8 = 0x01;
+
+// Terminal settings from before raw mode, to put back on the way out
+#[cfg(unix)]
+static SAVED: Mutex<Option<libc::termios>> = Mutex::new(None);
+
+// Keeps stdin in raw mode for as long as it's alive
+pub(crate) struct RawMode;
+
+impl RawMode {
+    // None if stdin isn't a terminal, or can't be switched
+    pub(crate) fn enable() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        enable_raw_mode().then_some(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+#[cfg(unix)]
+fn enable_raw_mode() -> bool {
+    // SAFETY: termios is plain data, and tcgetattr/tcsetattr only touch the struct we pass
+    unsafe {
+        let mut settings: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut settings) != 0 {
+            return false;
+        }
+        let saved = settings;
+        libc::cfmakeraw(&mut settings);
+        settings.c_oflag |= libc::OPOST;
+        if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &settings) != 0 {
+            return false;
+        }
+        *SAVED.lock().unwrap() = Some(saved);
+    }
+    true
+}
+
+#[cfg(not(unix))]
+fn enable_raw_mode() -> bool {
+    false
+}
+
+// Puts the terminal back the way it was, if raw mode was enabled
+fn restore() {
+    #[cfg(unix)]
+    if let Some(saved) = SAVED.lock().unwrap().take() {
+        // SAFETY: see enable_raw_mode
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &saved);
+        }
+    }
+}
+
+fn is_raw() -> bool {
+    #[cfg(unix)]
+    return SAVED.lock().unwrap().is_some();
+    #[cfg(not(unix))]
+    return false;
+}
+
+// Reads stdin on its own thread, handing over every byte through the returned channel
+pub(crate) fn stdin_reader() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut escape = false;
+        for byte in io::stdin().lock().bytes() {
+            let Ok(byte) = byte else { break };
+            if is_raw() {
+                match (escape, byte) {
+                    (false, CTRL_A) => {
+                        escape = true;
+                        continue;
+                    }
+                    (true, b'x') => {
+                        restore();
+                        eprintln!("\nterminated by Ctrl-A x");
+                        process::exit(1);
+                    }
+                    _ => escape = false,
+                }
+            }
+            if sender.send(byte).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}